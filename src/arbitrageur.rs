@@ -1,39 +1,322 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use log::debug;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use solana_sdk::pubkey::Pubkey;
-use crate::r#struct::account::{DeserializedAccount, DeserializedPoolAccount};
+use tokio::sync::mpsc::unbounded_channel;
+
+use crate::config::ArbitrageConfig;
+use crate::constants::NATIVE_MINT_PUBKEY;
+use crate::executor::fee::estimate_compute_units;
+use crate::executor::wallet::Inventory;
+use crate::formula::base::SwapQuote;
+use crate::metrics::registry::Metrics;
+use crate::path::{CycleId, CycleIndex};
+use crate::r#struct::account::{DeserializedAccount, DeserializedPoolAccount, PoolEdge};
+use crate::r#struct::market::Market;
+
+#[derive(Clone, Debug)]
+pub struct Opportunity {
+    pub base_mint: Pubkey,
+    /// pool pubkeys in swap order
+    pub path: Vec<Pubkey>,
+    /// output mint of every swap of the path, tells apart the edges of a multi-asset pool
+    pub mints_out: Vec<Pubkey>,
+    /// market of every pool of the path
+    pub markets: Vec<Market>,
+    /// quote of every swap of the path
+    pub quotes: Vec<SwapQuote>,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// profit in base mint
+    pub profit: u64,
+    /// profit converted into the quote mint, `None` if no pool prices the base mint against it
+    pub quote_profit: Option<u64>,
+    /// profit converted into lamports, which tips and priority fees are paid in.
+    /// `None` if no pool prices the base mint against SOL
+    pub lamport_profit: Option<u64>,
+    /// compute unit limit of the transaction, estimated from the ticks and bins each swap crosses
+    pub compute_units: u32,
+}
 
 pub struct Arbitrageur {
-    shared_account_bin: Arc<Mutex<Vec<DeserializedAccount>>>,
-    path_list: Arc<Mutex<HashMap<Pubkey, Vec<DeserializedPoolAccount>>>>
+    config: Arc<ArbitrageConfig>,
+    shared_account_bin: Arc<Mutex<Arc<Vec<DeserializedAccount>>>>,
+    // paths and pools are resolved once at startup, only the accounts they read change afterward
+    path_list: Arc<HashMap<Pubkey, Vec<Vec<DeserializedPoolAccount>>>>,
+    quote_pools: Arc<Vec<DeserializedPoolAccount>>,
+    related_pubkeys: Arc<HashMap<PoolEdge, HashSet<Pubkey>>>,
+    cycle_index: CycleIndex,
+    thread_pool: Arc<ThreadPool>,
+    inventory: Arc<Inventory>,
+    metrics: Arc<Metrics>
 }
 
 impl Arbitrageur {
     pub fn new(
-        shared_account_bin: Arc<Mutex<Vec<DeserializedAccount>>>,
-        path_list: Arc<Mutex<HashMap<Pubkey, Vec<DeserializedPoolAccount>>>>
+        config: ArbitrageConfig,
+        pool_account_bin: Arc<Mutex<Vec<DeserializedPoolAccount>>>,
+        shared_account_bin: Arc<Mutex<Arc<Vec<DeserializedAccount>>>>,
+        path_list: Arc<Mutex<HashMap<Pubkey, Vec<Vec<DeserializedPoolAccount>>>>>,
+        related_pubkeys: &HashMap<PoolEdge, Vec<(DeserializedAccount, Pubkey)>>,
+        inventory: Arc<Inventory>,
+        metrics: Arc<Metrics>
     ) -> Arbitrageur {
+        let related_pubkeys = related_pubkeys.iter().map(|(pool, related_pubkeys)| {
+            (*pool, related_pubkeys.iter().map(|(_, pubkey)| *pubkey).collect::<HashSet<Pubkey>>())
+        }).collect::<HashMap<PoolEdge, HashSet<Pubkey>>>();
+
+        let path_list = path_list.lock().unwrap().clone();
+        let cycle_index = CycleIndex::new(&path_list, &related_pubkeys);
+        config.base_mints.iter().for_each(|base_mint| {
+            if path_list.get(&base_mint.mint).map_or(true, |paths| paths.is_empty()) {
+                debug!("arbitrageur: no path for {} ({})", base_mint.symbol, base_mint.mint);
+            }
+        });
+
+        // pools pricing a base mint directly against the quote mint or SOL
+        let native_mint = Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap();
+        let quote_pools = pool_account_bin.lock().unwrap().iter().filter(|pool| {
+            config.base_mints.iter().any(|base_mint| {
+                let pair = pool.operation.get_mint_pair();
+                pair.all(base_mint.mint, config.quote_mint) || pair.all(base_mint.mint, native_mint)
+            })
+        }).cloned().collect::<Vec<DeserializedPoolAccount>>();
+
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(config.quote_threads)
+            .thread_name(|index| format!("quote-{}", index))
+            .panic_handler(|_| eprintln!("arbitrageur: quoting thread panicked"))
+            .build()
+            .expect("failed to build quote thread pool");
+
         Arbitrageur {
+            config: Arc::new(config),
             shared_account_bin,
-            path_list
+            path_list: Arc::new(path_list),
+            quote_pools: Arc::new(quote_pools),
+            related_pubkeys: Arc::new(related_pubkeys),
+            cycle_index,
+            thread_pool: Arc::new(thread_pool),
+            inventory,
+            metrics
+        }
+    }
+
+    /// Evaluates every cycle in parallel and returns profitable opportunities,
+    /// best first by profit in the quote mint
+    pub async fn arbitrage(&self) -> Vec<Opportunity> {
+        let cycles = self.path_list.iter().flat_map(|(base_mint, paths)| {
+            (0..paths.len()).map(move |index| CycleId {
+                base_mint: *base_mint,
+                index
+            })
+        }).collect::<Vec<CycleId>>();
+
+        self.evaluate(cycles).await
+    }
+
+    /// Same as `arbitrage`, but only evaluates the cycles containing a pool that depends on one of `pubkeys`
+    pub async fn arbitrage_changed(&self, pubkeys: &Vec<Pubkey>) -> Vec<Opportunity> {
+        let cycles = self.cycle_index.get_cycles(pubkeys).into_iter().collect::<Vec<CycleId>>();
+        debug!("arbitrageur: {} account(s) changed, {} cycle(s) affected", pubkeys.len(), cycles.len());
+
+        self.evaluate(cycles).await
+    }
+
+    /// Quotes `cycles` on the quote thread pool against one snapshot of the account store.
+    /// The store may be replaced while quoting, the snapshot stays untouched
+    async fn evaluate(&self, cycles: Vec<CycleId>) -> Vec<Opportunity> {
+        if cycles.is_empty() {
+            return Vec::new();
         }
+
+        let accounts = Arc::clone(&self.shared_account_bin.lock().unwrap());
+        let config = Arc::clone(&self.config);
+        let path_list = Arc::clone(&self.path_list);
+        let quote_pools = Arc::clone(&self.quote_pools);
+        let related_pubkeys = Arc::clone(&self.related_pubkeys);
+        let inventory = Arc::clone(&self.inventory);
+        let metrics = Arc::clone(&self.metrics);
+
+        let (tx, mut rx) = unbounded_channel::<Opportunity>();
+        self.thread_pool.spawn(move || {
+            let mut pools = cycles.iter().filter_map(|cycle| {
+                path_list.get(&cycle.base_mint)?.get(cycle.index)
+            }).flatten().map(|pool| pool.get_edge()).collect::<HashSet<PoolEdge>>();
+            pools.extend(quote_pools.iter().map(|pool| pool.get_edge()));
+            let related_accounts = Self::get_related_accounts(&pools, &accounts, &related_pubkeys);
+
+            cycles.par_iter().for_each_with(tx, |tx, cycle| {
+                metrics.cycles_evaluated.inc();
+                if let Some(opportunity) = Self::evaluate_cycle(&config, cycle, &path_list, &quote_pools, &related_accounts, &inventory, &metrics) {
+                    // the receiver is only gone if the caller stopped waiting
+                    let _ = tx.send(opportunity);
+                }
+            });
+        });
+
+        // closes once every sender is dropped, including when a quoting thread panics
+        let mut opportunities: Vec<Opportunity> = Vec::new();
+        while let Some(opportunity) = rx.recv().await {
+            opportunities.push(opportunity);
+        }
+
+        Self::rank(&mut opportunities);
+        opportunities
     }
 
-    pub fn arbitrage_single(
-        &self,
-        target_mint: Pubkey,
-        init_amount: u64
-    ) {
-        if let Some(path_list) = self.path_list.lock().unwrap().get(&target_mint) {
-            path_list.iter().for_each(|pool| {
-                let accounts = self.shared_account_bin.lock().unwrap().iter().filter(|account| {
-                    account.get_market() == pool.market
-                }).map(|account| {
-                    account.clone()
-                }).collect::<Vec<DeserializedAccount>>();
-
-                pool.operation.swap(&accounts);
+    fn evaluate_cycle(
+        config: &ArbitrageConfig,
+        cycle: &CycleId,
+        path_list: &HashMap<Pubkey, Vec<Vec<DeserializedPoolAccount>>>,
+        quote_pools: &Vec<DeserializedPoolAccount>,
+        related_accounts: &HashMap<PoolEdge, Vec<DeserializedAccount>>,
+        inventory: &Inventory,
+        metrics: &Metrics
+    ) -> Option<Opportunity> {
+        let base_mint = config.base_mints.iter().find(|base_mint| base_mint.mint == cycle.base_mint)?;
+        let path = path_list.get(&cycle.base_mint)?.get(cycle.index)?;
+
+        // best input size of the cycle, within what the wallet holds
+        base_mint.get_input_amounts(config.input_steps, inventory.get_available(&base_mint.mint)).iter().filter_map(|amount_in| {
+            let quotes = Self::quote_path(path, related_accounts, &base_mint.mint, *amount_in, metrics).ok()?;
+            let amount_out = quotes.last()?.amount_out;
+            if amount_out <= *amount_in {
+                return None;
+            }
+
+            Some((*amount_in, amount_out, quotes))
+        }).max_by_key(|(amount_in, amount_out, _)| amount_out - amount_in)
+            .filter(|(amount_in, amount_out, _)| amount_out - amount_in >= base_mint.min_profit)
+            .map(|(amount_in, amount_out, quotes)| {
+                let profit = amount_out - amount_in;
+                let hops = path.iter().zip(quotes.iter()).map(|(pool, quote)| {
+                    (pool.operation.get_formula(), quote.crossings)
+                }).collect::<Vec<_>>();
+
+                Opportunity {
+                    base_mint: base_mint.mint,
+                    path: path.iter().map(|pool| pool.pubkey).collect(),
+                    mints_out: Self::get_mints_out(path, &base_mint.mint),
+                    markets: path.iter().map(|pool| pool.market).collect(),
+                    quotes,
+                    amount_in,
+                    amount_out,
+                    profit,
+                    quote_profit: Self::to_quote_amount(quote_pools, related_accounts, &base_mint.mint, &config.quote_mint, profit),
+                    lamport_profit: Self::to_quote_amount(quote_pools, related_accounts, &base_mint.mint, &Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap(), profit),
+                    compute_units: estimate_compute_units(&hops),
+                }
             })
+    }
+
+    /// Returns the quote of every swap of the cycle for `amount_in` of base mint in,
+    /// the last amount out is the amount of base mint out
+    pub fn quote_path(
+        path: &Vec<DeserializedPoolAccount>,
+        related_accounts: &HashMap<PoolEdge, Vec<DeserializedAccount>>,
+        base_mint: &Pubkey,
+        amount_in: u64,
+        metrics: &Metrics
+    ) -> Result<Vec<SwapQuote>, &'static str> {
+        let mut mint = *base_mint;
+        let mut amount = amount_in;
+        let mut quotes: Vec<SwapQuote> = Vec::with_capacity(path.len());
+
+        for pool in path {
+            let time = Instant::now();
+            let quote = pool.operation.swap(Self::get_pool_accounts(pool, related_accounts), &mint, amount);
+            metrics.observe_quote(&pool.market, time.elapsed());
+
+            let quote = quote?;
+            if quote.amount_out == 0 {
+                return Err("zero amount out");
+            }
+
+            let pair = pool.operation.get_mint_pair();
+            mint = if pair.pubkey_a == mint { pair.pubkey_b } else { pair.pubkey_a };
+            amount = quote.amount_out;
+            quotes.push(quote);
+        }
+
+        if mint != *base_mint {
+            return Err("path does not end with base mint");
+        }
+
+        Ok(quotes)
+    }
+
+    /// Converts `amount` of `mint` into `quote_mint` at the best direct pool price
+    pub(crate) fn to_quote_amount(
+        quote_pools: &Vec<DeserializedPoolAccount>,
+        related_accounts: &HashMap<PoolEdge, Vec<DeserializedAccount>>,
+        mint: &Pubkey,
+        quote_mint: &Pubkey,
+        amount: u64
+    ) -> Option<u64> {
+        if mint == quote_mint {
+            return Some(amount);
         }
+
+        quote_pools.iter().filter(|pool| {
+            pool.operation.get_mint_pair().all(*mint, *quote_mint)
+        }).filter_map(|pool| {
+            pool.operation.swap(Self::get_pool_accounts(pool, related_accounts), mint, amount).ok()
+        }).map(|quote| quote.amount_out).max()
+    }
+
+    /// Groups the snapshot accounts each of `pools` reads when quoting.
+    /// Tick arrays are shared with the snapshot, so this copies no tick data
+    fn get_related_accounts(
+        pools: &HashSet<PoolEdge>,
+        accounts: &Vec<DeserializedAccount>,
+        related_pubkeys: &HashMap<PoolEdge, HashSet<Pubkey>>
+    ) -> HashMap<PoolEdge, Vec<DeserializedAccount>> {
+        let accounts = accounts.iter().map(|account| {
+            (account.get_pubkey(), account)
+        }).collect::<HashMap<Pubkey, &DeserializedAccount>>();
+
+        pools.iter().map(|pool| {
+            let pool_accounts = match related_pubkeys.get(pool) {
+                Some(related_pubkeys) => {
+                    related_pubkeys.iter().filter_map(|pubkey| {
+                        accounts.get(pubkey).map(|account| (*account).clone())
+                    }).collect::<Vec<DeserializedAccount>>()
+                }
+                None => Vec::new()
+            };
+
+            (*pool, pool_accounts)
+        }).collect::<HashMap<PoolEdge, Vec<DeserializedAccount>>>()
+    }
+
+    fn get_pool_accounts<'a>(
+        pool: &DeserializedPoolAccount,
+        related_accounts: &'a HashMap<PoolEdge, Vec<DeserializedAccount>>
+    ) -> &'a Vec<DeserializedAccount> {
+        static EMPTY: Vec<DeserializedAccount> = Vec::new();
+        related_accounts.get(&pool.get_edge()).unwrap_or(&EMPTY)
     }
-}
\ No newline at end of file
+
+    fn get_mints_out(path: &Vec<DeserializedPoolAccount>, base_mint: &Pubkey) -> Vec<Pubkey> {
+        let mut mint = *base_mint;
+
+        path.iter().map(|pool| {
+            let pair = pool.operation.get_mint_pair();
+            mint = if pair.pubkey_a == mint { pair.pubkey_b } else { pair.pubkey_a };
+            mint
+        }).collect()
+    }
+
+    /// Orders opportunities by profit in the quote mint, those that could not be priced go last
+    pub(crate) fn rank(opportunities: &mut Vec<Opportunity>) {
+        opportunities.sort_by(|a, b| {
+            b.quote_profit.cmp(&a.quote_profit).then(b.profit.cmp(&a.profit))
+        });
+    }
+}
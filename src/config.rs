@@ -0,0 +1,324 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::r#struct::account::DeserializedPoolAccount;
+
+/// Arbitrage settings, read from `./src/config.json`
+#[derive(Clone, Debug)]
+pub struct ArbitrageConfig {
+    /// mint every opportunity's profit is converted into before ranking
+    pub quote_mint: Pubkey,
+    /// number of input sizes tried per cycle, spread between the min and max input of a base mint
+    pub input_steps: usize,
+    /// threads quoting cycles in parallel, 0 uses one per cpu
+    pub quote_threads: usize,
+    pub base_mints: Vec<BaseMintConfig>,
+    pub pool_filter: PoolFilter,
+    pub jito: JitoConfig,
+    pub fee: FeeConfig,
+    pub simulation: SimulationConfig,
+    pub wallet: WalletConfig,
+    /// address lookup tables holding the static accounts of the pools, created by `setup`
+    pub lookup_tables: Vec<Pubkey>,
+    pub tracker: TrackerConfig,
+    pub metrics: MetricsConfig,
+}
+
+/// A mint cycles start and end with
+#[derive(Clone, Debug)]
+pub struct BaseMintConfig {
+    pub symbol: String,
+    pub mint: Pubkey,
+    pub min_amount_in: u64,
+    pub max_amount_in: u64,
+    /// amount of the mint held for arbitrage, caps `max_amount_in`
+    pub inventory: u64,
+    /// profit below which a cycle is not reported, and simulated profit below which a transaction is not sent
+    pub min_profit: u64,
+}
+
+/// Pools rejected by the filter are left out of path resolution.
+/// A pool passes a criterion it has no value for, e.g. the fee tier of a Raydium CLMM pool lives in its amm config
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PoolFilter {
+    /// minimum active liquidity, see `PoolOperation::get_liquidity`
+    #[serde(default)]
+    pub min_liquidity: Option<u128>,
+    /// allowed fee rates in hundredths of a basis point, empty allows all
+    #[serde(default)]
+    pub fee_tiers: Vec<u32>,
+    /// skip pools whose status disables swap
+    #[serde(default)]
+    pub tradable_only: bool,
+    /// skip pools the executor cannot build swap instructions for, see `PoolOperation::has_swap_instructions`
+    #[serde(default)]
+    pub executable_only: bool,
+}
+
+/// Bundle submission to the Jito block engine
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct JitoConfig {
+    pub block_engine_url: String,
+    /// share of the expected profit, in lamports, paid as tip
+    pub tip_fraction: f64,
+    pub min_tip_lamports: u64,
+    pub max_tip_lamports: u64,
+    pub status_poll_interval_ms: u64,
+    /// stop polling a bundle that has not landed after this long
+    pub status_timeout_ms: u64,
+}
+
+impl Default for JitoConfig {
+    fn default() -> Self {
+        JitoConfig {
+            block_engine_url: "https://mainnet.block-engine.jito.wtf".to_string(),
+            tip_fraction: 0.5,
+            // the block engine rejects bundles tipping less than 1000 lamports
+            min_tip_lamports: 1000,
+            max_tip_lamports: 10_000_000,
+            status_poll_interval_ms: 500,
+            status_timeout_ms: 30_000,
+        }
+    }
+}
+
+/// Compute budget of arbitrage transactions
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct FeeConfig {
+    /// percentile of the recent prioritization fees paid on the written pools, 0 to 100
+    pub fee_percentile: u8,
+    /// share of the expected profit, in lamports, the signature and priority fees may take
+    pub max_fee_fraction: f64,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        FeeConfig {
+            fee_percentile: 75,
+            max_fee_fraction: 0.2,
+        }
+    }
+}
+
+/// Preflight simulation of arbitrage transactions
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    /// gap between a quoted and a simulated swap output, in basis points of the simulated output,
+    /// above which the quote is recorded as a mismatch
+    pub mismatch_tolerance_bps: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            mismatch_tolerance_bps: 10,
+        }
+    }
+}
+
+/// Keypair and token accounts funding arbitrage transactions
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct WalletConfig {
+    pub keypair_path: String,
+    /// wSOL balance below which the wSOL account is topped up, in lamports
+    pub wsol_min_balance: u64,
+    /// wSOL balance a top-up brings the wSOL account back to, in lamports
+    pub wsol_target_balance: u64,
+    /// SOL kept out of wSOL top-ups to pay fees and rent, in lamports
+    pub sol_reserve: u64,
+    /// wrap SOL on every refresh once the wSOL balance drops below its minimum, otherwise only `setup` wraps
+    pub auto_top_up_wsol: bool,
+    pub refresh_interval_ms: u64,
+}
+
+impl Default for WalletConfig {
+    fn default() -> Self {
+        WalletConfig {
+            keypair_path: "./keypair.json".to_string(),
+            wsol_min_balance: 1_000_000_000,
+            wsol_target_balance: 5_000_000_000,
+            sol_reserve: 100_000_000,
+            auto_top_up_wsol: false,
+            refresh_interval_ms: 10_000,
+        }
+    }
+}
+
+/// Confirmation tracking of submitted transactions
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct TrackerConfig {
+    /// SQLite ledger the trades and their outcome are recorded in
+    pub ledger_path: String,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        TrackerConfig {
+            ledger_path: "./ledger.sqlite".to_string(),
+            poll_interval_ms: 1000,
+        }
+    }
+}
+
+/// Prometheus endpoint
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// address `/metrics` is served on
+    pub listen_address: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            listen_address: "127.0.0.1:9184".to_string(),
+        }
+    }
+}
+
+impl PoolFilter {
+    pub fn accept(&self, pool: &DeserializedPoolAccount) -> bool {
+        if self.tradable_only && !pool.operation.is_tradable() {
+            return false;
+        }
+
+        if self.executable_only && !pool.operation.has_swap_instructions() {
+            return false;
+        }
+
+        if let (Some(min_liquidity), Some(liquidity)) = (self.min_liquidity, pool.operation.get_liquidity()) {
+            if liquidity < min_liquidity {
+                return false;
+            }
+        }
+
+        if let Some(fee_rate) = pool.operation.get_fee_rate() {
+            if !self.fee_tiers.is_empty() && !self.fee_tiers.contains(&fee_rate) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl ArbitrageConfig {
+    pub fn get_base_mints(&self) -> Vec<Pubkey> {
+        self.base_mints.iter().map(|base_mint| base_mint.mint).collect()
+    }
+}
+
+impl BaseMintConfig {
+    /// Input sizes growing geometrically from `min_amount_in` up to the max input the inventory allows,
+    /// `available` being the amount of the mint the wallet holds
+    pub fn get_input_amounts(&self, steps: usize, available: u64) -> Vec<u64> {
+        let max_amount_in = self.max_amount_in.min(self.inventory).min(available);
+        if self.min_amount_in == 0 || max_amount_in < self.min_amount_in {
+            return Vec::new();
+        }
+        if steps < 2 || max_amount_in == self.min_amount_in {
+            return vec![self.min_amount_in];
+        }
+
+        let ratio = (max_amount_in as f64 / self.min_amount_in as f64).powf(1f64 / (steps - 1) as f64);
+        let mut amounts = (0..steps).map(|step| {
+            if step == steps - 1 {
+                max_amount_in
+            }
+            else {
+                ((self.min_amount_in as f64) * ratio.powi(step as i32)) as u64
+            }
+        }).collect::<Vec<u64>>();
+        amounts.dedup();
+
+        amounts
+    }
+}
+
+pub fn read_config<P: AsRef<Path>>(path: P) -> Result<ArbitrageConfig, Box<dyn Error>> {
+    let file = File::open(path)?;
+    let buffer_reader = BufReader::new(file);
+
+    let data: Config = serde_json::from_reader(buffer_reader)?;
+    let base_mints = data.base_mints.iter().map(|base_mint| {
+        Ok(BaseMintConfig {
+            symbol: base_mint.symbol.clone(),
+            mint: Pubkey::from_str(&base_mint.mint)?,
+            min_amount_in: base_mint.min_amount_in,
+            max_amount_in: base_mint.max_amount_in,
+            inventory: base_mint.inventory,
+            min_profit: base_mint.min_profit,
+        })
+    }).collect::<Result<Vec<BaseMintConfig>, Box<dyn Error>>>()?;
+    let lookup_tables = data.lookup_tables.iter().map(|lookup_table| {
+        Ok(Pubkey::from_str(lookup_table)?)
+    }).collect::<Result<Vec<Pubkey>, Box<dyn Error>>>()?;
+
+    Ok(ArbitrageConfig {
+        quote_mint: Pubkey::from_str(&data.quote_mint)?,
+        input_steps: data.input_steps,
+        quote_threads: data.quote_threads,
+        base_mints,
+        pool_filter: data.pool_filter,
+        jito: data.jito,
+        fee: data.fee,
+        simulation: data.simulation,
+        wallet: data.wallet,
+        lookup_tables,
+        tracker: data.tracker,
+        metrics: data.metrics,
+    })
+}
+
+#[derive(Deserialize, Debug)]
+struct Config {
+    quote_mint: String,
+    #[serde(default = "default_input_steps")]
+    input_steps: usize,
+    #[serde(default)]
+    quote_threads: usize,
+    base_mints: Vec<BaseMint>,
+    #[serde(default)]
+    pool_filter: PoolFilter,
+    #[serde(default)]
+    jito: JitoConfig,
+    #[serde(default)]
+    fee: FeeConfig,
+    #[serde(default)]
+    simulation: SimulationConfig,
+    #[serde(default)]
+    wallet: WalletConfig,
+    #[serde(default)]
+    lookup_tables: Vec<String>,
+    #[serde(default)]
+    tracker: TrackerConfig,
+    #[serde(default)]
+    metrics: MetricsConfig,
+}
+
+#[derive(Deserialize, Debug)]
+struct BaseMint {
+    symbol: String,
+    mint: String,
+    min_amount_in: u64,
+    max_amount_in: u64,
+    inventory: u64,
+    #[serde(default)]
+    min_profit: u64,
+}
+
+fn default_input_steps() -> usize {
+    4
+}
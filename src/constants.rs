@@ -1,8 +1,26 @@
-pub static RPC_URL: &str = "https://go.getblock.io/bd8eab2bbe6e448b84ca2ae3b282b819";
+// public endpoint, overridden by the `RPC_URL` environment variable
+pub static RPC_URL: &str = "https://api.mainnet-beta.solana.com";
+pub const RPC_URL_ENV: &str = "RPC_URL";
 pub static MAX_DEPTH: usize = 4;
+pub const EVENT_BUS_CAPACITY: usize = 256;
+
+// Jito
+pub const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
 
 pub static TOKEN_PROGRAM_PUBKEY: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
 pub static TOKEN_ACCOUNT_DATA_LEN: usize = 165;
+pub static ASSOCIATED_TOKEN_PROGRAM_PUBKEY: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+pub static TOKEN_2022_PROGRAM_PUBKEY: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+pub static NATIVE_MINT_PUBKEY: &str = "So11111111111111111111111111111111111111112";
 
 // Raydium
 pub const RAYDIUM_CLMM_PROGRAM_PUBKEY: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
@@ -13,11 +31,66 @@ pub const RAYDIUM_CLMM_AMM_CONFIG: usize = 117;
 pub const RAYDIUM_CLMM_OBSERVATION_KEY: usize = 4483;
 pub const RAYDIUM_CLMM_TICK_ARRAY_STATE: usize = 10240;
 pub const RAYDIUM_CLMM_TICK_ARRAY_BITMAP_EXTENSION: usize = 1832;
+pub const RAYDIUM_CPMM_PROGRAM_PUBKEY: &str = "CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C";
+pub const RAYDIUM_CPMM_DATA_LEN: usize = 637;
+pub const RAYDIUM_CPMM_AMM_CONFIG: usize = 236;
+pub const RAYDIUM_CPMM_OBSERVATION_STATE: usize = 4075;
+pub const RAYDIUM_CPMM_AUTH_SEED: &str = "vault_and_lp_mint_auth_seed";
 
 
 
 pub const ORCA_CLMM_TICK_ARRAY: usize = 9988;
 pub const ORCA_CLMM_WHIRLPOOL_CONFIG: usize = 108;
+// dynamic tick arrays store only initialized ticks, 112 bytes each over the 148 bytes of an empty array
+pub const ORCA_CLMM_DYNAMIC_TICK_ARRAY_MIN_LEN: usize = 148;
+pub const ORCA_CLMM_DYNAMIC_TICK_ARRAY_MAX_LEN: usize = 10004;
+pub const ORCA_CLMM_DYNAMIC_TICK_DATA: usize = 112;
+pub const ORCA_CLMM_ORACLE: usize = 254;
+pub const ORCA_CLMM_ORACLE_SEED: &str = "oracle";
+
+
+pub const METEORA_DLMM_PROGRAM_PUBKEY: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
+pub const METEORA_DLMM_BIN_ARRAY: usize = 10136;
+pub const METEORA_DLMM_BIN_ARRAY_BITMAP_EXTENSION: usize = 1576;
+pub const METEORA_DAMM_PROGRAM_PUBKEY: &str = "Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB";
+pub const METEORA_DAMM_DATA_LEN: usize = 944;
+pub const METEORA_VAULT_PROGRAM_PUBKEY: &str = "24Uqj9JCLxUeoC3hGfh5W3s9FM9uCHDS2SG3LYwBpyTi";
+pub const METEORA_VAULT_DATA_LEN: usize = 1227;
+pub const METEORA_DAMM_V2_PROGRAM_PUBKEY: &str = "cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG";
+pub const METEORA_DAMM_V2_DATA_LEN: usize = 1112;
+
+
+pub const SABER_PROGRAM_PUBKEY: &str = "SSwpkEEcbUqx4vtoEByFjSkhKdCT862DNVb52nZg1UZ";
+pub const SABER_SWAP_INFO_DATA_LEN: usize = 395;
+
+pub const SPL_STAKE_POOL_PROGRAM_PUBKEY: &str = "SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy";
+pub const MARINADE_PROGRAM_PUBKEY: &str = "MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD";
+pub const MARINADE_STATE_PUBKEY: &str = "8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC";
+pub const MARINADE_LIQ_POOL_SOL_LEG_SEED: &str = "liq_sol";
+
+pub const SANCTUM_INFINITY_PROGRAM_PUBKEY: &str = "5ocnV1qiCgaQR8Jb8xWnVbApfaygJ8tNoZfgPwsgx9kx";
+pub const SANCTUM_FLAT_FEE_PROGRAM_PUBKEY: &str = "f1tUoNEKrDp1oeGn4zxr7bh41eN6VcfHjfrL3ZqQday";
+pub const SANCTUM_WSOL_CALCULATOR_PUBKEY: &str = "wsoGmxQLSvwWpuaidCApxN5kEowLe2HLQLJhCQnj4bE";
+pub const SANCTUM_MARINADE_CALCULATOR_PUBKEY: &str = "mare3SCyfZkAndpBRBeonETmkCCB3TJTTrz8ZN2dnhP";
+// spl, sanctum spl and sanctum spl multi stake pool calculators
+pub const SANCTUM_SPL_CALCULATOR_PUBKEYS: [&str; 3] = [
+    "sp1V4h2gWorkGhVcazBc22Hfo2f5sd7jcjT4EDPrWFF",
+    "sspUE1vrh7xRoXxGsg7vR1zde2WdGtJRbyK9uRumBDy",
+    "ssmbu3KZxgonUtjEMCKspZzxvUQCxAFnyh1rcHUeEDo",
+];
+
+pub const PUMP_FUN_PROGRAM_PUBKEY: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+pub const PUMP_FUN_BONDING_CURVE_SEED: &str = "bonding-curve";
+pub const PUMP_FUN_GLOBAL_SEED: &str = "global";
+pub const PUMP_SWAP_PROGRAM_PUBKEY: &str = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
+pub const PUMP_SWAP_GLOBAL_CONFIG_SEED: &str = "global_config";
 
+pub const PHOENIX_PROGRAM_PUBKEY: &str = "PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY";
+pub const PHOENIX_MARKET_HEADER_LEN: usize = 576;
+pub const PHOENIX_LOG_AUTHORITY_SEED: &str = "log";
 
-pub const METEORA_DLMM_PROGRAM_PUBKEY: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
\ No newline at end of file
+pub const OPENBOOK_V1_PROGRAM_PUBKEY: &str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
+pub const OPENBOOK_V2_PROGRAM_PUBKEY: &str = "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb";
+pub const OPENBOOK_V2_MARKET_DATA_LEN: usize = 848;
+pub const OPENBOOK_V2_BOOK_SIDE_DATA_LEN: usize = 90952;
+pub const OPENBOOK_V2_EVENT_HEAP_DATA_LEN: usize = 91288;
\ No newline at end of file
@@ -0,0 +1,149 @@
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::arbitrageur::Opportunity;
+use crate::config::FeeConfig;
+use crate::formula::base::Formula;
+
+// compute units measured on mainnet swaps, rounded up
+pub const BASE_COMPUTE_UNITS: u32 = 10_000;
+pub const CLMM_SWAP_COMPUTE_UNITS: u32 = 60_000;
+pub const CLMM_TICK_CROSSING_COMPUTE_UNITS: u32 = 12_000;
+pub const DLMM_SWAP_COMPUTE_UNITS: u32 = 50_000;
+pub const DLMM_BIN_COMPUTE_UNITS: u32 = 4_000;
+pub const AMM_V4_SWAP_COMPUTE_UNITS: u32 = 40_000;
+pub const AMM_V4_CANCEL_ORDER_COMPUTE_UNITS: u32 = 5_000;
+pub const ORDER_BOOK_SWAP_COMPUTE_UNITS: u32 = 30_000;
+pub const ORDER_BOOK_FILL_COMPUTE_UNITS: u32 = 3_000;
+pub const STABLE_SWAP_COMPUTE_UNITS: u32 = 40_000;
+pub const STAKE_POOL_COMPUTE_UNITS: u32 = 50_000;
+pub const DEFAULT_SWAP_COMPUTE_UNITS: u32 = 80_000;
+pub const COMPUTE_UNIT_MARGIN_PERCENT: u32 = 20;
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+pub const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+/// Compute units of one swap, `crossings` as reported by `SwapQuote`
+pub fn estimate_hop_compute_units(formula: &Formula, crossings: u32) -> u32 {
+    match formula {
+        Formula::ConcentratedLiquidity => {
+            CLMM_SWAP_COMPUTE_UNITS + crossings * CLMM_TICK_CROSSING_COMPUTE_UNITS
+        }
+        Formula::DynamicLiquidity => {
+            DLMM_SWAP_COMPUTE_UNITS + crossings * DLMM_BIN_COMPUTE_UNITS
+        }
+        Formula::OpenBook => {
+            AMM_V4_SWAP_COMPUTE_UNITS + crossings * AMM_V4_CANCEL_ORDER_COMPUTE_UNITS
+        }
+        Formula::OrderBook => {
+            ORDER_BOOK_SWAP_COMPUTE_UNITS + crossings * ORDER_BOOK_FILL_COMPUTE_UNITS
+        }
+        Formula::StableSwap => {
+            // newton iterations for D and y, independent of the amount
+            STABLE_SWAP_COMPUTE_UNITS
+        }
+        Formula::StakePool => {
+            STAKE_POOL_COMPUTE_UNITS
+        }
+        _ => {
+            DEFAULT_SWAP_COMPUTE_UNITS
+        }
+    }
+}
+
+/// Compute unit limit of a transaction swapping through `hops`, with a safety margin
+pub fn estimate_compute_units(hops: &[(Formula, u32)]) -> u32 {
+    let units = BASE_COMPUTE_UNITS + hops.iter().map(|(formula, crossings)| {
+        estimate_hop_compute_units(formula, *crossings)
+    }).sum::<u32>();
+
+    (units + units * COMPUTE_UNIT_MARGIN_PERCENT / 100).min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ComputeBudget {
+    pub unit_limit: u32,
+    /// micro-lamports per compute unit
+    pub unit_price: u64,
+}
+
+impl ComputeBudget {
+    /// Priority fee in lamports
+    pub fn get_priority_fee(&self) -> u64 {
+        (self.unit_limit as u128 * self.unit_price as u128).div_ceil(MICRO_LAMPORTS_PER_LAMPORT) as u64
+    }
+
+    /// Instructions to put in front of the transaction
+    pub fn get_instructions(&self) -> Vec<Instruction> {
+        vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(self.unit_limit),
+            ComputeBudgetInstruction::set_compute_unit_price(self.unit_price),
+        ]
+    }
+}
+
+pub struct FeeEstimator {
+    rpc_client: RpcClient,
+    config: FeeConfig
+}
+
+impl FeeEstimator {
+    pub fn new(rpc_url: String, config: FeeConfig) -> FeeEstimator {
+        FeeEstimator {
+            rpc_client: RpcClient::new(rpc_url),
+            config
+        }
+    }
+
+    /// Compute budget of the transaction executing `opportunity`, its fees bounded by the profit in lamports.
+    /// Opportunities not priced in lamports get no priority fee
+    pub async fn get_opportunity_budget(
+        &self,
+        writable_pubkeys: &[Pubkey],
+        opportunity: &Opportunity,
+        signatures: u64
+    ) -> Result<ComputeBudget, ClientError> {
+        self.get_compute_budget(writable_pubkeys, opportunity.compute_units, opportunity.lamport_profit.unwrap_or(0), signatures).await
+    }
+
+    /// Prices `compute_units` from the recent prioritization fees of the accounts the transaction writes
+    pub async fn get_compute_budget(
+        &self,
+        writable_pubkeys: &[Pubkey],
+        compute_units: u32,
+        lamport_profit: u64,
+        signatures: u64
+    ) -> Result<ComputeBudget, ClientError> {
+        let fees = self.rpc_client.get_recent_prioritization_fees(writable_pubkeys).await?.iter().map(|fee| {
+            fee.prioritization_fee
+        }).collect::<Vec<u64>>();
+
+        Ok(ComputeBudget {
+            unit_limit: compute_units,
+            unit_price: self.get_unit_price(fees, compute_units, lamport_profit, signatures),
+        })
+    }
+
+    /// The configured percentile of `recent_fees`, lowered so the signature and priority fees
+    /// stay within the configured share of `lamport_profit`, the expected profit converted into lamports.
+    /// Returns 0 if the signature fees alone exceed that share
+    pub fn get_unit_price(&self, mut recent_fees: Vec<u64>, compute_units: u32, lamport_profit: u64, signatures: u64) -> u64 {
+        let unit_price = if recent_fees.is_empty() {
+            0
+        }
+        else {
+            recent_fees.sort_unstable();
+            let index = (recent_fees.len() - 1) * self.config.fee_percentile.min(100) as usize / 100;
+            recent_fees[index]
+        };
+
+        let fee_budget = ((lamport_profit as f64 * self.config.max_fee_fraction) as u64)
+            .saturating_sub(LAMPORTS_PER_SIGNATURE * signatures);
+        let max_unit_price = fee_budget as u128 * MICRO_LAMPORTS_PER_LAMPORT / compute_units.max(1) as u128;
+
+        unit_price.min(u64::try_from(max_unit_price).unwrap_or(u64::MAX))
+    }
+}
@@ -0,0 +1,191 @@
+use std::error::Error;
+use std::str::FromStr;
+use std::time::Duration;
+
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use tokio::time::{Instant, sleep};
+
+use crate::config::JitoConfig;
+use crate::constants::JITO_TIP_ACCOUNTS;
+use crate::executor::fee::LAMPORTS_PER_SIGNATURE;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BundleStatus {
+    /// not seen by the block engine yet, or not confirmed
+    Pending,
+    Landed {
+        slot: u64
+    },
+    Failed(String)
+}
+
+/// Submits arbitrage transactions as Jito bundles through the block engine JSON-RPC
+pub struct JitoClient {
+    client: reqwest::Client,
+    config: JitoConfig
+}
+
+impl JitoClient {
+    pub fn new(config: JitoConfig) -> JitoClient {
+        JitoClient {
+            client: reqwest::Client::new(),
+            config
+        }
+    }
+
+    /// Tip for an expected profit of `lamport_profit`, bounded by the configured min and max tip and by the profit left
+    /// after `fees`, the signature and priority fees in lamports. `None` if what is left does not cover the min tip.
+    /// The profit must be converted into lamports first, see `Opportunity::lamport_profit`
+    pub fn get_tip(&self, lamport_profit: u64, fees: u64) -> Option<u64> {
+        let left = lamport_profit.saturating_sub(fees);
+        if left < self.config.min_tip_lamports {
+            return None;
+        }
+
+        let tip = (lamport_profit as f64 * self.config.tip_fraction) as u64;
+        Some(tip.min(self.config.max_tip_lamports).max(self.config.min_tip_lamports).min(left))
+    }
+
+    pub fn get_tip_account() -> Pubkey {
+        let tip_account = JITO_TIP_ACCOUNTS.choose(&mut rand::thread_rng()).unwrap();
+        Pubkey::from_str(tip_account).unwrap()
+    }
+
+    /// Transfer of `tip` lamports to a random tip account
+    pub fn get_tip_instruction(payer: &Pubkey, tip: u64) -> Instruction {
+        system_instruction::transfer(payer, &Self::get_tip_account(), tip)
+    }
+
+    /// Signs `instructions` followed by the tip transfer into one transaction, `None` if the profit does not cover
+    /// the tip and the signature fee. The tip lives in the arbitrage transaction, so it is only paid if the arbitrage lands
+    pub fn build_transaction(
+        &self,
+        payer: &Keypair,
+        instructions: &[Instruction],
+        lamport_profit: u64,
+        recent_blockhash: Hash
+    ) -> Option<Transaction> {
+        let tip = self.get_tip(lamport_profit, LAMPORTS_PER_SIGNATURE)?;
+        let mut instructions = instructions.to_vec();
+        instructions.push(Self::get_tip_instruction(&payer.pubkey(), tip));
+
+        Some(Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash
+        ))
+    }
+
+    /// Returns the bundle id
+    pub async fn send_bundle(&self, transactions: &[Transaction]) -> Result<String, Box<dyn Error + Send + Sync>> {
+        self.send_encoded_bundle(Self::encode(transactions)?).await
+    }
+
+    /// `send_bundle` of v0 transactions, those compiled with lookup tables
+    pub async fn send_versioned_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String, Box<dyn Error + Send + Sync>> {
+        self.send_encoded_bundle(Self::encode(transactions)?).await
+    }
+
+    fn encode<T: Serialize>(transactions: &[T]) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        transactions.iter().map(|transaction| {
+            Ok(BASE64_STANDARD.encode(bincode::serialize(transaction)?))
+        }).collect()
+    }
+
+    async fn send_encoded_bundle(&self, encoded: Vec<String>) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let result = self.request("sendBundle", json!([encoded, { "encoding": "base64" }])).await?;
+        result.as_str().map(|bundle_id| bundle_id.to_string()).ok_or("sendBundle: result is not a bundle id".into())
+    }
+
+    /// Returns the status of every bundle, in the order of `bundle_ids`
+    pub async fn get_bundle_statuses(&self, bundle_ids: &[String]) -> Result<Vec<BundleStatus>, Box<dyn Error + Send + Sync>> {
+        let result = self.request("getBundleStatuses", json!([bundle_ids])).await?;
+        let statuses: BundleStatuses = serde_json::from_value(result)?;
+
+        Ok(bundle_ids.iter().map(|bundle_id| {
+            statuses.value.iter().flatten().find(|status| {
+                status.bundle_id == *bundle_id
+            }).map(|status| status.to_bundle_status()).unwrap_or(BundleStatus::Pending)
+        }).collect())
+    }
+
+    /// Polls the status of `bundle_id` until it lands or fails.
+    /// Returns `BundleStatus::Pending` if it did neither before the configured timeout
+    pub async fn confirm_bundle(&self, bundle_id: &str) -> Result<BundleStatus, Box<dyn Error + Send + Sync>> {
+        let bundle_ids = [bundle_id.to_string()];
+        let timeout = Duration::from_millis(self.config.status_timeout_ms);
+        let time = Instant::now();
+
+        loop {
+            let status = self.get_bundle_statuses(&bundle_ids).await?.remove(0);
+            if status != BundleStatus::Pending || time.elapsed() >= timeout {
+                return Ok(status);
+            }
+
+            sleep(Duration::from_millis(self.config.status_poll_interval_ms)).await;
+        }
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let response = self.client.post(format!("{}/api/v1/bundles", self.config.block_engine_url))
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params
+            }))
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            return Err(format!("{}: {}", method, error).into());
+        }
+
+        response.get("result").cloned().ok_or(format!("{}: missing result", method).into())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct BundleStatuses {
+    value: Vec<Option<BundleStatusValue>>
+}
+
+#[derive(Deserialize, Debug)]
+struct BundleStatusValue {
+    bundle_id: String,
+    slot: u64,
+    confirmation_status: Option<String>,
+    #[serde(default)]
+    err: Value
+}
+
+impl BundleStatusValue {
+    fn to_bundle_status(&self) -> BundleStatus {
+        // err is `{"Ok": null}` when every transaction succeeded
+        if self.err.get("Ok").is_none() && !self.err.is_null() {
+            return BundleStatus::Failed(self.err.to_string());
+        }
+
+        match self.confirmation_status.as_deref() {
+            Some("confirmed") | Some("finalized") => {
+                BundleStatus::Landed {
+                    slot: self.slot
+                }
+            }
+            _ => BundleStatus::Pending
+        }
+    }
+}
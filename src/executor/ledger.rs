@@ -0,0 +1,212 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use solana_sdk::signature::Signature;
+
+use crate::arbitrageur::Opportunity;
+use crate::executor::tracker::{TradeResult, TxStatus};
+
+// amounts are stored as sqlite integers, i64 holds any amount a trade moves
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS trades (
+        signature TEXT PRIMARY KEY,
+        base_mint TEXT NOT NULL,
+        path TEXT NOT NULL,
+        amount_in INTEGER NOT NULL,
+        quoted_amount_out INTEGER NOT NULL,
+        expected_profit INTEGER NOT NULL,
+        tip INTEGER NOT NULL,
+        sent_at INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        slot INTEGER,
+        landed_at INTEGER,
+        error TEXT,
+        fee INTEGER,
+        realized_profit INTEGER,
+        settled_at INTEGER
+    );
+    CREATE INDEX IF NOT EXISTS trades_base_mint ON trades (base_mint);
+";
+
+/// A submitted arbitrage transaction as recorded in the ledger
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Trade {
+    pub signature: String,
+    pub base_mint: String,
+    /// pool pubkeys in swap order, comma separated
+    pub path: String,
+    pub amount_in: u64,
+    pub quoted_amount_out: u64,
+    pub expected_profit: u64,
+    pub tip: u64,
+    /// unix time in milliseconds
+    pub sent_at: u64,
+    pub status: String,
+    pub slot: Option<u64>,
+    pub landed_at: Option<u64>,
+    pub error: Option<String>,
+    pub fee: Option<u64>,
+    pub realized_profit: Option<i64>,
+    /// unix time in milliseconds the final status was handled
+    pub settled_at: Option<u64>,
+}
+
+/// Trades of one base mint, summed
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReportRow {
+    pub base_mint: String,
+    pub sent: u64,
+    pub landed: u64,
+    pub failed: u64,
+    pub expired: u64,
+    pub expected_profit: u64,
+    pub realized_profit: i64,
+    /// lamports
+    pub fees: u64,
+    /// lamports
+    pub tips: u64,
+}
+
+/// SQLite ledger of submitted trades and their outcome
+pub struct Ledger {
+    connection: Mutex<Connection>
+}
+
+impl Ledger {
+    pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Ledger> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Ledger> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> rusqlite::Result<Ledger> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Ledger {
+            connection: Mutex::new(connection)
+        })
+    }
+
+    pub fn record_sent(&self, signature: &Signature, opportunity: &Opportunity, tip: u64, sent_at: u64) -> rusqlite::Result<()> {
+        let path = opportunity.path.iter().map(|pool| pool.to_string()).collect::<Vec<String>>().join(",");
+        self.connection.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO trades (signature, base_mint, path, amount_in, quoted_amount_out, expected_profit, tip, sent_at, status)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                signature.to_string(),
+                opportunity.base_mint.to_string(),
+                path,
+                opportunity.amount_in as i64,
+                opportunity.amount_out as i64,
+                opportunity.profit as i64,
+                tip as i64,
+                sent_at as i64,
+                TxStatus::Sent.as_str()
+            ]
+        )?;
+
+        Ok(())
+    }
+
+    /// Records the latest status of a trade, `at` being when it was observed.
+    /// The first status with a slot sets when the trade landed
+    pub fn record_status(&self, signature: &Signature, status: &TxStatus, at: u64) -> rusqlite::Result<()> {
+        let error = match status {
+            TxStatus::Failed { reason, .. } => Some(reason.clone()),
+            TxStatus::Expired => Some("blockhash expired".to_string()),
+            _ => None
+        };
+        let landed_at = status.get_slot().map(|_| at as i64);
+
+        self.connection.lock().unwrap().execute(
+            "UPDATE trades SET status = ?2, slot = COALESCE(?3, slot), landed_at = COALESCE(landed_at, ?4), error = ?5
+             WHERE signature = ?1",
+            params![signature.to_string(), status.as_str(), status.get_slot().map(|slot| slot as i64), landed_at, error]
+        )?;
+
+        Ok(())
+    }
+
+    /// Marks a trade settled, returns false when it already was or is not recorded.
+    /// A trade settles once, whichever final status is seen first
+    pub fn mark_settled(&self, signature: &Signature, at: u64) -> rusqlite::Result<bool> {
+        let updated = self.connection.lock().unwrap().execute(
+            "UPDATE trades SET settled_at = ?2 WHERE signature = ?1 AND settled_at IS NULL",
+            params![signature.to_string(), at as i64]
+        )?;
+
+        Ok(updated == 1)
+    }
+
+    pub fn record_result(&self, signature: &Signature, result: &TradeResult) -> rusqlite::Result<()> {
+        self.connection.lock().unwrap().execute(
+            "UPDATE trades SET fee = ?2, realized_profit = ?3 WHERE signature = ?1",
+            params![signature.to_string(), result.fee as i64, result.realized_profit as i64]
+        )?;
+
+        Ok(())
+    }
+
+    pub fn get_trade(&self, signature: &Signature) -> rusqlite::Result<Option<Trade>> {
+        self.connection.lock().unwrap().query_row(
+            "SELECT signature, base_mint, path, amount_in, quoted_amount_out, expected_profit, tip, sent_at, status, slot, landed_at, error, fee, realized_profit, settled_at
+             FROM trades WHERE signature = ?1",
+            params![signature.to_string()],
+            |row| {
+                Ok(Trade {
+                    signature: row.get(0)?,
+                    base_mint: row.get(1)?,
+                    path: row.get(2)?,
+                    amount_in: row.get::<_, i64>(3)? as u64,
+                    quoted_amount_out: row.get::<_, i64>(4)? as u64,
+                    expected_profit: row.get::<_, i64>(5)? as u64,
+                    tip: row.get::<_, i64>(6)? as u64,
+                    sent_at: row.get::<_, i64>(7)? as u64,
+                    status: row.get(8)?,
+                    slot: row.get::<_, Option<i64>>(9)?.map(|slot| slot as u64),
+                    landed_at: row.get::<_, Option<i64>>(10)?.map(|landed_at| landed_at as u64),
+                    error: row.get(11)?,
+                    fee: row.get::<_, Option<i64>>(12)?.map(|fee| fee as u64),
+                    realized_profit: row.get(13)?,
+                    settled_at: row.get::<_, Option<i64>>(14)?.map(|settled_at| settled_at as u64),
+                })
+            }
+        ).optional()
+    }
+
+    /// Trades summed per base mint, sent since `since` (unix time in milliseconds)
+    pub fn get_report(&self, since: u64) -> rusqlite::Result<Vec<ReportRow>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT base_mint,
+                    COUNT(*),
+                    SUM(CASE WHEN status IN ('processed', 'confirmed', 'finalized') THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN status = 'failed' THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN status = 'expired' THEN 1 ELSE 0 END),
+                    SUM(expected_profit),
+                    COALESCE(SUM(realized_profit), 0),
+                    COALESCE(SUM(fee), 0),
+                    SUM(CASE WHEN status IN ('processed', 'confirmed', 'finalized') THEN tip ELSE 0 END)
+             FROM trades WHERE sent_at >= ?1
+             GROUP BY base_mint ORDER BY base_mint"
+        )?;
+
+        let rows = statement.query_map(params![since as i64], |row| {
+            Ok(ReportRow {
+                base_mint: row.get(0)?,
+                sent: row.get::<_, i64>(1)? as u64,
+                landed: row.get::<_, i64>(2)? as u64,
+                failed: row.get::<_, i64>(3)? as u64,
+                expired: row.get::<_, i64>(4)? as u64,
+                expected_profit: row.get::<_, i64>(5)? as u64,
+                realized_profit: row.get(6)?,
+                fees: row.get::<_, i64>(7)? as u64,
+                tips: row.get::<_, i64>(8)? as u64,
+            })
+        })?;
+
+        rows.collect()
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::RwLock;
+
+use log::debug;
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::address_lookup_table::instruction::{create_lookup_table, extend_lookup_table};
+use solana_sdk::address_lookup_table::state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES};
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, CompileError, VersionedMessage};
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::signer::SignerError;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+use crate::arbitrageur::Opportunity;
+use crate::r#struct::account::DeserializedPoolAccount;
+
+// addresses per extend instruction, keeping the extend transaction within a packet
+const MAX_ADDRESSES_PER_EXTEND: usize = 20;
+
+/// Pool account, program and static accounts of `pool`, the accounts worth holding in a lookup table
+pub fn get_pool_static_pubkeys(pool: &DeserializedPoolAccount) -> Vec<Pubkey> {
+    let mut pubkeys = vec![pool.pubkey, pool.account.owner];
+    pool.operation.get_static_pubkeys().into_iter().for_each(|pubkey| {
+        if pubkey != Pubkey::default() && !pubkeys.contains(&pubkey) {
+            pubkeys.push(pubkey);
+        }
+    });
+
+    pubkeys
+}
+
+#[derive(Debug)]
+pub enum BuildError {
+    Compile(CompileError),
+    Sign(SignerError),
+    Serialize(bincode::Error),
+    /// the signed transaction does not fit in a packet
+    TooLarge {
+        size: usize,
+        limit: usize
+    }
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::Compile(err) => {
+                write!(f, "failed to compile message: {}", err)
+            }
+            BuildError::Sign(err) => {
+                write!(f, "failed to sign transaction: {}", err)
+            }
+            BuildError::Serialize(err) => {
+                write!(f, "failed to serialize transaction: {}", err)
+            }
+            BuildError::TooLarge { size, limit } => {
+                write!(f, "transaction is {} bytes, over the {} bytes limit", size, limit)
+            }
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+/// Address lookup tables owned by the wallet, used to compile v0 messages
+pub struct LookupTableManager {
+    rpc_client: RpcClient,
+    tables: RwLock<Vec<AddressLookupTableAccount>>
+}
+
+impl LookupTableManager {
+    pub fn new(rpc_url: String) -> LookupTableManager {
+        LookupTableManager {
+            rpc_client: RpcClient::new(rpc_url),
+            tables: RwLock::new(Vec::new())
+        }
+    }
+
+    /// Fetches `table_pubkeys` and keeps the addresses each holds.
+    /// Tables that are missing or fail to deserialize are left out
+    pub async fn load(&self, table_pubkeys: &[Pubkey]) -> Result<(), ClientError> {
+        let accounts = self.rpc_client.get_multiple_accounts(table_pubkeys).await?;
+        let loaded = table_pubkeys.iter().zip(accounts.iter()).filter_map(|(pubkey, account)| {
+            let table = AddressLookupTable::deserialize(&account.as_ref()?.data).ok();
+            if table.is_none() {
+                debug!("lookup_table: {} is not a lookup table", pubkey);
+            }
+
+            Some(AddressLookupTableAccount {
+                key: *pubkey,
+                addresses: table?.addresses.to_vec()
+            })
+        }).collect::<Vec<AddressLookupTableAccount>>();
+
+        loaded.into_iter().for_each(|table| self.insert_table(table));
+        Ok(())
+    }
+
+    /// Adds `table` or replaces the table with the same key
+    pub fn insert_table(&self, table: AddressLookupTableAccount) {
+        let mut tables = self.tables.write().unwrap();
+        match tables.iter_mut().find(|loaded| loaded.key == table.key) {
+            Some(loaded) => *loaded = table,
+            None => tables.push(table)
+        }
+    }
+
+    pub fn get_tables(&self) -> Vec<AddressLookupTableAccount> {
+        self.tables.read().unwrap().clone()
+    }
+
+    /// `pubkeys` held by none of the tables, in order and without duplicates
+    pub fn get_missing(&self, pubkeys: &[Pubkey]) -> Vec<Pubkey> {
+        let tables = self.tables.read().unwrap();
+        let held = tables.iter().flat_map(|table| table.addresses.iter()).collect::<HashSet<&Pubkey>>();
+
+        let mut seen: HashSet<Pubkey> = HashSet::new();
+        pubkeys.iter().filter(|pubkey| !held.contains(pubkey) && seen.insert(**pubkey)).copied().collect()
+    }
+
+    /// Adds the `pubkeys` no table holds yet, extending the last table until it is full and creating new tables after.
+    /// Returns the tables created. Added addresses can be looked up from the slot after their extension
+    pub async fn extend(&self, authority: &Keypair, pubkeys: &[Pubkey]) -> Result<Vec<Pubkey>, ClientError> {
+        let mut missing = self.get_missing(pubkeys);
+        let mut created: Vec<Pubkey> = Vec::new();
+
+        while !missing.is_empty() {
+            let last_table = self.tables.read().unwrap().last().cloned();
+            let mut table = match last_table {
+                Some(table) if table.addresses.len() < LOOKUP_TABLE_MAX_ADDRESSES => table,
+                _ => {
+                    let table = self.create(authority).await?;
+                    created.push(table.key);
+                    table
+                }
+            };
+
+            let room = (LOOKUP_TABLE_MAX_ADDRESSES - table.addresses.len()).min(MAX_ADDRESSES_PER_EXTEND);
+            let addresses = missing.drain(..room.min(missing.len())).collect::<Vec<Pubkey>>();
+            let instruction = extend_lookup_table(table.key, authority.pubkey(), Some(authority.pubkey()), addresses.clone());
+            let signature = self.send(authority, &[instruction]).await?;
+            debug!("lookup_table: added {} address(es) to {} ({})", addresses.len(), table.key, signature);
+
+            table.addresses.extend(addresses);
+            self.insert_table(table);
+        }
+
+        Ok(created)
+    }
+
+    /// Compiles `instructions` into a v0 message looking accounts up from every table
+    pub fn compile(
+        &self,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+        recent_blockhash: Hash
+    ) -> Result<VersionedMessage, BuildError> {
+        let tables = self.tables.read().unwrap();
+        let message = v0::Message::try_compile(payer, instructions, tables.as_slice(), recent_blockhash).map_err(BuildError::Compile)?;
+
+        Ok(VersionedMessage::V0(message))
+    }
+
+    /// Signs a v0 transaction of `instructions`, failing if it does not fit in a packet
+    pub fn build_transaction(
+        &self,
+        payer: &Keypair,
+        instructions: &[Instruction],
+        recent_blockhash: Hash
+    ) -> Result<VersionedTransaction, BuildError> {
+        let message = self.compile(&payer.pubkey(), instructions, recent_blockhash)?;
+        let transaction = VersionedTransaction::try_new(message, &[payer]).map_err(BuildError::Sign)?;
+
+        let size = bincode::serialize(&transaction).map_err(BuildError::Serialize)?.len();
+        if size > PACKET_DATA_SIZE {
+            return Err(BuildError::TooLarge {
+                size,
+                limit: PACKET_DATA_SIZE
+            });
+        }
+
+        Ok(transaction)
+    }
+
+    /// Builds the transaction of the first candidate that fits, candidates being an opportunity with its instructions
+    /// ordered by preference. Once a route is too large only routes with fewer hops are tried,
+    /// any other error is returned as is. Returns the index of the candidate built, `None` if none fits
+    pub fn build_with_fallback(
+        &self,
+        payer: &Keypair,
+        candidates: &[(&Opportunity, Vec<Instruction>)],
+        recent_blockhash: Hash
+    ) -> Result<Option<(usize, VersionedTransaction)>, BuildError> {
+        let mut max_hops = usize::MAX;
+
+        for (index, (opportunity, instructions)) in candidates.iter().enumerate() {
+            let hops = opportunity.path.len();
+            if hops >= max_hops {
+                continue;
+            }
+
+            match self.build_transaction(payer, instructions, recent_blockhash) {
+                Ok(transaction) => return Ok(Some((index, transaction))),
+                Err(err @ BuildError::TooLarge { .. }) => {
+                    debug!("lookup_table: {} hop route does not fit: {}", hops, err);
+                    max_hops = hops;
+                }
+                Err(err) => return Err(err)
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn create(&self, authority: &Keypair) -> Result<AddressLookupTableAccount, ClientError> {
+        // the derivation slot has to be in the slot hashes, a finalized slot always is
+        let recent_slot = self.rpc_client.get_slot_with_commitment(CommitmentConfig::finalized()).await?;
+        let (instruction, table) = create_lookup_table(authority.pubkey(), authority.pubkey(), recent_slot);
+        let signature = self.send(authority, &[instruction]).await?;
+        debug!("lookup_table: created {} ({})", table, signature);
+
+        Ok(AddressLookupTableAccount {
+            key: table,
+            addresses: Vec::new()
+        })
+    }
+
+    async fn send(&self, payer: &Keypair, instructions: &[Instruction]) -> Result<Signature, ClientError> {
+        let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash
+        );
+
+        self.rpc_client.send_and_confirm_transaction(&transaction).await
+    }
+}
@@ -0,0 +1,9 @@
+pub mod jito;
+pub mod fee;
+pub mod ledger;
+pub mod lookup_table;
+pub mod simulator;
+pub mod submitter;
+pub mod tracker;
+pub mod wallet;
+mod test;
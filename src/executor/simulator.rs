@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use arrayref::{array_ref, array_refs};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use log::{debug, warn};
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::SerializableTransaction;
+use solana_client::rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+
+use crate::arbitrageur::Opportunity;
+use crate::config::SimulationConfig;
+use crate::constants::{NATIVE_MINT_PUBKEY, TOKEN_ACCOUNT_DATA_LEN};
+use crate::metrics::registry::Metrics;
+use crate::r#struct::account::AccountDataSerializer;
+use crate::r#struct::market::Market;
+use crate::r#struct::token::TokenAccount;
+use crate::utils::get_discriminator;
+
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+const RAY_LOG_PREFIX: &str = "Program log: ray_log: ";
+
+// raydium amm v4 ray_log types
+const RAY_LOG_SWAP_BASE_IN: u8 = 3;
+const RAY_LOG_SWAP_BASE_OUT: u8 = 4;
+
+/// A swap as executed by the simulation, read from the event its program logged
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SimulatedSwap {
+    /// `None` if the event does not name the pool
+    pub pool: Option<Pubkey>,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct SimulationResult {
+    /// slot the transaction was simulated at
+    pub slot: u64,
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    /// swaps in execution order
+    pub swaps: Vec<SimulatedSwap>,
+    /// token amount of every requested token account after the transaction, `None` if it is not a token account
+    pub post_balances: Vec<Option<u64>>,
+}
+
+#[derive(Debug)]
+pub enum PreflightError {
+    Rpc(ClientError),
+    Failed {
+        err: TransactionError,
+        logs: Vec<String>
+    },
+    MissingBalance,
+    BelowThreshold {
+        simulated_profit: i128,
+        min_profit: u64
+    }
+}
+
+impl Display for PreflightError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PreflightError::Rpc(err) => {
+                write!(f, "simulation request failed: {}", err)
+            }
+            PreflightError::Failed { err, .. } => {
+                write!(f, "simulation failed: {}", err)
+            }
+            PreflightError::MissingBalance => {
+                write!(f, "simulation returned no balance for the base token account")
+            }
+            PreflightError::BelowThreshold { simulated_profit, min_profit } => {
+                write!(f, "simulated profit {} is below {}", simulated_profit, min_profit)
+            }
+        }
+    }
+}
+
+impl Error for PreflightError {}
+
+/// Quote accuracy of a pool, compared against simulations
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MismatchStats {
+    pub compared: u64,
+    pub mismatches: u64,
+    pub max_diff_bps: u64,
+    /// quoted and simulated output of the last mismatch
+    pub last_mismatch: Option<(u64, u64)>,
+}
+
+/// Records quote-vs-simulation mismatches per market and pool
+#[derive(Default)]
+pub struct MismatchRecorder {
+    stats: Mutex<HashMap<(Market, Pubkey), MismatchStats>>
+}
+
+impl MismatchRecorder {
+    /// Returns true if the gap is over `tolerance_bps` of the simulated output
+    pub fn record(&self, market: Market, pool: Pubkey, quoted: u64, simulated: u64, tolerance_bps: u64) -> bool {
+        let diff_bps = get_diff_bps(quoted, simulated);
+        let mismatch = diff_bps > tolerance_bps;
+
+        let mut stats = self.stats.lock().unwrap();
+        let pool_stats = stats.entry((market, pool)).or_default();
+        pool_stats.compared += 1;
+        pool_stats.max_diff_bps = pool_stats.max_diff_bps.max(diff_bps);
+        if mismatch {
+            pool_stats.mismatches += 1;
+            pool_stats.last_mismatch = Some((quoted, simulated));
+            warn!("simulator: {:?} pool {} quoted {} but simulated {} ({} bps)", market, pool, quoted, simulated, diff_bps);
+        }
+
+        mismatch
+    }
+
+    /// Pools with the most mismatches first
+    pub fn get_stats(&self) -> Vec<(Market, Pubkey, MismatchStats)> {
+        let mut stats = self.stats.lock().unwrap().iter().map(|((market, pool), stats)| {
+            (*market, *pool, stats.clone())
+        }).collect::<Vec<(Market, Pubkey, MismatchStats)>>();
+        stats.sort_by(|a, b| b.2.mismatches.cmp(&a.2.mismatches).then(b.2.max_diff_bps.cmp(&a.2.max_diff_bps)));
+
+        stats
+    }
+}
+
+pub struct Simulator {
+    rpc_client: RpcClient,
+    config: SimulationConfig,
+    pub recorder: MismatchRecorder,
+    metrics: Arc<Metrics>
+}
+
+impl Simulator {
+    pub fn new(rpc_url: String, config: SimulationConfig, metrics: Arc<Metrics>) -> Simulator {
+        Simulator {
+            rpc_client: RpcClient::new(rpc_url),
+            config,
+            recorder: MismatchRecorder::default(),
+            metrics
+        }
+    }
+
+    /// Simulates `transaction` at `min_context_slot` or later and returns the post balances of `token_accounts`
+    pub async fn simulate<T: SerializableTransaction>(
+        &self,
+        transaction: &T,
+        token_accounts: &[Pubkey],
+        min_context_slot: Option<u64>
+    ) -> Result<SimulationResult, ClientError> {
+        let response = self.rpc_client.simulate_transaction_with_config(transaction, RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(CommitmentConfig::confirmed()),
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                // base64 is the default encoding
+                encoding: None,
+                addresses: token_accounts.iter().map(|pubkey| pubkey.to_string()).collect(),
+            }),
+            min_context_slot,
+            ..RpcSimulateTransactionConfig::default()
+        }).await?;
+        let (slot, response) = (response.context.slot, response.value);
+
+        let logs = response.logs.unwrap_or_default();
+        let post_balances = match response.accounts {
+            Some(accounts) => {
+                accounts.iter().map(|account| {
+                    get_token_amount(&account.as_ref()?.decode::<Account>()?)
+                }).collect::<Vec<Option<u64>>>()
+            }
+            None => vec![None; token_accounts.len()]
+        };
+
+        Ok(SimulationResult {
+            slot,
+            err: response.err,
+            swaps: parse_swaps(&logs),
+            logs,
+            units_consumed: response.units_consumed,
+            post_balances,
+        })
+    }
+
+    /// Token amounts of `token_accounts` at the latest confirmed slot, with that slot.
+    /// `None` for accounts that do not exist or are not token accounts
+    pub async fn get_token_balances(&self, token_accounts: &[Pubkey]) -> Result<(u64, Vec<Option<u64>>), ClientError> {
+        let response = self.rpc_client.get_multiple_accounts_with_commitment(token_accounts, CommitmentConfig::confirmed()).await?;
+
+        Ok((
+            response.context.slot,
+            response.value.iter().map(|account| get_token_amount(account.as_ref()?)).collect()
+        ))
+    }
+
+    /// Simulates the transaction of `opportunity`, records how every swap compares with its quote,
+    /// and fails if the base token account gains less than `min_profit`.
+    /// `fees` are the lamports the transaction pays besides its swaps, tip included, taken out of the profit
+    /// when the base mint is SOL.
+    /// The base token account and `hop_vaults`, the output vault of every hop if known, are read right before the simulation,
+    /// which runs on that state or a later one. The output of a hop whose program logs no swap event is read from
+    /// its vault, only if the simulation ran at the slot the vaults were read at
+    pub async fn preflight<T: SerializableTransaction>(
+        &self,
+        transaction: &T,
+        opportunity: &Opportunity,
+        base_token_account: &Pubkey,
+        min_profit: u64,
+        fees: u64,
+        hop_vaults: &[Option<Pubkey>]
+    ) -> Result<SimulationResult, PreflightError> {
+        let mut token_accounts = vec![*base_token_account];
+        token_accounts.extend(hop_vaults.iter().flatten());
+
+        let (slot, pre_balances) = self.get_token_balances(&token_accounts).await.map_err(PreflightError::Rpc)?;
+        let pre_balance = pre_balances.first().copied().flatten().ok_or(PreflightError::MissingBalance)?;
+
+        let result = self.simulate(transaction, &token_accounts, Some(slot)).await.map_err(PreflightError::Rpc)?;
+        if let Some(err) = result.err.clone() {
+            return Err(PreflightError::Failed {
+                err,
+                logs: result.logs
+            });
+        }
+
+        // vaults pay the hop output out, their balance drops by it.
+        // Other transactions move them as well, so a simulation at a later slot leaves them out
+        let vault_outputs = if result.slot == slot {
+            let mut balances = pre_balances.iter().zip(result.post_balances.iter()).skip(1);
+            hop_vaults.iter().map(|vault| {
+                vault.as_ref()?;
+                let (pre_balance, post_balance) = balances.next()?;
+                Some((*pre_balance)?.saturating_sub((*post_balance)?))
+            }).collect::<Vec<Option<u64>>>()
+        }
+        else {
+            debug!("simulator: simulated at slot {} after the balances of slot {}, vault outputs are not compared", result.slot, slot);
+            Vec::new()
+        };
+        self.compare(opportunity, &result.swaps, &vault_outputs);
+
+        let post_balance = result.post_balances.first().copied().flatten().ok_or(PreflightError::MissingBalance)?;
+        // the fees are paid in SOL
+        let fees = if opportunity.base_mint == Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap() { fees } else { 0 };
+        let simulated_profit = post_balance as i128 - pre_balance as i128 - fees as i128;
+        if simulated_profit < min_profit as i128 {
+            return Err(PreflightError::BelowThreshold {
+                simulated_profit,
+                min_profit
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// Records the quoted output of every hop against its simulated output.
+    /// A hop takes the swap event naming its pool, a pool swapped twice taking its events in order.
+    /// Hops without such an event fall back to `vault_outputs`, hops with neither are not recorded
+    pub fn compare(&self, opportunity: &Opportunity, swaps: &[SimulatedSwap], vault_outputs: &[Option<u64>]) {
+        let mut matched = vec![false; swaps.len()];
+
+        opportunity.path.iter().zip(opportunity.markets.iter()).zip(opportunity.quotes.iter()).enumerate()
+            .for_each(|(index, ((pool, market), quote))| {
+                let event = (0..swaps.len()).find(|swap_index| !matched[*swap_index] && swaps[*swap_index].pool == Some(*pool));
+                let simulated = match event {
+                    Some(swap_index) => {
+                        matched[swap_index] = true;
+                        swaps[swap_index].amount_out
+                    }
+                    None => match vault_outputs.get(index).copied().flatten() {
+                        Some(amount_out) => amount_out,
+                        None => {
+                            debug!("simulator: no simulated output for {:?} pool {}", market, pool);
+                            return;
+                        }
+                    }
+                };
+
+                self.metrics.observe_quote_error(market, get_diff_bps(quote.amount_out, simulated));
+                self.recorder.record(*market, *pool, quote.amount_out, simulated, self.config.mismatch_tolerance_bps);
+            });
+    }
+}
+
+fn get_token_amount(account: &Account) -> Option<u64> {
+    if account.data.len() < TOKEN_ACCOUNT_DATA_LEN {
+        return None;
+    }
+
+    Some(TokenAccount::unpack_data(&account.data).amount)
+}
+
+/// Gap between `quoted` and `simulated`, in basis points of `simulated`
+pub fn get_diff_bps(quoted: u64, simulated: u64) -> u64 {
+    (quoted.abs_diff(simulated) as u128 * 10_000 / simulated.max(1) as u128) as u64
+}
+
+/// Reads the swaps a transaction executed from the events in its logs, in execution order.
+/// Understands the raydium clmm `SwapEvent`, the orca whirlpool `Traded` event and the raydium amm v4 `ray_log`
+pub fn parse_swaps(logs: &[String]) -> Vec<SimulatedSwap> {
+    let raydium_swap_event = get_discriminator("event", "SwapEvent");
+    let orca_traded_event = get_discriminator("event", "Traded");
+
+    logs.iter().filter_map(|log| {
+        if let Some(data) = log.strip_prefix(PROGRAM_DATA_PREFIX) {
+            let data = BASE64_STANDARD.decode(data).ok()?;
+            if data.len() < 8 {
+                return None;
+            }
+
+            if data[..8] == raydium_swap_event {
+                parse_raydium_swap_event(&data[8..])
+            }
+            else if data[..8] == orca_traded_event {
+                parse_orca_traded_event(&data[8..])
+            }
+            else {
+                None
+            }
+        }
+        else if let Some(data) = log.strip_prefix(RAY_LOG_PREFIX) {
+            parse_ray_log(&BASE64_STANDARD.decode(data).ok()?)
+        }
+        else {
+            None
+        }
+    }).collect()
+}
+
+fn parse_raydium_swap_event(data: &[u8]) -> Option<SimulatedSwap> {
+    if data.len() < 161 {
+        return None;
+    }
+
+    let src = array_ref![data, 0, 161];
+    let (pool_state, _sender, _token_account_0, _token_account_1, amount_0, _transfer_fee_0, amount_1, _transfer_fee_1, zero_for_one) =
+        array_refs![src, 32, 32, 32, 32, 8, 8, 8, 8, 1];
+    let (amount_0, amount_1) = (u64::from_le_bytes(*amount_0), u64::from_le_bytes(*amount_1));
+
+    Some(SimulatedSwap {
+        pool: Some(Pubkey::new_from_array(*pool_state)),
+        amount_in: if zero_for_one[0] != 0 { amount_0 } else { amount_1 },
+        amount_out: if zero_for_one[0] != 0 { amount_1 } else { amount_0 },
+    })
+}
+
+fn parse_orca_traded_event(data: &[u8]) -> Option<SimulatedSwap> {
+    if data.len() < 65 {
+        return None;
+    }
+
+    let src = array_ref![data, 0, 65];
+    let (whirlpool, _a_to_b, _pre_sqrt_price, _post_sqrt_price, input_amount, output_amount) =
+        array_refs![src, 32, 1, 16, 16, 8, 8];
+
+    Some(SimulatedSwap {
+        pool: Some(Pubkey::new_from_array(*whirlpool)),
+        amount_in: u64::from_le_bytes(*input_amount),
+        amount_out: u64::from_le_bytes(*output_amount),
+    })
+}
+
+fn parse_ray_log(data: &[u8]) -> Option<SimulatedSwap> {
+    if data.len() < 57 {
+        return None;
+    }
+
+    let src = array_ref![data, 0, 57];
+    let (log_type, first, second, _direction, _user_source, _pool_coin, _pool_pc, last) =
+        array_refs![src, 1, 8, 8, 8, 8, 8, 8, 8];
+
+    match log_type[0] {
+        // amount_in, minimum_out, .., out_amount
+        RAY_LOG_SWAP_BASE_IN => Some(SimulatedSwap {
+            pool: None,
+            amount_in: u64::from_le_bytes(*first),
+            amount_out: u64::from_le_bytes(*last),
+        }),
+        // max_in, amount_out, .., deduct_in
+        RAY_LOG_SWAP_BASE_OUT => Some(SimulatedSwap {
+            pool: None,
+            amount_in: u64::from_le_bytes(*last),
+            amount_out: u64::from_le_bytes(*second),
+        }),
+        _ => None
+    }
+}
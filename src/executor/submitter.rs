@@ -0,0 +1,236 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use log::{debug, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use tokio::spawn;
+
+use crate::arbitrageur::Opportunity;
+use crate::config::{ArbitrageConfig, BaseMintConfig};
+use crate::executor::fee::{FeeEstimator, LAMPORTS_PER_SIGNATURE};
+use crate::executor::jito::{BundleStatus, JitoClient};
+use crate::executor::lookup_table::LookupTableManager;
+use crate::executor::simulator::Simulator;
+use crate::executor::wallet::Wallet;
+use crate::metrics::registry::Metrics;
+use crate::r#struct::account::DeserializedPoolAccount;
+
+/// Transaction sent for an opportunity
+#[derive(Clone, Copy, Debug)]
+pub struct Submission {
+    pub signature: Signature,
+    /// last block height the transaction's blockhash is valid at
+    pub last_valid_block_height: u64,
+    /// lamports
+    pub tip: u64,
+}
+
+/// Sends opportunities as single-transaction Jito bundles, compiled with the lookup tables.
+/// Every transaction is simulated first and only sent if it keeps the profit of its base mint
+pub struct Submitter {
+    rpc_client: RpcClient,
+    // minimum simulated profit per base mint
+    base_mints: Vec<BaseMintConfig>,
+    wallet: Arc<Wallet>,
+    // pools are resolved once at startup, a path names them by pubkey
+    pools: Vec<DeserializedPoolAccount>,
+    fee_estimator: FeeEstimator,
+    jito: Arc<JitoClient>,
+    lookup_tables: LookupTableManager,
+    simulator: Simulator
+}
+
+impl Submitter {
+    pub fn new(
+        rpc_url: String,
+        config: &ArbitrageConfig,
+        wallet: Arc<Wallet>,
+        pools: Vec<DeserializedPoolAccount>,
+        lookup_tables: LookupTableManager,
+        metrics: Arc<Metrics>
+    ) -> Submitter {
+        Submitter {
+            rpc_client: RpcClient::new(rpc_url.clone()),
+            base_mints: config.base_mints.clone(),
+            wallet,
+            pools,
+            fee_estimator: FeeEstimator::new(rpc_url.clone(), config.fee.clone()),
+            jito: Arc::new(JitoClient::new(config.jito.clone())),
+            lookup_tables,
+            simulator: Simulator::new(rpc_url, config.simulation.clone(), metrics)
+        }
+    }
+
+    /// Pool, input mint and output mint of every hop of `opportunity`
+    fn get_hops(&self, opportunity: &Opportunity) -> Result<Vec<(&DeserializedPoolAccount, Pubkey, Pubkey)>, &'static str> {
+        if opportunity.mints_out.len() != opportunity.path.len() {
+            return Err("output mints do not match the path");
+        }
+        let mut mint_in = opportunity.base_mint;
+        let mut hops = Vec::new();
+
+        for (pool_pubkey, mint_out) in opportunity.path.iter().zip(opportunity.mints_out.iter()) {
+            // edges of a multi-asset pool share its pubkey, the mints tell them apart
+            let pool = self.pools.iter().find(|pool| {
+                pool.pubkey == *pool_pubkey && pool.operation.get_mint_pair().all(mint_in, *mint_out)
+            }).ok_or("pool of the path not found")?;
+
+            hops.push((pool, mint_in, *mint_out));
+            mint_in = *mint_out;
+        }
+
+        Ok(hops)
+    }
+
+    /// Output vault of every hop of `opportunity`
+    fn get_hop_vaults(&self, opportunity: &Opportunity) -> Result<Vec<Pubkey>, &'static str> {
+        Ok(self.get_hops(opportunity)?.into_iter().map(|(pool, _, mint_out)| {
+            // the pool pair is ordered as the mint pair
+            let (mints, vaults) = (pool.operation.get_mint_pair(), pool.operation.get_pool_pair());
+            if mints.pubkey_a == mint_out { vaults.pubkey_a } else { vaults.pubkey_b }
+        }).collect())
+    }
+
+    /// Swap instructions of every hop of `opportunity`. Each hop spends its quoted input and requires its quoted output,
+    /// so a hop falling short fails the transaction instead of starving the next one
+    pub fn get_swap_instructions(&self, opportunity: &Opportunity) -> Result<Vec<Instruction>, &'static str> {
+        let user = self.wallet.get_pubkey();
+        let mut instructions = Vec::new();
+
+        for ((pool, mint_in, mint_out), quote) in self.get_hops(opportunity)?.into_iter().zip(opportunity.quotes.iter()) {
+            let source = self.wallet.get_token_account(&mint_in).ok_or("no token account of the input mint")?;
+            let destination = self.wallet.get_token_account(&mint_out).ok_or("no token account of the output mint")?;
+            instructions.extend(pool.operation.get_swap_instructions(
+                &pool.pubkey, &user, &source, &destination, &mint_in, quote.amount_in, quote.amount_out
+            )?);
+        }
+
+        Ok(instructions)
+    }
+
+    /// Compute budget, swap and tip instructions of `opportunity`, with the tip and the fees in lamports
+    async fn get_instructions(&self, opportunity: &Opportunity) -> Result<(Vec<Instruction>, u64, u64), Box<dyn Error + Send + Sync>> {
+        let payer = self.wallet.get_pubkey();
+        let swap_instructions = self.get_swap_instructions(opportunity)?;
+
+        let mut writable_pubkeys = Vec::new();
+        swap_instructions.iter().flat_map(|instruction| instruction.accounts.iter()).for_each(|account| {
+            if account.is_writable && !account.is_signer && !writable_pubkeys.contains(&account.pubkey) {
+                writable_pubkeys.push(account.pubkey);
+            }
+        });
+        // the tip and fees are paid in lamports, an opportunity not priced in lamports cannot be bounded by its profit
+        let lamport_profit = opportunity.lamport_profit.ok_or("profit not priced in lamports")?;
+        let budget = self.fee_estimator.get_opportunity_budget(&writable_pubkeys, opportunity, 1).await?;
+        let fees = LAMPORTS_PER_SIGNATURE + budget.get_priority_fee();
+        let tip = self.jito.get_tip(lamport_profit, fees).ok_or("profit does not cover the tip and fees")?;
+
+        let mut instructions = budget.get_instructions();
+        instructions.extend(swap_instructions);
+        // the tip lives in the arbitrage transaction, so it is only paid if the arbitrage lands
+        instructions.push(JitoClient::get_tip_instruction(&payer, tip));
+
+        Ok((instructions, tip, fees))
+    }
+
+    /// Sends the first of `opportunities`, ranked best first, whose transaction fits in a packet.
+    /// Once a route is too large only routes with fewer hops are tried, opportunities without
+    /// the input at hand or without instructions are skipped. Returns the index of the opportunity sent,
+    /// `None` if none could be built. Its input stays reserved until the tracker settles the transaction
+    pub async fn submit(&self, opportunities: &[Opportunity]) -> Result<Option<(usize, Submission)>, Box<dyn Error + Send + Sync>> {
+        let mut indexes = Vec::new();
+        let mut candidates = Vec::new();
+        let mut costs = Vec::new();
+        for (index, opportunity) in opportunities.iter().enumerate() {
+            if self.wallet.inventory.get_available(&opportunity.base_mint) < opportunity.amount_in {
+                debug!("executor: not enough {} at hand for opportunity {}", opportunity.base_mint, index);
+                continue;
+            }
+            match self.get_instructions(opportunity).await {
+                Ok((instructions, tip, fees)) => {
+                    indexes.push(index);
+                    candidates.push((opportunity, instructions));
+                    costs.push((tip, fees));
+                }
+                Err(err) => debug!("executor: skipped opportunity {}: {}", index, err)
+            }
+        }
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        let (recent_blockhash, last_valid_block_height) = self.rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed()).await?;
+        let (candidate, transaction) = match self.lookup_tables.build_with_fallback(self.wallet.get_keypair(), &candidates, recent_blockhash)? {
+            Some(built) => built,
+            None => return Ok(None)
+        };
+        let opportunity = candidates[candidate].0;
+
+        if !self.wallet.inventory.reserve(&opportunity.base_mint, opportunity.amount_in) {
+            return Err("not enough inventory of the base mint".into());
+        }
+        let (tip, fees) = costs[candidate];
+        match self.send(opportunity, transaction, last_valid_block_height, tip, fees).await {
+            Ok(submission) => Ok(Some((indexes[candidate], submission))),
+            Err(err) => {
+                // nothing was sent, the input is spendable again
+                self.wallet.inventory.release(&opportunity.base_mint, opportunity.amount_in);
+                Err(err)
+            }
+        }
+    }
+
+    /// Simulates the transaction of `opportunity`, then sends it as a bundle.
+    /// `tip` and `fees` are the lamports the transaction pays besides its swaps.
+    /// The bundle status is polled in the background, the signature is what the tracker follows
+    async fn send(
+        &self,
+        opportunity: &Opportunity,
+        transaction: VersionedTransaction,
+        last_valid_block_height: u64,
+        tip: u64,
+        fees: u64
+    ) -> Result<Submission, Box<dyn Error + Send + Sync>> {
+        let signature = transaction.signatures[0];
+
+        let base_token_account = self.wallet.get_token_account(&opportunity.base_mint).ok_or("no token account of the base mint")?;
+        let min_profit = self.base_mints.iter()
+            .find(|base_mint| base_mint.mint == opportunity.base_mint)
+            .map(|base_mint| base_mint.min_profit)
+            .unwrap_or(0);
+        let hop_vaults = self.get_hop_vaults(opportunity)?.into_iter().map(Some).collect::<Vec<Option<Pubkey>>>();
+        self.simulator.preflight(
+            &transaction,
+            opportunity,
+            &base_token_account,
+            min_profit,
+            tip + fees,
+            &hop_vaults
+        ).await?;
+
+        let bundle_id = self.jito.send_versioned_bundle(&[transaction]).await?;
+        debug!("executor: sent {} in bundle {}", signature, bundle_id);
+
+        let jito = Arc::clone(&self.jito);
+        spawn(async move {
+            match jito.confirm_bundle(&bundle_id).await {
+                Ok(BundleStatus::Landed { slot }) => debug!("executor: bundle {} landed at slot {}", bundle_id, slot),
+                Ok(BundleStatus::Failed(err)) => warn!("executor: bundle {} failed: {}", bundle_id, err),
+                Ok(BundleStatus::Pending) => debug!("executor: bundle {} did not land in time", bundle_id),
+                Err(err) => warn!("executor: failed to poll bundle {}: {}", bundle_id, err)
+            }
+        });
+
+        Ok(Submission {
+            signature,
+            last_valid_block_height,
+            tip
+        })
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod fee_test {
+    use serde_json::json;
+    use solana_sdk::compute_budget::ComputeBudgetInstruction;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::arbitrageur::Opportunity;
+    use crate::config::FeeConfig;
+    use crate::executor::fee::{AMM_V4_SWAP_COMPUTE_UNITS, BASE_COMPUTE_UNITS, CLMM_SWAP_COMPUTE_UNITS, CLMM_TICK_CROSSING_COMPUTE_UNITS, ComputeBudget, DLMM_BIN_COMPUTE_UNITS, DLMM_SWAP_COMPUTE_UNITS, estimate_compute_units, estimate_hop_compute_units, FeeEstimator, MAX_COMPUTE_UNIT_LIMIT};
+    use crate::executor::test::mock_rpc_server::MockRpcServer;
+    use crate::formula::base::Formula;
+
+    fn build_estimator(rpc_url: &str) -> FeeEstimator {
+        FeeEstimator::new(rpc_url.to_string(), FeeConfig {
+            fee_percentile: 50,
+            max_fee_fraction: 0.2,
+        })
+    }
+
+    #[test]
+    fn hop_compute_units() {
+        assert_eq!(estimate_hop_compute_units(&Formula::ConcentratedLiquidity, 0), CLMM_SWAP_COMPUTE_UNITS);
+        assert_eq!(
+            estimate_hop_compute_units(&Formula::ConcentratedLiquidity, 3),
+            CLMM_SWAP_COMPUTE_UNITS + 3 * CLMM_TICK_CROSSING_COMPUTE_UNITS
+        );
+        assert_eq!(
+            estimate_hop_compute_units(&Formula::DynamicLiquidity, 5),
+            DLMM_SWAP_COMPUTE_UNITS + 5 * DLMM_BIN_COMPUTE_UNITS
+        );
+        assert_eq!(estimate_hop_compute_units(&Formula::OpenBook, 0), AMM_V4_SWAP_COMPUTE_UNITS);
+    }
+
+    #[test]
+    fn transaction_compute_units() {
+        let hops = [(Formula::ConcentratedLiquidity, 1), (Formula::OpenBook, 0)];
+        let units = BASE_COMPUTE_UNITS + CLMM_SWAP_COMPUTE_UNITS + CLMM_TICK_CROSSING_COMPUTE_UNITS + AMM_V4_SWAP_COMPUTE_UNITS;
+        assert_eq!(estimate_compute_units(&hops), units * 120 / 100);
+
+        let hops = [(Formula::ConcentratedLiquidity, 200); 3];
+        assert_eq!(estimate_compute_units(&hops), MAX_COMPUTE_UNIT_LIMIT);
+    }
+
+    #[test]
+    fn compute_budget() {
+        let budget = ComputeBudget {
+            unit_limit: 200_000,
+            unit_price: 1_500,
+        };
+
+        assert_eq!(budget.get_priority_fee(), 300);
+        assert_eq!(budget.get_instructions(), vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_500),
+        ]);
+
+        // rounded up to the next lamport
+        assert_eq!(ComputeBudget { unit_limit: 3, unit_price: 1 }.get_priority_fee(), 1);
+    }
+
+    #[test]
+    fn unit_price_is_percentile_of_recent_fees() {
+        let estimator = build_estimator("http://localhost");
+
+        assert_eq!(estimator.get_unit_price(vec![500, 100, 300, 0, 200], 200_000, 1_000_000_000, 1), 200);
+        assert_eq!(estimator.get_unit_price(vec![], 200_000, 1_000_000_000, 1), 0);
+    }
+
+    #[test]
+    fn unit_price_keeps_fees_within_profit_share() {
+        let estimator = build_estimator("http://localhost");
+
+        // 20% of 105_000 lamports leaves 16_000 lamports of priority fee after the signature
+        let unit_price = estimator.get_unit_price(vec![1_000_000], 200_000, 105_000, 1);
+        assert_eq!(unit_price, 80_000);
+        assert!(ComputeBudget { unit_limit: 200_000, unit_price }.get_priority_fee() <= 16_000);
+
+        // the signature fee alone takes the whole share
+        assert_eq!(estimator.get_unit_price(vec![1_000_000], 200_000, 20_000, 1), 0);
+    }
+
+    #[tokio::test]
+    async fn compute_budget_from_recent_fees() {
+        let server = MockRpcServer::start(|method, _| {
+            assert_eq!(method, "getRecentPrioritizationFees");
+            Ok(json!([
+                { "slot": 100, "prioritizationFee": 0 },
+                { "slot": 101, "prioritizationFee": 1_000 },
+                { "slot": 102, "prioritizationFee": 5_000 }
+            ]))
+        }).await;
+        let estimator = build_estimator(&server.url);
+        let pools = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let budget = estimator.get_compute_budget(&pools, 150_000, 1_000_000_000, 1).await.unwrap();
+        assert_eq!(budget, ComputeBudget { unit_limit: 150_000, unit_price: 1_000 });
+
+        let requests = server.get_requests_of("getRecentPrioritizationFees");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["params"][0], json!(pools.iter().map(|pool| pool.to_string()).collect::<Vec<String>>()));
+    }
+
+    #[tokio::test]
+    async fn opportunity_budget_is_bounded_by_lamport_profit() {
+        let server = MockRpcServer::start(|_, _| {
+            Ok(json!([{ "slot": 100, "prioritizationFee": 1_000_000 }]))
+        }).await;
+        let estimator = build_estimator(&server.url);
+        let mut opportunity = Opportunity {
+            base_mint: Pubkey::new_unique(),
+            path: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            mints_out: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            markets: Vec::new(),
+            quotes: Vec::new(),
+            amount_in: 1_000_000,
+            // a large profit in base mint atoms worth few lamports
+            amount_out: 1_000_000_000,
+            profit: 999_000_000,
+            quote_profit: None,
+            lamport_profit: Some(105_000),
+            compute_units: 200_000,
+        };
+
+        // 20% of 105_000 lamports less 5000 for the signature, over 200_000 units
+        let budget = estimator.get_opportunity_budget(&[], &opportunity, 1).await.unwrap();
+        assert_eq!(budget, ComputeBudget { unit_limit: 200_000, unit_price: 80_000 });
+
+        opportunity.lamport_profit = None;
+        let budget = estimator.get_opportunity_budget(&[], &opportunity, 1).await.unwrap();
+        assert_eq!(budget.unit_price, 0);
+    }
+}
@@ -0,0 +1,218 @@
+#[cfg(test)]
+mod jito_test {
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use base64::Engine;
+    use base64::prelude::BASE64_STANDARD;
+    use serde_json::json;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::{v0, VersionedMessage};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_instruction::SystemInstruction;
+    use solana_sdk::system_program;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+    use crate::config::JitoConfig;
+    use crate::constants::JITO_TIP_ACCOUNTS;
+    use crate::executor::jito::{BundleStatus, JitoClient};
+    use crate::executor::test::mock_rpc_server::MockRpcServer;
+
+    fn build_client(block_engine_url: &str) -> JitoClient {
+        JitoClient::new(JitoConfig {
+            block_engine_url: block_engine_url.to_string(),
+            tip_fraction: 0.5,
+            min_tip_lamports: 1000,
+            max_tip_lamports: 1_000_000,
+            status_poll_interval_ms: 10,
+            status_timeout_ms: 1000,
+        })
+    }
+
+    fn build_transaction(client: &JitoClient, payer: &Keypair, expected_profit: u64) -> Transaction {
+        let memo = Instruction::new_with_bytes(Pubkey::new_unique(), b"arbitrage", vec![]);
+        client.build_transaction(payer, &[memo], expected_profit, Hash::new_unique()).unwrap()
+    }
+
+    fn landed(bundle_id: &str, slot: u64) -> serde_json::Value {
+        json!({
+            "context": { "slot": slot },
+            "value": [{
+                "bundle_id": bundle_id,
+                "transactions": [],
+                "slot": slot,
+                "confirmation_status": "confirmed",
+                "err": { "Ok": null }
+            }]
+        })
+    }
+
+    #[test]
+    fn tip_is_fraction_of_profit_within_bounds() {
+        let client = build_client("http://localhost");
+
+        assert_eq!(client.get_tip(100_000, 0), Some(50_000));
+        assert_eq!(client.get_tip(1999, 0), Some(1000));
+        assert_eq!(client.get_tip(10_000_000, 0), Some(1_000_000));
+        assert_eq!(client.get_tip(100_000, 5_000), Some(50_000));
+    }
+
+    #[test]
+    fn tip_never_exceeds_profit_after_fees() {
+        let client = build_client("http://localhost");
+
+        // the profit left after fees does not cover the min tip
+        assert_eq!(client.get_tip(0, 0), None);
+        assert_eq!(client.get_tip(999, 0), None);
+        assert_eq!(client.get_tip(6_000, 5_001), None);
+        assert_eq!(client.get_tip(4_000, 5_000), None);
+        // the fraction is cut to what the fees leave
+        assert_eq!(client.get_tip(6_000, 5_000), Some(1000));
+        assert_eq!(client.get_tip(20_000, 12_000), Some(8_000));
+        assert!(client.build_transaction(&Keypair::new(), &[], 5_999, Hash::new_unique()).is_none());
+    }
+
+    #[test]
+    fn transaction_ends_with_tip_transfer() {
+        let client = build_client("http://localhost");
+        let payer = Keypair::new();
+        let transaction = build_transaction(&client, &payer, 100_000);
+
+        transaction.verify().unwrap();
+        assert_eq!(transaction.message.account_keys[0], payer.pubkey());
+        assert_eq!(transaction.message.instructions.len(), 2);
+
+        let tip = transaction.message.instructions.last().unwrap();
+        let accounts = &transaction.message.account_keys;
+        assert_eq!(accounts[tip.program_id_index as usize], system_program::id());
+        assert_eq!(bincode::deserialize::<SystemInstruction>(&tip.data).unwrap(), SystemInstruction::Transfer { lamports: 50_000 });
+
+        let tip_account = accounts[tip.accounts[1] as usize];
+        assert!(JITO_TIP_ACCOUNTS.iter().any(|pubkey| Pubkey::from_str(pubkey).unwrap() == tip_account));
+    }
+
+    #[tokio::test]
+    async fn send_bundle() {
+        let server = MockRpcServer::start(|method, _| {
+            match method {
+                "sendBundle" => Ok(json!("bundle-0")),
+                _ => Err(json!({ "code": -32601, "message": "method not found" }))
+            }
+        }).await;
+        let client = build_client(&server.url);
+        let transaction = build_transaction(&client, &Keypair::new(), 100_000);
+
+        let bundle_id = client.send_bundle(&[transaction.clone()]).await.unwrap();
+        assert_eq!(bundle_id, "bundle-0");
+
+        let requests = server.get_requests_of("sendBundle");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["params"][1]["encoding"], "base64");
+
+        let encoded = requests[0]["params"][0][0].as_str().unwrap();
+        let sent: Transaction = bincode::deserialize(&BASE64_STANDARD.decode(encoded).unwrap()).unwrap();
+        assert_eq!(sent, transaction);
+    }
+
+    #[tokio::test]
+    async fn send_versioned_bundle() {
+        let server = MockRpcServer::start(|_, _| Ok(json!("bundle-1"))).await;
+        let client = build_client(&server.url);
+        let payer = Keypair::new();
+        let memo = Instruction::new_with_bytes(Pubkey::new_unique(), b"arbitrage", vec![]);
+        let message = v0::Message::try_compile(&payer.pubkey(), &[memo], &[], Hash::new_unique()).unwrap();
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+        assert_eq!(client.send_versioned_bundle(&[transaction.clone()]).await.unwrap(), "bundle-1");
+
+        let requests = server.get_requests_of("sendBundle");
+        let encoded = requests[0]["params"][0][0].as_str().unwrap();
+        let sent: VersionedTransaction = bincode::deserialize(&BASE64_STANDARD.decode(encoded).unwrap()).unwrap();
+        assert_eq!(sent, transaction);
+    }
+
+    #[tokio::test]
+    async fn send_bundle_error() {
+        let server = MockRpcServer::start(|_, _| {
+            Err(json!({ "code": -32602, "message": "bundle must tip at least 1000 lamports" }))
+        }).await;
+        let client = build_client(&server.url);
+        let transaction = build_transaction(&client, &Keypair::new(), 100_000);
+
+        let err = client.send_bundle(&[transaction]).await.unwrap_err();
+        assert!(err.to_string().contains("at least 1000 lamports"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn confirm_bundle_polls_until_landed() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&polls);
+        let server = MockRpcServer::start(move |method, params| {
+            assert_eq!(method, "getBundleStatuses");
+            let bundle_id = params[0][0].as_str().unwrap();
+
+            // unknown to the block engine for the first polls
+            if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                Ok(json!({ "context": { "slot": 100 }, "value": [null] }))
+            } else {
+                Ok(landed(bundle_id, 102))
+            }
+        }).await;
+        let client = build_client(&server.url);
+
+        let status = client.confirm_bundle("bundle-0").await.unwrap();
+        assert_eq!(status, BundleStatus::Landed { slot: 102 });
+        assert_eq!(polls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn confirm_bundle_times_out() {
+        let server = MockRpcServer::start(|_, _| {
+            Ok(json!({ "context": { "slot": 100 }, "value": [] }))
+        }).await;
+        let client = build_client(&server.url);
+
+        let status = client.confirm_bundle("bundle-0").await.unwrap();
+        assert_eq!(status, BundleStatus::Pending);
+        assert!(server.get_requests_of("getBundleStatuses").len() > 1);
+    }
+
+    #[tokio::test]
+    async fn bundle_statuses() {
+        let landed_status = landed("landed", 98)["value"][0].clone();
+        let server = MockRpcServer::start(move |_, _| {
+            Ok(json!({
+                "context": { "slot": 100 },
+                "value": [
+                    {
+                        "bundle_id": "failed",
+                        "transactions": [],
+                        "slot": 99,
+                        "confirmation_status": "processed",
+                        "err": { "Err": { "InstructionError": [0, { "Custom": 6001 }] } }
+                    },
+                    {
+                        "bundle_id": "processed",
+                        "transactions": [],
+                        "slot": 100,
+                        "confirmation_status": "processed",
+                        "err": { "Ok": null }
+                    },
+                    landed_status
+                ]
+            }))
+        }).await;
+        let client = build_client(&server.url);
+
+        let bundle_ids = ["landed", "unknown", "processed", "failed"].map(|bundle_id| bundle_id.to_string());
+        let statuses = client.get_bundle_statuses(&bundle_ids).await.unwrap();
+
+        assert_eq!(statuses[0], BundleStatus::Landed { slot: 98 });
+        assert_eq!(statuses[1], BundleStatus::Pending);
+        assert_eq!(statuses[2], BundleStatus::Pending);
+        assert!(matches!(&statuses[3], BundleStatus::Failed(err) if err.contains("6001")), "{:?}", statuses[3]);
+    }
+}
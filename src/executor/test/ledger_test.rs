@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod ledger_test {
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+
+    use crate::arbitrageur::Opportunity;
+    use crate::executor::ledger::{Ledger, ReportRow};
+    use crate::executor::tracker::{TradeResult, TxStatus};
+
+    fn build_opportunity(base_mint: Pubkey, profit: u64) -> Opportunity {
+        Opportunity {
+            base_mint,
+            path: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            mints_out: vec![Pubkey::new_unique(), base_mint],
+            markets: Vec::new(),
+            quotes: Vec::new(),
+            amount_in: 1_000_000,
+            amount_out: 1_000_000 + profit,
+            profit,
+            quote_profit: None,
+            lamport_profit: None,
+            compute_units: 200_000,
+        }
+    }
+
+    #[test]
+    fn trade_lifecycle() {
+        let ledger = Ledger::open_in_memory().unwrap();
+        let signature = Signature::new_unique();
+        let opportunity = build_opportunity(Pubkey::new_unique(), 10_000);
+
+        ledger.record_sent(&signature, &opportunity, 2_000, 1_000).unwrap();
+        let trade = ledger.get_trade(&signature).unwrap().unwrap();
+        assert_eq!(trade.status, "sent");
+        assert_eq!(trade.path, format!("{},{}", opportunity.path[0], opportunity.path[1]));
+        assert_eq!((trade.amount_in, trade.quoted_amount_out, trade.expected_profit, trade.tip), (1_000_000, 1_010_000, 10_000, 2_000));
+        assert_eq!((trade.slot, trade.landed_at, trade.fee, trade.realized_profit), (None, None, None, None));
+
+        ledger.record_status(&signature, &TxStatus::Processed { slot: 50 }, 1_400).unwrap();
+        ledger.record_status(&signature, &TxStatus::Finalized { slot: 50 }, 9_000).unwrap();
+        ledger.record_result(&signature, &TradeResult { fee: 7_000, realized_profit: 9_500 }).unwrap();
+
+        let trade = ledger.get_trade(&signature).unwrap().unwrap();
+        assert_eq!(trade.status, "finalized");
+        assert_eq!(trade.slot, Some(50));
+        // landed when first seen with a slot
+        assert_eq!(trade.landed_at, Some(1_400));
+        assert_eq!(trade.fee, Some(7_000));
+        assert_eq!(trade.realized_profit, Some(9_500));
+        assert_eq!(trade.error, None);
+
+        assert!(ledger.get_trade(&Signature::new_unique()).unwrap().is_none());
+    }
+
+    #[test]
+    fn trade_settles_once() {
+        let ledger = Ledger::open_in_memory().unwrap();
+        let signature = Signature::new_unique();
+        ledger.record_sent(&signature, &build_opportunity(Pubkey::new_unique(), 10_000), 2_000, 1_000).unwrap();
+
+        assert!(ledger.mark_settled(&signature, 2_000).unwrap());
+        // settled without a result, a later status must not settle it again
+        assert!(!ledger.mark_settled(&signature, 3_000).unwrap());
+        let trade = ledger.get_trade(&signature).unwrap().unwrap();
+        assert_eq!((trade.settled_at, trade.fee), (Some(2_000), None));
+
+        assert!(!ledger.mark_settled(&Signature::new_unique(), 2_000).unwrap());
+    }
+
+    #[test]
+    fn report_sums_per_base_mint() {
+        let ledger = Ledger::open_in_memory().unwrap();
+        let base_mint = Pubkey::new_unique();
+        let (landed, failed, expired, old) = (Signature::new_unique(), Signature::new_unique(), Signature::new_unique(), Signature::new_unique());
+
+        ledger.record_sent(&landed, &build_opportunity(base_mint, 10_000), 2_000, 1_000).unwrap();
+        ledger.record_status(&landed, &TxStatus::Confirmed { slot: 50 }, 1_500).unwrap();
+        ledger.record_result(&landed, &TradeResult { fee: 7_000, realized_profit: 9_500 }).unwrap();
+
+        ledger.record_sent(&failed, &build_opportunity(base_mint, 20_000), 4_000, 2_000).unwrap();
+        ledger.record_status(&failed, &TxStatus::Failed { slot: 51, reason: "slippage".to_string() }, 2_500).unwrap();
+        ledger.record_result(&failed, &TradeResult { fee: 5_000, realized_profit: 0 }).unwrap();
+
+        ledger.record_sent(&expired, &build_opportunity(base_mint, 5_000), 1_000, 3_000).unwrap();
+        ledger.record_status(&expired, &TxStatus::Expired, 90_000).unwrap();
+        assert_eq!(ledger.get_trade(&expired).unwrap().unwrap().error, Some("blockhash expired".to_string()));
+
+        ledger.record_sent(&old, &build_opportunity(base_mint, 1), 1_000, 10).unwrap();
+
+        assert_eq!(ledger.get_report(1_000).unwrap(), vec![ReportRow {
+            base_mint: base_mint.to_string(),
+            sent: 3,
+            landed: 1,
+            failed: 1,
+            expired: 1,
+            expected_profit: 35_000,
+            realized_profit: 9_500,
+            fees: 12_000,
+            // only landed trades pay their tip
+            tips: 2_000,
+        }]);
+        assert_eq!(ledger.get_report(0).unwrap()[0].sent, 4);
+    }
+}
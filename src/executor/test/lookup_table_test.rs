@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod lookup_table_test {
+    use base64::Engine;
+    use base64::prelude::BASE64_STANDARD;
+    use serde_json::{json, Value};
+    use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    use crate::arbitrageur::Opportunity;
+    use crate::executor::lookup_table::{BuildError, LookupTableManager};
+    use crate::executor::test::mock_rpc_server::MockRpcServer;
+
+    fn build_opportunity(hops: usize) -> Opportunity {
+        Opportunity {
+            base_mint: Pubkey::new_unique(),
+            path: (0..hops).map(|_| Pubkey::new_unique()).collect(),
+            mints_out: (0..hops).map(|_| Pubkey::new_unique()).collect(),
+            markets: Vec::new(),
+            quotes: Vec::new(),
+            amount_in: 1_000_000,
+            amount_out: 1_010_000,
+            profit: 10_000,
+            quote_profit: None,
+            lamport_profit: None,
+            compute_units: 200_000,
+        }
+    }
+
+    /// One instruction passing `accounts` writable, too many accounts overflow a packet without lookup tables
+    fn build_instruction(program_id: &Pubkey, accounts: &[Pubkey]) -> Instruction {
+        Instruction::new_with_bytes(
+            *program_id,
+            &[0; 8],
+            accounts.iter().map(|pubkey| AccountMeta::new(*pubkey, false)).collect()
+        )
+    }
+
+    fn lookup_table_data(authority: &Pubkey, addresses: &[Pubkey]) -> Vec<u8> {
+        let mut data = Vec::new();
+        // ProgramState::LookupTable
+        data.extend_from_slice(&1u32.to_le_bytes());
+        // deactivation slot, last extended slot and its start index
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(0);
+        data.push(1);
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(&[0; 2]);
+        addresses.iter().for_each(|address| data.extend_from_slice(address.as_ref()));
+
+        data
+    }
+
+    #[test]
+    fn missing_pubkeys_are_unique_and_not_held() {
+        let manager = LookupTableManager::new("http://localhost".to_string());
+        let held = Pubkey::new_unique();
+        let missing = Pubkey::new_unique();
+        manager.insert_table(AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![held],
+        });
+
+        assert_eq!(manager.get_missing(&[held, missing, missing]), vec![missing]);
+    }
+
+    #[test]
+    fn lookup_tables_shrink_transaction() {
+        let manager = LookupTableManager::new("http://localhost".to_string());
+        let payer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let accounts = (0..40).map(|_| Pubkey::new_unique()).collect::<Vec<Pubkey>>();
+        let instructions = [build_instruction(&program_id, &accounts)];
+
+        assert!(matches!(manager.build_transaction(&payer, &instructions, Hash::new_unique()), Err(BuildError::TooLarge { .. })));
+
+        manager.insert_table(AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: accounts.clone(),
+        });
+        let transaction = manager.build_transaction(&payer, &instructions, Hash::new_unique()).unwrap();
+        let lookups = transaction.message.address_table_lookups().unwrap();
+        assert_eq!(lookups.len(), 1);
+        assert_eq!(lookups[0].writable_indexes.len(), 40);
+        assert_eq!(transaction.message.static_account_keys(), &[payer.pubkey(), program_id]);
+        assert!(transaction.verify_with_results().iter().all(|verified| *verified));
+    }
+
+    #[test]
+    fn fallback_to_fewer_hops() {
+        let manager = LookupTableManager::new("http://localhost".to_string());
+        let payer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let large = build_instruction(&program_id, &(0..40).map(|_| Pubkey::new_unique()).collect::<Vec<Pubkey>>());
+        let small = build_instruction(&program_id, &(0..8).map(|_| Pubkey::new_unique()).collect::<Vec<Pubkey>>());
+
+        let (four_hops, other_four_hops, two_hops) = (build_opportunity(4), build_opportunity(4), build_opportunity(2));
+        let candidates = [
+            (&four_hops, vec![large.clone()]),
+            // as many hops as the route that did not fit, skipped
+            (&other_four_hops, vec![small.clone()]),
+            (&two_hops, vec![small.clone()]),
+        ];
+
+        let (index, _) = manager.build_with_fallback(&payer, &candidates, Hash::new_unique()).unwrap().unwrap();
+        assert_eq!(index, 2);
+
+        assert!(manager.build_with_fallback(&payer, &candidates[..2], Hash::new_unique()).unwrap().is_none());
+    }
+
+    #[test]
+    fn fallback_returns_other_errors() {
+        let manager = LookupTableManager::new("http://localhost".to_string());
+        let payer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        // more accounts than a message can index, a compile error rather than a size error
+        let unindexable = build_instruction(&program_id, &(0..300).map(|_| Pubkey::new_unique()).collect::<Vec<Pubkey>>());
+        let small = build_instruction(&program_id, &(0..8).map(|_| Pubkey::new_unique()).collect::<Vec<Pubkey>>());
+
+        let (four_hops, two_hops) = (build_opportunity(4), build_opportunity(2));
+        let candidates = [
+            (&four_hops, vec![unindexable]),
+            (&two_hops, vec![small]),
+        ];
+
+        let err = manager.build_with_fallback(&payer, &candidates, Hash::new_unique()).unwrap_err();
+        assert!(matches!(err, BuildError::Compile(_)));
+    }
+
+    #[tokio::test]
+    async fn load_lookup_tables() {
+        let authority = Pubkey::new_unique();
+        let addresses = (0..3).map(|_| Pubkey::new_unique()).collect::<Vec<Pubkey>>();
+        let data = lookup_table_data(&authority, &addresses);
+        let server = MockRpcServer::start(move |method, _| {
+            assert_eq!(method, "getMultipleAccounts");
+            let table: Value = json!({
+                "lamports": 1_000_000,
+                "data": [BASE64_STANDARD.encode(&data), "base64"],
+                "owner": "AddressLookupTab1e1111111111111111111111111",
+                "executable": false,
+                "rentEpoch": 0,
+                "space": data.len()
+            });
+            Ok(json!({ "context": { "slot": 100 }, "value": [table, null] }))
+        }).await;
+
+        let manager = LookupTableManager::new(server.url.clone());
+        let (table, missing_table) = (Pubkey::new_unique(), Pubkey::new_unique());
+        manager.load(&[table, missing_table]).await.unwrap();
+
+        let tables = manager.get_tables();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].key, table);
+        assert_eq!(tables[0].addresses, addresses);
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::spawn;
+
+type Handler = dyn Fn(&str, &Value) -> Result<Value, Value> + Send + Sync;
+
+/// Minimal JSON-RPC over HTTP server standing in for an RPC node or the Jito block engine.
+/// Every request body is recorded, whatever the path it was posted to
+pub struct MockRpcServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl MockRpcServer {
+    /// `handler` maps the method and params of a request to its `result`, or to its `error` when it returns `Err`
+    pub async fn start<F>(handler: F) -> MockRpcServer
+    where
+        F: Fn(&str, &Value) -> Result<Value, Value> + Send + Sync + 'static
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let handler: Arc<Handler> = Arc::new(handler);
+        let recorded = Arc::clone(&requests);
+        spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let handler = Arc::clone(&handler);
+                let recorded = Arc::clone(&recorded);
+                spawn(async move {
+                    let _ = Self::serve(stream, handler, recorded).await;
+                });
+            }
+        });
+
+        MockRpcServer {
+            url,
+            requests
+        }
+    }
+
+    pub fn get_requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn get_requests_of(&self, method: &str) -> Vec<Value> {
+        self.get_requests().into_iter().filter(|request| request["method"] == method).collect()
+    }
+
+    async fn serve(stream: TcpStream, handler: Arc<Handler>, recorded: Arc<Mutex<Vec<Value>>>) -> std::io::Result<()> {
+        let mut reader = BufReader::new(stream);
+
+        // one request after another while the client keeps the connection alive
+        loop {
+            let mut content_length = 0usize;
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line).await? == 0 {
+                    return Ok(());
+                }
+
+                let header = line.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+            }
+
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).await?;
+            let request: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+            recorded.lock().unwrap().push(request.clone());
+
+            let response = match handler(request["method"].as_str().unwrap_or_default(), &request["params"]) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+                Err(error) => json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }),
+            }.to_string();
+
+            let stream = reader.get_mut();
+            stream.write_all(format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+                response.len()
+            ).as_bytes()).await?;
+            stream.write_all(response.as_bytes()).await?;
+            stream.flush().await?;
+        }
+    }
+}
@@ -0,0 +1,9 @@
+pub mod mock_rpc_server;
+mod jito_test;
+mod fee_test;
+mod simulator_test;
+mod wallet_test;
+mod lookup_table_test;
+mod tracker_test;
+mod ledger_test;
+mod submitter_test;
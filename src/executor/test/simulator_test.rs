@@ -0,0 +1,318 @@
+#[cfg(test)]
+mod simulator_test {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use base64::Engine;
+    use base64::prelude::BASE64_STANDARD;
+    use serde_json::{json, Value};
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::transaction::Transaction;
+
+    use crate::arbitrageur::Opportunity;
+    use crate::config::SimulationConfig;
+    use crate::constants::NATIVE_MINT_PUBKEY;
+    use crate::executor::simulator::{parse_swaps, PreflightError, SimulatedSwap, Simulator};
+    use crate::executor::test::mock_rpc_server::MockRpcServer;
+    use crate::formula::base::SwapQuote;
+    use crate::metrics::registry::Metrics;
+    use crate::r#struct::market::Market;
+    use crate::utils::get_discriminator;
+
+    fn build_simulator(rpc_url: &str) -> Simulator {
+        Simulator::new(rpc_url.to_string(), SimulationConfig {
+            mismatch_tolerance_bps: 10,
+        }, Arc::new(Metrics::new()))
+    }
+
+    fn build_transaction() -> Transaction {
+        let payer = Keypair::new();
+        let memo = Instruction::new_with_bytes(Pubkey::new_unique(), b"arbitrage", vec![]);
+        Transaction::new_signed_with_payer(&[memo], Some(&payer.pubkey()), &[&payer], Hash::new_unique())
+    }
+
+    fn build_opportunity(path: Vec<Pubkey>, quoted_outs: &[u64]) -> Opportunity {
+        Opportunity {
+            base_mint: Pubkey::new_unique(),
+            markets: vec![Market::RAYDIUM, Market::ORCA],
+            quotes: quoted_outs.iter().map(|amount_out| SwapQuote {
+                amount_in: 0,
+                amount_out: *amount_out,
+                crossings: 0,
+            }).collect(),
+            mints_out: path.iter().map(|_| Pubkey::new_unique()).collect(),
+            path,
+            amount_in: 1_000_000,
+            amount_out: 1_010_000,
+            profit: 10_000,
+            quote_profit: None,
+            lamport_profit: None,
+            compute_units: 200_000,
+        }
+    }
+
+    fn raydium_swap_event(pool: &Pubkey, amount_0: u64, amount_1: u64, zero_for_one: bool) -> String {
+        let mut data = get_discriminator("event", "SwapEvent").to_vec();
+        data.extend_from_slice(pool.as_ref());
+        data.extend_from_slice(&[0u8; 96]);
+        data.extend_from_slice(&amount_0.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&amount_1.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(zero_for_one as u8);
+        data.extend_from_slice(&[0u8; 36]);
+        format!("Program data: {}", BASE64_STANDARD.encode(data))
+    }
+
+    fn orca_traded_event(whirlpool: &Pubkey, input_amount: u64, output_amount: u64) -> String {
+        let mut data = get_discriminator("event", "Traded").to_vec();
+        data.extend_from_slice(whirlpool.as_ref());
+        data.push(1);
+        data.extend_from_slice(&[0u8; 32]);
+        data.extend_from_slice(&input_amount.to_le_bytes());
+        data.extend_from_slice(&output_amount.to_le_bytes());
+        data.extend_from_slice(&[0u8; 32]);
+        format!("Program data: {}", BASE64_STANDARD.encode(data))
+    }
+
+    fn ray_log(log_type: u8, values: [u64; 7]) -> String {
+        let mut data = vec![log_type];
+        values.iter().for_each(|value| data.extend_from_slice(&value.to_le_bytes()));
+        format!("Program log: ray_log: {}", BASE64_STANDARD.encode(data))
+    }
+
+    fn token_account(amount: u64) -> Value {
+        let mut data = vec![0u8; 165];
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        json!({
+            "lamports": 2_039_280,
+            "data": [BASE64_STANDARD.encode(data), "base64"],
+            "owner": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+            "executable": false,
+            "rentEpoch": 0,
+            "space": 165
+        })
+    }
+
+    fn balances_response(slot: u64, accounts: Value) -> Value {
+        json!({ "context": { "slot": slot }, "value": accounts })
+    }
+
+    fn simulation_response(slot: u64, err: Value, logs: Vec<String>, accounts: Value) -> Value {
+        json!({
+            "context": { "slot": slot },
+            "value": {
+                "err": err,
+                "logs": logs,
+                "accounts": accounts,
+                "unitsConsumed": 150_000,
+                "returnData": null,
+                "innerInstructions": null,
+                "replacementBlockhash": null
+            }
+        })
+    }
+
+    /// Serves `pre_balances` at slot 100 and a simulation at `simulated_slot` ending with `post_balances`
+    async fn start_server(pre_balances: Vec<u64>, simulated_slot: u64, logs: Vec<String>, post_balances: Vec<u64>) -> MockRpcServer {
+        MockRpcServer::start(move |method, _| {
+            match method {
+                "getMultipleAccounts" => {
+                    Ok(balances_response(100, pre_balances.iter().map(|amount| token_account(*amount)).collect()))
+                }
+                "simulateTransaction" => {
+                    let accounts = post_balances.iter().map(|amount| token_account(*amount)).collect();
+                    Ok(simulation_response(simulated_slot, Value::Null, logs.clone(), accounts))
+                }
+                method => Err(json!({ "code": -32601, "message": format!("unexpected method {}", method) }))
+            }
+        }).await
+    }
+
+    #[test]
+    fn parse_swap_events() {
+        let raydium_pool = Pubkey::new_unique();
+        let whirlpool = Pubkey::new_unique();
+        let logs = vec![
+            "Program log: Instruction: Swap".to_string(),
+            raydium_swap_event(&raydium_pool, 1_000, 2_000, false),
+            orca_traded_event(&whirlpool, 2_000, 3_000),
+            ray_log(3, [3_000, 3_900, 0, 0, 0, 0, 4_000]),
+            ray_log(4, [5_000, 4_500, 0, 0, 0, 0, 4_800]),
+            "Program data: not base64".to_string(),
+        ];
+
+        assert_eq!(parse_swaps(&logs), vec![
+            SimulatedSwap { pool: Some(raydium_pool), amount_in: 2_000, amount_out: 1_000 },
+            SimulatedSwap { pool: Some(whirlpool), amount_in: 2_000, amount_out: 3_000 },
+            SimulatedSwap { pool: None, amount_in: 3_000, amount_out: 4_000 },
+            SimulatedSwap { pool: None, amount_in: 4_800, amount_out: 4_500 },
+        ]);
+    }
+
+    #[test]
+    fn mismatch_over_tolerance_is_recorded() {
+        let simulator = build_simulator("http://localhost");
+        let pool = Pubkey::new_unique();
+
+        assert!(!simulator.recorder.record(Market::ORCA, pool, 1_000_500, 1_000_000, 10));
+        assert!(simulator.recorder.record(Market::ORCA, pool, 1_002_000, 1_000_000, 10));
+
+        let stats = simulator.recorder.get_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].2.compared, 2);
+        assert_eq!(stats[0].2.mismatches, 1);
+        assert_eq!(stats[0].2.max_diff_bps, 20);
+        assert_eq!(stats[0].2.last_mismatch, Some((1_002_000, 1_000_000)));
+    }
+
+    #[tokio::test]
+    async fn preflight_compares_quotes_and_profit() {
+        let path = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let logs = vec![
+            raydium_swap_event(&path[0], 1_000_000, 500_000, true),
+            orca_traded_event(&path[1], 500_000, 1_010_000),
+        ];
+        let server = start_server(vec![5_000_000], 100, logs, vec![5_010_000]).await;
+        let simulator = build_simulator(&server.url);
+        let base_token_account = Pubkey::new_unique();
+
+        // the second hop is quoted 1% over its simulated output
+        let opportunity = build_opportunity(path.clone(), &[500_000, 1_020_100]);
+        let result = simulator.preflight(&build_transaction(), &opportunity, &base_token_account, 5_000, 0, &[]).await.unwrap();
+        assert_eq!(result.slot, 100);
+        assert_eq!(result.units_consumed, Some(150_000));
+        assert_eq!(result.post_balances, vec![Some(5_010_000)]);
+
+        let stats = simulator.recorder.get_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].0, stats[0].1, stats[0].2.mismatches, stats[0].2.max_diff_bps), (Market::ORCA, path[1], 1, 100));
+        assert_eq!((stats[1].0, stats[1].1, stats[1].2.mismatches), (Market::RAYDIUM, path[0], 0));
+
+        // the pre balance is read right before the simulation, which runs at its slot or later
+        let requests = server.get_requests_of("getMultipleAccounts");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["params"][0], json!([base_token_account.to_string()]));
+        assert_eq!(requests[0]["params"][1]["commitment"], json!("confirmed"));
+        let requests = server.get_requests_of("simulateTransaction");
+        assert_eq!(requests.len(), 1);
+        let config = &requests[0]["params"][1];
+        assert_eq!(config["sigVerify"], json!(false));
+        assert_eq!(config["replaceRecentBlockhash"], json!(true));
+        assert_eq!(config["commitment"], json!("confirmed"));
+        assert_eq!(config["minContextSlot"], json!(100));
+        assert_eq!(config["accounts"]["addresses"], json!([base_token_account.to_string()]));
+
+        // profit of 10_000 is below the threshold
+        let err = simulator.preflight(&build_transaction(), &opportunity, &base_token_account, 20_000, 0, &[]).await.unwrap_err();
+        assert!(matches!(err, PreflightError::BelowThreshold { simulated_profit: 10_000, min_profit: 20_000 }));
+    }
+
+    #[tokio::test]
+    async fn preflight_takes_fees_out_of_sol_profits() {
+        let server = start_server(vec![5_000_000], 100, vec![], vec![5_010_000]).await;
+        let simulator = build_simulator(&server.url);
+        let base_token_account = Pubkey::new_unique();
+        let mut opportunity = build_opportunity(vec![Pubkey::new_unique(), Pubkey::new_unique()], &[500_000, 1_010_000]);
+
+        // fees are paid in SOL, they leave other base mints untouched
+        simulator.preflight(&build_transaction(), &opportunity, &base_token_account, 5_000, 6_000, &[]).await.unwrap();
+
+        opportunity.base_mint = Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap();
+        let err = simulator.preflight(&build_transaction(), &opportunity, &base_token_account, 5_000, 6_000, &[]).await.unwrap_err();
+        assert!(matches!(err, PreflightError::BelowThreshold { simulated_profit: 4_000, min_profit: 5_000 }));
+    }
+
+    #[test]
+    fn swaps_are_matched_to_hops_by_pool() {
+        let simulator = build_simulator("http://localhost");
+        let path = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let opportunity = build_opportunity(path.clone(), &[500_000, 1_010_000]);
+
+        // events out of path order and an event of another pool
+        simulator.compare(&opportunity, &[
+            SimulatedSwap { pool: Some(path[1]), amount_in: 500_000, amount_out: 1_010_000 },
+            SimulatedSwap { pool: Some(Pubkey::new_unique()), amount_in: 1, amount_out: 1 },
+            SimulatedSwap { pool: Some(path[0]), amount_in: 1_000_000, amount_out: 500_000 },
+        ], &[]);
+
+        let stats = simulator.recorder.get_stats();
+        assert_eq!(stats.len(), 2);
+        assert!(stats.iter().all(|(_, pool, stats)| path.contains(pool) && stats.compared == 1 && stats.mismatches == 0));
+    }
+
+    #[test]
+    fn hops_without_event_use_vault_outputs() {
+        let simulator = build_simulator("http://localhost");
+        let path = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let opportunity = build_opportunity(path.clone(), &[500_000, 1_010_000]);
+
+        // an amm v4 ray_log does not name its pool, the second hop has no output at all
+        simulator.compare(&opportunity, &[
+            SimulatedSwap { pool: None, amount_in: 1_000_000, amount_out: 500_000 },
+        ], &[Some(495_000), None]);
+
+        let stats = simulator.recorder.get_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].1, stats[0].2.mismatches, stats[0].2.last_mismatch), (path[0], 1, Some((500_000, 495_000))));
+    }
+
+    #[tokio::test]
+    async fn preflight_reads_vault_outputs() {
+        let server = start_server(vec![5_000_000, 2_000_000, 10_000_000], 100, vec![], vec![5_010_000, 1_500_000, 8_990_000]).await;
+        let simulator = build_simulator(&server.url);
+        let base_token_account = Pubkey::new_unique();
+        let vaults = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let opportunity = build_opportunity(vec![Pubkey::new_unique(), Pubkey::new_unique()], &[500_000, 1_010_000]);
+
+        simulator.preflight(&build_transaction(), &opportunity, &base_token_account, 5_000, 0, &[Some(vaults[0]), Some(vaults[1])]).await.unwrap();
+
+        let addresses = json!([base_token_account.to_string(), vaults[0].to_string(), vaults[1].to_string()]);
+        assert_eq!(server.get_requests_of("getMultipleAccounts")[0]["params"][0], addresses);
+        assert_eq!(server.get_requests_of("simulateTransaction")[0]["params"][1]["accounts"]["addresses"], addresses);
+        // both vaults paid out the quoted amounts
+        let stats = simulator.recorder.get_stats();
+        assert_eq!(stats.len(), 2);
+        assert!(stats.iter().all(|(_, _, stats)| stats.compared == 1 && stats.mismatches == 0));
+    }
+
+    #[tokio::test]
+    async fn preflight_skips_vault_outputs_of_a_later_slot() {
+        // other transactions moved the vaults between the slot they were read at and the simulated one
+        let server = start_server(vec![5_000_000, 2_000_000, 10_000_000], 101, vec![], vec![5_010_000, 1_400_000, 8_990_000]).await;
+        let simulator = build_simulator(&server.url);
+        let vaults = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let opportunity = build_opportunity(vec![Pubkey::new_unique(), Pubkey::new_unique()], &[500_000, 1_010_000]);
+
+        let result = simulator.preflight(&build_transaction(), &opportunity, &Pubkey::new_unique(), 5_000, 0, &[Some(vaults[0]), Some(vaults[1])]).await.unwrap();
+        assert_eq!(result.slot, 101);
+        assert!(simulator.recorder.get_stats().is_empty());
+    }
+
+    #[tokio::test]
+    async fn preflight_fails_with_simulation_error() {
+        let server = MockRpcServer::start(|method, _| {
+            match method {
+                "getMultipleAccounts" => Ok(balances_response(100, json!([token_account(5_000_000)]))),
+                _ => Ok(simulation_response(
+                    100,
+                    json!({ "InstructionError": [0, { "Custom": 6022 }] }),
+                    vec!["Program log: AnchorError occurred".to_string()],
+                    json!([null])
+                ))
+            }
+        }).await;
+        let simulator = build_simulator(&server.url);
+        let opportunity = build_opportunity(vec![Pubkey::new_unique(), Pubkey::new_unique()], &[500_000, 1_010_000]);
+
+        let err = simulator.preflight(&build_transaction(), &opportunity, &Pubkey::new_unique(), 5_000, 0, &[]).await.unwrap_err();
+        match err {
+            PreflightError::Failed { logs, .. } => assert_eq!(logs, vec!["Program log: AnchorError occurred".to_string()]),
+            err => panic!("unexpected error: {}", err),
+        }
+        assert!(simulator.recorder.get_stats().is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+#[cfg(test)]
+mod submitter_test {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use base64::Engine;
+    use base64::prelude::BASE64_STANDARD;
+    use serde_json::{json, Value};
+    use solana_sdk::account::Account;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Keypair;
+
+    use crate::arbitrageur::Opportunity;
+    use crate::config::read_config;
+    use crate::constants::{SABER_PROGRAM_PUBKEY, TOKEN_PROGRAM_PUBKEY};
+    use crate::executor::lookup_table::LookupTableManager;
+    use crate::executor::submitter::Submitter;
+    use crate::executor::test::mock_rpc_server::MockRpcServer;
+    use crate::executor::wallet::{get_associated_token_address, Wallet};
+    use crate::formula::base::SwapQuote;
+    use crate::metrics::registry::Metrics;
+    use crate::r#struct::account::DeserializedPoolAccount;
+    use crate::r#struct::market::Market;
+    use crate::r#struct::pools::{OrcaClmmMarket, SaberMarket};
+
+    const CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/config.json");
+
+    fn saber_pool(mint_a: Pubkey, mint_b: Pubkey) -> DeserializedPoolAccount {
+        let pubkey = Pubkey::new_unique();
+        let (_, nonce) = Pubkey::find_program_address(&[pubkey.as_ref()], &Pubkey::from_str(SABER_PROGRAM_PUBKEY).unwrap());
+        DeserializedPoolAccount {
+            pubkey,
+            account: Account::default(),
+            market: Market::SABER,
+            operation: Box::new(SaberMarket {
+                is_initialized: true,
+                nonce,
+                token_a: Pubkey::new_unique(),
+                token_b: Pubkey::new_unique(),
+                token_a_mint: mint_a,
+                token_b_mint: mint_b,
+                admin_fee_key_a: Pubkey::new_unique(),
+                admin_fee_key_b: Pubkey::new_unique(),
+                ..SaberMarket::default()
+            }),
+        }
+    }
+
+    fn build_opportunity(base_mint: Pubkey, path: Vec<Pubkey>, mints_out: Vec<Pubkey>, amounts: &[(u64, u64)]) -> Opportunity {
+        Opportunity {
+            base_mint,
+            markets: vec![Market::SABER; path.len()],
+            quotes: amounts.iter().map(|(amount_in, amount_out)| SwapQuote {
+                amount_in: *amount_in,
+                amount_out: *amount_out,
+                crossings: 0,
+            }).collect(),
+            path,
+            mints_out,
+            amount_in: amounts[0].0,
+            amount_out: amounts[amounts.len() - 1].1,
+            profit: 0,
+            quote_profit: None,
+            lamport_profit: Some(1_000_000),
+            compute_units: 200_000,
+        }
+    }
+
+    /// Serves every account as a mint of the token program, and what a transaction is built from but no simulation
+    async fn start_server() -> MockRpcServer {
+        MockRpcServer::start(|method, params| {
+            match method {
+                "getMultipleAccounts" => {
+                    let value = params[0].as_array().unwrap().iter().map(|_| json!({
+                        "lamports": 1_461_600,
+                        "data": [BASE64_STANDARD.encode([0u8; 82]), "base64"],
+                        "owner": TOKEN_PROGRAM_PUBKEY,
+                        "executable": false,
+                        "rentEpoch": 0,
+                        "space": 82
+                    })).collect::<Vec<Value>>();
+                    Ok(json!({ "context": { "slot": 100 }, "value": value }))
+                }
+                "getRecentPrioritizationFees" => Ok(json!([])),
+                "getLatestBlockhash" => {
+                    Ok(json!({ "context": { "slot": 100 }, "value": { "blockhash": Hash::new_unique().to_string(), "lastValidBlockHeight": 250 } }))
+                }
+                method => Err(json!({ "code": -32601, "message": format!("unexpected method {}", method) }))
+            }
+        }).await
+    }
+
+    #[tokio::test]
+    async fn swap_instructions_follow_the_path() {
+        let server = start_server().await;
+        let config = read_config(CONFIG_PATH).unwrap();
+        let (base_mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut wallet = Wallet::from_keypair(server.url.clone(), config.wallet.clone(), Keypair::new());
+        wallet.load_mints(&[base_mint, other_mint]).await.unwrap();
+        let owner = wallet.get_pubkey();
+
+        let whirlpool = DeserializedPoolAccount {
+            pubkey: Pubkey::new_unique(),
+            market: Market::ORCA,
+            operation: Box::new(OrcaClmmMarket { token_mint_a: base_mint, token_mint_b: other_mint, ..OrcaClmmMarket::default() }),
+            ..DeserializedPoolAccount::default()
+        };
+        let pools = vec![saber_pool(base_mint, other_mint), saber_pool(other_mint, base_mint), whirlpool];
+        let path = vec![pools[0].pubkey, pools[1].pubkey];
+        let unsupported = pools[2].pubkey;
+        let submitter = Submitter::new(
+            server.url.clone(),
+            &config,
+            Arc::new(wallet),
+            pools,
+            LookupTableManager::new(server.url.clone()),
+            Arc::new(Metrics::new())
+        );
+
+        let opportunity = build_opportunity(base_mint, path, vec![other_mint, base_mint], &[(1_000, 2_000), (2_000, 1_010)]);
+        let instructions = submitter.get_swap_instructions(&opportunity).unwrap();
+        assert_eq!(instructions.len(), 2);
+
+        // each hop spends its quoted input and requires its quoted output
+        let token_program = Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap();
+        let (base_account, other_account) = (
+            get_associated_token_address(&owner, &base_mint, &token_program),
+            get_associated_token_address(&owner, &other_mint, &token_program)
+        );
+        assert_eq!(instructions[0].data[1..9], 1_000u64.to_le_bytes());
+        assert_eq!(instructions[0].data[9..17], 2_000u64.to_le_bytes());
+        assert_eq!((instructions[0].accounts[3].pubkey, instructions[0].accounts[6].pubkey), (base_account, other_account));
+        assert_eq!(instructions[1].data[9..17], 1_010u64.to_le_bytes());
+        assert_eq!((instructions[1].accounts[3].pubkey, instructions[1].accounts[6].pubkey), (other_account, base_account));
+
+        // markets without an instruction builder cannot be sent
+        let opportunity = build_opportunity(base_mint, vec![unsupported], vec![other_mint], &[(1_000, 1_010)]);
+        assert_eq!(submitter.get_swap_instructions(&opportunity).unwrap_err(), "swap instructions not supported");
+    }
+
+    #[tokio::test]
+    async fn inventory_is_released_if_nothing_is_sent() {
+        let server = start_server().await;
+        let config = read_config(CONFIG_PATH).unwrap();
+        let (base_mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut wallet = Wallet::from_keypair(server.url.clone(), config.wallet.clone(), Keypair::new());
+        wallet.load_mints(&[base_mint, other_mint]).await.unwrap();
+        let wallet = Arc::new(wallet);
+
+        let pools = vec![saber_pool(base_mint, other_mint), saber_pool(other_mint, base_mint)];
+        let path = vec![pools[0].pubkey, pools[1].pubkey];
+        let submitter = Submitter::new(
+            server.url.clone(),
+            &config,
+            Arc::clone(&wallet),
+            pools,
+            LookupTableManager::new(server.url.clone()),
+            Arc::new(Metrics::new())
+        );
+        let opportunity = build_opportunity(base_mint, path, vec![other_mint, base_mint], &[(1_000, 2_000), (2_000, 1_010)]);
+
+        // nothing is built without the input at hand
+        assert!(submitter.submit(&[opportunity.clone()]).await.unwrap().is_none());
+        assert!(server.get_requests_of("getRecentPrioritizationFees").is_empty());
+
+        // the preflight reads no base token balance so nothing is sent, the input is spendable again
+        wallet.inventory.update(0, HashMap::from([(base_mint, 1_500)]));
+        let reads = server.get_requests_of("getMultipleAccounts").len();
+        assert!(submitter.submit(&[opportunity]).await.is_err());
+        assert_eq!(server.get_requests_of("getMultipleAccounts").len(), reads + 1);
+        assert!(server.get_requests_of("simulateTransaction").is_empty());
+        assert_eq!(wallet.inventory.get_available(&base_mint), 1_500);
+    }
+
+    #[tokio::test]
+    async fn opportunities_not_covering_the_tip_are_skipped() {
+        let server = start_server().await;
+        let config = read_config(CONFIG_PATH).unwrap();
+        let (base_mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut wallet = Wallet::from_keypair(server.url.clone(), config.wallet.clone(), Keypair::new());
+        wallet.load_mints(&[base_mint, other_mint]).await.unwrap();
+        wallet.inventory.update(0, HashMap::from([(base_mint, 1_500)]));
+
+        let pools = vec![saber_pool(base_mint, other_mint), saber_pool(other_mint, base_mint)];
+        let path = vec![pools[0].pubkey, pools[1].pubkey];
+        let submitter = Submitter::new(
+            server.url.clone(),
+            &config,
+            Arc::new(wallet),
+            pools,
+            LookupTableManager::new(server.url.clone()),
+            Arc::new(Metrics::new())
+        );
+
+        // a profit not priced in lamports, and one the signature fee leaves nothing of
+        let mut unpriced = build_opportunity(base_mint, path.clone(), vec![other_mint, base_mint], &[(1_000, 2_000), (2_000, 1_010)]);
+        unpriced.lamport_profit = None;
+        let mut thin = build_opportunity(base_mint, path, vec![other_mint, base_mint], &[(1_000, 2_000), (2_000, 1_010)]);
+        thin.lamport_profit = Some(config.jito.min_tip_lamports + 4_999);
+
+        assert!(submitter.submit(&[unpriced, thin]).await.unwrap().is_none());
+        assert!(server.get_requests_of("getLatestBlockhash").is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod tracker_test {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde_json::{json, Value};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use solana_transaction_status::UiTransactionStatusMeta;
+
+    use crate::executor::test::mock_rpc_server::MockRpcServer;
+    use crate::executor::tracker::{get_trade_result, ConfirmationTracker, TradeResult, TxStatus};
+
+    fn signature_status(slot: u64, confirmation_status: &str, err: Value) -> Value {
+        json!({
+            "slot": slot,
+            "confirmations": null,
+            "err": err,
+            "status": if err.is_null() { json!({ "Ok": null }) } else { json!({ "Err": err }) },
+            "confirmationStatus": confirmation_status
+        })
+    }
+
+    fn token_balance(account_index: u8, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Value {
+        json!({
+            "accountIndex": account_index,
+            "mint": mint.to_string(),
+            "uiTokenAmount": {
+                "uiAmount": null,
+                "decimals": 9,
+                "amount": amount.to_string(),
+                "uiAmountString": "0"
+            },
+            "owner": owner.to_string(),
+            "programId": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+        })
+    }
+
+    #[tokio::test]
+    async fn statuses_move_until_final() {
+        let (landing, failing, expiring, waiting) = (Signature::new_unique(), Signature::new_unique(), Signature::new_unique(), Signature::new_unique());
+        let polls = Arc::new(AtomicUsize::new(0));
+        let server_polls = Arc::clone(&polls);
+        let server = MockRpcServer::start(move |method, params| {
+            match method {
+                "getSignatureStatuses" => {
+                    let poll = server_polls.fetch_add(1, Ordering::SeqCst);
+                    let value = params[0].as_array().unwrap().iter().map(|signature| {
+                        match signature.as_str().unwrap() {
+                            signature if signature == landing.to_string() && poll == 0 => signature_status(100, "processed", Value::Null),
+                            signature if signature == landing.to_string() => signature_status(100, "finalized", Value::Null),
+                            signature if signature == failing.to_string() => signature_status(101, "confirmed", json!({ "InstructionError": [2, { "Custom": 6022 }] })),
+                            _ => Value::Null
+                        }
+                    }).collect::<Vec<Value>>();
+                    Ok(json!({ "context": { "slot": 110 }, "value": value }))
+                }
+                "getBlockHeight" => Ok(json!(1_000)),
+                method => Err(json!({ "code": -32601, "message": format!("unexpected method {}", method) }))
+            }
+        }).await;
+
+        let tracker = ConfirmationTracker::new(server.url.clone());
+        tracker.track(landing, 1_100);
+        tracker.track(failing, 1_100);
+        tracker.track(expiring, 999);
+        tracker.track(waiting, 1_100);
+
+        let mut changes = tracker.poll().await.unwrap();
+        changes.sort_by_key(|(signature, _)| signature.to_string());
+        let mut expected = vec![
+            (landing, TxStatus::Processed { slot: 100 }),
+            (failing, TxStatus::Failed { slot: 101, reason: "Error processing Instruction 2: custom program error: 0x1786".to_string() }),
+            (expiring, TxStatus::Expired),
+        ];
+        expected.sort_by_key(|(signature, _)| signature.to_string());
+        assert_eq!(changes, expected);
+        assert_eq!(tracker.get_pending_count(), 2);
+
+        assert_eq!(tracker.poll().await.unwrap(), vec![(landing, TxStatus::Finalized { slot: 100 })]);
+        assert_eq!(tracker.get_pending_count(), 1);
+
+        // nothing changed for the signature still waiting on its blockhash
+        assert!(tracker.poll().await.unwrap().is_empty());
+        assert_eq!(tracker.get_pending_count(), 1);
+    }
+
+    #[test]
+    fn trade_result_from_token_balances() {
+        let owner = Pubkey::new_unique();
+        let base_mint = Pubkey::new_unique();
+        let other_mint = Pubkey::new_unique();
+        let pool_vault_owner = Pubkey::new_unique();
+
+        let meta: UiTransactionStatusMeta = serde_json::from_value(json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 15_000,
+            "preBalances": [],
+            "postBalances": [],
+            "innerInstructions": null,
+            "logMessages": null,
+            "preTokenBalances": [
+                token_balance(1, &base_mint, &owner, 1_000_000),
+                token_balance(2, &other_mint, &owner, 500),
+                token_balance(3, &base_mint, &pool_vault_owner, 9_000_000)
+            ],
+            "postTokenBalances": [
+                token_balance(1, &base_mint, &owner, 1_012_500),
+                token_balance(2, &other_mint, &owner, 500),
+                token_balance(3, &base_mint, &pool_vault_owner, 8_987_500)
+            ],
+            "rewards": null,
+            "loadedAddresses": null,
+            "returnData": null,
+            "computeUnitsConsumed": 180_000
+        })).unwrap();
+
+        assert_eq!(get_trade_result(&meta, &owner, &base_mint), TradeResult {
+            fee: 15_000,
+            realized_profit: 12_500,
+        });
+        assert_eq!(get_trade_result(&meta, &pool_vault_owner, &base_mint).realized_profit, -12_500);
+    }
+}
@@ -0,0 +1,187 @@
+#[cfg(test)]
+mod wallet_test {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use base64::Engine;
+    use base64::prelude::BASE64_STANDARD;
+    use serde_json::{json, Value};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_program;
+
+    use crate::config::{BaseMintConfig, WalletConfig};
+    use crate::constants::{ASSOCIATED_TOKEN_PROGRAM_PUBKEY, NATIVE_MINT_PUBKEY, TOKEN_PROGRAM_PUBKEY};
+    use crate::executor::test::mock_rpc_server::MockRpcServer;
+    use crate::executor::wallet::{create_associated_token_account_idempotent, get_associated_token_address, sync_native, Inventory, Wallet};
+
+    fn build_config() -> WalletConfig {
+        WalletConfig {
+            keypair_path: String::new(),
+            wsol_min_balance: 1_000_000_000,
+            wsol_target_balance: 5_000_000_000,
+            sol_reserve: 100_000_000,
+            auto_top_up_wsol: true,
+            refresh_interval_ms: 1000,
+        }
+    }
+
+    fn account(owner: &Pubkey, data: Vec<u8>) -> Value {
+        json!({
+            "lamports": 2_039_280,
+            "data": [BASE64_STANDARD.encode(&data), "base64"],
+            "owner": owner.to_string(),
+            "executable": false,
+            "rentEpoch": 0,
+            "space": data.len()
+        })
+    }
+
+    fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Value {
+        let mut data = vec![0u8; 165];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        account(&Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap(), data)
+    }
+
+    /// Serves `accounts` by pubkey and `lamports` as the balance of any account
+    async fn start_server(accounts: HashMap<Pubkey, Value>, lamports: u64) -> MockRpcServer {
+        MockRpcServer::start(move |method, params| {
+            match method {
+                "getBalance" => Ok(json!({ "context": { "slot": 100 }, "value": lamports })),
+                "getMultipleAccounts" => {
+                    let value = params[0].as_array().unwrap().iter().map(|pubkey| {
+                        let pubkey = Pubkey::from_str(pubkey.as_str().unwrap()).unwrap();
+                        accounts.get(&pubkey).cloned().unwrap_or(Value::Null)
+                    }).collect::<Vec<Value>>();
+                    Ok(json!({ "context": { "slot": 100 }, "value": value }))
+                }
+                method => Err(json!({ "code": -32601, "message": format!("unexpected method {}", method) }))
+            }
+        }).await
+    }
+
+    #[test]
+    fn associated_token_account_instructions() {
+        let payer = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let token_program = Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap();
+        let associated_token_program = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_PUBKEY).unwrap();
+
+        let token_account = get_associated_token_address(&payer, &mint, &token_program);
+        assert_eq!(token_account, Pubkey::find_program_address(
+            &[payer.as_ref(), token_program.as_ref(), mint.as_ref()],
+            &associated_token_program
+        ).0);
+
+        let instruction = create_associated_token_account_idempotent(&payer, &payer, &mint, &token_program);
+        assert_eq!(instruction.program_id, associated_token_program);
+        assert_eq!(instruction.data, vec![1]);
+        assert_eq!(instruction.accounts.iter().map(|meta| {
+            (meta.pubkey, meta.is_signer, meta.is_writable)
+        }).collect::<Vec<_>>(), vec![
+            (payer, true, true),
+            (token_account, false, true),
+            (payer, false, false),
+            (mint, false, false),
+            (system_program::id(), false, false),
+            (token_program, false, false),
+        ]);
+
+        let instruction = sync_native(&token_account, &token_program);
+        assert_eq!(instruction.program_id, token_program);
+        assert_eq!(instruction.data, vec![17]);
+        assert_eq!(instruction.accounts.len(), 1);
+        assert!(instruction.accounts[0].is_writable);
+    }
+
+    #[test]
+    fn input_amounts_capped_by_inventory() {
+        let base_mint = BaseMintConfig {
+            symbol: "SOL".to_string(),
+            mint: Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap(),
+            min_amount_in: 100,
+            max_amount_in: 10_000,
+            inventory: 5_000,
+            min_profit: 0,
+        };
+
+        assert_eq!(base_mint.get_input_amounts(3, u64::MAX), vec![100, 707, 5_000]);
+        assert_eq!(base_mint.get_input_amounts(3, 400), vec![100, 200, 400]);
+        assert_eq!(base_mint.get_input_amounts(3, 100), vec![100]);
+        assert!(base_mint.get_input_amounts(3, 99).is_empty());
+    }
+
+    #[test]
+    fn reserved_inventory_is_not_available() {
+        let inventory = Inventory::default();
+        let mint = Pubkey::new_unique();
+        inventory.update(0, HashMap::from([(mint, 1_000)]));
+
+        assert!(inventory.reserve(&mint, 600));
+        assert_eq!(inventory.get_available(&mint), 400);
+        assert_eq!(inventory.get_balance(&mint), 1_000);
+        // a second in-flight transaction cannot spend what the first holds
+        assert!(!inventory.reserve(&mint, 500));
+        assert!(!inventory.reserve(&Pubkey::new_unique(), 1));
+
+        inventory.release(&mint, 600);
+        assert_eq!(inventory.get_available(&mint), 1_000);
+        inventory.release(&mint, 600);
+        assert_eq!(inventory.get_available(&mint), 1_000);
+    }
+
+    #[tokio::test]
+    async fn refresh_tracks_balances_and_missing_accounts() {
+        let keypair = Keypair::new();
+        let owner = keypair.pubkey();
+        let token_program = Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap();
+        let native_mint = Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap();
+        let usdc = Pubkey::new_unique();
+        let unknown = Pubkey::new_unique();
+
+        let accounts = HashMap::from([
+            (native_mint, account(&token_program, vec![0u8; 82])),
+            (usdc, account(&token_program, vec![0u8; 82])),
+            (get_associated_token_address(&owner, &native_mint, &token_program), token_account(&native_mint, &owner, 400_000_000)),
+        ]);
+        let server = start_server(accounts, 3_000_000_000).await;
+
+        let mut wallet = Wallet::from_keypair(server.url.clone(), build_config(), keypair);
+        wallet.load_mints(&[native_mint, usdc, unknown]).await.unwrap();
+        assert!(wallet.get_token_account(&usdc).is_some());
+        assert!(wallet.get_token_account(&unknown).is_none());
+
+        let missing = wallet.refresh().await.unwrap();
+        assert_eq!(missing, vec![usdc]);
+        assert_eq!(wallet.inventory.get_lamports(), 3_000_000_000);
+        assert_eq!(wallet.inventory.get_available(&native_mint), 400_000_000);
+        assert_eq!(wallet.inventory.get_available(&usdc), 0);
+
+        // wraps up to the target, limited by the SOL left above the reserve
+        assert_eq!(wallet.get_wsol_top_up(), 2_900_000_000);
+    }
+
+    #[tokio::test]
+    async fn wsol_top_up_only_below_minimum() {
+        let keypair = Keypair::new();
+        let owner = keypair.pubkey();
+        let token_program = Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap();
+        let native_mint = Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap();
+
+        let accounts = HashMap::from([
+            (native_mint, account(&token_program, vec![0u8; 82])),
+            (get_associated_token_address(&owner, &native_mint, &token_program), token_account(&native_mint, &owner, 1_000_000_000)),
+        ]);
+        let server = start_server(accounts, 10_000_000_000).await;
+
+        let mut wallet = Wallet::from_keypair(server.url.clone(), build_config(), keypair);
+        wallet.load_mints(&[native_mint]).await.unwrap();
+        wallet.refresh().await.unwrap();
+
+        assert_eq!(wallet.get_wsol_top_up(), 0);
+        assert_eq!(wallet.top_up_wsol().await.unwrap(), None);
+        assert!(server.get_requests_of("sendTransaction").is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus, UiTransactionEncoding, UiTransactionStatusMeta, UiTransactionTokenBalance};
+
+// getSignatureStatuses takes at most 256 signatures
+const MAX_SIGNATURE_STATUSES: usize = 256;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TxStatus {
+    Sent,
+    Processed {
+        slot: u64
+    },
+    Confirmed {
+        slot: u64
+    },
+    Finalized {
+        slot: u64
+    },
+    Failed {
+        slot: u64,
+        reason: String
+    },
+    /// not seen before its blockhash expired, it can no longer land
+    Expired
+}
+
+impl TxStatus {
+    /// No later status can follow
+    pub fn is_final(&self) -> bool {
+        matches!(self, TxStatus::Finalized { .. } | TxStatus::Failed { .. } | TxStatus::Expired)
+    }
+
+    /// Slot the transaction landed in, failed transactions land too
+    pub fn get_slot(&self) -> Option<u64> {
+        match self {
+            TxStatus::Processed { slot } | TxStatus::Confirmed { slot } | TxStatus::Finalized { slot } | TxStatus::Failed { slot, .. } => {
+                Some(*slot)
+            }
+            TxStatus::Sent | TxStatus::Expired => None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxStatus::Sent => "sent",
+            TxStatus::Processed { .. } => "processed",
+            TxStatus::Confirmed { .. } => "confirmed",
+            TxStatus::Finalized { .. } => "finalized",
+            TxStatus::Failed { .. } => "failed",
+            TxStatus::Expired => "expired"
+        }
+    }
+
+    fn from_transaction_status(status: &TransactionStatus) -> TxStatus {
+        if let Some(err) = &status.err {
+            return TxStatus::Failed {
+                slot: status.slot,
+                reason: err.to_string()
+            };
+        }
+
+        match status.confirmation_status {
+            Some(TransactionConfirmationStatus::Finalized) => TxStatus::Finalized { slot: status.slot },
+            Some(TransactionConfirmationStatus::Confirmed) => TxStatus::Confirmed { slot: status.slot },
+            _ => TxStatus::Processed { slot: status.slot }
+        }
+    }
+}
+
+/// Outcome of a landed transaction, read from its metadata
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TradeResult {
+    /// signature and priority fees, in lamports
+    pub fee: u64,
+    /// change of the owner's balance of the base mint
+    pub realized_profit: i128,
+}
+
+struct PendingTx {
+    last_valid_block_height: u64,
+    status: TxStatus
+}
+
+/// Follows submitted signatures until they are finalized, fail, or expire with their blockhash
+pub struct ConfirmationTracker {
+    rpc_client: RpcClient,
+    pending: Mutex<HashMap<Signature, PendingTx>>
+}
+
+impl ConfirmationTracker {
+    pub fn new(rpc_url: String) -> ConfirmationTracker {
+        ConfirmationTracker {
+            rpc_client: RpcClient::new(rpc_url),
+            pending: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// `last_valid_block_height` is the one of the blockhash the transaction was signed with
+    pub fn track(&self, signature: Signature, last_valid_block_height: u64) {
+        self.pending.lock().unwrap().insert(signature, PendingTx {
+            last_valid_block_height,
+            status: TxStatus::Sent
+        });
+    }
+
+    pub fn get_pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Fetches the status of every tracked signature and returns those that changed.
+    /// Signatures reaching a final status stop being tracked
+    pub async fn poll(&self) -> Result<Vec<(Signature, TxStatus)>, ClientError> {
+        let signatures = self.pending.lock().unwrap().keys().copied().collect::<Vec<Signature>>();
+        if signatures.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut statuses: Vec<Option<TransactionStatus>> = Vec::with_capacity(signatures.len());
+        for signatures in signatures.chunks(MAX_SIGNATURE_STATUSES) {
+            statuses.extend(self.rpc_client.get_signature_statuses(signatures).await?.value);
+        }
+        // only unseen signatures can expire
+        let block_height = if statuses.iter().any(|status| status.is_none()) {
+            Some(self.rpc_client.get_block_height().await?)
+        }
+        else {
+            None
+        };
+
+        let mut pending = self.pending.lock().unwrap();
+        let changes = signatures.iter().zip(statuses.iter()).filter_map(|(signature, status)| {
+            let tx = pending.get_mut(signature)?;
+            let status = match status {
+                Some(status) => TxStatus::from_transaction_status(status),
+                None if block_height.is_some_and(|height| height > tx.last_valid_block_height) => TxStatus::Expired,
+                None => return None
+            };
+            if status == tx.status {
+                return None;
+            }
+
+            tx.status = status.clone();
+            Some((*signature, status))
+        }).collect::<Vec<(Signature, TxStatus)>>();
+
+        changes.iter().filter(|(_, status)| status.is_final()).for_each(|(signature, _)| {
+            pending.remove(signature);
+        });
+
+        Ok(changes)
+    }
+
+    /// Reads the fee and the base mint balance change of `owner` from the landed transaction,
+    /// `None` if the node returned no metadata
+    pub async fn get_trade_result(
+        &self,
+        signature: &Signature,
+        owner: &Pubkey,
+        base_mint: &Pubkey
+    ) -> Result<Option<TradeResult>, ClientError> {
+        let transaction = self.rpc_client.get_transaction_with_config(signature, RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        }).await?;
+
+        Ok(transaction.transaction.meta.as_ref().map(|meta| get_trade_result(meta, owner, base_mint)))
+    }
+}
+
+/// Fee and base mint balance change of `owner` recorded in `meta`
+pub fn get_trade_result(meta: &UiTransactionStatusMeta, owner: &Pubkey, base_mint: &Pubkey) -> TradeResult {
+    let pre_amount = get_token_amount(&meta.pre_token_balances, owner, base_mint);
+    let post_amount = get_token_amount(&meta.post_token_balances, owner, base_mint);
+
+    TradeResult {
+        fee: meta.fee,
+        realized_profit: post_amount as i128 - pre_amount as i128,
+    }
+}
+
+fn get_token_amount(balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>, owner: &Pubkey, mint: &Pubkey) -> u128 {
+    let (owner, mint) = (owner.to_string(), mint.to_string());
+    match balances {
+        OptionSerializer::Some(balances) => {
+            balances.iter().filter(|balance| {
+                balance.mint == mint && matches!(&balance.owner, OptionSerializer::Some(balance_owner) if *balance_owner == owner)
+            }).filter_map(|balance| {
+                balance.ui_token_amount.amount.parse::<u64>().ok()
+            }).map(|amount| amount as u128).sum()
+        }
+        _ => 0
+    }
+}
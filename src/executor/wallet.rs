@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use log::debug;
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_sdk::{system_instruction, system_program};
+
+use crate::config::WalletConfig;
+use crate::constants::{ASSOCIATED_TOKEN_PROGRAM_PUBKEY, NATIVE_MINT_PUBKEY, TOKEN_ACCOUNT_DATA_LEN, TOKEN_PROGRAM_PUBKEY};
+use crate::r#struct::account::AccountDataSerializer;
+use crate::r#struct::token::TokenAccount;
+
+// getMultipleAccounts takes at most 100 pubkeys
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+// associated token accounts created by one setup transaction
+const ATA_CREATIONS_PER_TRANSACTION: usize = 8;
+
+// spl token and associated token account instructions
+const CREATE_IDEMPOTENT_INSTRUCTION: u8 = 1;
+const SYNC_NATIVE_INSTRUCTION: u8 = 17;
+
+/// Amounts the wallet holds, shared with the arbitrageur to size inputs
+#[derive(Debug, Default)]
+pub struct Inventory {
+    lamports: AtomicU64,
+    /// token amount per mint, held in the associated token account of the mint
+    balances: RwLock<HashMap<Pubkey, u64>>,
+    /// token amount per mint spent by transactions sent but not settled yet
+    reserved: RwLock<HashMap<Pubkey, u64>>
+}
+
+impl Inventory {
+    /// Amount of `mint` arbitrage may spend, the balance less what in-flight transactions spend.
+    /// 0 for a mint the wallet holds no account of
+    pub fn get_available(&self, mint: &Pubkey) -> u64 {
+        let reserved = self.reserved.read().unwrap().get(mint).copied().unwrap_or(0);
+        self.get_balance(mint).saturating_sub(reserved)
+    }
+
+    /// Amount of `mint` held, in-flight transactions included
+    pub fn get_balance(&self, mint: &Pubkey) -> u64 {
+        self.balances.read().unwrap().get(mint).copied().unwrap_or(0)
+    }
+
+    pub fn get_lamports(&self) -> u64 {
+        self.lamports.load(Ordering::Relaxed)
+    }
+
+    pub fn get_balances(&self) -> HashMap<Pubkey, u64> {
+        self.balances.read().unwrap().clone()
+    }
+
+    pub fn update(&self, lamports: u64, balances: HashMap<Pubkey, u64>) {
+        self.lamports.store(lamports, Ordering::Relaxed);
+        *self.balances.write().unwrap() = balances;
+    }
+
+    /// Sets `amount` of `mint` aside for a transaction being sent, returns false if less is available
+    pub fn reserve(&self, mint: &Pubkey, amount: u64) -> bool {
+        let balance = self.get_balance(mint);
+        let mut reserved = self.reserved.write().unwrap();
+        let mint_reserved = reserved.entry(*mint).or_insert(0);
+        if balance.saturating_sub(*mint_reserved) < amount {
+            return false;
+        }
+
+        *mint_reserved += amount;
+        true
+    }
+
+    /// Returns `amount` of `mint` once the transaction it was reserved for settled or was not sent
+    pub fn release(&self, mint: &Pubkey, amount: u64) {
+        if let Some(mint_reserved) = self.reserved.write().unwrap().get_mut(mint) {
+            *mint_reserved = mint_reserved.saturating_sub(amount);
+        }
+    }
+}
+
+pub struct Wallet {
+    keypair: Keypair,
+    rpc_client: RpcClient,
+    config: WalletConfig,
+    /// token program owning every tracked mint
+    mints: HashMap<Pubkey, Pubkey>,
+    pub inventory: Arc<Inventory>
+}
+
+impl Wallet {
+    /// Loads the keypair at the configured path
+    pub fn new(rpc_url: String, config: WalletConfig) -> Result<Wallet, Box<dyn Error>> {
+        let keypair = read_keypair_file(&config.keypair_path)
+            .map_err(|err| format!("failed to read keypair {}: {}", config.keypair_path, err))?;
+
+        Ok(Self::from_keypair(rpc_url, config, keypair))
+    }
+
+    pub fn from_keypair(rpc_url: String, config: WalletConfig, keypair: Keypair) -> Wallet {
+        Wallet {
+            keypair,
+            rpc_client: RpcClient::new(rpc_url),
+            config,
+            mints: HashMap::new(),
+            inventory: Default::default()
+        }
+    }
+
+    pub fn get_pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    pub fn get_keypair(&self) -> &Keypair {
+        &self.keypair
+    }
+
+    /// Tracks `mints`, reading which token program owns each.
+    /// Mints without an account are left out
+    pub async fn load_mints(&mut self, mints: &[Pubkey]) -> Result<(), ClientError> {
+        let accounts = self.fetch_accounts(mints).await?;
+        mints.iter().zip(accounts.iter()).for_each(|(mint, account)| {
+            match account {
+                Some(account) => {
+                    self.mints.insert(*mint, account.owner);
+                }
+                None => {
+                    debug!("wallet: mint {} not found", mint);
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Associated token account of `mint`, `None` if the mint is not tracked
+    pub fn get_token_account(&self, mint: &Pubkey) -> Option<Pubkey> {
+        let token_program = self.mints.get(mint)?;
+        Some(get_associated_token_address(&self.get_pubkey(), mint, token_program))
+    }
+
+    /// Fetches the SOL balance and the balance of every tracked mint into the inventory.
+    /// Returns the mints whose associated token account does not exist
+    pub async fn refresh(&self) -> Result<Vec<Pubkey>, ClientError> {
+        let lamports = self.rpc_client.get_balance(&self.get_pubkey()).await?;
+
+        let mints = self.mints.keys().copied().collect::<Vec<Pubkey>>();
+        let token_accounts = mints.iter().map(|mint| {
+            self.get_token_account(mint).unwrap()
+        }).collect::<Vec<Pubkey>>();
+        let accounts = self.fetch_accounts(&token_accounts).await?;
+
+        let mut balances: HashMap<Pubkey, u64> = HashMap::new();
+        let mut missing: Vec<Pubkey> = Vec::new();
+        mints.iter().zip(accounts.iter()).for_each(|(mint, account)| {
+            match account {
+                Some(account) if account.data.len() >= TOKEN_ACCOUNT_DATA_LEN => {
+                    balances.insert(*mint, TokenAccount::unpack_data(&account.data).amount);
+                }
+                _ => {
+                    missing.push(*mint);
+                }
+            }
+        });
+
+        self.inventory.update(lamports, balances);
+        Ok(missing)
+    }
+
+    /// Lamports to wrap so the wSOL account gets back to the target balance,
+    /// keeping the SOL reserve unwrapped. 0 if the wSOL balance is not below its minimum
+    pub fn get_wsol_top_up(&self) -> u64 {
+        let native_mint = Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap();
+        let wsol_balance = self.inventory.get_balance(&native_mint);
+        if wsol_balance >= self.config.wsol_min_balance {
+            return 0;
+        }
+
+        let spendable = self.inventory.get_lamports().saturating_sub(self.config.sol_reserve);
+        (self.config.wsol_target_balance - wsol_balance.min(self.config.wsol_target_balance)).min(spendable)
+    }
+
+    /// Wraps SOL into the wSOL account if its balance dropped below the minimum
+    pub async fn top_up_wsol(&self) -> Result<Option<Signature>, ClientError> {
+        let native_mint = Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap();
+        let amount = self.get_wsol_top_up();
+        if amount == 0 {
+            return Ok(None);
+        }
+
+        let token_program = Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap();
+        let wsol_account = get_associated_token_address(&self.get_pubkey(), &native_mint, &token_program);
+        let instructions = vec![
+            create_associated_token_account_idempotent(&self.get_pubkey(), &self.get_pubkey(), &native_mint, &token_program),
+            system_instruction::transfer(&self.get_pubkey(), &wsol_account, amount),
+            sync_native(&wsol_account, &token_program),
+        ];
+
+        let signature = self.send(&instructions).await?;
+        debug!("wallet: wrapped {} lamports ({})", amount, signature);
+        Ok(Some(signature))
+    }
+
+    /// Creates the missing associated token accounts of the tracked mints and tops up the wSOL account
+    pub async fn setup(&self) -> Result<Vec<Signature>, ClientError> {
+        let missing = self.refresh().await?;
+        let mut signatures: Vec<Signature> = Vec::new();
+
+        for mints in missing.chunks(ATA_CREATIONS_PER_TRANSACTION) {
+            let instructions = mints.iter().map(|mint| {
+                create_associated_token_account_idempotent(&self.get_pubkey(), &self.get_pubkey(), mint, &self.mints[mint])
+            }).collect::<Vec<Instruction>>();
+
+            let signature = self.send(&instructions).await?;
+            debug!("wallet: created {} token account(s) ({})", mints.len(), signature);
+            signatures.push(signature);
+        }
+
+        self.refresh().await?;
+        if let Some(signature) = self.top_up_wsol().await? {
+            signatures.push(signature);
+        }
+
+        Ok(signatures)
+    }
+
+    async fn send(&self, instructions: &[Instruction]) -> Result<Signature, ClientError> {
+        let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.get_pubkey()),
+            &[&self.keypair],
+            recent_blockhash
+        );
+
+        self.rpc_client.send_and_confirm_transaction(&transaction).await
+    }
+
+    async fn fetch_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>, ClientError> {
+        let mut accounts: Vec<Option<Account>> = Vec::with_capacity(pubkeys.len());
+        for pubkeys in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            accounts.extend(self.rpc_client.get_multiple_accounts(pubkeys).await?);
+        }
+
+        Ok(accounts)
+    }
+}
+
+pub fn get_associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_PUBKEY).unwrap()
+    ).0
+}
+
+/// Creates the associated token account of `mint`, succeeding if it already exists
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey
+) -> Instruction {
+    Instruction::new_with_bytes(
+        Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_PUBKEY).unwrap(),
+        &[CREATE_IDEMPOTENT_INSTRUCTION],
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(get_associated_token_address(owner, mint, token_program), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ]
+    )
+}
+
+/// Sets the token amount of a wSOL account to its lamports above rent
+pub fn sync_native(wsol_account: &Pubkey, token_program: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *token_program,
+        &[SYNC_NATIVE_INSTRUCTION],
+        vec![AccountMeta::new(*wsol_account, false)]
+    )
+}
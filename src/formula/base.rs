@@ -5,8 +5,20 @@ pub enum Formula {
     ConstantProduct,
     ConcentratedLiquidity,
     DynamicLiquidity,
-    OpenBook
+    OpenBook,
+    OrderBook,
+    StableSwap,
+    StakePool
 }
 
 pub trait SwapSimulator {
-}
\ No newline at end of file
+}
+
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// initialized ticks crossed by a concentrated liquidity swap, bins traversed by a dynamic liquidity swap,
+    /// resting orders filled by an order book swap, orders an AMM v4 swap cancels on its OpenBook market
+    pub crossings: u32,
+}
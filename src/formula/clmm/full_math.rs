@@ -175,7 +175,7 @@ impl MulDiv for U128 {
     }
 
     fn to_underflow_u64(self) -> u64 {
-        if self < U128::from(u64::MAX) {
+        if self <= U128::from(u64::MAX) {
             self.as_u64()
         } else {
             0
@@ -207,7 +207,7 @@ impl MulDiv for U256 {
     }
 
     fn to_underflow_u64(self) -> u64 {
-        if self < U256::from(u64::MAX) {
+        if self <= U256::from(u64::MAX) {
             self.as_u64()
         } else {
             0
@@ -8,4 +8,5 @@ pub mod test;
 pub mod u256_math;
 pub mod raydium_tick_math;
 pub mod orca_tick_math;
+pub mod orca_adaptive_fee;
 pub mod full_math;
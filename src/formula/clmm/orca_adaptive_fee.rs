@@ -0,0 +1,189 @@
+use crate::formula::clmm::orca_swap_state::{MAX_TICK_INDEX, MIN_TICK_INDEX};
+use crate::formula::clmm::orca_tick_math::{sqrt_price_from_tick_index, tick_index_from_sqrt_price};
+use crate::r#struct::pools::{AdaptiveFeeConstants, AdaptiveFeeVariables};
+
+pub const FEE_RATE_HARD_LIMIT: u32 = 100_000;
+pub const VOLATILITY_ACCUMULATOR_SCALE_FACTOR: u16 = 10_000;
+pub const REDUCTION_FACTOR_DENOMINATOR: u16 = 10_000;
+pub const ADAPTIVE_FEE_CONTROL_FACTOR_DENOMINATOR: u32 = 100_000;
+pub const MAX_REFERENCE_AGE: u64 = 3_600;
+
+/// Adaptive fee state of a whirlpool, read from its oracle account
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct AdaptiveFeeInfo {
+    pub constants: AdaptiveFeeConstants,
+    pub variables: AdaptiveFeeVariables,
+}
+
+impl AdaptiveFeeVariables {
+    /// Decays or resets the volatility reference by the time elapsed since the last swaps
+    pub fn update_reference(
+        &mut self,
+        tick_group_index: i32,
+        current_timestamp: u64,
+        constants: &AdaptiveFeeConstants,
+    ) -> Result<(), &'static str> {
+        let max_timestamp = self.last_reference_update_timestamp.max(self.last_major_swap_timestamp);
+        if current_timestamp < max_timestamp {
+            return Err("ErrorCode::InvalidTimestamp");
+        }
+
+        let reference_age = current_timestamp - self.last_reference_update_timestamp;
+        if reference_age > MAX_REFERENCE_AGE {
+            self.tick_group_index_reference = tick_group_index;
+            self.volatility_reference = 0;
+            self.last_reference_update_timestamp = current_timestamp;
+            return Ok(());
+        }
+
+        let elapsed = current_timestamp - max_timestamp;
+        if elapsed < constants.filter_period as u64 {
+            // high frequency trades keep the reference
+        } else if elapsed < constants.decay_period as u64 {
+            self.tick_group_index_reference = tick_group_index;
+            self.volatility_reference = (self.volatility_accumulator as u64
+                * constants.reduction_factor as u64
+                / REDUCTION_FACTOR_DENOMINATOR as u64) as u32;
+            self.last_reference_update_timestamp = current_timestamp;
+        } else {
+            self.tick_group_index_reference = tick_group_index;
+            self.volatility_reference = 0;
+            self.last_reference_update_timestamp = current_timestamp;
+        }
+
+        Ok(())
+    }
+
+    /// Volatility of the swap at `tick_group_index`, the reference plus the tick groups moved from it
+    pub fn update_volatility_accumulator(&mut self, tick_group_index: i32, constants: &AdaptiveFeeConstants) {
+        let index_delta = (self.tick_group_index_reference as i64 - tick_group_index as i64).unsigned_abs();
+        let volatility_accumulator = self.volatility_reference as u64
+            + index_delta * VOLATILITY_ACCUMULATOR_SCALE_FACTOR as u64;
+
+        self.volatility_accumulator = volatility_accumulator.min(constants.max_volatility_accumulator as u64) as u32;
+    }
+}
+
+/// Adaptive part of the fee rate, growing with the square of the volatility accumulator
+pub fn compute_adaptive_fee_rate(constants: &AdaptiveFeeConstants, variables: &AdaptiveFeeVariables) -> u32 {
+    let crossed = variables.volatility_accumulator as u128 * constants.tick_group_size as u128;
+    let numerator = constants.adaptive_fee_control_factor as u128 * crossed * crossed;
+    let denominator = ADAPTIVE_FEE_CONTROL_FACTOR_DENOMINATOR as u128
+        * VOLATILITY_ACCUMULATOR_SCALE_FACTOR as u128
+        * VOLATILITY_ACCUMULATOR_SCALE_FACTOR as u128;
+
+    numerator.div_ceil(denominator).min(FEE_RATE_HARD_LIMIT as u128) as u32
+}
+
+/// Fee rate of each swap step, the adaptive fee being charged per tick group the price moves through
+pub enum FeeRateManager {
+    Static {
+        static_fee_rate: u16,
+    },
+    Adaptive {
+        a_to_b: bool,
+        tick_group_index: i32,
+        static_fee_rate: u16,
+        constants: AdaptiveFeeConstants,
+        variables: AdaptiveFeeVariables,
+    },
+}
+
+impl FeeRateManager {
+    pub fn new(
+        a_to_b: bool,
+        current_tick_index: i32,
+        timestamp: u64,
+        static_fee_rate: u16,
+        adaptive_fee_info: &Option<AdaptiveFeeInfo>,
+    ) -> Result<Self, &'static str> {
+        match adaptive_fee_info {
+            None => Ok(Self::Static { static_fee_rate }),
+            Some(info) => {
+                if info.constants.tick_group_size == 0 {
+                    return Err("ErrorCode::InvalidAdaptiveFeeConstants");
+                }
+                let tick_group_index = current_tick_index.div_euclid(info.constants.tick_group_size as i32);
+                let mut variables = info.variables;
+                variables.update_reference(tick_group_index, timestamp, &info.constants)?;
+
+                Ok(Self::Adaptive {
+                    a_to_b,
+                    tick_group_index,
+                    static_fee_rate,
+                    constants: info.constants,
+                    variables,
+                })
+            }
+        }
+    }
+
+    pub fn update_volatility_accumulator(&mut self) {
+        if let Self::Adaptive { tick_group_index, constants, variables, .. } = self {
+            variables.update_volatility_accumulator(*tick_group_index, constants);
+        }
+    }
+
+    pub fn get_total_fee_rate(&self) -> u32 {
+        match self {
+            Self::Static { static_fee_rate } => *static_fee_rate as u32,
+            Self::Adaptive { static_fee_rate, constants, variables, .. } => {
+                let total_fee_rate = *static_fee_rate as u32 + compute_adaptive_fee_rate(constants, variables);
+                total_fee_rate.min(FEE_RATE_HARD_LIMIT)
+            }
+        }
+    }
+
+    /// Bounds the step target to the edge of the current tick group, returns whether the bounding was skipped.
+    /// Steps without liquidity move no amount, so they are not bounded
+    pub fn get_bounded_sqrt_price_target(&self, sqrt_price: u128, curr_liquidity: u128) -> (u128, bool) {
+        match self {
+            Self::Static { .. } => (sqrt_price, false),
+            Self::Adaptive { a_to_b, tick_group_index, constants, .. } => {
+                if curr_liquidity == 0 {
+                    return (sqrt_price, true);
+                }
+
+                let tick_group_size = constants.tick_group_size as i32;
+                let boundary_tick_index = if *a_to_b {
+                    *tick_group_index * tick_group_size
+                } else {
+                    *tick_group_index * tick_group_size + tick_group_size
+                };
+                let boundary_sqrt_price = sqrt_price_from_tick_index(boundary_tick_index.clamp(MIN_TICK_INDEX, MAX_TICK_INDEX));
+
+                if *a_to_b {
+                    (sqrt_price.max(boundary_sqrt_price), false)
+                } else {
+                    (sqrt_price.min(boundary_sqrt_price), false)
+                }
+            }
+        }
+    }
+
+    /// Moves to the next tick group after a step ended on the edge of the current one
+    pub fn advance_tick_group(&mut self) {
+        if let Self::Adaptive { a_to_b, tick_group_index, .. } = self {
+            *tick_group_index += if *a_to_b { -1 } else { 1 };
+        }
+    }
+
+    /// Moves to the tick group the price ended in after an unbounded step
+    pub fn advance_tick_group_after_skip(&mut self, sqrt_price: u128, next_tick_sqrt_price: u128, next_tick_index: i32) {
+        if let Self::Adaptive { a_to_b, tick_group_index, constants, .. } = self {
+            let tick_index = if sqrt_price == next_tick_sqrt_price {
+                next_tick_index
+            } else {
+                tick_index_from_sqrt_price(&sqrt_price)
+            };
+            let tick_group_size = constants.tick_group_size as i32;
+
+            // on an edge the last group traversed is the one the edge closes
+            *tick_group_index = if !*a_to_b && tick_index % tick_group_size == 0 && sqrt_price == sqrt_price_from_tick_index(tick_index) {
+                tick_index.div_euclid(tick_group_size) - 1
+            } else {
+                tick_index.div_euclid(tick_group_size)
+            };
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use std::ops::{Add, Div, Mul};
+use std::sync::Arc;
 use arrayref::{array_ref, array_refs};
 use num_traits::ToPrimitive;
 use solana_sdk::pubkey::Pubkey;
 
+use crate::constants::{ORCA_CLMM_DYNAMIC_TICK_DATA, ORCA_CLMM_TICK_ARRAY};
 use crate::formula::clmm::constant::TICK_ARRAY_SEED;
 use crate::r#struct::account::AccountDataSerializer;
 use crate::r#struct::market::Market;
@@ -27,6 +29,8 @@ pub struct PostSwapUpdate {
     pub next_fee_growth_global: u128,
     pub next_reward_infos: [WhirlpoolRewardInfo; NUM_REWARDS],
     pub next_protocol_fee: u64,
+    /// Ticks crossed by the swap as (array index, tick index, update), the quote does not write them
+    pub tick_updates: Vec<(usize, i32, TickUpdate)>,
 }
 
 pub struct SwapTickSequence {
@@ -38,6 +42,20 @@ impl SwapTickSequence {
         ta0: TickArray,
         ta1: Option<TickArray>,
         ta2: Option<TickArray>,
+    ) -> Self {
+        Self::new_shared(
+            Arc::new(ta0),
+            ta1.map(Arc::new),
+            ta2.map(Arc::new),
+        )
+    }
+
+    /// Same as `new`, but borrows the tick arrays of an account snapshot.
+    /// A tick array is copied only when the swap crosses one of its initialized ticks
+    pub fn new_shared(
+        ta0: Arc<TickArray>,
+        ta1: Option<Arc<TickArray>>,
+        ta2: Option<Arc<TickArray>>,
     ) -> Self {
         Self::new_with_proxy(
             ProxiedTickArray::new_initialized(ta0),
@@ -165,12 +183,12 @@ impl SwapTickSequence {
 }
 
 pub(crate) enum ProxiedTickArray {
-    Initialized(TickArray),
+    Initialized(Arc<TickArray>),
     Uninitialized(ZeroedTickArray),
 }
 
 impl ProxiedTickArray {
-    pub fn new_initialized(refmut: TickArray) -> Self {
+    pub fn new_initialized(refmut: Arc<TickArray>) -> Self {
         ProxiedTickArray::Initialized(refmut)
     }
 
@@ -228,7 +246,7 @@ impl ProxiedTickArray {
 impl<'a> AsRef<dyn TickArrayType + 'a> for ProxiedTickArray {
     fn as_ref(&self) -> &(dyn TickArrayType + 'a) {
         match self {
-            ProxiedTickArray::Initialized(ref array) => array,
+            ProxiedTickArray::Initialized(ref array) => &**array,
             ProxiedTickArray::Uninitialized(ref array) => array,
         }
     }
@@ -237,7 +255,7 @@ impl<'a> AsRef<dyn TickArrayType + 'a> for ProxiedTickArray {
 impl<'a> AsMut<dyn TickArrayType + 'a> for ProxiedTickArray {
     fn as_mut(&mut self) -> &mut (dyn TickArrayType + 'a) {
         match self {
-            ProxiedTickArray::Initialized(ref mut array) => &mut *array,
+            ProxiedTickArray::Initialized(ref mut array) => Arc::make_mut(array),
             ProxiedTickArray::Uninitialized(ref mut array) => array,
         }
     }
@@ -270,7 +288,7 @@ impl TickUpdate {
 pub struct TickArrayAccount {
     pub pubkey: Pubkey,
     pub market: Market,
-    pub tick_array: TickArray
+    pub tick_array: Arc<TickArray>
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -282,6 +300,10 @@ pub struct TickArray {
 
 impl AccountDataSerializer for TickArray {
     fn unpack_data(data: &Vec<u8>) -> Self {
+        if data.len() != ORCA_CLMM_TICK_ARRAY {
+            return Self::unpack_dynamic_data(data);
+        }
+
         let src = array_ref![data, 0, 9988];
         let (discriminator, start_tick_index, ticks, whirlpool) =
             array_refs![src, 8, 4, 9944, 32];
@@ -294,6 +316,40 @@ impl AccountDataSerializer for TickArray {
     }
 }
 
+impl TickArray {
+    /// Decodes a DynamicTickArray, whose ticks are a borsh enum carrying data only when initialized
+    pub fn unpack_dynamic_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 60]; // 60
+        let (discriminator, start_tick_index, whirlpool, tick_bitmap) =
+            array_refs![src, 8, 4, 32, 16];
+
+        let mut ticks = [Tick::default(); TICK_ARRAY_SIZE_USIZE];
+        let mut offset = src.len();
+        for tick in ticks.iter_mut() {
+            // the initialized variant tag is laid out as the initialized flag of a fixed tick
+            if data[offset] != 0 {
+                *tick = Tick::unpack_data(&data[offset..offset + 1 + ORCA_CLMM_DYNAMIC_TICK_DATA].to_vec());
+                offset += ORCA_CLMM_DYNAMIC_TICK_DATA;
+            }
+            offset += 1;
+        }
+
+        TickArray {
+            start_tick_index: i32::from_le_bytes(*start_tick_index),
+            ticks,
+            whirlpool: Pubkey::new_from_array(*whirlpool),
+        }
+    }
+
+    /// Initialized ticks whose index is in `(lower_tick_index, upper_tick_index]`
+    pub fn count_initialized_ticks(&self, tick_spacing: u16, lower_tick_index: i32, upper_tick_index: i32) -> u32 {
+        self.ticks.iter().enumerate().filter(|(offset, tick)| {
+            let tick_index = self.start_tick_index + *offset as i32 * tick_spacing as i32;
+            tick.initialized && lower_tick_index < tick_index && tick_index <= upper_tick_index
+        }).count() as u32
+    }
+}
+
 impl TickArrayType for TickArray {
 
     fn start_tick_index(&self) -> i32 {
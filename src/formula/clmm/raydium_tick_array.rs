@@ -1,4 +1,5 @@
 use std::ops::BitXor;
+use std::sync::Arc;
 use arrayref::{array_ref, array_refs};
 use num_traits::Zero;
 use solana_sdk::pubkey::Pubkey;
@@ -153,7 +154,7 @@ impl TickState {
 pub struct TickArrayStateAccount {
     pub pubkey: Pubkey,
     pub market: Market,
-    pub tick_array_state: TickArrayState
+    pub tick_array_state: Arc<TickArrayState>
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -230,12 +231,19 @@ impl TickArrayState {
     pub fn tick_count(tick_spacing: u16) -> i32 {
         TICK_ARRAY_SIZE * i32::from(tick_spacing)
     }
-    pub fn first_initialized_tick(&mut self, zero_for_one: bool) -> Result<&mut TickState, &'static str> {
+
+    /// Initialized ticks whose index is in `(lower_tick_index, upper_tick_index]`
+    pub fn count_initialized_ticks(&self, lower_tick_index: i32, upper_tick_index: i32) -> u32 {
+        self.ticks.iter().filter(|tick| {
+            tick.is_initialized() && lower_tick_index < tick.tick && tick.tick <= upper_tick_index
+        }).count() as u32
+    }
+    pub fn first_initialized_tick(&self, zero_for_one: bool) -> Result<&TickState, &'static str> {
         if zero_for_one {
             let mut i = TICK_ARRAY_SIZE - 1;
             while i >= 0 {
                 if self.ticks[i as usize].is_initialized() {
-                    return Ok(self.ticks.get_mut(i as usize).unwrap());
+                    return Ok(&self.ticks[i as usize]);
                 }
                 i = i - 1;
             }
@@ -243,7 +251,7 @@ impl TickArrayState {
             let mut i = 0;
             while i < TICK_ARRAY_SIZE_USIZE {
                 if self.ticks[i].is_initialized() {
-                    return Ok(self.ticks.get_mut(i).unwrap());
+                    return Ok(&self.ticks[i]);
                 }
                 i = i + 1;
             }
@@ -252,11 +260,11 @@ impl TickArrayState {
     }
 
     pub fn next_initialized_tick(
-        &mut self,
+        &self,
         current_tick_index: i32,
         tick_spacing: u16,
         zero_for_one: bool,
-    ) -> Result<Option<&mut TickState>, &'static str> {
+    ) -> Result<Option<&TickState>, &'static str> {
         let current_tick_array_start_index =
             TickArrayState::get_array_start_index(current_tick_index, tick_spacing);
         if current_tick_array_start_index != self.start_tick_index {
@@ -268,7 +276,7 @@ impl TickArrayState {
         if zero_for_one {
             while offset_in_array >= 0 {
                 if self.ticks[offset_in_array as usize].is_initialized() {
-                    return Ok(self.ticks.get_mut(offset_in_array as usize));
+                    return Ok(self.ticks.get(offset_in_array as usize));
                 }
                 offset_in_array = offset_in_array - 1;
             }
@@ -276,7 +284,7 @@ impl TickArrayState {
             offset_in_array = offset_in_array + 1;
             while offset_in_array < TICK_ARRAY_SIZE {
                 if self.ticks[offset_in_array as usize].is_initialized() {
-                    return Ok(self.ticks.get_mut(offset_in_array as usize));
+                    return Ok(self.ticks.get(offset_in_array as usize));
                 }
                 offset_in_array = offset_in_array + 1;
             }
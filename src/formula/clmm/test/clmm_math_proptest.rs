@@ -0,0 +1,161 @@
+/*
+    property tests for the tick math of both concentrated liquidity implementations and MulDiv
+    every property is checked against either the other implementation or a BigUint oracle
+ */
+
+#[cfg(test)]
+mod clmm_math_proptest {
+    use std::str::FromStr;
+
+    use num_bigint::BigUint;
+    use proptest::prelude::*;
+
+    use crate::formula::clmm::constant::{MAX_SQRT_PRICE_X64, MAX_TICK, MIN_SQRT_PRICE_X64, MIN_TICK, ORCA_MAX_SQRT_PRICE_X64};
+    use crate::formula::clmm::full_math::MulDiv;
+    use crate::formula::clmm::orca_tick_math::{sqrt_price_from_tick_index, tick_index_from_sqrt_price};
+    use crate::formula::clmm::raydium_tick_math::{get_sqrt_price_at_tick, get_tick_at_sqrt_price};
+    use crate::formula::clmm::u256_math::{U128, U256};
+
+    fn to_biguint<T: ToString>(value: T) -> BigUint {
+        BigUint::from_str(value.to_string().as_str()).unwrap()
+    }
+
+    fn oracle_mul_div(value: BigUint, num: BigUint, denom: BigUint, round_up: bool) -> BigUint {
+        let product = value * num;
+        let quotient = &product / &denom;
+        if round_up && product % denom != BigUint::default() {
+            quotient + 1u32
+        } else {
+            quotient
+        }
+    }
+
+    // mixes full range values with small ones so both the overflowing and the fitting branches get exercised
+    fn any_u64() -> impl Strategy<Value=u64> {
+        prop_oneof![any::<u64>(), 0..=u32::MAX as u64, Just(u64::MAX)]
+    }
+
+    fn any_u128() -> impl Strategy<Value=u128> {
+        prop_oneof![any::<u128>(), 0..=u64::MAX as u128, Just(u64::MAX as u128), Just(u128::MAX)]
+    }
+
+    fn any_u256() -> impl Strategy<Value=U256> {
+        prop_oneof![
+            any::<[u64; 4]>().prop_map(U256),
+            any::<[u64; 2]>().prop_map(|limbs| U256([limbs[0], limbs[1], 0, 0])),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn orca_tick_round_trip(tick in MIN_TICK..=MAX_TICK) {
+            let sqrt_price_x64 = sqrt_price_from_tick_index(tick);
+            prop_assert_eq!(tick_index_from_sqrt_price(&sqrt_price_x64), tick);
+        }
+
+        #[test]
+        fn raydium_tick_round_trip(tick in MIN_TICK..=MAX_TICK) {
+            let sqrt_price_x64 = get_sqrt_price_at_tick(tick).unwrap();
+            prop_assert_eq!(get_tick_at_sqrt_price(sqrt_price_x64).unwrap(), tick);
+        }
+
+        #[test]
+        fn sqrt_price_is_monotonic(tick in MIN_TICK..MAX_TICK) {
+            prop_assert!(sqrt_price_from_tick_index(tick) < sqrt_price_from_tick_index(tick + 1));
+            prop_assert!(get_sqrt_price_at_tick(tick).unwrap() < get_sqrt_price_at_tick(tick + 1).unwrap());
+        }
+
+        #[test]
+        fn sqrt_price_within_bounds(tick in MIN_TICK..=MAX_TICK) {
+            let orca = sqrt_price_from_tick_index(tick);
+            let raydium = get_sqrt_price_at_tick(tick).unwrap();
+
+            prop_assert!((MIN_SQRT_PRICE_X64..=ORCA_MAX_SQRT_PRICE_X64).contains(&orca));
+            prop_assert!((MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&raydium));
+        }
+
+        #[test]
+        fn orca_and_raydium_sqrt_price_agree(tick in MIN_TICK..=MAX_TICK) {
+            let orca = sqrt_price_from_tick_index(tick);
+            let raydium = get_sqrt_price_at_tick(tick).unwrap();
+
+            // both use Q64.64 but different magic factors, the gap stays below 2^-32 relative
+            let diff = orca.abs_diff(raydium);
+            prop_assert!(diff << 32 <= orca.max(raydium), "tick {}: orca {} raydium {}", tick, orca, raydium);
+        }
+
+        #[test]
+        fn orca_tick_is_floor_of_sqrt_price(sqrt_price_x64 in MIN_SQRT_PRICE_X64..ORCA_MAX_SQRT_PRICE_X64) {
+            let tick = tick_index_from_sqrt_price(&sqrt_price_x64);
+
+            prop_assert!(sqrt_price_from_tick_index(tick) <= sqrt_price_x64);
+            prop_assert!(tick == MAX_TICK || sqrt_price_x64 < sqrt_price_from_tick_index(tick + 1));
+        }
+
+        #[test]
+        fn raydium_tick_is_floor_of_sqrt_price(sqrt_price_x64 in MIN_SQRT_PRICE_X64..MAX_SQRT_PRICE_X64) {
+            let tick = get_tick_at_sqrt_price(sqrt_price_x64).unwrap();
+
+            prop_assert!(get_sqrt_price_at_tick(tick).unwrap() <= sqrt_price_x64);
+            prop_assert!(tick == MAX_TICK || sqrt_price_x64 < get_sqrt_price_at_tick(tick + 1).unwrap());
+        }
+
+        #[test]
+        fn mul_div_u64(value in any_u64(), num in any_u64(), denom in any_u64().prop_filter("non-zero", |d| *d != 0)) {
+            for round_up in [false, true] {
+                let expected = oracle_mul_div(value.into(), num.into(), denom.into(), round_up);
+                let result = if round_up { value.mul_div_ceil(num, denom) } else { value.mul_div_floor(num, denom) };
+
+                if expected > BigUint::from(u64::MAX) {
+                    prop_assert_eq!(result, None);
+                } else {
+                    prop_assert_eq!(result.map(BigUint::from), Some(expected));
+                }
+            }
+        }
+
+        #[test]
+        fn mul_div_u128(value in any_u128(), num in any_u128(), denom in any_u128().prop_filter("non-zero", |d| *d != 0)) {
+            for round_up in [false, true] {
+                let expected = oracle_mul_div(value.into(), num.into(), denom.into(), round_up);
+                let (value, num, denom) = (U128::from(value), U128::from(num), U128::from(denom));
+                let result = if round_up { value.mul_div_ceil(num, denom) } else { value.mul_div_floor(num, denom) };
+
+                if expected > BigUint::from(u128::MAX) {
+                    prop_assert_eq!(result, None);
+                } else {
+                    prop_assert_eq!(result.map(to_biguint), Some(expected));
+                }
+            }
+        }
+
+        #[test]
+        fn mul_div_u256(value in any_u256(), num in any_u256(), denom in any_u256().prop_filter("non-zero", |d| !d.is_zero())) {
+            for round_up in [false, true] {
+                let expected = oracle_mul_div(to_biguint(value), to_biguint(num), to_biguint(denom), round_up);
+                let result = if round_up { value.mul_div_ceil(num, denom) } else { value.mul_div_floor(num, denom) };
+
+                if expected > to_biguint(U256::MAX) {
+                    prop_assert_eq!(result, None);
+                } else {
+                    prop_assert_eq!(result.map(to_biguint), Some(expected));
+                }
+            }
+        }
+
+        #[test]
+        fn mul_div_ceil_is_floor_or_next(value in any_u64(), num in any_u64(), denom in any_u64().prop_filter("non-zero", |d| *d != 0)) {
+            if let (Some(floor), Some(ceil)) = (value.mul_div_floor(num, denom), value.mul_div_ceil(num, denom)) {
+                let exact = (value as u128 * num as u128) % denom as u128 == 0;
+                prop_assert_eq!(ceil, if exact { floor } else { floor + 1 });
+            }
+        }
+
+        #[test]
+        fn to_underflow_u64(value in any_u128()) {
+            let expected = u64::try_from(value).unwrap_or(0);
+            prop_assert_eq!(U128::from(value).to_underflow_u64(), expected);
+            prop_assert_eq!(U256::from(value).to_underflow_u64(), expected);
+        }
+    }
+}
@@ -2,4 +2,7 @@ pub mod swap_test_fixture;
 pub mod liquidity_test_fixture;
 mod raydium_swap_test;
 mod orca_swap_test;
-mod complex_swap_test;
\ No newline at end of file
+mod complex_swap_test;
+mod clmm_math_proptest;
+mod swap_step_proptest;
+mod orca_adaptive_fee_test;
@@ -0,0 +1,325 @@
+#[cfg(test)]
+mod orca_adaptive_fee_test {
+    use std::sync::Arc;
+
+    use solana_sdk::account::Account;
+    use solana_sdk::clock::Clock;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::sysvar::clock;
+
+    use crate::constants::{ORCA_CLMM_DYNAMIC_TICK_ARRAY_MIN_LEN, ORCA_CLMM_DYNAMIC_TICK_DATA, ORCA_CLMM_ORACLE, ORCA_CLMM_TICK_ARRAY};
+    use crate::formula::clmm::orca_adaptive_fee::{AdaptiveFeeInfo, compute_adaptive_fee_rate};
+    use crate::formula::clmm::orca_swap_state::{NO_EXPLICIT_SQRT_PRICE_LIMIT, SwapTickSequence, Tick, TickArrayAccount};
+    use crate::formula::clmm::test::swap_test_fixture::{SwapTestFixture, SwapTestFixtureInfo, TS_128};
+    use crate::formula::orca_clmm::swap_internal;
+    use crate::r#struct::account::{DeserializedAccount, DeserializedConfigAccount, DeserializedDataAccount};
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::{AdaptiveFeeConstants, AdaptiveFeeVariables, OrcaClmmAccount, OrcaClmmMarket, WhirlpoolOracle, WhirlpoolOracleAccount};
+
+    const INITIALIZED_OFFSETS: [usize; 2] = [3, 40];
+
+    fn tick_data(liquidity_net: i128) -> Vec<u8> {
+        let mut data = vec![1u8];
+        data.extend_from_slice(&liquidity_net.to_le_bytes());
+        data.extend_from_slice(&liquidity_net.unsigned_abs().to_le_bytes());
+        data.extend_from_slice(&7u128.to_le_bytes());
+        data.extend_from_slice(&11u128.to_le_bytes());
+        [1u128, 2, 3].iter().for_each(|growth| data.extend_from_slice(&growth.to_le_bytes()));
+        data
+    }
+
+    fn fixed_tick_array_data(start_tick_index: i32, whirlpool: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; 8];
+        data.extend_from_slice(&start_tick_index.to_le_bytes());
+        for offset in 0..88 {
+            if INITIALIZED_OFFSETS.contains(&offset) {
+                data.extend_from_slice(&tick_data(-5000 * offset as i128));
+            } else {
+                data.extend_from_slice(&[0u8; 113]);
+            }
+        }
+        data.extend_from_slice(whirlpool.as_ref());
+        data
+    }
+
+    fn dynamic_tick_array_data(start_tick_index: i32, whirlpool: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; 8];
+        data.extend_from_slice(&start_tick_index.to_le_bytes());
+        data.extend_from_slice(whirlpool.as_ref());
+        let tick_bitmap = INITIALIZED_OFFSETS.iter().fold(0u128, |bitmap, offset| bitmap | 1 << offset);
+        data.extend_from_slice(&tick_bitmap.to_le_bytes());
+        for offset in 0..88 {
+            if INITIALIZED_OFFSETS.contains(&offset) {
+                data.extend_from_slice(&tick_data(-5000 * offset as i128));
+            } else {
+                data.push(0);
+            }
+        }
+        data
+    }
+
+    fn adaptive_fee_constants() -> AdaptiveFeeConstants {
+        AdaptiveFeeConstants {
+            filter_period: 30,
+            decay_period: 600,
+            reduction_factor: 5000,
+            adaptive_fee_control_factor: 4000,
+            max_volatility_accumulator: 350_000,
+            tick_group_size: TS_128,
+            major_swap_threshold_ticks: 0,
+        }
+    }
+
+    fn oracle_data(oracle: &WhirlpoolOracle) -> Vec<u8> {
+        let constants = oracle.adaptive_fee_constants;
+        let variables = oracle.adaptive_fee_variables;
+
+        let mut data = vec![0u8; 8];
+        data.extend_from_slice(oracle.whirlpool.as_ref());
+        data.extend_from_slice(&oracle.trade_enable_timestamp.to_le_bytes());
+        data.extend_from_slice(&constants.filter_period.to_le_bytes());
+        data.extend_from_slice(&constants.decay_period.to_le_bytes());
+        data.extend_from_slice(&constants.reduction_factor.to_le_bytes());
+        data.extend_from_slice(&constants.adaptive_fee_control_factor.to_le_bytes());
+        data.extend_from_slice(&constants.max_volatility_accumulator.to_le_bytes());
+        data.extend_from_slice(&constants.tick_group_size.to_le_bytes());
+        data.extend_from_slice(&constants.major_swap_threshold_ticks.to_le_bytes());
+        data.extend_from_slice(&[0u8; 16]);
+        data.extend_from_slice(&variables.last_reference_update_timestamp.to_le_bytes());
+        data.extend_from_slice(&variables.last_major_swap_timestamp.to_le_bytes());
+        data.extend_from_slice(&variables.volatility_reference.to_le_bytes());
+        data.extend_from_slice(&variables.tick_group_index_reference.to_le_bytes());
+        data.extend_from_slice(&variables.volatility_accumulator.to_le_bytes());
+        data.extend_from_slice(&[0u8; 16]);
+        data.extend_from_slice(&[0u8; 128]);
+        data
+    }
+
+    // b to a over empty tick arrays from the middle of the first tick group
+    fn swap_fixture(trade_amount: u64) -> SwapTestFixture {
+        SwapTestFixture::new(SwapTestFixtureInfo {
+            tick_spacing: TS_128,
+            liquidity: 10_000_000_000,
+            curr_tick_index: 64,
+            start_tick_index: 0,
+            trade_amount,
+            amount_specified_is_input: true,
+            a_to_b: false,
+            array_2_ticks: Some(&vec![]),
+            array_3_ticks: Some(&vec![]),
+            fee_rate: 3000,
+            ..Default::default()
+        })
+    }
+
+    fn quote(fixture: &SwapTestFixture, adaptive_fee_info: &Option<AdaptiveFeeInfo>) -> u64 {
+        let tick_sequence = SwapTickSequence::new(
+            fixture.tick_arrays[0].to_owned(),
+            Some(fixture.tick_arrays[1].to_owned()),
+            Some(fixture.tick_arrays[2].to_owned()),
+        );
+        let post_swap = swap_internal(
+            &fixture.whirlpool,
+            &tick_sequence,
+            fixture.trade_amount,
+            NO_EXPLICIT_SQRT_PRICE_LIMIT,
+            fixture.amount_specified_is_input,
+            fixture.a_to_b,
+            1_000,
+            adaptive_fee_info,
+        ).unwrap();
+
+        assert_eq!(post_swap.amount_b, fixture.trade_amount);
+        post_swap.amount_a
+    }
+
+    #[test]
+    fn dynamic_tick_array_decodes_as_fixed() {
+        let whirlpool = Pubkey::new_unique();
+        let fixed = fixed_tick_array_data(-11264, &whirlpool);
+        let dynamic = dynamic_tick_array_data(-11264, &whirlpool);
+        assert_eq!(fixed.len(), ORCA_CLMM_TICK_ARRAY);
+        assert_eq!(dynamic.len(), ORCA_CLMM_DYNAMIC_TICK_ARRAY_MIN_LEN + INITIALIZED_OFFSETS.len() * ORCA_CLMM_DYNAMIC_TICK_DATA);
+
+        let fixed = match OrcaClmmAccount::resolve_account(Pubkey::new_unique(), &fixed) {
+            OrcaClmmAccount::TickArray(fixed) => fixed,
+            _ => panic!("not a tick array")
+        };
+        let dynamic = match OrcaClmmAccount::resolve_account(Pubkey::new_unique(), &dynamic) {
+            OrcaClmmAccount::TickArray(dynamic) => dynamic,
+            _ => panic!("not a tick array")
+        };
+
+        assert_eq!(dynamic.tick_array.start_tick_index, -11264);
+        assert_eq!(dynamic.tick_array.whirlpool, whirlpool);
+        for (offset, tick) in dynamic.tick_array.ticks.iter().enumerate() {
+            if INITIALIZED_OFFSETS.contains(&offset) {
+                assert_eq!(*tick, fixed.tick_array.ticks[offset]);
+                assert_eq!(tick.liquidity_net, -5000 * offset as i128);
+                assert_eq!(tick.reward_growths_outside, [1, 2, 3]);
+            } else {
+                assert_eq!(*tick, Tick::default());
+            }
+        }
+        assert_eq!(dynamic.tick_array.count_initialized_ticks(TS_128, i32::MIN, i32::MAX), 2);
+
+        // an empty dynamic tick array is its header and a tag per tick
+        let mut empty = vec![0u8; ORCA_CLMM_DYNAMIC_TICK_ARRAY_MIN_LEN];
+        empty[8..12].copy_from_slice(&11264i32.to_le_bytes());
+        let empty = match OrcaClmmAccount::resolve_account(Pubkey::new_unique(), &empty) {
+            OrcaClmmAccount::TickArray(empty) => empty,
+            _ => panic!("not a tick array")
+        };
+        assert_eq!(empty.tick_array.start_tick_index, 11264);
+        assert!(empty.tick_array.ticks.iter().all(|tick| !tick.initialized));
+    }
+
+    #[test]
+    fn decode_oracle() {
+        let oracle = WhirlpoolOracle {
+            whirlpool: Pubkey::new_unique(),
+            trade_enable_timestamp: 1_700_000_000,
+            adaptive_fee_constants: adaptive_fee_constants(),
+            adaptive_fee_variables: AdaptiveFeeVariables {
+                last_reference_update_timestamp: 1_700_000_100,
+                last_major_swap_timestamp: 1_700_000_050,
+                volatility_reference: 20_000,
+                tick_group_index_reference: -3,
+                volatility_accumulator: 40_000,
+            },
+        };
+        let data = oracle_data(&oracle);
+        assert_eq!(data.len(), ORCA_CLMM_ORACLE);
+
+        let pubkey = Pubkey::new_unique();
+        let account = match OrcaClmmAccount::resolve_account(pubkey, &data) {
+            OrcaClmmAccount::Oracle(account) => account,
+            _ => panic!("not an oracle")
+        };
+        assert_eq!(account.pubkey, pubkey);
+        assert_eq!(account.oracle, oracle);
+    }
+
+    #[test]
+    fn adaptive_fee_rate_and_reference() {
+        let constants = adaptive_fee_constants();
+        let variables = AdaptiveFeeVariables { volatility_accumulator: 10_000, ..Default::default() };
+        // 4000 * (10000 * 128)^2 / 10^13, rounded up
+        assert_eq!(compute_adaptive_fee_rate(&constants, &variables), 656);
+        assert_eq!(compute_adaptive_fee_rate(&constants, &AdaptiveFeeVariables::default()), 0);
+
+        let mut variables = AdaptiveFeeVariables {
+            last_reference_update_timestamp: 1_000,
+            last_major_swap_timestamp: 1_000,
+            volatility_reference: 5_000,
+            tick_group_index_reference: 2,
+            volatility_accumulator: 20_000,
+        };
+
+        // within the filter period the reference is kept
+        variables.update_reference(5, 1_010, &constants).unwrap();
+        assert_eq!((variables.tick_group_index_reference, variables.volatility_reference), (2, 5_000));
+        variables.update_volatility_accumulator(5, &constants);
+        assert_eq!(variables.volatility_accumulator, 35_000);
+
+        // within the decay period the accumulator decays into the reference
+        variables.update_reference(5, 1_100, &constants).unwrap();
+        assert_eq!((variables.tick_group_index_reference, variables.volatility_reference), (5, 17_500));
+
+        // past the decay period the reference resets
+        variables.update_reference(4, 1_800, &constants).unwrap();
+        assert_eq!((variables.tick_group_index_reference, variables.volatility_reference), (4, 0));
+
+        assert!(variables.update_reference(4, 1_700, &constants).is_err());
+
+        variables.update_volatility_accumulator(100, &constants);
+        assert_eq!(variables.volatility_accumulator, constants.max_volatility_accumulator);
+    }
+
+    #[test]
+    fn adaptive_fee_applied_in_swap_internal() {
+        let adaptive_fee_info = Some(AdaptiveFeeInfo {
+            constants: adaptive_fee_constants(),
+            variables: AdaptiveFeeVariables::default(),
+        });
+
+        // inside the first tick group the volatility is zero, only the static fee is charged
+        let fixture = swap_fixture(1_000_000);
+        assert_eq!(quote(&fixture, &adaptive_fee_info), quote(&fixture, &None));
+
+        // crossing tick groups raises the fee of each group moved through
+        let fixture = swap_fixture(100_000_000);
+        let static_amount_out = quote(&fixture, &None);
+        let adaptive_amount_out = quote(&fixture, &adaptive_fee_info);
+        assert!(adaptive_amount_out < static_amount_out);
+
+        // no control factor, no adaptive fee, the steps split on the tick groups round the same way
+        let no_control_info = Some(AdaptiveFeeInfo {
+            constants: AdaptiveFeeConstants { adaptive_fee_control_factor: 0, ..adaptive_fee_constants() },
+            variables: AdaptiveFeeVariables::default(),
+        });
+        let no_control_amount_out = quote(&fixture, &no_control_info);
+        assert!(no_control_amount_out.abs_diff(static_amount_out) <= 4);
+        assert!(adaptive_amount_out < no_control_amount_out);
+    }
+
+    #[test]
+    fn swap_requires_oracle_of_adaptive_fee_pool() {
+        let fixture = swap_fixture(100_000_000);
+        let mint_a = Pubkey::new_unique();
+        let mint_b = Pubkey::new_unique();
+
+        let static_market = OrcaClmmMarket {
+            token_mint_a: mint_a,
+            token_mint_b: mint_b,
+            ..fixture.whirlpool
+        };
+        // the seed of an adaptive fee pool is its fee tier index
+        let adaptive_market = OrcaClmmMarket {
+            tick_spacing_seed: 1024u16.to_le_bytes(),
+            ..static_market
+        };
+        assert!(!static_market.is_initialized_with_adaptive_fee());
+        assert!(adaptive_market.is_initialized_with_adaptive_fee());
+
+        let mut tick_arrays: Vec<DeserializedAccount> = fixture.tick_arrays.iter().map(|tick_array| {
+            DeserializedAccount::ConfigAccount(DeserializedConfigAccount::OrcaClmmConfigAccount(OrcaClmmAccount::TickArray(TickArrayAccount {
+                pubkey: Pubkey::new_unique(),
+                market: Market::ORCA,
+                tick_array: Arc::new(tick_array.clone()),
+            })))
+        }).collect();
+        // the quote reads the time from the clock sysvar
+        assert_eq!(static_market.swap(&tick_arrays, &mint_b, fixture.trade_amount).err(), Some("clock not found"));
+        assert!(static_market.get_swap_related_pubkeys().iter().any(|(_, pubkey)| *pubkey == clock::id()));
+        let clock = Clock { unix_timestamp: 1_700_000_000, ..Clock::default() };
+        tick_arrays.push(DeserializedAccount::Account(DeserializedDataAccount {
+            pubkey: clock::id(),
+            account: Account { data: bincode::serialize(&clock).unwrap(), ..Account::default() },
+            market: Market::ORCA,
+        }));
+        assert_eq!(adaptive_market.swap(&tick_arrays, &mint_b, fixture.trade_amount).err(), Some("oracle not found"));
+
+        let oracle = WhirlpoolOracle {
+            adaptive_fee_constants: adaptive_fee_constants(),
+            ..Default::default()
+        };
+        let with_oracle = |oracle: WhirlpoolOracle| {
+            let mut accounts = tick_arrays.clone();
+            accounts.push(DeserializedAccount::ConfigAccount(DeserializedConfigAccount::OrcaClmmConfigAccount(OrcaClmmAccount::Oracle(WhirlpoolOracleAccount {
+                pubkey: Pubkey::new_unique(),
+                oracle,
+                market: Market::ORCA,
+            }))));
+            accounts
+        };
+
+        let disabled = WhirlpoolOracle { trade_enable_timestamp: u64::MAX, ..oracle };
+        assert_eq!(adaptive_market.swap(&with_oracle(disabled), &mint_b, fixture.trade_amount).err(), Some("trade not enabled"));
+
+        let static_quote = static_market.swap(&tick_arrays, &mint_b, fixture.trade_amount).unwrap();
+        let adaptive_quote = adaptive_market.swap(&with_oracle(oracle), &mint_b, fixture.trade_amount).unwrap();
+        assert_eq!(adaptive_quote.amount_in, static_quote.amount_in);
+        assert!(adaptive_quote.amount_out < static_quote.amount_out);
+    }
+}
@@ -0,0 +1,151 @@
+/*
+    property tests for compute_swap_step, shared by orca and raydium swap_internal
+    prices are kept within +-40000 ticks and liquidity below 2^61 so every delta amount fits u64
+ */
+
+#[cfg(test)]
+mod swap_step_proptest {
+    use proptest::prelude::*;
+
+    use crate::formula::clmm::concentrated_liquidity::compute_swap_step;
+    use crate::formula::clmm::raydium_swap_state::calculate_amount_in_range;
+    use crate::formula::clmm::raydium_tick_math::get_sqrt_price_at_tick;
+
+    #[derive(Debug, Clone)]
+    struct StepParam {
+        sqrt_price_current_x64: u128,
+        sqrt_price_target_x64: u128,
+        liquidity: u128,
+        zero_for_one: bool,
+    }
+
+    impl StepParam {
+        fn max_amount_out(&self) -> u64 {
+            calculate_amount_in_range(
+                self.sqrt_price_current_x64,
+                self.sqrt_price_target_x64,
+                self.liquidity,
+                self.zero_for_one,
+                false
+            ).unwrap()
+        }
+
+        fn is_between(&self, sqrt_price_x64: u128) -> bool {
+            if self.zero_for_one {
+                self.sqrt_price_target_x64 <= sqrt_price_x64 && sqrt_price_x64 <= self.sqrt_price_current_x64
+            } else {
+                self.sqrt_price_current_x64 <= sqrt_price_x64 && sqrt_price_x64 <= self.sqrt_price_target_x64
+            }
+        }
+    }
+
+    fn step_param() -> impl Strategy<Value=StepParam> {
+        (-20000i32..20000, 1i32..20000, 1u128..(1u128 << 61), any::<bool>()).prop_map(|(tick, offset, liquidity, zero_for_one)| {
+            let target_tick = if zero_for_one { tick - offset } else { tick + offset };
+            StepParam {
+                sqrt_price_current_x64: get_sqrt_price_at_tick(tick).unwrap(),
+                sqrt_price_target_x64: get_sqrt_price_at_tick(target_tick).unwrap(),
+                liquidity,
+                zero_for_one,
+            }
+        })
+    }
+
+    fn amount() -> impl Strategy<Value=u64> {
+        prop_oneof![1u64..1_000_000, 1u64..(1u64 << 62)]
+    }
+
+    fn fee_rate() -> impl Strategy<Value=u32> {
+        prop_oneof![Just(0u32), 1u32..100_000]
+    }
+
+    proptest! {
+        #[test]
+        fn exact_in_spends_at_most_amount(param in step_param(), amount_remaining in amount(), fee_rate in fee_rate()) {
+            let step = compute_swap_step(
+                param.sqrt_price_current_x64,
+                param.sqrt_price_target_x64,
+                param.liquidity,
+                amount_remaining,
+                fee_rate,
+                true,
+                param.zero_for_one
+            ).unwrap();
+
+            prop_assert!(param.is_between(step.sqrt_price_next_x64), "{:?} -> {:?}", param, step);
+            prop_assert!(step.amount_out <= param.max_amount_out(), "{:?} -> {:?}", param, step);
+
+            if step.sqrt_price_next_x64 == param.sqrt_price_target_x64 {
+                prop_assert!(step.amount_in + step.fee_amount <= amount_remaining, "{:?} -> {:?}", param, step);
+            } else {
+                prop_assert_eq!(step.amount_in + step.fee_amount, amount_remaining);
+            }
+
+            if fee_rate > 0 && step.amount_in > 0 {
+                prop_assert!(step.fee_amount > 0, "{:?} -> {:?}", param, step);
+            }
+        }
+
+        #[test]
+        fn exact_out_never_exceeds_request(param in step_param(), amount_remaining in amount(), fee_rate in fee_rate()) {
+            let step = compute_swap_step(
+                param.sqrt_price_current_x64,
+                param.sqrt_price_target_x64,
+                param.liquidity,
+                amount_remaining,
+                fee_rate,
+                false,
+                param.zero_for_one
+            ).unwrap();
+
+            prop_assert!(param.is_between(step.sqrt_price_next_x64), "{:?} -> {:?}", param, step);
+            prop_assert!(step.amount_out <= amount_remaining, "{:?} -> {:?}", param, step);
+            prop_assert!(step.amount_out <= param.max_amount_out(), "{:?} -> {:?}", param, step);
+
+            // stopping short of the target means the whole request was filled
+            if step.sqrt_price_next_x64 != param.sqrt_price_target_x64 {
+                prop_assert_eq!(step.amount_out, amount_remaining);
+            }
+
+            if fee_rate > 0 && step.amount_in > 0 {
+                prop_assert!(step.fee_amount > 0, "{:?} -> {:?}", param, step);
+            }
+        }
+
+        #[test]
+        fn exact_in_then_exact_out_is_consistent(param in step_param(), amount_in in amount(), fee_rate in fee_rate()) {
+            let exact_in = compute_swap_step(
+                param.sqrt_price_current_x64,
+                param.sqrt_price_target_x64,
+                param.liquidity,
+                amount_in,
+                fee_rate,
+                true,
+                param.zero_for_one
+            ).unwrap();
+            prop_assume!(exact_in.amount_out > 0);
+
+            let exact_out = compute_swap_step(
+                param.sqrt_price_current_x64,
+                param.sqrt_price_target_x64,
+                param.liquidity,
+                exact_in.amount_out,
+                fee_rate,
+                false,
+                param.zero_for_one
+            ).unwrap();
+
+            prop_assert_eq!(exact_out.amount_out, exact_in.amount_out);
+
+            if exact_in.sqrt_price_next_x64 == param.sqrt_price_target_x64 {
+                prop_assert_eq!(exact_out.sqrt_price_next_x64, param.sqrt_price_target_x64);
+                prop_assert_eq!(exact_out.amount_in, exact_in.amount_in);
+                prop_assert_eq!(exact_out.fee_amount, exact_in.fee_amount);
+            } else if exact_out.sqrt_price_next_x64 != param.sqrt_price_target_x64 {
+                // buying back the same output never costs more than what was paid for it
+                prop_assert!(exact_out.amount_in <= exact_in.amount_in, "{:?}: {:?} / {:?}", param, exact_in, exact_out);
+                prop_assert!(exact_out.amount_in + exact_out.fee_amount <= amount_in, "{:?}: {:?} / {:?}", param, exact_in, exact_out);
+            }
+        }
+    }
+}
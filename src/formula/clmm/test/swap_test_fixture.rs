@@ -204,16 +204,7 @@ impl SwapTestFixture {
     }
 
     pub fn run(&self, tick_sequence: &mut SwapTickSequence, next_timestamp: u64) -> PostSwapUpdate {
-        swap_internal(
-            &self.whirlpool,
-            tick_sequence,
-            self.trade_amount,
-            self.sqrt_price_limit,
-            self.amount_specified_is_input,
-            self.a_to_b,
-            next_timestamp,
-        )
-        .unwrap()
+        self.eval(tick_sequence, next_timestamp).unwrap()
     }
 
     pub fn eval(
@@ -221,7 +212,7 @@ impl SwapTestFixture {
         tick_sequence: &mut SwapTickSequence,
         next_timestamp: u64,
     ) -> Result<PostSwapUpdate, &'static str> {
-        swap_internal(
+        let post_swap = swap_internal(
             &self.whirlpool,
             tick_sequence,
             self.trade_amount,
@@ -229,6 +220,13 @@ impl SwapTestFixture {
             self.amount_specified_is_input,
             self.a_to_b,
             next_timestamp,
-        )
+            &None,
+        )?;
+
+        // write the crossed ticks the way the program does
+        for (array_index, tick_index, update) in post_swap.tick_updates.iter() {
+            tick_sequence.update_tick(*array_index, *tick_index, self.whirlpool.tick_spacing, update)?;
+        }
+        Ok(post_swap)
     }
 }
@@ -502,12 +502,9 @@ impl BinArray {
         let (lower_bin_id, upper_bin_id) =
             BinArray::get_bin_array_lower_upper_bin_id(self.index as i32)?;
 
-        // todo
-        // require!(
-        //     bin_id >= lower_bin_id && bin_id <= upper_bin_id,
-        //     LBError::InvalidBinId
-        // );
-        assert!(bin_id >= lower_bin_id && bin_id <= upper_bin_id);
+        if bin_id < lower_bin_id || bin_id > upper_bin_id {
+            return Err("LBError::InvalidBinId");
+        }
 
         Ok(())
     }
@@ -1,9 +1,12 @@
+use arrayref::{array_ref, array_refs};
 use ruint::aliases::U512;
 use std::ops::BitXor;
 use solana_sdk::pubkey::Pubkey;
+use crate::constants::METEORA_DLMM_BIN_ARRAY_BITMAP_EXTENSION;
 use crate::formula::dlmm::constant::{BIN_ARRAY_BITMAP_SIZE, EXTENSION_BIN_ARRAY_BITMAP_SIZE};
 use crate::formula::dlmm::safe_math::SafeMath;
 use crate::formula::dlmm::utils_math::one;
+use crate::r#struct::account::AccountDataSerializer;
 
 #[derive(Debug)]
 pub struct BinArrayBitmapExtension {
@@ -25,6 +28,20 @@ impl Default for BinArrayBitmapExtension {
     }
 }
 
+impl AccountDataSerializer for BinArrayBitmapExtension {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, METEORA_DLMM_BIN_ARRAY_BITMAP_EXTENSION];
+        let (discriminator, lb_pair, positive_bin_array_bitmap, negative_bin_array_bitmap) =
+            array_refs![src, 8, 32, 768, 768];
+
+        BinArrayBitmapExtension {
+            lb_pair: Pubkey::new_from_array(*lb_pair),
+            positive_bin_array_bitmap: bytemuck::cast(*positive_bin_array_bitmap),
+            negative_bin_array_bitmap: bytemuck::cast(*negative_bin_array_bitmap),
+        }
+    }
+}
+
 impl BinArrayBitmapExtension {
     pub fn initialize(&mut self, lb_pair: Pubkey) {
         self.lb_pair = lb_pair;
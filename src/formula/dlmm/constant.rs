@@ -9,4 +9,5 @@ pub const BIN_ARRAY_BITMAP_SIZE: i32 = 512;
 pub const MIN_BIN_ID: i32 = -443636;
 pub const MAX_BIN_ID: i32 = 443636;
 
-pub const BIN_ARRAY: &[u8] = b"bin_array";
\ No newline at end of file
+pub const BIN_ARRAY: &[u8] = b"bin_array";
+pub const BIN_ARRAY_BITMAP_SEED: &[u8] = b"bitmap";
\ No newline at end of file
@@ -0,0 +1,448 @@
+// Snapshot regression tests: the expected amounts are outputs of this crate's math taken when the quoting was written,
+// not vectors recorded from the Meteora SDK or the program. They catch changes in behavior, parity with the program
+// is still to be checked against SDK vectors
+#[cfg(test)]
+mod dlmm_snapshot_test {
+    use std::collections::HashMap;
+
+    use solana_sdk::clock::Clock;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::sysvar::clock;
+
+    use crate::formula::dlmm::bin::{Bin, BinArray, get_price_from_id};
+    use crate::formula::dlmm::bin_array_bitmap_extension::BinArrayBitmapExtension;
+    use crate::formula::dlmm::constant::{BIN_ARRAY_BITMAP_SIZE, MAX_BIN_ID, MAX_BIN_PER_ARRAY, MIN_BIN_ID};
+    use crate::formula::dlmm::u64x64_math::{get_base, ONE, pow};
+    use crate::formula::meteora_dlmm::{derive_bin_array_bitmap_extension_pda, derive_bin_array_pda, get_bin_array_pubkeys_for_swap, quote_exact_in, quote_exact_out};
+    use crate::formula::test::account_fixture::{clock_account, data_account};
+    use crate::r#struct::account::{DeserializedAccount, DeserializedPoolAccount};
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::{MeteoraDlmmMarket, StaticParameters, VariableParameters};
+
+    const LIQUIDITY: u64 = 1_000_000_000;
+    const CURRENT_TIMESTAMP: u64 = 1_000;
+
+    fn static_parameters(variable_fee_control: u32) -> StaticParameters {
+        StaticParameters {
+            base_factor: 10_000,
+            filter_period: 30,
+            decay_period: 600,
+            reduction_factor: 5_000,
+            variable_fee_control,
+            max_volatility_accumulator: 350_000,
+            min_bin_id: MIN_BIN_ID,
+            max_bin_id: MAX_BIN_ID,
+            protocol_share: 0,
+            padding: [0; 6],
+        }
+    }
+
+    fn lb_pair(active_id: i32, bin_step: u16, variable_fee_control: u32) -> MeteoraDlmmMarket {
+        MeteoraDlmmMarket {
+            parameters: static_parameters(variable_fee_control),
+            active_id,
+            bin_step,
+            ..Default::default()
+        }
+    }
+
+    fn set_bin_array_bit(lb_pair: &mut MeteoraDlmmMarket, bin_array_index: i32) {
+        let offset = (bin_array_index + BIN_ARRAY_BITMAP_SIZE) as usize;
+        lb_pair.bin_array_bitmap[offset / 64] |= 1u64 << (offset % 64);
+    }
+
+    fn bin_array<F: Fn(i32) -> (u64, u64)>(lb_pair_pubkey: Pubkey, index: i32, amounts: F) -> (Pubkey, BinArray) {
+        let mut bin_array = BinArray {
+            index: index as i64,
+            version: 1,
+            _padding: [0; 7],
+            lb_pair: lb_pair_pubkey,
+            bins: [Bin::default(); MAX_BIN_PER_ARRAY],
+        };
+
+        let (lower_bin_id, upper_bin_id) = BinArray::get_bin_array_lower_upper_bin_id(index).unwrap();
+        for bin_id in lower_bin_id..=upper_bin_id {
+            let (amount_x, amount_y) = amounts(bin_id);
+            bin_array.get_bin_mut(bin_id).unwrap()
+                .deposit(amount_x, amount_y, (amount_x as u128 + amount_y as u128) << 64)
+                .unwrap();
+        }
+
+        (derive_bin_array_pda(lb_pair_pubkey, index as i64).0, bin_array)
+    }
+
+    /// Bin -70..=69 with token Y at and below bin 0, token X above it
+    fn two_sided_bin_arrays(lb_pair_pubkey: Pubkey) -> HashMap<Pubkey, BinArray> {
+        let amounts = |bin_id: i32| {
+            if bin_id <= 0 { (0, LIQUIDITY) } else { (LIQUIDITY, 0) }
+        };
+
+        HashMap::from([
+            bin_array(lb_pair_pubkey, -1, amounts),
+            bin_array(lb_pair_pubkey, 0, amounts),
+        ])
+    }
+
+    fn two_sided_lb_pair() -> MeteoraDlmmMarket {
+        let mut lb_pair = lb_pair(0, 10, 40_000);
+        set_bin_array_bit(&mut lb_pair, -1);
+        set_bin_array_bit(&mut lb_pair, 0);
+        lb_pair
+    }
+
+    #[test]
+    fn price_from_id() {
+        assert_eq!(get_price_from_id(0, 10).unwrap(), ONE);
+        assert_eq!(get_price_from_id(1, 10).unwrap(), 18465190817783261167);
+        assert_eq!(get_price_from_id(-1, 10).unwrap(), 18428315757951600016);
+        assert_eq!(get_price_from_id(100, 10).unwrap(), 20385786447693972794);
+        assert_eq!(get_price_from_id(-100, 10).unwrap(), 16692138308916259079);
+        assert_eq!(get_price_from_id(5000, 25).unwrap(), 4873334070482946822097674);
+    }
+
+    #[test]
+    fn price_is_monotonic() {
+        for bin_step in [1u16, 10, 25, 100] {
+            let mut previous = get_price_from_id(-2_000, bin_step).unwrap();
+            for bin_id in -1_999..=2_000 {
+                let price = get_price_from_id(bin_id, bin_step).unwrap();
+                assert!(price > previous, "bin_step {} bin_id {}", bin_step, bin_id);
+                previous = price;
+            }
+        }
+    }
+
+    #[test]
+    fn pow_bounds() {
+        let base = get_base(10).unwrap();
+        assert_eq!(base, ONE + (10u128 << 64) / 10_000);
+        assert_eq!(pow(base, 0), Some(ONE));
+        assert_eq!(pow(base, 1), Some(18465190817783261167));
+        assert_eq!(pow(base, 0x80000), None);
+        assert_eq!(pow(base, -0x80000), None);
+    }
+
+    #[test]
+    fn bin_swap_within_bin() {
+        let lb_pair = lb_pair(0, 10, 0);
+        let mut bin = Bin::default();
+        bin.deposit(0, LIQUIDITY, 1).unwrap();
+
+        let result = bin.swap(1_000_000, ONE, true, &lb_pair, None).unwrap();
+        assert_eq!(result.amount_in_with_fees, 1_000_000);
+        assert_eq!(result.amount_out, 999_000);
+        assert_eq!(result.fee, 1_000);
+        assert_eq!(bin.amount_x, 999_000);
+        assert_eq!(bin.amount_y, LIQUIDITY - 999_000);
+    }
+
+    #[test]
+    fn bin_swap_drains_bin() {
+        let lb_pair = lb_pair(0, 10, 0);
+        let mut bin = Bin::default();
+        bin.deposit(0, LIQUIDITY, 1).unwrap();
+
+        let result = bin.swap(2 * LIQUIDITY, ONE, true, &lb_pair, None).unwrap();
+        assert_eq!(result.amount_in_with_fees, 1_001_001_002);
+        assert_eq!(result.amount_out, LIQUIDITY);
+        assert_eq!(result.fee, 1_001_002);
+        assert_eq!(bin.amount_x, LIQUIDITY);
+        assert!(bin.is_empty(false));
+    }
+
+    #[test]
+    fn bin_swap_exact_out() {
+        let lb_pair = lb_pair(0, 10, 0);
+        let mut bin = Bin::default();
+        bin.deposit(0, LIQUIDITY, 1).unwrap();
+
+        let result = bin.swap_exact_out(u64::MAX, ONE, true, &lb_pair, None, 500_000).unwrap();
+        assert_eq!(result.amount_in_with_fees, 500_501);
+        assert_eq!(result.amount_out, 500_000);
+        assert_eq!(result.fee, 501);
+        assert!(result.is_exact_out_amount);
+    }
+
+    #[test]
+    fn quote_exact_in_across_bins() {
+        let lb_pair_pubkey = Pubkey::new_unique();
+        let lb_pair = two_sided_lb_pair();
+        let bin_arrays = two_sided_bin_arrays(lb_pair_pubkey);
+
+        let quote = quote_exact_in(lb_pair_pubkey, &lb_pair, 1_000_000, true, bin_arrays.clone(), None, CURRENT_TIMESTAMP, 0).unwrap();
+        assert_eq!(quote.amount_out, 999_000);
+        assert_eq!(quote.fee, 1_000);
+
+        // crosses bin 0 -> -3, and bin array 0 -> -1
+        let quote = quote_exact_in(lb_pair_pubkey, &lb_pair, 3_500_000_000, true, bin_arrays.clone(), None, CURRENT_TIMESTAMP, 0).unwrap();
+        assert_eq!(quote.amount_out, 3_491_983_729);
+        assert_eq!(quote.fee, 3_537_842);
+        assert_eq!(quote.bins_crossed, 3);
+
+        // bin 0 holds no token X, swap starts from bin 1
+        let quote = quote_exact_in(lb_pair_pubkey, &lb_pair, 3_500_000_000, false, bin_arrays, None, CURRENT_TIMESTAMP, 0).unwrap();
+        assert_eq!(quote.amount_out, 3_488_451_634);
+        assert_eq!(quote.fee, 3_587_624);
+    }
+
+    #[test]
+    fn quote_exact_out_across_bins() {
+        let lb_pair_pubkey = Pubkey::new_unique();
+        let lb_pair = two_sided_lb_pair();
+        let bin_arrays = two_sided_bin_arrays(lb_pair_pubkey);
+
+        let quote = quote_exact_out(lb_pair_pubkey, &lb_pair, 1_000_000, true, bin_arrays.clone(), None, CURRENT_TIMESTAMP, 0).unwrap();
+        assert_eq!(quote.amount_in, 1_000_000);
+        assert_eq!(quote.fee, 1_002);
+
+        let quote = quote_exact_out(lb_pair_pubkey, &lb_pair, 3_500_000_000, true, bin_arrays.clone(), None, CURRENT_TIMESTAMP, 0).unwrap();
+        assert_eq!(quote.amount_in, 3_504_502_502);
+        assert_eq!(quote.fee, 3_546_181);
+
+        // paying the exact-out quote as exact-in returns the requested amount
+        let quote = quote_exact_in(lb_pair_pubkey, &lb_pair, quote.amount_in + quote.fee, true, bin_arrays, None, CURRENT_TIMESTAMP, 0).unwrap();
+        assert_eq!(quote.amount_out, 3_500_000_000);
+    }
+
+    #[test]
+    fn swap_related_pubkeys() {
+        let lb_pair_pubkey = Pubkey::new_unique();
+        let lb_pair = two_sided_lb_pair();
+        assert_eq!(lb_pair.get_swap_related_pubkeys().iter().map(|(_, pubkey)| *pubkey).collect::<Vec<Pubkey>>(), vec![clock::id()]);
+
+        // bin array 0 holds the active bin, it is listed once for both directions
+        let related = lb_pair.get_bin_array_related_pubkeys(&lb_pair_pubkey).into_iter().map(|(_, pubkey)| pubkey).collect::<Vec<Pubkey>>();
+        assert_eq!(related, vec![
+            derive_bin_array_bitmap_extension_pda(lb_pair_pubkey).0,
+            derive_bin_array_pda(lb_pair_pubkey, 0).0,
+            derive_bin_array_pda(lb_pair_pubkey, -1).0,
+        ]);
+    }
+
+    fn bin_array_data(bin_array: &BinArray) -> Vec<u8> {
+        let mut data = vec![0u8; 8];
+        data.extend_from_slice(&bin_array.index.to_le_bytes());
+        data.push(bin_array.version);
+        data.extend_from_slice(&bin_array._padding);
+        data.extend_from_slice(bin_array.lb_pair.as_ref());
+        bin_array.bins.iter().for_each(|bin| {
+            let mut bin_data = [0u8; 144];
+            bin_data[0..8].copy_from_slice(&bin.amount_x.to_le_bytes());
+            bin_data[8..16].copy_from_slice(&bin.amount_y.to_le_bytes());
+            bin_data[16..32].copy_from_slice(&bin.price.to_le_bytes());
+            bin_data[32..48].copy_from_slice(&bin.liquidity_supply.to_le_bytes());
+            data.extend_from_slice(&bin_data);
+        });
+        data
+    }
+
+    #[test]
+    fn swap_through_pool_operation() {
+        let lb_pair_pubkey = Pubkey::new_unique();
+        let lb_pair = MeteoraDlmmMarket { token_x_mint: Pubkey::new_unique(), token_y_mint: Pubkey::new_unique(), ..two_sided_lb_pair() };
+        let mut accounts = two_sided_bin_arrays(lb_pair_pubkey).iter().map(|(pubkey, bin_array)| {
+            data_account(*pubkey, bin_array_data(bin_array), Market::METEORA)
+        }).collect::<Vec<DeserializedAccount>>();
+        accounts.push(DeserializedAccount::PoolAccount(DeserializedPoolAccount {
+            pubkey: lb_pair_pubkey,
+            market: Market::METEORA,
+            operation: Box::new(lb_pair),
+            ..DeserializedPoolAccount::default()
+        }));
+        assert_eq!(lb_pair.swap(&accounts, &lb_pair.token_x_mint, 3_500_000_000), Err("clock not found"));
+
+        // the same swap as quote_exact_in_across_bins, bin 0 -> -3
+        accounts.push(clock_account(Clock { unix_timestamp: CURRENT_TIMESTAMP as i64, ..Clock::default() }, Market::METEORA));
+        let quote = lb_pair.swap(&accounts, &lb_pair.token_x_mint, 3_500_000_000).unwrap();
+        assert_eq!((quote.amount_in, quote.amount_out, quote.crossings), (3_500_000_000, 3_491_983_729, 3));
+
+        assert_eq!(lb_pair.swap(&accounts, &Pubkey::new_unique(), 1_000_000), Err("mint not in pool"));
+    }
+
+    #[test]
+    fn quote_errors_instead_of_panicking() {
+        let lb_pair_pubkey = Pubkey::new_unique();
+        let lb_pair = two_sided_lb_pair();
+        let mut bin_arrays = two_sided_bin_arrays(lb_pair_pubkey);
+
+        // 71 bins of token Y in total
+        assert_eq!(quote_exact_in(lb_pair_pubkey, &lb_pair, 100 * LIQUIDITY, true, bin_arrays.clone(), None, CURRENT_TIMESTAMP, 0).unwrap_err(), "Pool out of liquidity");
+        assert_eq!(quote_exact_out(lb_pair_pubkey, &lb_pair, 100 * LIQUIDITY, true, bin_arrays.clone(), None, CURRENT_TIMESTAMP, 0).unwrap_err(), "Pool out of liquidity");
+
+        let disabled = MeteoraDlmmMarket { status: 1, ..lb_pair };
+        assert_eq!(quote_exact_in(lb_pair_pubkey, &disabled, 1_000_000, true, bin_arrays.clone(), None, CURRENT_TIMESTAMP, 0).unwrap_err(), "pair disabled");
+        let not_activated = MeteoraDlmmMarket { pair_type: 1, activation_type: 1, activation_point: CURRENT_TIMESTAMP + 1, ..lb_pair };
+        assert_eq!(quote_exact_in(lb_pair_pubkey, &not_activated, 1_000_000, true, bin_arrays.clone(), None, CURRENT_TIMESTAMP, 0).unwrap_err(), "pair not activated");
+
+        bin_arrays.remove(&derive_bin_array_pda(lb_pair_pubkey, -1).0);
+        assert_eq!(quote_exact_in(lb_pair_pubkey, &lb_pair, 3_500_000_000, true, bin_arrays, None, CURRENT_TIMESTAMP, 0).unwrap_err(), "Active bin array not found");
+    }
+
+    #[test]
+    fn quote_exact_in_output_is_bounded() {
+        let lb_pair_pubkey = Pubkey::new_unique();
+        let lb_pair = two_sided_lb_pair();
+        let bin_arrays = two_sided_bin_arrays(lb_pair_pubkey);
+
+        let mut previous = 0;
+        for amount_in in (1..=60).map(|i| i * 100_000_000u64) {
+            let quote = quote_exact_in(lb_pair_pubkey, &lb_pair, amount_in, true, bin_arrays.clone(), None, CURRENT_TIMESTAMP, 0).unwrap();
+
+            // price is at most 1 on the Y side, fee is always charged
+            assert!(quote.amount_out < amount_in);
+            assert!(quote.fee > 0);
+            assert!(quote.amount_out >= previous);
+            previous = quote.amount_out;
+        }
+    }
+
+    #[test]
+    fn bitmap_extension() {
+        let mut extension = BinArrayBitmapExtension::default();
+        extension.flip_bin_array_bit(512).unwrap();
+        extension.flip_bin_array_bit(-600).unwrap();
+
+        assert!(extension.bit(512).unwrap());
+        assert!(extension.bit(-600).unwrap());
+        assert!(!extension.bit(513).unwrap());
+
+        assert_eq!(extension.next_bin_array_index_with_liquidity(false, 512).unwrap(), (512, true));
+        assert_eq!(extension.next_bin_array_index_with_liquidity(true, 600).unwrap(), (512, true));
+        assert_eq!(extension.next_bin_array_index_with_liquidity(true, -513).unwrap(), (-600, true));
+        assert_eq!(extension.next_bin_array_index_with_liquidity(false, -700).unwrap(), (-600, true));
+        assert!(extension.next_bin_array_index_with_liquidity(false, 513).is_err());
+
+        // nothing left in the extension towards the internal bitmap, hand over to it
+        assert_eq!(extension.next_bin_array_index_with_liquidity(false, -513).unwrap(), (-BIN_ARRAY_BITMAP_SIZE, false));
+
+        extension.flip_bin_array_bit(512).unwrap();
+        assert!(!extension.bit(512).unwrap());
+        assert_eq!(extension.next_bin_array_index_with_liquidity(true, 600).unwrap(), (BIN_ARRAY_BITMAP_SIZE - 1, false));
+    }
+
+    #[test]
+    fn quote_exact_in_crosses_bitmap_extension() {
+        let lb_pair_pubkey = Pubkey::new_unique();
+
+        // bin 35839 is the last bin of bin array 511, the last one tracked by the internal bitmap
+        let mut lb_pair = lb_pair(35_839, 1, 0);
+        set_bin_array_bit(&mut lb_pair, 511);
+
+        let mut extension = BinArrayBitmapExtension::default();
+        extension.flip_bin_array_bit(512).unwrap();
+
+        let bin_arrays = HashMap::from([
+            bin_array(lb_pair_pubkey, 511, |bin_id| if bin_id == 35_839 { (1_000_000, 0) } else { (0, 0) }),
+            bin_array(lb_pair_pubkey, 512, |_| (LIQUIDITY, 0)),
+        ]);
+
+        let pubkeys = get_bin_array_pubkeys_for_swap(lb_pair_pubkey, &lb_pair, Some(&extension), false, 2).unwrap();
+        assert_eq!(pubkeys, vec![
+            derive_bin_array_pda(lb_pair_pubkey, 511).0,
+            derive_bin_array_pda(lb_pair_pubkey, 512).0,
+        ]);
+
+        let quote = quote_exact_in(lb_pair_pubkey, &lb_pair, 100_000_000, false, bin_arrays, Some(&extension), CURRENT_TIMESTAMP, 0).unwrap();
+        assert_eq!(quote.amount_out, 2_776_761);
+        assert_eq!(quote.fee, 10_001);
+    }
+
+    #[test]
+    fn variable_fee() {
+        let mut lb_pair = lb_pair(0, 10, 40_000);
+
+        // base fee: base_factor * bin_step * 10
+        assert_eq!(lb_pair.get_base_fee().unwrap(), 1_000_000);
+        assert_eq!(lb_pair.get_variable_fee().unwrap(), 0);
+
+        lb_pair.v_parameters.volatility_accumulator = 10_000;
+        assert_eq!(lb_pair.get_variable_fee().unwrap(), 4_000);
+        assert_eq!(lb_pair.compute_fee_from_amount(1_000_000).unwrap(), 1_004);
+
+        // capped at MAX_FEE_RATE
+        lb_pair.v_parameters.volatility_accumulator = 350_000;
+        assert_eq!(lb_pair.get_variable_fee().unwrap(), 4_900_000);
+        lb_pair.parameters.variable_fee_control = 4_000_000;
+        assert_eq!(lb_pair.get_total_fee().unwrap(), 100_000_000);
+    }
+
+    #[test]
+    fn volatility_accumulator() {
+        let static_parameters = static_parameters(40_000);
+        let mut v_parameters = VariableParameters {
+            volatility_reference: 10_000,
+            index_reference: -3,
+            ..Default::default()
+        };
+
+        v_parameters.update_volatility_accumulator(0, &static_parameters).unwrap();
+        assert_eq!(v_parameters.volatility_accumulator, 40_000);
+
+        v_parameters.update_volatility_accumulator(-3, &static_parameters).unwrap();
+        assert_eq!(v_parameters.volatility_accumulator, 10_000);
+
+        v_parameters.update_volatility_accumulator(1_000, &static_parameters).unwrap();
+        assert_eq!(v_parameters.volatility_accumulator, static_parameters.max_volatility_accumulator);
+    }
+
+    #[test]
+    fn volatility_decay() {
+        let static_parameters = static_parameters(40_000);
+        let v_parameters = VariableParameters {
+            volatility_accumulator: 30_000,
+            volatility_reference: 10_000,
+            index_reference: -3,
+            last_update_timestamp: 1_000,
+            ..Default::default()
+        };
+
+        // within filter period, references are kept
+        let mut filtered = v_parameters;
+        filtered.update_references(0, 1_010, &static_parameters).unwrap();
+        assert_eq!(filtered.index_reference, -3);
+        assert_eq!(filtered.volatility_reference, 10_000);
+
+        // within decay period, reference decays by reduction_factor
+        let mut decayed = v_parameters;
+        decayed.update_references(0, 1_100, &static_parameters).unwrap();
+        assert_eq!(decayed.index_reference, 0);
+        assert_eq!(decayed.volatility_reference, 15_000);
+
+        // past decay period, reference is reset
+        let mut reset = v_parameters;
+        reset.update_references(0, 2_000, &static_parameters).unwrap();
+        assert_eq!(reset.index_reference, 0);
+        assert_eq!(reset.volatility_reference, 0);
+    }
+
+    #[test]
+    fn quote_fee_follows_volatility() {
+        let lb_pair_pubkey = Pubkey::new_unique();
+        let mut lb_pair = two_sided_lb_pair();
+        lb_pair.v_parameters = VariableParameters {
+            volatility_accumulator: 30_000,
+            volatility_reference: 10_000,
+            index_reference: -3,
+            ..Default::default()
+        };
+
+        let bin_arrays = two_sided_bin_arrays(lb_pair_pubkey);
+        let quote_at = |last_update_timestamp: i64| {
+            let mut lb_pair = lb_pair;
+            lb_pair.v_parameters.last_update_timestamp = last_update_timestamp;
+            quote_exact_in(lb_pair_pubkey, &lb_pair, 1_000_000, true, bin_arrays.clone(), None, CURRENT_TIMESTAMP, 0).unwrap()
+        };
+
+        let filtered = quote_at(990);
+        assert_eq!(filtered.amount_out, 998_936);
+        assert_eq!(filtered.fee, 1_064);
+
+        let decayed = quote_at(900);
+        assert_eq!(decayed.amount_out, 998_991);
+        assert_eq!(decayed.fee, 1_009);
+
+        let reset = quote_at(0);
+        assert_eq!(reset.amount_out, 999_000);
+        assert_eq!(reset.fee, 1_000);
+    }
+}
@@ -10,6 +10,7 @@ mod test {
     use crate::formula::meteora_dlmm::{get_bin_array_pubkeys_for_swap, quote_exact_in, quote_exact_out};
     use crate::r#struct::account::AccountDataSerializer;
     use crate::r#struct::pools::MeteoraDlmmMarket;
+    use crate::utils::get_rpc_url;
 
     async fn get_clock(rpc_client: RpcClient) -> Result<Clock, &'static str> {
         let clock_account = rpc_client
@@ -23,10 +24,10 @@ mod test {
     }
 
     #[tokio::test]
+    #[ignore = "requires mainnet rpc"]
     async fn test_swap_quote_exact_out() {
         // RPC client. No gPA is required.
-        let alchemy = "https://solana-mainnet.g.alchemy.com/v2/76-rZCjoPGCHXLfjHNojk5CiqX8I36AT".to_string();
-        let rpc_client = RpcClient::new(alchemy);
+        let rpc_client = RpcClient::new(get_rpc_url());
 
         let SOL_USDC = Pubkey::from_str("HTvjzsfX3yU6BUodCjZ5vZkUrAxMDTrBs3CJaq43ashR").unwrap();
 
@@ -150,10 +151,10 @@ mod test {
     }
 
     #[tokio::test]
+    #[ignore = "requires mainnet rpc"]
     async fn test_swap_quote_exact_in() {
         // RPC client. No gPA is required.
-        let alchemy = "https://solana-mainnet.g.alchemy.com/v2/76-rZCjoPGCHXLfjHNojk5CiqX8I36AT".to_string();
-        let rpc_client = RpcClient::new(alchemy);
+        let rpc_client = RpcClient::new(get_rpc_url());
 
         let SOL_USDC = Pubkey::from_str("HTvjzsfX3yU6BUodCjZ5vZkUrAxMDTrBs3CJaq43ashR").unwrap();
 
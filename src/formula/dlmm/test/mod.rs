@@ -1 +1,2 @@
-pub mod meteora_test;
\ No newline at end of file
+mod meteora_test;
+mod dlmm_snapshot_test;
\ No newline at end of file
@@ -0,0 +1,160 @@
+use crate::formula::stable_swap;
+use crate::r#struct::pools::{DammCurveType, MeteoraDammMarket, MeteoraVault};
+
+pub const LOCKED_PROFIT_DEGRADATION_DENOMINATOR: u128 = 1_000_000_000_000;
+
+/// A dynamic vault together with the vault LP the pool holds and the vault LP supply
+#[derive(Copy, Clone, Debug, Default)]
+pub struct VaultShare {
+    pub vault: MeteoraVault,
+    pub pool_lp_amount: u64,
+    pub lp_supply: u64,
+}
+
+impl MeteoraVault {
+    /// Profit of the last strategy report not released yet, it unlocks linearly over time
+    pub fn get_locked_profit(&self, current_time: u64) -> u64 {
+        let tracker = &self.locked_profit_tracker;
+        let duration = current_time.saturating_sub(tracker.last_report) as u128;
+        let locked_fund_ratio = duration * tracker.locked_profit_degradation as u128;
+        if locked_fund_ratio > LOCKED_PROFIT_DEGRADATION_DENOMINATOR {
+            return 0;
+        }
+
+        (tracker.last_updated_locked_profit as u128 * (LOCKED_PROFIT_DEGRADATION_DENOMINATOR - locked_fund_ratio)
+            / LOCKED_PROFIT_DEGRADATION_DENOMINATOR) as u64
+    }
+
+    pub fn get_unlocked_amount(&self, current_time: u64) -> Result<u64, &'static str> {
+        self.total_amount.checked_sub(self.get_locked_profit(current_time)).ok_or("locked profit over total amount")
+    }
+
+    /// Tokens `share` vault LP redeems for
+    pub fn get_amount_by_share(&self, current_time: u64, share: u64, lp_supply: u64) -> Result<u64, &'static str> {
+        if lp_supply == 0 {
+            return Err("empty vault lp supply");
+        }
+        let amount = share as u128 * self.get_unlocked_amount(current_time)? as u128 / lp_supply as u128;
+        u64::try_from(amount).or(Err("vault amount overflow"))
+    }
+
+    /// Vault LP depositing `amount` mints
+    pub fn get_unmint_amount(&self, current_time: u64, amount: u64, lp_supply: u64) -> Result<u64, &'static str> {
+        let unlocked_amount = self.get_unlocked_amount(current_time)?;
+        if unlocked_amount == 0 {
+            return Err("empty vault");
+        }
+        let share = amount as u128 * lp_supply as u128 / unlocked_amount as u128;
+        u64::try_from(share).or(Err("vault share overflow"))
+    }
+}
+
+impl VaultShare {
+    /// Tokens the pool holds through the vault
+    pub fn get_pool_amount(&self, current_time: u64) -> Result<u64, &'static str> {
+        self.vault.get_amount_by_share(current_time, self.pool_lp_amount, self.lp_supply)
+    }
+}
+
+/// Fee of the spl token-swap fee model, at least 1 when any is due
+pub fn calculate_fee(amount: u64, numerator: u64, denominator: u64) -> Result<u64, &'static str> {
+    if numerator == 0 || amount == 0 {
+        return Ok(0);
+    }
+    if denominator == 0 {
+        return Err("zero fee denominator");
+    }
+    let fee = (amount as u128 * numerator as u128 / denominator as u128) as u64;
+    Ok(fee.max(1))
+}
+
+/// Output of the curve before the output is withdrawn from the vault
+pub fn swap_curve(curve_type: &DammCurveType, amount_in: u64, source_amount: u64, destination_amount: u64, a_to_b: bool) -> Result<u64, &'static str> {
+    match curve_type {
+        DammCurveType::ConstantProduct => {
+            // destination - ceil(invariant / new source), as the spl token-swap curve
+            let invariant = source_amount as u128 * destination_amount as u128;
+            let new_source_amount = source_amount as u128 + amount_in as u128;
+            if new_source_amount == 0 {
+                return Err("empty pool");
+            }
+            let new_destination_amount = invariant.div_ceil(new_source_amount);
+            Ok((destination_amount as u128).saturating_sub(new_destination_amount) as u64)
+        }
+        DammCurveType::Stable { amp, token_multiplier, depeg, .. } => {
+            if depeg.depeg_type != 0 {
+                return Err("depeg stable pools are not supported");
+            }
+            let (source_multiplier, destination_multiplier) = if a_to_b {
+                (token_multiplier.token_a_multiplier, token_multiplier.token_b_multiplier)
+            } else {
+                (token_multiplier.token_b_multiplier, token_multiplier.token_a_multiplier)
+            };
+            if destination_multiplier == 0 {
+                return Err("zero token multiplier");
+            }
+
+            let amount_out = stable_swap::swap_to(
+                *amp,
+                amount_in as u128 * source_multiplier as u128,
+                source_amount as u128 * source_multiplier as u128,
+                destination_amount as u128 * destination_multiplier as u128
+            ).ok_or("stable swap math failed")?;
+            u64::try_from(amount_out / destination_multiplier as u128).or(Err("amount out overflow"))
+        }
+    }
+}
+
+/// Quotes an exact-in swap, the input is deposited to its vault and the output withdrawn from the other
+pub fn quote_exact_in(
+    pool: &MeteoraDammMarket,
+    vault_a: &VaultShare,
+    vault_b: &VaultShare,
+    amount_in: u64,
+    a_to_b: bool,
+    current_timestamp: u64,
+    current_slot: u64,
+) -> Result<u64, &'static str> {
+    if !pool.enabled {
+        return Err("pool disabled");
+    }
+    let current_point = if pool.bootstrapping.activation_type == 0 { current_slot } else { current_timestamp };
+    if current_point < pool.bootstrapping.activation_point {
+        return Err("pool not activated");
+    }
+
+    let token_a_amount = vault_a.get_pool_amount(current_timestamp)?;
+    let token_b_amount = vault_b.get_pool_amount(current_timestamp)?;
+    let (vault_in, vault_out, source_amount, destination_amount) = if a_to_b {
+        (vault_a, vault_b, token_a_amount, token_b_amount)
+    } else {
+        (vault_b, vault_a, token_b_amount, token_a_amount)
+    };
+
+    let trade_fee = calculate_fee(amount_in, pool.fees.trade_fee_numerator, pool.fees.trade_fee_denominator)?;
+    let protocol_fee = calculate_fee(trade_fee, pool.fees.protocol_trade_fee_numerator, pool.fees.protocol_trade_fee_denominator)?;
+    let amount_in_after_protocol_fee = amount_in.checked_sub(protocol_fee).ok_or("fee over amount in")?;
+
+    // the deposit is rounded down to vault LP, what the pool gains can be less than deposited
+    let lp_in = vault_in.vault.get_unmint_amount(current_timestamp, amount_in_after_protocol_fee, vault_in.lp_supply)?;
+    let mut deposited_vault = vault_in.vault;
+    deposited_vault.total_amount = deposited_vault.total_amount.checked_add(amount_in_after_protocol_fee).ok_or("vault amount overflow")?;
+    let after_source_amount = deposited_vault.get_amount_by_share(
+        current_timestamp,
+        vault_in.pool_lp_amount.checked_add(lp_in).ok_or("vault share overflow")?,
+        vault_in.lp_supply.checked_add(lp_in).ok_or("vault share overflow")?
+    )?;
+    let actual_amount_in = after_source_amount.checked_sub(source_amount).ok_or("vault amount decreased")?;
+    let actual_amount_in_after_fee = actual_amount_in.checked_sub(trade_fee - protocol_fee).ok_or("fee over amount in")?;
+
+    let destination_amount_swapped = swap_curve(&pool.curve_type, actual_amount_in_after_fee, source_amount, destination_amount, a_to_b)?;
+
+    // the output is withdrawn as vault LP, rounded down twice
+    let lp_out = vault_out.vault.get_unmint_amount(current_timestamp, destination_amount_swapped, vault_out.lp_supply)?;
+    let amount_out = vault_out.vault.get_amount_by_share(current_timestamp, lp_out, vault_out.lp_supply)?;
+    if amount_out >= destination_amount {
+        return Err("insufficient liquidity");
+    }
+
+    Ok(amount_out)
+}
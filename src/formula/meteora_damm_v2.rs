@@ -0,0 +1,174 @@
+use ruint::aliases::U256;
+
+use crate::formula::dlmm::u64x64_math::{pow, ONE};
+use crate::r#struct::pools::{DammV2BaseFee, DammV2DynamicFee, MeteoraDammV2Market};
+
+pub const FEE_DENOMINATOR: u64 = 1_000_000_000;
+pub const MAX_FEE_NUMERATOR: u64 = 500_000_000;
+const BASIS_POINT_MAX: u64 = 10_000;
+// liquidity and sqrt prices are Q64.64, their product is shifted back by 128
+const RESOLUTION: usize = 64;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct DammV2SwapResult {
+    pub amount_out: u64,
+    pub trading_fee: u64,
+    pub next_sqrt_price: u128,
+}
+
+fn mul_div(x: U256, y: U256, denominator: U256, round_up: bool) -> Result<U256, &'static str> {
+    if denominator.is_zero() {
+        return Err("division by zero");
+    }
+    let product = x.checked_mul(y).ok_or("math overflow")?;
+    Ok(if round_up { product.div_ceil(denominator) } else { product / denominator })
+}
+
+impl DammV2BaseFee {
+    /// Base fee numerator of the fee scheduler `current_point` falls in, the cliff fee decays by period
+    pub fn get_fee_numerator(&self, current_point: u64, activation_point: u64) -> Result<u64, &'static str> {
+        if self.period_frequency == 0 {
+            return Ok(self.cliff_fee_numerator);
+        }
+        let period = if current_point < activation_point {
+            self.number_of_period as u64
+        } else {
+            ((current_point - activation_point) / self.period_frequency).min(self.number_of_period as u64)
+        };
+
+        match self.fee_scheduler_mode {
+            // linear
+            0 => {
+                let reduction = period.checked_mul(self.reduction_factor).ok_or("math overflow")?;
+                self.cliff_fee_numerator.checked_sub(reduction).ok_or("fee numerator underflow")
+            }
+            // exponential, cliff * (1 - reduction_factor / 10_000) ^ period
+            1 => {
+                if period == 0 {
+                    return Ok(self.cliff_fee_numerator);
+                }
+                let bps = ((self.reduction_factor as u128) << 64) / BASIS_POINT_MAX as u128;
+                let base = ONE.checked_sub(bps).ok_or("invalid reduction factor")?;
+                let result = pow(base, period as i32).ok_or("math overflow")?;
+                let (fee, _) = U256::from(result).checked_mul(U256::from(self.cliff_fee_numerator)).ok_or("math overflow")?.overflowing_shr(64);
+                u64::try_from(fee).or(Err("math overflow"))
+            }
+            _ => Err("unknown fee scheduler mode")
+        }
+    }
+}
+
+impl DammV2DynamicFee {
+    /// Fee numerator added by volatility, 0 when the dynamic fee is off
+    pub fn get_variable_fee(&self) -> Result<u128, &'static str> {
+        if self.initialized == 0 {
+            return Ok(0);
+        }
+        let square_vfa_bin = self.volatility_accumulator.checked_mul(self.bin_step as u128)
+            .and_then(|value| value.checked_pow(2))
+            .ok_or("math overflow")?;
+        let v_fee = square_vfa_bin.checked_mul(self.variable_fee_control as u128).ok_or("math overflow")?;
+
+        Ok(v_fee.checked_add(99_999_999_999).ok_or("math overflow")? / 100_000_000_000)
+    }
+}
+
+/// Trading fee numerator over `FEE_DENOMINATOR` at `current_point`.
+/// The volatility accumulator is taken as stored, the pool only moves it after a swap
+pub fn get_total_fee_numerator(pool: &MeteoraDammV2Market, current_point: u64) -> Result<u64, &'static str> {
+    let base_fee = pool.pool_fees.base_fee.get_fee_numerator(current_point, pool.activation_point)? as u128;
+    let total_fee = base_fee.checked_add(pool.pool_fees.dynamic_fee.get_variable_fee()?).ok_or("math overflow")?;
+
+    Ok(total_fee.min(MAX_FEE_NUMERATOR as u128) as u64)
+}
+
+/// (amount after fee, fee), the fee is rounded up
+pub fn get_fee_on_amount(amount: u64, fee_numerator: u64) -> Result<(u64, u64), &'static str> {
+    let fee = mul_div(U256::from(amount), U256::from(fee_numerator), U256::from(FEE_DENOMINATOR), true)?;
+    let fee = u64::try_from(fee).or(Err("math overflow"))?;
+
+    Ok((amount.checked_sub(fee).ok_or("fee over amount")?, fee))
+}
+
+/// Δa = L * (√P_upper - √P_lower) / (√P_upper * √P_lower)
+pub fn get_delta_amount_a(lower_sqrt_price: u128, upper_sqrt_price: u128, liquidity: u128, round_up: bool) -> Result<u64, &'static str> {
+    let delta = U256::from(upper_sqrt_price.checked_sub(lower_sqrt_price).ok_or("invalid sqrt price range")?);
+    let denominator = U256::from(lower_sqrt_price).checked_mul(U256::from(upper_sqrt_price)).ok_or("math overflow")?;
+    let result = mul_div(U256::from(liquidity), delta, denominator, round_up)?;
+
+    u64::try_from(result).or(Err("math overflow"))
+}
+
+/// Δb = L * (√P_upper - √P_lower)
+pub fn get_delta_amount_b(lower_sqrt_price: u128, upper_sqrt_price: u128, liquidity: u128, round_up: bool) -> Result<u64, &'static str> {
+    let delta = U256::from(upper_sqrt_price.checked_sub(lower_sqrt_price).ok_or("invalid sqrt price range")?);
+    let denominator = U256::from(1u8) << (RESOLUTION * 2);
+    let result = mul_div(U256::from(liquidity), delta, denominator, round_up)?;
+
+    u64::try_from(result).or(Err("math overflow"))
+}
+
+/// Sqrt price after `amount_in` enters, rounded so that the pool never gives out more than it should
+pub fn get_next_sqrt_price_from_input(sqrt_price: u128, liquidity: u128, amount_in: u64, a_for_b: bool) -> Result<u128, &'static str> {
+    if amount_in == 0 {
+        return Ok(sqrt_price);
+    }
+    if liquidity == 0 {
+        return Err("no liquidity");
+    }
+
+    let next_sqrt_price = if a_for_b {
+        // √P' = L * √P / (L + Δa * √P), rounded up
+        let product = U256::from(amount_in).checked_mul(U256::from(sqrt_price)).ok_or("math overflow")?;
+        let denominator = U256::from(liquidity).checked_add(product).ok_or("math overflow")?;
+        mul_div(U256::from(liquidity), U256::from(sqrt_price), denominator, true)?
+    } else {
+        // √P' = √P + Δb / L, rounded down
+        let quotient = (U256::from(amount_in) << (RESOLUTION * 2)) / U256::from(liquidity);
+        U256::from(sqrt_price).checked_add(quotient).ok_or("math overflow")?
+    };
+
+    u128::try_from(next_sqrt_price).or(Err("math overflow"))
+}
+
+/// Quotes an exact-in swap on the single price range of the pool
+pub fn quote_exact_in(pool: &MeteoraDammV2Market, amount_in: u64, a_to_b: bool, current_timestamp: u64, current_slot: u64) -> Result<DammV2SwapResult, &'static str> {
+    if pool.pool_status != 0 {
+        return Err("pool disabled");
+    }
+    let current_point = if pool.activation_type == 0 { current_slot } else { current_timestamp };
+    if current_point < pool.activation_point {
+        return Err("pool not activated");
+    }
+
+    // collect_fee_mode 0 takes the fee on the output, 1 always on token b
+    let fees_on_input = pool.collect_fee_mode == 1 && !a_to_b;
+    let fee_numerator = get_total_fee_numerator(pool, current_point)?;
+
+    let (amount_in, input_fee) = if fees_on_input {
+        get_fee_on_amount(amount_in, fee_numerator)?
+    } else {
+        (amount_in, 0)
+    };
+
+    let next_sqrt_price = get_next_sqrt_price_from_input(pool.sqrt_price, pool.liquidity, amount_in, a_to_b)?;
+    let amount_out = if a_to_b {
+        if next_sqrt_price < pool.sqrt_min_price {
+            return Err("price range is violated");
+        }
+        get_delta_amount_b(next_sqrt_price, pool.sqrt_price, pool.liquidity, false)?
+    } else {
+        if next_sqrt_price > pool.sqrt_max_price {
+            return Err("price range is violated");
+        }
+        get_delta_amount_a(pool.sqrt_price, next_sqrt_price, pool.liquidity, false)?
+    };
+
+    let (amount_out, trading_fee) = if fees_on_input {
+        (amount_out, input_fee)
+    } else {
+        get_fee_on_amount(amount_out, fee_numerator)?
+    };
+
+    Ok(DammV2SwapResult { amount_out, trading_fee, next_sqrt_price })
+}
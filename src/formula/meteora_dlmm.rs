@@ -1,12 +1,11 @@
 use std::collections::HashMap;
 use std::str::FromStr;
-use anyhow::Context;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use solana_sdk::pubkey::Pubkey;
 use crate::constants::METEORA_DLMM_PROGRAM_PUBKEY;
 use crate::formula::dlmm::bin::{Bin, BinArray, SwapResult};
 use crate::formula::dlmm::bin_array_bitmap_extension::BinArrayBitmapExtension;
-use crate::formula::dlmm::constant::BIN_ARRAY;
+use crate::formula::dlmm::constant::{BIN_ARRAY, BIN_ARRAY_BITMAP_SEED};
 use crate::r#struct::pools::MeteoraDlmmMarket;
 
 pub fn quote_exact_out(
@@ -36,12 +35,12 @@ pub fn quote_exact_out(
             1,
         )?
             .pop()
-            .context("Pool out of liquidity").expect("Pool out of liquidity");
+            .ok_or("Pool out of liquidity")?;
 
         let mut active_bin_array = bin_arrays
             .get(&active_bin_array_pubkey)
             .cloned()
-            .context("Active bin array not found").expect("Active bin array not found");
+            .ok_or("Active bin array not found")?;
 
         loop {
             if active_bin_array
@@ -65,22 +64,22 @@ pub fn quote_exact_out(
 
                     total_amount_in = total_amount_in
                         .checked_add(max_amount_in)
-                        .context("MathOverflow").expect("MathOverflow");
+                        .ok_or("MathOverflow")?;
 
-                    total_fee = total_fee.checked_add(max_fee).context("MathOverflow").expect("MathOverflow");
+                    total_fee = total_fee.checked_add(max_fee).ok_or("MathOverflow")?;
 
                     amount_out = amount_out
                         .checked_sub(bin_max_amount_out)
-                        .context("MathOverflow").expect("MathOverflow");
+                        .ok_or("MathOverflow")?;
                 } else {
                     let amount_in = Bin::get_amount_in(amount_out, price, swap_for_y)?;
                     let fee = lb_pair.compute_fee(amount_in)?;
 
                     total_amount_in = total_amount_in
                         .checked_add(amount_in)
-                        .context("MathOverflow").expect("MathOverflow");
+                        .ok_or("MathOverflow")?;
 
-                    total_fee = total_fee.checked_add(fee).context("MathOverflow").expect("MathOverflow");
+                    total_fee = total_fee.checked_add(fee).ok_or("MathOverflow")?;
 
                     amount_out = 0;
                 }
@@ -115,6 +114,7 @@ pub fn quote_exact_in(
 
     let mut total_amount_out: u64 = 0;
     let mut total_fee: u64 = 0;
+    let mut bins_crossed: u32 = 0;
 
     while amount_in > 0 {
         let active_bin_array_pubkey = get_bin_array_pubkeys_for_swap(
@@ -125,12 +125,12 @@ pub fn quote_exact_in(
             1,
         )?
             .pop()
-            .context("Pool out of liquidity").expect("Pool out of liquidity");
+            .ok_or("Pool out of liquidity")?;
 
         let mut active_bin_array = bin_arrays
             .get(&active_bin_array_pubkey)
             .cloned()
-            .context("Active bin array not found").expect("Active bin array not found");
+            .ok_or("Active bin array not found")?;
 
         loop {
             if active_bin_array
@@ -156,16 +156,17 @@ pub fn quote_exact_in(
 
                 amount_in = amount_in
                     .checked_sub(amount_in_with_fees)
-                    .context("MathOverflow").expect("MathOverflow");
+                    .ok_or("MathOverflow")?;
 
                 total_amount_out = total_amount_out
                     .checked_add(amount_out)
-                    .context("MathOverflow").expect("MathOverflow");
-                total_fee = total_fee.checked_add(fee).context("MathOverflow").expect("MathOverflow");
+                    .ok_or("MathOverflow")?;
+                total_fee = total_fee.checked_add(fee).ok_or("MathOverflow")?;
             }
 
             if amount_in > 0 {
                 lb_pair.advance_active_bin(swap_for_y)?;
+                bins_crossed += 1;
             }
         }
     }
@@ -173,6 +174,7 @@ pub fn quote_exact_in(
     Ok(SwapExactInQuote {
         amount_out: total_amount_out,
         fee: total_fee,
+        bins_crossed,
     })
 }
 
@@ -238,17 +240,21 @@ fn validate_swap_activation(
     current_timestamp: u64,
     current_slot: u64,
 ) -> Result<(), &'static str> {
-    assert!(lb_pair.status()?.eq(&PairStatus::Enabled));
+    if lb_pair.status()? != PairStatus::Enabled {
+        return Err("pair disabled");
+    }
 
     let pair_type = lb_pair.pair_type()?;
     if pair_type.eq(&PairType::Permission) {
-        let activation_type = ActivationType::try_from(lb_pair.activation_type).expect("unknown activation_type");
+        let activation_type = ActivationType::try_from(lb_pair.activation_type).map_err(|_| "LBError::TypeCastFailed")?;
         let current_point = match activation_type {
             ActivationType::Slot => current_slot,
             ActivationType::Timestamp => current_timestamp,
         };
 
-        assert!(current_point >= lb_pair.activation_point)
+        if current_point < lb_pair.activation_point {
+            return Err("pair not activated");
+        }
     }
 
     Ok(())
@@ -262,6 +268,11 @@ pub fn derive_bin_array_pda(lb_pair: Pubkey, bin_array_index: i64) -> (Pubkey, u
     )
 }
 
+pub fn derive_bin_array_bitmap_extension_pda(lb_pair: Pubkey) -> (Pubkey, u8) {
+    let program_id = Pubkey::from_str(METEORA_DLMM_PROGRAM_PUBKEY).unwrap();
+    Pubkey::find_program_address(&[BIN_ARRAY_BITMAP_SEED, lb_pair.as_ref()], &program_id)
+}
+
 #[derive(Debug)]
 pub struct SwapExactOutQuote {
     pub amount_in: u64,
@@ -272,6 +283,8 @@ pub struct SwapExactOutQuote {
 pub struct SwapExactInQuote {
     pub amount_out: u64,
     pub fee: u64,
+    /// bins the active bin moved through
+    pub bins_crossed: u32,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
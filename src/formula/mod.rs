@@ -2,8 +2,19 @@ pub mod base;
 pub mod constant_product;
 pub mod clmm;
 pub mod raydium_clmm;
+pub mod raydium_cpmm;
 pub mod orca_clmm;
 pub mod meteora_dlmm;
+pub mod meteora_damm;
+pub mod meteora_damm_v2;
+pub mod phoenix;
+pub mod stable_swap;
+pub mod saber;
+pub mod stake_pool;
+pub mod sanctum;
+pub mod pump;
 pub mod dlmm;
-mod raydium_openbook;
-mod openbook;
\ No newline at end of file
+#[cfg(test)]
+pub mod test;
+pub mod raydium_openbook;
+pub mod openbook;
\ No newline at end of file
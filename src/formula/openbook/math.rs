@@ -5,9 +5,12 @@
 #![allow(clippy::manual_range_contains)]
 
 use num_traits::CheckedDiv;
-use std::{cmp::Eq, convert::identity, convert::TryInto};
+use solana_sdk::pubkey::Pubkey;
+use std::{cmp::Eq, convert::TryInto};
 use uint::construct_uint;
 
+use crate::r#struct::pools::{RaydiumOpenBookMarket, SerumEventQueue, SerumOpenOrders};
+
 construct_uint! {
     pub struct U256(4);
 }
@@ -235,112 +238,81 @@ impl Calculator {
         ret
     }
 
-    pub fn calc_exact_vault_in_serum<'a>(
-        open_orders: &'a OpenOrders,
-        market_state: &'a Box<MarketState>,
-        event_q_account: &'a AccountInfo,
-        amm_open_account: &'a AccountInfo,
-    ) -> Result<(u64, u64), AmmError> {
-        let event_q = market_state.load_event_queue_mut(event_q_account).unwrap();
+    /// Funds of the AMM's open orders, less what makers already paid in fills the crank has not consumed yet
+    pub fn calc_exact_vault_in_serum(
+        open_orders: &SerumOpenOrders,
+        event_queue: &SerumEventQueue,
+        amm_open_orders: &Pubkey,
+    ) -> Result<(u64, u64), &'static str> {
         let mut native_pc_total = open_orders.native_pc_total;
         let mut native_coin_total = open_orders.native_coin_total;
-        println!("calc_exact len:{}", event_q.len());
-        // todo
-        // sol_log_compute_units();
-        for event in event_q.iter() {
-            if identity(event.owner) != (*amm_open_account.key).to_aligned_bytes() {
+        for fill in event_queue.fills.iter() {
+            if fill.owner != *amm_open_orders || !fill.maker {
                 continue;
             }
-            // msg!("{:?}", event.as_view().unwrap());
-            match event.as_view().unwrap() {
-                EventView::Fill {
-                    side,
-                    maker,
-                    native_qty_paid,
-                    native_qty_received,
-                    native_fee_or_rebate: _,
-                    fee_tier: _,
-                    order_id: _,
-                    owner: _,
-                    owner_slot: _,
-                    client_order_id: _,
-                } => {
-                    match side {
-                        Side::Bid if maker => {
-                            native_pc_total -= native_qty_paid;
-                            native_coin_total += native_qty_received;
-                        }
-                        Side::Ask if maker => {
-                            native_coin_total -= native_qty_paid;
-                            native_pc_total += native_qty_received;
-                        }
-                        _ => (),
-                    };
-                }
-                _ => {
-                    continue;
-                }
+            if fill.is_bid {
+                native_pc_total = native_pc_total.checked_sub(fill.native_qty_paid).ok_or("AmmError::CheckedSubOverflow")?;
+                native_coin_total = native_coin_total.checked_add(fill.native_qty_received).ok_or("AmmError::CheckedAddOverflow")?;
+            } else {
+                native_coin_total = native_coin_total.checked_sub(fill.native_qty_paid).ok_or("AmmError::CheckedSubOverflow")?;
+                native_pc_total = native_pc_total.checked_add(fill.native_qty_received).ok_or("AmmError::CheckedAddOverflow")?;
             }
         }
-        // todo
-        // sol_log_compute_units();
         Ok((native_pc_total, native_coin_total))
     }
 
-    pub fn calc_total_without_take_pnl<'a>(
+    pub fn calc_total_without_take_pnl(
         pc_amount: u64,
         coin_amount: u64,
-        open_orders: &'a OpenOrders,
-        amm: &'a AmmInfo,
-        market_state: &'a Box<MarketState>,
-        event_q_account: &'a AccountInfo,
-        amm_open_account: &'a AccountInfo,
+        open_orders: &SerumOpenOrders,
+        amm: &RaydiumOpenBookMarket,
+        event_queue: &SerumEventQueue,
+        amm_open_orders: &Pubkey,
     ) -> Result<(u64, u64), &'static str> {
         let (pc_total_in_serum, coin_total_in_serum) = Self::calc_exact_vault_in_serum(
             open_orders,
-            market_state,
-            event_q_account,
-            amm_open_account,
+            event_queue,
+            amm_open_orders,
         )?;
 
         let total_pc_without_take_pnl = pc_amount
             .checked_add(pc_total_in_serum)
             .ok_or("AmmError::CheckedAddOverflow")?
-            .checked_sub(amm.state_data.need_take_pnl_pc)
+            .checked_sub(amm.quote_need_take_pnl)
             .ok_or("AmmError::CheckedSubOverflow")?;
         let total_coin_without_take_pnl = coin_amount
             .checked_add(coin_total_in_serum)
             .ok_or("AmmError::CheckedAddOverflow")?
-            .checked_sub(amm.state_data.need_take_pnl_coin)
+            .checked_sub(amm.base_need_take_pnl)
             .ok_or("AmmError::CheckedSubOverflow")?;
         Ok((total_pc_without_take_pnl, total_coin_without_take_pnl))
     }
 
-    pub fn calc_total_without_take_pnl_no_orderbook<'a>(
+    pub fn calc_total_without_take_pnl_no_orderbook(
         pc_amount: u64,
         coin_amount: u64,
-        amm: &'a AmmInfo,
+        amm: &RaydiumOpenBookMarket,
     ) -> Result<(u64, u64), &'static str> {
         let total_pc_without_take_pnl = pc_amount
-            .checked_sub(amm.state_data.need_take_pnl_pc)
+            .checked_sub(amm.quote_need_take_pnl)
             .ok_or("AmmError::CheckedSubOverflow")?;
         let total_coin_without_take_pnl = coin_amount
-            .checked_sub(amm.state_data.need_take_pnl_coin)
+            .checked_sub(amm.base_need_take_pnl)
             .ok_or("AmmError::CheckedSubOverflow")?;
         Ok((total_pc_without_take_pnl, total_coin_without_take_pnl))
     }
 
-    pub fn get_max_buy_size_at_price(price: u64, x: u128, y: u128, amm: &AmmInfo) -> u64 {
+    pub fn get_max_buy_size_at_price(price: u64, x: u128, y: u128, amm: &RaydiumOpenBookMarket) -> u64 {
         // max_size = x / (1.0025 * price) - y
         let price_with_fee = U128::from(price)
             .checked_mul(U128::from(
-                amm.fees.trade_fee_denominator + amm.fees.trade_fee_numerator,
+                amm.trade_fee_denominator + amm.trade_fee_numerator,
             ))
             .unwrap()
-            .checked_div(U128::from(amm.fees.trade_fee_denominator))
+            .checked_div(U128::from(amm.trade_fee_denominator))
             .unwrap();
         let mut max_size = U128::from(x)
-            .checked_mul(amm.sys_decimal_value.into())
+            .checked_mul(amm.system_decimal_value.into())
             .unwrap()
             .checked_div(price_with_fee)
             .unwrap();
@@ -348,17 +320,17 @@ impl Calculator {
         Self::to_u64(max_size.as_u128()).unwrap()
     }
 
-    pub fn get_max_sell_size_at_price(price: u64, x: u128, y: u128, amm: &AmmInfo) -> u64 {
+    pub fn get_max_sell_size_at_price(price: u64, x: u128, y: u128, amm: &RaydiumOpenBookMarket) -> u64 {
         // let max_size = y - x / (p / 1.0025)
         let price_with_fee = U128::from(price)
-            .checked_mul(amm.fees.trade_fee_denominator.into())
+            .checked_mul(amm.trade_fee_denominator.into())
             .unwrap()
             .checked_div(U128::from(
-                amm.fees.trade_fee_denominator + amm.fees.trade_fee_numerator,
+                amm.trade_fee_denominator + amm.trade_fee_numerator,
             ))
             .unwrap();
         let second_part = U128::from(x)
-            .checked_mul(amm.sys_decimal_value.into())
+            .checked_mul(amm.system_decimal_value.into())
             .unwrap()
             .checked_div(price_with_fee.into())
             .unwrap();
@@ -1,2 +1,3 @@
 pub mod openbook_processor;
-pub mod math;
\ No newline at end of file
+pub mod math;
+pub mod openbook_v2;
\ No newline at end of file
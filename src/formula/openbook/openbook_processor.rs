@@ -1,12 +1,14 @@
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-#[repr(packed)]
-pub struct LeafNode {
-    tag: u32,
-    owner_slot: u8,
-    fee_tier: u8,
-    padding: [u8; 2],
-    key: u128,
-    owner: [u64; 4],
-    quantity: u64,
-    client_order_id: u64,
-}
\ No newline at end of file
+use crate::r#struct::pools::{SerumBookSide, SerumOpenOrders, SerumOrder};
+
+/// Orders of the AMM resting on the book as (bids, asks), found by the order ids of its open orders
+pub fn get_amm_orders(
+    open_orders: &SerumOpenOrders,
+    bids: &SerumBookSide,
+    asks: &SerumBookSide,
+) -> (Vec<SerumOrder>, Vec<SerumOrder>) {
+    let find = |book_side: &SerumBookSide, order_ids: &Vec<u128>| {
+        order_ids.iter().filter_map(|order_id| book_side.find_by_order_id(*order_id)).collect::<Vec<SerumOrder>>()
+    };
+
+    (find(bids, &open_orders.bid_order_ids), find(asks, &open_orders.ask_order_ids))
+}
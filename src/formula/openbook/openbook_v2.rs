@@ -0,0 +1,201 @@
+use crate::r#struct::pools::{OpenBookV2BookSide, OpenBookV2EventHeap, OpenBookV2Market, OpenBookV2Order};
+
+pub const FEES_SCALE_FACTOR: i128 = 1_000_000;
+// expired orders a taker order removes from the book, each one pushes an out event
+pub const DROP_EXPIRED_ORDER_LIMIT: u32 = 5;
+// resting orders a taker order matches at most
+pub const MATCH_LIMIT: u8 = 50;
+
+/// Result of matching a taker order, in lots
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct OpenBookV2Fill {
+    pub base_lots: i64,
+    /// quote lots matched before the taker fee
+    pub quote_lots: i64,
+    /// resting orders matched
+    pub orders: u32,
+    /// expired or out of peg limit orders removed
+    pub dropped_orders: u32,
+}
+
+/// Order of the book with its price in quote lots per base lot, `valid` is false for orders the matching engine skips
+#[derive(Copy, Clone, Debug)]
+pub struct PricedOrder<'a> {
+    pub price_lots: i64,
+    pub order: &'a OpenBookV2Order,
+    pub valid: bool,
+}
+
+/// Taker fee on `quote_native`, rounded up
+pub fn taker_fees_ceil(market: &OpenBookV2Market, quote_native: i64) -> i64 {
+    (quote_native as i128 * market.taker_fee as i128).div_ceil(FEES_SCALE_FACTOR) as i64
+}
+
+/// Quote lots left to match once the taker fee of `quote_lots` is put aside
+pub fn subtract_taker_fees(market: &OpenBookV2Market, quote_lots: i64) -> i64 {
+    (quote_lots as i128 * FEES_SCALE_FACTOR / (FEES_SCALE_FACTOR + market.taker_fee as i128)) as i64
+}
+
+/// Orders of `book_side` by price priority, fixed and oracle pegged merged.
+/// Pegged orders are left out without an oracle price
+pub fn get_priced_orders(book_side: &OpenBookV2BookSide, oracle_price_lots: Option<i64>, now_ts: u64) -> Vec<PricedOrder<'_>> {
+    let is_bids = book_side.is_bids();
+    let mut orders = book_side.fixed.iter().map(|order| {
+        let price_lots = order.get_price_data() as i64;
+        PricedOrder { price_lots, order, valid: !order.is_expired(now_ts) && price_lots >= 1 }
+    }).collect::<Vec<PricedOrder>>();
+
+    if let Some(oracle_price_lots) = oracle_price_lots {
+        book_side.oracle_pegged.iter().for_each(|order| {
+            let price_lots = oracle_price_lots.saturating_add(order.get_peg_offset());
+            let within_peg_limit = order.peg_limit == -1
+                || (is_bids && price_lots <= order.peg_limit)
+                || (!is_bids && price_lots >= order.peg_limit);
+            orders.push(PricedOrder {
+                price_lots,
+                order,
+                valid: !order.is_expired(now_ts) && price_lots >= 1 && within_peg_limit,
+            });
+        });
+    }
+
+    // stable, a fixed order keeps priority over a pegged order of the same price
+    if is_bids {
+        orders.sort_by(|a, b| b.price_lots.cmp(&a.price_lots));
+    } else {
+        orders.sort_by(|a, b| a.price_lots.cmp(&b.price_lots));
+    }
+
+    orders
+}
+
+/// Matches a taker order against `book_side`, up to `max_base_lots` and `max_quote_lots`.
+/// Every fill and every removed order takes an event heap slot, matching stops once the heap is full
+pub fn match_taker(
+    book_side: &OpenBookV2BookSide,
+    event_heap: &OpenBookV2EventHeap,
+    max_base_lots: i64,
+    max_quote_lots: i64,
+    limit: u8,
+    oracle_price_lots: Option<i64>,
+    now_ts: u64
+) -> OpenBookV2Fill {
+    let mut fill = OpenBookV2Fill::default();
+    let (mut remaining_base_lots, mut remaining_quote_lots) = (max_base_lots, max_quote_lots);
+    let free_space = event_heap.get_free_space() as u32;
+
+    for priced in get_priced_orders(book_side, oracle_price_lots, now_ts) {
+        if remaining_base_lots <= 0 || remaining_quote_lots <= 0 || fill.orders + fill.dropped_orders >= free_space {
+            break;
+        }
+        if !priced.valid {
+            if fill.dropped_orders < DROP_EXPIRED_ORDER_LIMIT {
+                fill.dropped_orders += 1;
+            }
+            continue;
+        }
+        if fill.orders >= limit as u32 {
+            break;
+        }
+
+        let max_match_by_quote = remaining_quote_lots / priced.price_lots;
+        if max_match_by_quote == 0 {
+            break;
+        }
+        let match_base_lots = remaining_base_lots.min(priced.order.quantity).min(max_match_by_quote);
+        let match_quote_lots = match_base_lots * priced.price_lots;
+
+        remaining_base_lots -= match_base_lots;
+        remaining_quote_lots -= match_quote_lots;
+        fill.base_lots += match_base_lots;
+        fill.quote_lots += match_quote_lots;
+        fill.orders += 1;
+    }
+
+    fill
+}
+
+/// Quotes a `place_take_order` of `amount_in` at any price, selling base into the bids when `base_in`.
+/// Atoms short of a whole lot are not traded and stay with the taker
+pub fn quote_exact_in(
+    market: &OpenBookV2Market,
+    bids: &OpenBookV2BookSide,
+    asks: &OpenBookV2BookSide,
+    event_heap: &OpenBookV2EventHeap,
+    amount_in: u64,
+    base_in: bool,
+    oracle_price_lots: Option<i64>,
+    now_ts: u64
+) -> Result<(u64, OpenBookV2Fill), &'static str> {
+    if market.time_expiry != 0 && now_ts as i64 >= market.time_expiry {
+        return Err("market expired");
+    }
+    if market.base_lot_size <= 0 || market.quote_lot_size <= 0 {
+        return Err("invalid lot size");
+    }
+    let amount_in = i64::try_from(amount_in).or(Err("amount in overflow"))?;
+
+    if base_in {
+        let fill = match_taker(bids, event_heap, amount_in / market.base_lot_size, i64::MAX, MATCH_LIMIT, oracle_price_lots, now_ts);
+        let quote_native = fill.quote_lots.checked_mul(market.quote_lot_size).ok_or("math overflow")?;
+        let amount_out = quote_native - taker_fees_ceil(market, quote_native);
+        Ok((amount_out.max(0) as u64, fill))
+    } else {
+        let max_quote_lots = subtract_taker_fees(market, amount_in / market.quote_lot_size);
+        let fill = match_taker(asks, event_heap, i64::MAX, max_quote_lots, MATCH_LIMIT, oracle_price_lots, now_ts);
+        let amount_out = fill.base_lots.checked_mul(market.base_lot_size).ok_or("math overflow")?;
+        Ok((amount_out as u64, fill))
+    }
+}
+
+/// Price of a legacy Pyth price account as (price, exponent), `None` when stale, not trading or too uncertain
+pub fn get_pyth_price(data: &[u8], current_slot: u64, max_staleness_slots: i64, conf_filter: f64) -> Option<(i64, i32)> {
+    let read = |at: usize| data.get(at..at + 8).map(|bytes| <[u8; 8]>::try_from(bytes).unwrap());
+    if data.get(0..4)? != 0xa1b2c3d4u32.to_le_bytes() {
+        return None;
+    }
+    let exponent = i32::from_le_bytes(data.get(20..24)?.try_into().unwrap());
+    let price = i64::from_le_bytes(read(208)?);
+    let conf = u64::from_le_bytes(read(216)?);
+    let status = u32::from_le_bytes(data.get(224..228)?.try_into().unwrap());
+    let publish_slot = u64::from_le_bytes(read(232)?);
+
+    // status 1 is trading
+    if status != 1 || price <= 0 {
+        return None;
+    }
+    if max_staleness_slots >= 0 && current_slot > publish_slot.saturating_add(max_staleness_slots as u64) {
+        return None;
+    }
+    if conf as f64 > conf_filter * price as f64 {
+        return None;
+    }
+
+    Some((price, exponent))
+}
+
+/// Oracle price in quote lots per base lot, rounded down, oracle a over oracle b when the market has both
+pub fn get_oracle_price_lots(market: &OpenBookV2Market, oracle_a: Option<&[u8]>, oracle_b: Option<&[u8]>, current_slot: u64) -> Option<i64> {
+    let config = &market.oracle_config;
+    let (price_a, exponent_a) = get_pyth_price(oracle_a?, current_slot, config.max_staleness_slots, config.conf_filter)?;
+    let (price_b, exponent_b) = match market.oracle_b {
+        Some(_) => get_pyth_price(oracle_b?, current_slot, config.max_staleness_slots, config.conf_filter)?,
+        None => (1, 0)
+    };
+
+    // ui price to native, then to lots
+    let exponent = exponent_a - exponent_b + market.quote_decimals as i32 - market.base_decimals as i32;
+    let mut numerator = price_a as i128 * market.base_lot_size as i128;
+    let mut denominator = price_b as i128 * market.quote_lot_size as i128;
+    if exponent >= 0 {
+        numerator = numerator.checked_mul(10i128.checked_pow(exponent as u32)?)?;
+    } else {
+        denominator = denominator.checked_mul(10i128.checked_pow(exponent.unsigned_abs())?)?;
+    }
+    let price_lots = numerator.checked_div(denominator)?;
+    if price_lots < 1 {
+        return None;
+    }
+
+    i64::try_from(price_lots).ok()
+}
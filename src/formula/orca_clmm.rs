@@ -1,5 +1,6 @@
 use crate::formula::clmm::concentrated_liquidity::compute_swap_step;
 use crate::formula::clmm::constant::{ORCA_MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64};
+use crate::formula::clmm::orca_adaptive_fee::{AdaptiveFeeInfo, FeeRateManager};
 use crate::formula::clmm::orca_swap_state::{checked_mul_div, next_tick_cross_update, NO_EXPLICIT_SQRT_PRICE_LIMIT, NUM_REWARDS, PostSwapUpdate, PROTOCOL_FEE_RATE_MUL_VALUE, Q64_RESOLUTION, SwapTickSequence, Tick, TICK_ARRAY_SIZE, TickUpdate};
 use crate::formula::clmm::orca_tick_math::{sqrt_price_from_tick_index, tick_index_from_sqrt_price};
 use crate::formula::clmm::raydium_swap_state::add_delta;
@@ -7,12 +8,13 @@ use crate::r#struct::pools::{OrcaClmmMarket, WhirlpoolRewardInfo};
 
 pub fn swap_internal(
     whirlpool: &OrcaClmmMarket,
-    swap_tick_sequence: &mut SwapTickSequence,
+    swap_tick_sequence: &SwapTickSequence,
     amount: u64,
     sqrt_price_limit: u128,
     amount_specified_is_input: bool,
     a_to_b: bool,
     timestamp: u64,
+    adaptive_fee_info: &Option<AdaptiveFeeInfo>,
 ) -> Result<PostSwapUpdate, &'static str> {
     let adjusted_sqrt_price_limit = if sqrt_price_limit == NO_EXPLICIT_SQRT_PRICE_LIMIT {
         if a_to_b {
@@ -39,7 +41,6 @@ pub fn swap_internal(
     }
 
     let tick_spacing = whirlpool.tick_spacing;
-    let fee_rate = whirlpool.fee_rate;
     let protocol_fee_rate = whirlpool.protocol_fee_rate;
     let next_reward_infos = next_whirlpool_reward_infos(whirlpool, timestamp)?;
 
@@ -50,11 +51,19 @@ pub fn swap_internal(
     let mut curr_liquidity = whirlpool.liquidity;
     let mut curr_protocol_fee: u64 = 0;
     let mut curr_array_index: usize = 0;
+    let mut tick_updates = Vec::new();
     let mut curr_fee_growth_global_input = if a_to_b {
         whirlpool.fee_growth_global_a
     } else {
         whirlpool.fee_growth_global_b
     };
+    let mut fee_rate_manager = FeeRateManager::new(
+        a_to_b,
+        whirlpool.tick_current_index,
+        timestamp,
+        whirlpool.fee_rate,
+        adaptive_fee_info,
+    )?;
 
     while amount_remaining > 0 && adjusted_sqrt_price_limit != curr_sqrt_price {
         let (next_array_index, next_tick_index) = swap_tick_sequence
@@ -68,49 +77,75 @@ pub fn swap_internal(
         let (next_tick_sqrt_price, sqrt_price_target) =
             get_next_sqrt_prices(next_tick_index, adjusted_sqrt_price_limit, a_to_b);
 
-        let swap_computation = compute_swap_step(
-            curr_sqrt_price,
-            sqrt_price_target,
-            curr_liquidity,
-            amount_remaining,
-            fee_rate as u32,
-            amount_specified_is_input,
-            a_to_b,
-        )?;
-
-        if amount_specified_is_input {
-            amount_remaining = amount_remaining
-                .checked_sub(swap_computation.amount_in)
-                .ok_or("ErrorCode::AmountRemainingOverflow")?;
-            amount_remaining = amount_remaining
-                .checked_sub(swap_computation.fee_amount)
-                .ok_or("ErrorCode::AmountRemainingOverflow")?;
-
-            amount_calculated = amount_calculated
-                .checked_add(swap_computation.amount_out)
-                .ok_or("ErrorCode::AmountCalcOverflow")?;
-        } else {
-            amount_remaining = amount_remaining
-                .checked_sub(swap_computation.amount_out)
-                .ok_or("ErrorCode::AmountRemainingOverflow")?;
-
-            amount_calculated = amount_calculated
-                .checked_add(swap_computation.amount_in)
-                .ok_or("ErrorCode::AmountCalcOverflow")?;
-            amount_calculated = amount_calculated
-                .checked_add(swap_computation.fee_amount)
-                .ok_or("ErrorCode::AmountCalcOverflow")?;
-        }
+        // the adaptive fee is charged per tick group, so a step stops at the tick group edges
+        let swap_computation = loop {
+            fee_rate_manager.update_volatility_accumulator();
+            let (bounded_sqrt_price_target, adaptive_fee_update_skipped) =
+                fee_rate_manager.get_bounded_sqrt_price_target(sqrt_price_target, curr_liquidity);
+
+            let swap_computation = compute_swap_step(
+                curr_sqrt_price,
+                bounded_sqrt_price_target,
+                curr_liquidity,
+                amount_remaining,
+                fee_rate_manager.get_total_fee_rate(),
+                amount_specified_is_input,
+                a_to_b,
+            )?;
+
+            if amount_specified_is_input {
+                amount_remaining = amount_remaining
+                    .checked_sub(swap_computation.amount_in)
+                    .ok_or("ErrorCode::AmountRemainingOverflow")?;
+                amount_remaining = amount_remaining
+                    .checked_sub(swap_computation.fee_amount)
+                    .ok_or("ErrorCode::AmountRemainingOverflow")?;
+
+                amount_calculated = amount_calculated
+                    .checked_add(swap_computation.amount_out)
+                    .ok_or("ErrorCode::AmountCalcOverflow")?;
+            } else {
+                amount_remaining = amount_remaining
+                    .checked_sub(swap_computation.amount_out)
+                    .ok_or("ErrorCode::AmountRemainingOverflow")?;
+
+                amount_calculated = amount_calculated
+                    .checked_add(swap_computation.amount_in)
+                    .ok_or("ErrorCode::AmountCalcOverflow")?;
+                amount_calculated = amount_calculated
+                    .checked_add(swap_computation.fee_amount)
+                    .ok_or("ErrorCode::AmountCalcOverflow")?;
+            }
+
+            let (next_protocol_fee, next_fee_growth_global_input) = calculate_fees(
+                swap_computation.fee_amount as u64,
+                protocol_fee_rate,
+                curr_liquidity,
+                curr_protocol_fee,
+                curr_fee_growth_global_input,
+            );
+            curr_protocol_fee = next_protocol_fee;
+            curr_fee_growth_global_input = next_fee_growth_global_input;
+
+            if adaptive_fee_update_skipped {
+                fee_rate_manager.advance_tick_group_after_skip(
+                    swap_computation.sqrt_price_next_x64,
+                    next_tick_sqrt_price,
+                    next_tick_index,
+                );
+            }
+
+            if amount_remaining == 0 || swap_computation.sqrt_price_next_x64 == sqrt_price_target {
+                break swap_computation;
+            }
 
-        let (next_protocol_fee, next_fee_growth_global_input) = calculate_fees(
-            swap_computation.fee_amount as u64,
-            protocol_fee_rate,
-            curr_liquidity,
-            curr_protocol_fee,
-            curr_fee_growth_global_input,
-        );
-        curr_protocol_fee = next_protocol_fee;
-        curr_fee_growth_global_input = next_fee_growth_global_input;
+            // stopped on the tick group edge before the next tick
+            if swap_computation.sqrt_price_next_x64 != curr_sqrt_price {
+                curr_tick_index = tick_index_from_sqrt_price(&swap_computation.sqrt_price_next_x64);
+            }
+            curr_sqrt_price = swap_computation.sqrt_price_next_x64;
+            fee_rate_manager.advance_tick_group();
+        };
 
         if swap_computation.sqrt_price_next_x64 == next_tick_sqrt_price {
             let (next_tick, next_tick_initialized) = swap_tick_sequence
@@ -142,13 +177,8 @@ pub fn swap_internal(
                 // )?;
 
                 curr_liquidity = next_liquidity;
-                // todo: for test only
-                swap_tick_sequence.update_tick(
-                    next_array_index,
-                    next_tick_index,
-                    tick_spacing,
-                    &update,
-                )?;
+                // the snapshot tick arrays are shared, the crossed ticks are left to the caller
+                tick_updates.push((next_array_index, next_tick_index, update));
             }
 
             let tick_offset = swap_tick_sequence.get_tick_offset(
@@ -196,6 +226,7 @@ pub fn swap_internal(
         next_fee_growth_global: curr_fee_growth_global_input,
         next_reward_infos: [WhirlpoolRewardInfo::default(); 3],
         next_protocol_fee: curr_protocol_fee,
+        tick_updates,
     })
 }
 
@@ -0,0 +1,118 @@
+use crate::r#struct::pools::{PhoenixMarket, PhoenixRestingOrder};
+
+pub const BASIS_POINT_MAX: u64 = 10_000;
+
+/// Result of matching a taker order against one side of the book, in lots
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PhoenixFill {
+    pub base_lots: u64,
+    /// quote lots matched before the taker fee
+    pub quote_lots: u64,
+    pub fee_quote_lots: u64,
+    /// resting orders the taker order matched
+    pub orders: u32,
+}
+
+/// Taker fee on `quote_lots`, rounded up
+pub fn compute_fee(quote_lots: u64, taker_fee_bps: u64) -> Result<u64, &'static str> {
+    let fee = (quote_lots as u128 * taker_fee_bps as u128).div_ceil(BASIS_POINT_MAX as u128);
+    u64::try_from(fee).or(Err("math overflow"))
+}
+
+/// Quote lots `base_lots` are worth at `price_in_ticks`
+pub fn get_quote_lots(market: &PhoenixMarket, price_in_ticks: u64, base_lots: u64, round_up: bool) -> Result<u64, &'static str> {
+    if market.base_lots_per_base_unit == 0 {
+        return Err("zero base lots per base unit");
+    }
+    let numerator = price_in_ticks as u128 * market.tick_size_in_quote_lots_per_base_unit as u128 * base_lots as u128;
+    let quote_lots = if round_up {
+        numerator.div_ceil(market.base_lots_per_base_unit as u128)
+    } else {
+        numerator / market.base_lots_per_base_unit as u128
+    };
+    u64::try_from(quote_lots).or(Err("math overflow"))
+}
+
+fn get_live_orders<'a>(orders: &'a [PhoenixRestingOrder], current_slot: u64, current_timestamp: u64) -> impl Iterator<Item = &'a PhoenixRestingOrder> {
+    orders.iter().filter(move |order| {
+        order.num_base_lots > 0 && !order.is_expired(current_slot, current_timestamp)
+    })
+}
+
+/// Sells `base_lots` into the bids, best price first. The taker fee is taken from the quote received
+pub fn sell_base_lots(market: &PhoenixMarket, base_lots: u64, current_slot: u64, current_timestamp: u64) -> Result<PhoenixFill, &'static str> {
+    let mut fill = PhoenixFill::default();
+    let mut remaining_base_lots = base_lots;
+
+    for order in get_live_orders(&market.bids, current_slot, current_timestamp) {
+        if remaining_base_lots == 0 {
+            break;
+        }
+        let matched_base_lots = remaining_base_lots.min(order.num_base_lots);
+        // the seller is paid rounded down
+        let matched_quote_lots = get_quote_lots(market, order.price_in_ticks, matched_base_lots, false)?;
+
+        remaining_base_lots -= matched_base_lots;
+        fill.base_lots += matched_base_lots;
+        fill.quote_lots = fill.quote_lots.checked_add(matched_quote_lots).ok_or("math overflow")?;
+        fill.orders += 1;
+    }
+    fill.fee_quote_lots = compute_fee(fill.quote_lots, market.taker_fee_bps)?;
+
+    Ok(fill)
+}
+
+/// Buys from the asks, best price first, spending at most `quote_lots` including the taker fee
+pub fn buy_with_quote_lots(market: &PhoenixMarket, quote_lots: u64, current_slot: u64, current_timestamp: u64) -> Result<PhoenixFill, &'static str> {
+    let mut fill = PhoenixFill::default();
+    // the budget is reduced so that the matched quote and the fee on it fit in `quote_lots`
+    let mut remaining_quote_lots = (quote_lots as u128 * BASIS_POINT_MAX as u128 / (BASIS_POINT_MAX + market.taker_fee_bps) as u128) as u64;
+
+    for order in get_live_orders(&market.asks, current_slot, current_timestamp) {
+        if remaining_quote_lots == 0 {
+            break;
+        }
+        // the buyer pays rounded up
+        let order_quote_lots = get_quote_lots(market, order.price_in_ticks, order.num_base_lots, true)?;
+        let (matched_base_lots, matched_quote_lots) = if order_quote_lots <= remaining_quote_lots {
+            (order.num_base_lots, order_quote_lots)
+        } else {
+            let lot_price = order.price_in_ticks as u128 * market.tick_size_in_quote_lots_per_base_unit as u128;
+            if lot_price == 0 {
+                return Err("zero order price");
+            }
+            let base_lots = (remaining_quote_lots as u128 * market.base_lots_per_base_unit as u128 / lot_price) as u64;
+            (base_lots, get_quote_lots(market, order.price_in_ticks, base_lots, true)?)
+        };
+        if matched_base_lots == 0 {
+            break;
+        }
+
+        remaining_quote_lots -= matched_quote_lots;
+        fill.base_lots += matched_base_lots;
+        fill.quote_lots += matched_quote_lots;
+        fill.orders += 1;
+    }
+    fill.fee_quote_lots = compute_fee(fill.quote_lots, market.taker_fee_bps)?;
+
+    Ok(fill)
+}
+
+/// Quotes an immediate-or-cancel taker order of `amount_in` atoms, selling base when `base_in`.
+/// Atoms short of a whole lot are not traded and stay with the taker
+pub fn quote_exact_in(market: &PhoenixMarket, amount_in: u64, base_in: bool, current_slot: u64, current_timestamp: u64) -> Result<(u64, PhoenixFill), &'static str> {
+    if market.base_lot_size == 0 || market.quote_lot_size == 0 {
+        return Err("zero lot size");
+    }
+
+    if base_in {
+        let fill = sell_base_lots(market, amount_in / market.base_lot_size, current_slot, current_timestamp)?;
+        let quote_lots = fill.quote_lots.checked_sub(fill.fee_quote_lots).ok_or("fee over amount out")?;
+        let amount_out = quote_lots.checked_mul(market.quote_lot_size).ok_or("math overflow")?;
+        Ok((amount_out, fill))
+    } else {
+        let fill = buy_with_quote_lots(market, amount_in / market.quote_lot_size, current_slot, current_timestamp)?;
+        let amount_out = fill.base_lots.checked_mul(market.base_lot_size).ok_or("math overflow")?;
+        Ok((amount_out, fill))
+    }
+}
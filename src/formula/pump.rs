@@ -0,0 +1,124 @@
+use crate::r#struct::pools::{PumpFunBondingCurve, PumpFunGlobal, PumpSwapGlobalConfig};
+
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+// PumpSwap disable flags of the global config
+const PUMP_SWAP_DISABLE_BUY: u8 = 1 << 3;
+const PUMP_SWAP_DISABLE_SELL: u8 = 1 << 4;
+
+/// Fee of `fee_bps` on `amount`, rounded up as both programs do
+pub fn fee(amount: u64, fee_bps: u64) -> u64 {
+    (amount as u128 * fee_bps as u128).div_ceil(BPS_DENOMINATOR) as u64
+}
+
+/// Fee rates charged on the SOL side of a trade, the creator fee only when a creator is set
+fn fee_rates(fees: &[u64]) -> (u64, u64) {
+    let fees = fees.iter().filter(|fee_bps| **fee_bps > 0);
+    (fees.clone().sum(), fees.count() as u64)
+}
+
+/// Amount left to trade out of `amount_in` once fees of `fee_bps` in total are set aside.
+/// Each of the `fee_count` fees rounds up, a lamport each is kept back for them
+fn amount_before_fees(amount_in: u64, fee_bps: u64, fee_count: u64) -> u64 {
+    let amount = amount_in as u128 * BPS_DENOMINATOR / (BPS_DENOMINATOR + fee_bps as u128);
+    (amount as u64).saturating_sub(fee_count)
+}
+
+/// Tokens a buy of `lamports` SOL gets on the bonding curve, at its virtual reserves and net of the protocol and creator fees.
+/// The curve charges a lamport over the exact price, so the amount is the largest the lamports surely cover
+pub fn quote_bonding_curve_buy(curve: &PumpFunBondingCurve, global: &PumpFunGlobal, lamports: u64) -> Result<u64, &'static str> {
+    if curve.complete {
+        return Err("bonding curve complete");
+    }
+    let (fee_bps, fee_count) = fee_rates(&[global.fee_basis_points, curve.get_creator_fee_bps(global)]);
+    let sol_amount = amount_before_fees(lamports, fee_bps, fee_count).saturating_sub(1);
+    if sol_amount == 0 {
+        return Err("zero sol amount");
+    }
+
+    let denominator = curve.virtual_sol_reserves as u128 + sol_amount as u128;
+    let tokens = (sol_amount as u128 * curve.virtual_token_reserves as u128 / denominator) as u64;
+    let tokens = tokens.min(curve.real_token_reserves);
+    if tokens == 0 {
+        return Err("zero tokens");
+    }
+
+    Ok(tokens)
+}
+
+/// Lamports a sell of `tokens` gets from the bonding curve, net of the protocol and creator fees
+pub fn quote_bonding_curve_sell(curve: &PumpFunBondingCurve, global: &PumpFunGlobal, tokens: u64) -> Result<u64, &'static str> {
+    if curve.complete {
+        return Err("bonding curve complete");
+    }
+    let denominator = curve.virtual_token_reserves as u128 + tokens as u128;
+    if denominator == 0 {
+        return Err("empty bonding curve");
+    }
+    let sol_amount = (tokens as u128 * curve.virtual_sol_reserves as u128 / denominator) as u64;
+    if sol_amount > curve.real_sol_reserves {
+        return Err("insufficient sol reserves");
+    }
+
+    let fees = fee(sol_amount, global.fee_basis_points) + fee(sol_amount, curve.get_creator_fee_bps(global));
+    let lamports = sol_amount.checked_sub(fees).ok_or("fees over sol amount")?;
+    if lamports == 0 {
+        return Err("zero lamports");
+    }
+
+    Ok(lamports)
+}
+
+/// Base tokens a buy of `quote_in` gets from a PumpSwap pool, the lp, protocol and creator fees taken on the quote side
+pub fn quote_pump_swap_buy(
+    config: &PumpSwapGlobalConfig,
+    coin_creator_fee_bps: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+    quote_in: u64
+) -> Result<u64, &'static str> {
+    if config.disable_flags & PUMP_SWAP_DISABLE_BUY != 0 {
+        return Err("buy disabled");
+    }
+    let (fee_bps, fee_count) = fee_rates(&[config.lp_fee_basis_points, config.protocol_fee_basis_points, coin_creator_fee_bps]);
+    let quote_amount = amount_before_fees(quote_in, fee_bps, fee_count);
+
+    let denominator = quote_reserve as u128 + quote_amount as u128;
+    if denominator == 0 {
+        return Err("empty pool");
+    }
+    let base_out = (quote_amount as u128 * base_reserve as u128 / denominator) as u64;
+    if base_out == 0 {
+        return Err("zero amount out");
+    }
+
+    Ok(base_out)
+}
+
+/// Quote tokens a sell of `base_in` gets from a PumpSwap pool, net of the lp, protocol and creator fees
+pub fn quote_pump_swap_sell(
+    config: &PumpSwapGlobalConfig,
+    coin_creator_fee_bps: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+    base_in: u64
+) -> Result<u64, &'static str> {
+    if config.disable_flags & PUMP_SWAP_DISABLE_SELL != 0 {
+        return Err("sell disabled");
+    }
+    let denominator = base_reserve as u128 + base_in as u128;
+    if denominator == 0 {
+        return Err("empty pool");
+    }
+    let quote_amount = (base_in as u128 * quote_reserve as u128 / denominator) as u64;
+
+    let fees = fee(quote_amount, config.lp_fee_basis_points)
+        + fee(quote_amount, config.protocol_fee_basis_points)
+        + fee(quote_amount, coin_creator_fee_bps);
+    let quote_out = quote_amount.checked_sub(fees).ok_or("fees over quote amount")?;
+    if quote_out == 0 {
+        return Err("zero amount out");
+    }
+
+    Ok(quote_out)
+}
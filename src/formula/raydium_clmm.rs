@@ -1,5 +1,6 @@
 use std::collections::VecDeque;
 use std::ops::Neg;
+use std::sync::Arc;
 
 use crate::formula::clmm::concentrated_liquidity::compute_swap_step;
 use crate::formula::clmm::constant::{FEE_RATE_DENOMINATOR_VALUE, MAX_SQRT_PRICE_X64, MAX_TICK, MIN_SQRT_PRICE_X64, MIN_TICK};
@@ -9,10 +10,12 @@ use crate::formula::clmm::raydium_tick_math::{get_sqrt_price_at_tick, get_tick_a
 use crate::formula::clmm::u256_math::U128;
 use crate::r#struct::pools::{AmmConfig, RaydiumClmmMarket};
 
-pub fn swap_internal(
+/// Tick array states are either owned or shared with an account snapshot,
+/// a shared one is copied only when the swap crosses one of its initialized ticks
+pub fn swap_internal<T: Into<Arc<TickArrayState>>>(
     amm_config: &AmmConfig,
     pool_state: &mut RaydiumClmmMarket,
-    tick_array_states: &mut VecDeque<TickArrayState>,
+    tick_array_states: &mut VecDeque<T>,
     tick_array_bitmap_extension: &Option<&TickArrayBitmapExtension>,
     amount_specified: u64,
     sqrt_price_limit_x64: u128,
@@ -71,7 +74,7 @@ pub fn swap_internal(
         pool_state.get_first_initialized_tick_array(tick_array_bitmap_extension, zero_for_one)?;
     let mut current_valid_tick_array_start_index = first_valid_tick_array_start_index;
 
-    let mut tick_array_current = tick_array_states.pop_front().unwrap();
+    let mut tick_array_current: Arc<TickArrayState> = tick_array_states.pop_front().ok_or("not enough tick array account")?.into();
 
     for _ in 0..tick_array_states.len() {
         if tick_array_current.start_tick_index == current_valid_tick_array_start_index {
@@ -79,7 +82,8 @@ pub fn swap_internal(
         }
         tick_array_current = tick_array_states
             .pop_front()
-            .ok_or("not enough tick array account")?;
+            .ok_or("not enough tick array account")?
+            .into();
     }
 
     if tick_array_current.start_tick_index != current_valid_tick_array_start_index {
@@ -122,7 +126,8 @@ pub fn swap_internal(
             while tick_array_current.start_tick_index != next_initialized_tick_array_index.unwrap() {
                 tick_array_current = tick_array_states
                     .pop_front()
-                    .ok_or("not enough tick array account")?;
+                    .ok_or("not enough tick array account")?
+                    .into();
             }
             current_valid_tick_array_start_index = next_initialized_tick_array_index.unwrap();
 
@@ -244,14 +249,8 @@ pub fn swap_internal(
 
         if state.sqrt_price_x64 == step.sqrt_price_next_x64 {
             if step.initialized {
+                // the tick arrays are shared with the snapshot, crossing a tick only moves the liquidity
                 let mut liquidity_net = next_initialized_tick.liquidity_net;
-                //////////////////// todo: for test only
-                tick_array_current.update_tick_state(
-                    next_initialized_tick.tick,
-                    pool_state.tick_spacing.into(),
-                    *next_initialized_tick,
-                )?;
-                //////////////////// todo: for test only
 
                 if zero_for_one {
                     liquidity_net = liquidity_net.neg();
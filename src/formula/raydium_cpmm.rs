@@ -0,0 +1,122 @@
+use arrayref::{array_ref, array_refs};
+
+/// Trade fee rates of a cpmm AmmConfig are in hundredths of a basis point
+pub const FEE_RATE_DENOMINATOR_VALUE: u64 = 1_000_000;
+
+const MAX_FEE_BASIS_POINTS: u16 = 10_000;
+const MINT_DATA_LEN: usize = 82;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const EXTENSION_TYPE_TRANSFER_FEE_CONFIG: u16 = 1;
+// base account is padded to the token account length, then the account type and the extensions follow
+const EXTENSIONS_OFFSET: usize = 166;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TransferFee {
+    pub epoch: u64,
+    pub maximum_fee: u64,
+    pub transfer_fee_basis_points: u16,
+}
+
+impl TransferFee {
+    pub fn unpack_data(data: &[u8; 18]) -> TransferFee {
+        let (epoch, maximum_fee, transfer_fee_basis_points) = array_refs![data, 8, 8, 2];
+
+        TransferFee {
+            epoch: u64::from_le_bytes(*epoch),
+            maximum_fee: u64::from_le_bytes(*maximum_fee),
+            transfer_fee_basis_points: u16::from_le_bytes(*transfer_fee_basis_points),
+        }
+    }
+
+    /// Fee withheld when `amount` is transferred
+    pub fn calculate_fee(&self, amount: u64) -> u64 {
+        if self.transfer_fee_basis_points == 0 || amount == 0 {
+            return 0;
+        }
+        let fee = (amount as u128 * self.transfer_fee_basis_points as u128).div_ceil(MAX_FEE_BASIS_POINTS as u128);
+        (fee as u64).min(self.maximum_fee)
+    }
+
+    /// Fee to add to `post_fee_amount` so that `post_fee_amount` arrives after the transfer
+    pub fn calculate_inverse_fee(&self, post_fee_amount: u64) -> Result<u64, &'static str> {
+        if self.transfer_fee_basis_points == 0 || post_fee_amount == 0 {
+            return Ok(0);
+        }
+        if self.transfer_fee_basis_points >= MAX_FEE_BASIS_POINTS {
+            return Ok(self.maximum_fee);
+        }
+
+        let numerator = post_fee_amount as u128 * MAX_FEE_BASIS_POINTS as u128;
+        let denominator = (MAX_FEE_BASIS_POINTS - self.transfer_fee_basis_points) as u128;
+        let pre_fee_amount = u64::try_from(numerator.div_ceil(denominator)).or(Err("transfer fee overflow"))?;
+
+        Ok((pre_fee_amount - post_fee_amount).min(self.maximum_fee))
+    }
+}
+
+/// Transfer fee of a Token-2022 mint in effect at `epoch`, `None` if the mint has no transfer fee extension
+pub fn get_transfer_fee(mint_data: &[u8], epoch: u64) -> Option<TransferFee> {
+    if mint_data.len() <= EXTENSIONS_OFFSET || mint_data[EXTENSIONS_OFFSET - 1] != ACCOUNT_TYPE_MINT {
+        return None;
+    }
+    if mint_data[MINT_DATA_LEN..EXTENSIONS_OFFSET - 1].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+
+    let mut offset = EXTENSIONS_OFFSET;
+    while offset + 4 <= mint_data.len() {
+        let (extension_type, length) = array_refs![array_ref![mint_data, offset, 4], 2, 2];
+        let (extension_type, length) = (u16::from_le_bytes(*extension_type), u16::from_le_bytes(*length) as usize);
+        offset += 4;
+
+        if offset + length > mint_data.len() {
+            return None;
+        }
+        if extension_type == EXTENSION_TYPE_TRANSFER_FEE_CONFIG && length == 108 {
+            // authorities (64), withheld amount (8), then the older and the newer fee
+            let (older, newer) = array_refs![array_ref![mint_data, offset + 72, 36], 18, 18];
+            let (older, newer) = (TransferFee::unpack_data(older), TransferFee::unpack_data(newer));
+            return Some(if epoch >= newer.epoch { newer } else { older });
+        }
+        offset += length;
+    }
+
+    None
+}
+
+fn get_trade_fee(amount: u64, trade_fee_rate: u64) -> u64 {
+    (amount as u128 * trade_fee_rate as u128).div_ceil(FEE_RATE_DENOMINATOR_VALUE as u128) as u64
+}
+
+/// Swaps `amount_in` into the pool, returns (trade_fee, amount_out)
+pub fn swap_base_input(amount_in: u64, source_amount: u64, destination_amount: u64, trade_fee_rate: u64) -> Result<(u64, u64), &'static str> {
+    if trade_fee_rate >= FEE_RATE_DENOMINATOR_VALUE {
+        return Err("invalid trade fee rate");
+    }
+    let trade_fee = get_trade_fee(amount_in, trade_fee_rate);
+    let amount_in_less_fees = (amount_in - trade_fee) as u128;
+
+    let denominator = source_amount as u128 + amount_in_less_fees;
+    if denominator == 0 {
+        return Err("empty pool");
+    }
+    let amount_out = amount_in_less_fees * destination_amount as u128 / denominator;
+
+    Ok((trade_fee, amount_out as u64))
+}
+
+/// Swaps out `amount_out` of the pool, returns (trade_fee, amount_in)
+pub fn swap_base_output(amount_out: u64, source_amount: u64, destination_amount: u64, trade_fee_rate: u64) -> Result<(u64, u64), &'static str> {
+    if trade_fee_rate >= FEE_RATE_DENOMINATOR_VALUE {
+        return Err("invalid trade fee rate");
+    }
+    if amount_out >= destination_amount {
+        return Err("insufficient liquidity");
+    }
+    let amount_in_less_fees = (amount_out as u128 * source_amount as u128).div_ceil((destination_amount - amount_out) as u128);
+
+    let amount_in = (amount_in_less_fees * FEE_RATE_DENOMINATOR_VALUE as u128).div_ceil((FEE_RATE_DENOMINATOR_VALUE - trade_fee_rate) as u128);
+    let amount_in = u64::try_from(amount_in).or(Err("amount in overflow"))?;
+
+    Ok((get_trade_fee(amount_in, trade_fee_rate), amount_in))
+}
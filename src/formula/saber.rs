@@ -0,0 +1,55 @@
+use crate::formula::stable_swap;
+use crate::r#struct::pools::{SaberFees, SaberMarket};
+
+impl SaberFees {
+    /// Trade fee taken from the output, rounded down
+    pub fn trade_fee(&self, amount: u64) -> Result<u64, &'static str> {
+        mul_div(amount, self.trade_fee_numerator, self.trade_fee_denominator)
+    }
+
+    /// Part of the trade fee sent to the admin fee account instead of staying in the pool
+    pub fn admin_trade_fee(&self, fee: u64) -> Result<u64, &'static str> {
+        mul_div(fee, self.admin_trade_fee_numerator, self.admin_trade_fee_denominator)
+    }
+}
+
+fn mul_div(amount: u64, numerator: u64, denominator: u64) -> Result<u64, &'static str> {
+    if numerator == 0 || amount == 0 {
+        return Ok(0);
+    }
+    if denominator == 0 {
+        return Err("zero fee denominator");
+    }
+    u64::try_from(amount as u128 * numerator as u128 / denominator as u128).or(Err("fee overflow"))
+}
+
+/// Quotes an exact-in swap against reserves holding `source_amount` and `destination_amount`,
+/// the trade fee is taken from the output as the Saber program does
+pub fn quote_exact_in(
+    pool: &SaberMarket,
+    amount_in: u64,
+    source_amount: u64,
+    destination_amount: u64,
+    current_ts: i64,
+) -> Result<u64, &'static str> {
+    if pool.is_paused {
+        return Err("pool paused");
+    }
+    let amp = stable_swap::compute_amp_factor(
+        pool.initial_amp_factor,
+        pool.target_amp_factor,
+        pool.start_ramp_ts,
+        pool.stop_ramp_ts,
+        current_ts
+    ).ok_or("amp factor math failed")?;
+
+    let dy = stable_swap::swap_to(amp, amount_in as u128, source_amount as u128, destination_amount as u128)
+        .ok_or("stable swap math failed")?;
+    let dy = u64::try_from(dy).or(Err("amount out overflow"))?;
+    let amount_out = dy.checked_sub(pool.fees.trade_fee(dy)?).ok_or("fee over amount out")?;
+    if amount_out == 0 {
+        return Err("zero amount out");
+    }
+
+    Ok(amount_out)
+}
@@ -0,0 +1,162 @@
+use crate::r#struct::pools::{MarinadeState, SanctumLstState, SanctumPoolState, SplStakePool};
+
+pub const BPS_DENOMINATOR: i64 = 10_000;
+// marinade fee cents are hundredths of a basis point
+const FEE_CENTS_DENOMINATOR: u128 = 1_000_000;
+
+/// State a SOL value calculator prices its LST from
+#[derive(Clone, Debug)]
+pub enum SolValueCalculatorState {
+    Wsol,
+    SplStakePool(SplStakePool),
+    Marinade(MarinadeState),
+}
+
+/// One side of an Infinity trade, the LST with its calculator state, its flat fees and the pool reserves of it
+#[derive(Clone, Debug)]
+pub struct LstQuoteState {
+    pub lst_state: SanctumLstState,
+    pub calculator: SolValueCalculatorState,
+    pub input_fee_bps: i16,
+    pub output_fee_bps: i16,
+    pub reserves: u64,
+}
+
+/// SOL value of `amount` of the LST, the lower end of the calculator range, as if redeemed through the slow path
+pub fn lst_to_sol(calculator: &SolValueCalculatorState, amount: u64, epoch: u64) -> Result<u64, &'static str> {
+    match calculator {
+        SolValueCalculatorState::Wsol => Ok(amount),
+        SolValueCalculatorState::SplStakePool(pool) => {
+            if pool.last_update_epoch < epoch {
+                return Err("stake pool not updated");
+            }
+            let fee = pool.stake_withdrawal_fee.apply(amount).ok_or("fee math failed")?;
+            let pool_tokens = amount.checked_sub(fee).ok_or("fee over pool tokens")?;
+            pool.calc_lamports_withdraw_amount(pool_tokens).ok_or("lamports math failed")
+        }
+        SolValueCalculatorState::Marinade(state) => {
+            if state.paused {
+                return Err("marinade paused");
+            }
+            let fee = (amount as u128 * state.delayed_unstake_fee as u128 / FEE_CENTS_DENOMINATOR) as u64;
+            state.calc_lamports_from_msol(amount - fee).ok_or("lamports math failed")
+        }
+    }
+}
+
+/// LST amount worth `lamports`, inverse of `lst_to_sol` rounded down
+pub fn sol_to_lst(calculator: &SolValueCalculatorState, lamports: u64, epoch: u64) -> Result<u64, &'static str> {
+    match calculator {
+        SolValueCalculatorState::Wsol => Ok(lamports),
+        SolValueCalculatorState::SplStakePool(pool) => {
+            if pool.last_update_epoch < epoch {
+                return Err("stake pool not updated");
+            }
+            let pool_tokens = pool.calc_pool_tokens_for_deposit(lamports).ok_or("pool token math failed")?;
+            let fee = pool.stake_withdrawal_fee;
+            if fee.denominator == 0 || fee.numerator == 0 {
+                return Ok(pool_tokens);
+            }
+            let kept = fee.denominator.checked_sub(fee.numerator).filter(|kept| *kept > 0).ok_or("invalid withdrawal fee")?;
+            u64::try_from(pool_tokens as u128 * fee.denominator as u128 / kept as u128).or(Err("pool token overflow"))
+        }
+        SolValueCalculatorState::Marinade(state) => {
+            if state.paused {
+                return Err("marinade paused");
+            }
+            let msol = state.calc_msol_from_lamports(lamports).ok_or("msol math failed")? as u128;
+            let kept = FEE_CENTS_DENOMINATOR.checked_sub(state.delayed_unstake_fee as u128).filter(|kept| *kept > 0).ok_or("invalid delayed unstake fee")?;
+            u64::try_from(msol * FEE_CENTS_DENOMINATOR / kept).or(Err("msol overflow"))
+        }
+    }
+}
+
+/// Applies a fee in basis points to a SOL value, a negative fee being a rebate
+pub fn apply_fee_bps(sol_value: u64, fee_bps: i64) -> Result<u64, &'static str> {
+    let kept = BPS_DENOMINATOR.checked_sub(fee_bps).filter(|kept| *kept >= 0).ok_or("fee over 100%")?;
+    u64::try_from(sol_value as u128 * kept as u128 / BPS_DENOMINATOR as u128).or(Err("sol value overflow"))
+}
+
+fn check_pool(pool: &SanctumPoolState) -> Result<(), &'static str> {
+    if pool.is_disabled != 0 {
+        return Err("pool disabled");
+    }
+    if pool.is_rebalancing != 0 {
+        return Err("pool rebalancing");
+    }
+    Ok(())
+}
+
+fn check_output(amount_out: u64, output: &LstQuoteState) -> Result<u64, &'static str> {
+    if amount_out == 0 {
+        return Err("zero amount out");
+    }
+    if amount_out > output.reserves {
+        return Err("insufficient reserves");
+    }
+    Ok(amount_out)
+}
+
+/// `SwapExactIn` between two LSTs of the pool, the flat fee program charging the input fee of one and the output fee of the other
+pub fn quote_swap_exact_in(
+    pool: &SanctumPoolState,
+    input: &LstQuoteState,
+    output: &LstQuoteState,
+    amount_in: u64,
+    epoch: u64,
+) -> Result<u64, &'static str> {
+    check_pool(pool)?;
+    if input.lst_state.is_input_disabled != 0 {
+        return Err("input disabled");
+    }
+    let in_sol_value = lst_to_sol(&input.calculator, amount_in, epoch)?;
+    let out_sol_value = apply_fee_bps(in_sol_value, input.input_fee_bps as i64 + output.output_fee_bps as i64)?;
+    let amount_out = sol_to_lst(&output.calculator, out_sol_value, epoch)?;
+
+    check_output(amount_out, output)
+}
+
+/// LP tokens `AddLiquidity` of `amount_in` of the LST mints, at the SOL value per LP token of the pool
+pub fn quote_add_liquidity(
+    pool: &SanctumPoolState,
+    input: &LstQuoteState,
+    lp_supply: u64,
+    amount_in: u64,
+    epoch: u64,
+) -> Result<u64, &'static str> {
+    check_pool(pool)?;
+    if input.lst_state.is_input_disabled != 0 {
+        return Err("input disabled");
+    }
+    let sol_value = lst_to_sol(&input.calculator, amount_in, epoch)?;
+    let lp_amount = if lp_supply == 0 || pool.total_sol_value == 0 {
+        sol_value
+    } else {
+        u64::try_from(sol_value as u128 * lp_supply as u128 / pool.total_sol_value as u128).or(Err("lp amount overflow"))?
+    };
+    if lp_amount == 0 {
+        return Err("zero lp amount");
+    }
+
+    Ok(lp_amount)
+}
+
+/// LST `RemoveLiquidity` of `lp_amount` pays, net of the LP withdrawal fee
+pub fn quote_remove_liquidity(
+    pool: &SanctumPoolState,
+    output: &LstQuoteState,
+    lp_supply: u64,
+    lp_withdrawal_fee_bps: u16,
+    lp_amount: u64,
+    epoch: u64,
+) -> Result<u64, &'static str> {
+    check_pool(pool)?;
+    if lp_supply == 0 {
+        return Err("empty lp supply");
+    }
+    let sol_value = u64::try_from(lp_amount as u128 * pool.total_sol_value as u128 / lp_supply as u128).or(Err("sol value overflow"))?;
+    let sol_value = apply_fee_bps(sol_value, lp_withdrawal_fee_bps as i64)?;
+    let amount_out = sol_to_lst(&output.calculator, sol_value, epoch)?;
+
+    check_output(amount_out, output)
+}
@@ -0,0 +1,104 @@
+//! Curve of two-coin stable pools (Saber and its forks).
+//! Amounts are expected to be upscaled to a common precision by the caller
+
+use ruint::aliases::U256;
+
+pub const N_COINS: u8 = 2;
+const MAX_ITERATIONS: u8 = 255;
+
+/// Amplification coefficient at `current_ts`, moving linearly from `initial_amp` to `target_amp` while a ramp is in progress
+pub fn compute_amp_factor(initial_amp: u64, target_amp: u64, start_ramp_ts: i64, stop_ramp_ts: i64, current_ts: i64) -> Option<u64> {
+    if current_ts >= stop_ramp_ts {
+        return Some(target_amp);
+    }
+
+    let time_range = stop_ramp_ts.checked_sub(start_ramp_ts)? as u128;
+    let time_delta = current_ts.checked_sub(start_ramp_ts)?.max(0) as u128;
+    if time_range == 0 {
+        return Some(target_amp);
+    }
+    if target_amp >= initial_amp {
+        let amp_delta = (target_amp - initial_amp) as u128 * time_delta / time_range;
+        initial_amp.checked_add(u64::try_from(amp_delta).ok()?)
+    } else {
+        let amp_delta = (initial_amp - target_amp) as u128 * time_delta / time_range;
+        initial_amp.checked_sub(u64::try_from(amp_delta).ok()?)
+    }
+}
+
+fn converged(a: U256, b: U256) -> bool {
+    let diff = if a > b { a - b } else { b - a };
+    diff <= U256::from(1u8)
+}
+
+/// Invariant D of the pool holding `amount_a` and `amount_b`
+pub fn compute_d(amp: u64, amount_a: u128, amount_b: u128) -> Option<U256> {
+    let sum_x = U256::from(amount_a).checked_add(U256::from(amount_b))?;
+    if sum_x.is_zero() {
+        return Some(U256::ZERO);
+    }
+    if amount_a == 0 || amount_b == 0 {
+        return None;
+    }
+
+    let n_coins = U256::from(N_COINS);
+    let ann = U256::from(amp).checked_mul(n_coins)?;
+    let leverage = sum_x.checked_mul(ann)?;
+    let amount_a_times_coins = U256::from(amount_a).checked_mul(n_coins)?;
+    let amount_b_times_coins = U256::from(amount_b).checked_mul(n_coins)?;
+
+    let mut d = sum_x;
+    for _ in 0..MAX_ITERATIONS {
+        let mut d_product = d;
+        d_product = d_product.checked_mul(d)?.checked_div(amount_a_times_coins)?;
+        d_product = d_product.checked_mul(d)?.checked_div(amount_b_times_coins)?;
+
+        let d_prev = d;
+        // d = (ann * sum_x + d_product * n) * d / ((ann - 1) * d + (n + 1) * d_product)
+        let numerator = d.checked_mul(d_product.checked_mul(n_coins)?.checked_add(leverage)?)?;
+        let denominator = d.checked_mul(ann.checked_sub(U256::from(1u8))?)?
+            .checked_add(d_product.checked_mul(n_coins + U256::from(1u8))?)?;
+        d = numerator.checked_div(denominator)?;
+
+        if converged(d, d_prev) {
+            break;
+        }
+    }
+
+    Some(d)
+}
+
+/// Balance of the other coin keeping the invariant `d` when one coin holds `x`
+pub fn compute_y(amp: u64, x: u128, d: U256) -> Option<U256> {
+    let n_coins = U256::from(N_COINS);
+    let ann = U256::from(amp).checked_mul(n_coins)?;
+    let x = U256::from(x);
+
+    // c = d^(n+1) / (n^(2n) * x * ann)
+    let mut c = d.checked_mul(d)?.checked_div(x.checked_mul(n_coins)?)?;
+    c = c.checked_mul(d)?.checked_div(ann.checked_mul(n_coins)?)?;
+    // b = x + d / ann, d is subtracted in the loop
+    let b = d.checked_div(ann)?.checked_add(x)?;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        // y = (y^2 + c) / (2y + b - d)
+        y = y.checked_mul(y)?.checked_add(c)?
+            .checked_div(y.checked_mul(n_coins)?.checked_add(b)?.checked_sub(d)?)?;
+
+        if converged(y, y_prev) {
+            break;
+        }
+    }
+
+    Some(y)
+}
+
+/// Output of swapping `amount_in` into a pool holding `source_amount` and `destination_amount`, before fees
+pub fn swap_to(amp: u64, amount_in: u128, source_amount: u128, destination_amount: u128) -> Option<u128> {
+    let d = compute_d(amp, source_amount, destination_amount)?;
+    let y = compute_y(amp, source_amount.checked_add(amount_in)?, d)?;
+
+    u128::try_from(U256::from(destination_amount).checked_sub(y)?).ok()
+}
@@ -0,0 +1,165 @@
+use crate::r#struct::pools::{MarinadeFee, MarinadeState, SplStakePool, StakePoolFee};
+
+// marinade fees are in basis points
+const MARINADE_FEE_DENOMINATOR: u128 = 10_000;
+
+impl StakePoolFee {
+    /// Fee on `amount`, rounded up as the stake pool program does
+    pub fn apply(&self, amount: u64) -> Option<u64> {
+        if self.denominator == 0 {
+            return Some(0);
+        }
+        let denominator = self.denominator as u128;
+        let fee = (amount as u128).checked_mul(self.numerator as u128)?
+            .checked_add(denominator - 1)?
+            / denominator;
+        u64::try_from(fee).ok()
+    }
+}
+
+impl MarinadeFee {
+    pub fn apply(&self, amount: u64) -> u64 {
+        (amount as u128 * self.basis_points as u128 / MARINADE_FEE_DENOMINATOR) as u64
+    }
+}
+
+impl SplStakePool {
+    /// Pool tokens minted for `lamports` at the pool rate, before fees
+    pub fn calc_pool_tokens_for_deposit(&self, lamports: u64) -> Option<u64> {
+        if self.total_lamports == 0 || self.pool_token_supply == 0 {
+            return Some(lamports);
+        }
+        u64::try_from(lamports as u128 * self.pool_token_supply as u128 / self.total_lamports as u128).ok()
+    }
+
+    /// Lamports `pool_tokens` redeem for at the pool rate, before fees
+    pub fn calc_lamports_withdraw_amount(&self, pool_tokens: u64) -> Option<u64> {
+        let numerator = pool_tokens as u128 * self.total_lamports as u128;
+        let denominator = self.pool_token_supply as u128;
+        if numerator < denominator || denominator == 0 {
+            return Some(0);
+        }
+        u64::try_from(numerator / denominator).ok()
+    }
+}
+
+/// Pool tokens `DepositSol` of `lamports` mints to the depositor, net of the sol deposit fee.
+/// The referral share of the fee is not credited back
+pub fn quote_deposit_sol(pool: &SplStakePool, lamports: u64) -> Result<u64, &'static str> {
+    if pool.sol_deposit_authority.is_some() {
+        return Err("sol deposit requires authority");
+    }
+    let pool_tokens = pool.calc_pool_tokens_for_deposit(lamports).ok_or("pool token math failed")?;
+    let fee = pool.sol_deposit_fee.apply(pool_tokens).ok_or("fee math failed")?;
+    let pool_tokens = pool_tokens.checked_sub(fee).ok_or("fee over pool tokens")?;
+    if pool_tokens == 0 {
+        return Err("zero pool tokens");
+    }
+
+    Ok(pool_tokens)
+}
+
+/// Lamports `WithdrawSol` of `pool_tokens` pays out of the reserve, `reserve_available` being
+/// the reserve lamports above its rent-exempt reserve
+pub fn quote_withdraw_sol(pool: &SplStakePool, pool_tokens: u64, reserve_available: u64) -> Result<u64, &'static str> {
+    if pool.sol_withdraw_authority.is_some() {
+        return Err("sol withdrawal requires authority");
+    }
+    let fee = pool.sol_withdrawal_fee.apply(pool_tokens).ok_or("fee math failed")?;
+    let lamports = withdraw_lamports(pool, pool_tokens, fee)?;
+    if lamports > reserve_available {
+        return Err("sol withdrawal too large");
+    }
+
+    Ok(lamports)
+}
+
+fn withdraw_lamports(pool: &SplStakePool, pool_tokens: u64, fee: u64) -> Result<u64, &'static str> {
+    let pool_tokens_burnt = pool_tokens.checked_sub(fee).ok_or("fee over pool tokens")?;
+    let lamports = pool.calc_lamports_withdraw_amount(pool_tokens_burnt).ok_or("lamports math failed")?;
+    if lamports == 0 {
+        return Err("zero lamports");
+    }
+
+    Ok(lamports)
+}
+
+impl MarinadeState {
+    /// Lamports backing the mSOL supply, tickets of delayed unstakes excluded
+    pub fn total_virtual_staked_lamports(&self) -> u64 {
+        (self.total_active_balance as u128
+            + self.delayed_unstake_cooling_down as u128
+            + self.emergency_cooling_down as u128
+            + self.available_reserve_balance as u128)
+            .saturating_sub(self.circulating_ticket_balance as u128) as u64
+    }
+
+    pub fn calc_msol_from_lamports(&self, lamports: u64) -> Option<u64> {
+        let total = self.total_virtual_staked_lamports();
+        if total == 0 || self.msol_supply == 0 {
+            return Some(lamports);
+        }
+        u64::try_from(lamports as u128 * self.msol_supply as u128 / total as u128).ok()
+    }
+
+    pub fn calc_lamports_from_msol(&self, msol: u64) -> Option<u64> {
+        if self.msol_supply == 0 {
+            return Some(msol);
+        }
+        u64::try_from(msol as u128 * self.total_virtual_staked_lamports() as u128 / self.msol_supply as u128).ok()
+    }
+
+    /// Liquid unstake fee once the sol leg is left with `lamports`, from the max fee when empty down to the min fee at the target
+    pub fn linear_fee(&self, lamports: u64) -> MarinadeFee {
+        if lamports >= self.lp_liquidity_target {
+            return self.lp_min_fee;
+        }
+        let delta = self.lp_max_fee.basis_points.saturating_sub(self.lp_min_fee.basis_points) as u128;
+        MarinadeFee {
+            basis_points: self.lp_max_fee.basis_points - (delta * lamports as u128 / self.lp_liquidity_target as u128) as u32
+        }
+    }
+}
+
+/// mSOL `Deposit` of `lamports` gives, whether taken from the liquidity pool or minted
+pub fn quote_marinade_deposit(state: &MarinadeState, lamports: u64) -> Result<u64, &'static str> {
+    if state.paused {
+        return Err("marinade paused");
+    }
+    if lamports < state.min_deposit {
+        return Err("deposit below minimum");
+    }
+    if state.total_virtual_staked_lamports().saturating_add(lamports) > state.staking_sol_cap {
+        return Err("staking cap reached");
+    }
+    let msol = state.calc_msol_from_lamports(lamports).ok_or("msol math failed")?;
+    if msol == 0 {
+        return Err("zero msol");
+    }
+
+    Ok(msol)
+}
+
+/// Lamports `LiquidUnstake` of `msol` pays out of the liquidity pool sol leg holding `sol_leg_lamports`
+pub fn quote_marinade_liquid_unstake(state: &MarinadeState, msol: u64, sol_leg_lamports: u64) -> Result<u64, &'static str> {
+    if state.paused {
+        return Err("marinade paused");
+    }
+    let available = sol_leg_lamports.saturating_sub(state.rent_exempt_for_token_acc);
+    let user_remove_lamports = state.calc_lamports_from_msol(msol).ok_or("lamports math failed")?;
+    let fee = if user_remove_lamports >= available {
+        state.lp_max_fee
+    } else {
+        state.linear_fee(available - user_remove_lamports)
+    };
+    let msol_fee = fee.apply(msol);
+    let lamports = state.calc_lamports_from_msol(msol - msol_fee).ok_or("lamports math failed")?;
+    if lamports > available {
+        return Err("insufficient liquidity");
+    }
+    if lamports == 0 {
+        return Err("zero lamports");
+    }
+
+    Ok(lamports)
+}
@@ -0,0 +1,704 @@
+//! Loads swap fixtures recorded from mainnet.
+//!
+//! A fixture is a json file holding the accounts a swap touched, each dumped with
+//! `solana account <pubkey> --output json`, together with the swaps observed against that state:
+//!
+//! ```json
+//! {
+//!   "market": "ORCA",
+//!   "pool": "<pool pubkey>",
+//!   "slot": 290000000,
+//!   "timestamp": 1726000000,
+//!   "accounts": [
+//!     { "kind": "pool", "pubkey": "...", "account": { "lamports": 0, "data": ["<base64>", "base64"], "owner": "...", "executable": false, "rentEpoch": 0, "space": 653 } }
+//!   ],
+//!   "swaps": [
+//!     { "signature": "...", "amount": 1000000, "a_to_b": true, "amount_specified_is_input": true, "amount_in": 1000000, "amount_out": 131870 }
+//!   ]
+//! }
+//! ```
+//!
+//! `kind` is optional, if omitted it is inferred from the pubkey, owner and data length.
+//! `data` accounts, such as mints and the clock sysvar, are kept undecoded for the pools that read them.
+//! Accounts are decoded with the same decoders `Probe` uses.
+//!
+//! `record` dumps a pool and its swap-related accounts into this format together with the next swap on the pool.
+//! The accounts are dumped as they are when `record` runs, not as they were before an older swap, so the only swap
+//! a recorded fixture can replay is one landing after the dump.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcTransactionConfig};
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::sysvar::clock;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::constants::{METEORA_DLMM_BIN_ARRAY, METEORA_DLMM_BIN_ARRAY_BITMAP_EXTENSION, METEORA_DLMM_PROGRAM_PUBKEY, TOKEN_2022_PROGRAM_PUBKEY, TOKEN_ACCOUNT_DATA_LEN, TOKEN_PROGRAM_PUBKEY};
+use crate::formula::clmm::orca_swap_state::{NO_EXPLICIT_SQRT_PRICE_LIMIT, SwapTickSequence, TICK_ARRAY_SIZE, TickArray};
+use crate::formula::clmm::raydium_tick_array::{TickArrayBitmapExtension, TickArrayState};
+use crate::formula::dlmm::bin::BinArray;
+use crate::formula::dlmm::bin_array_bitmap_extension::BinArrayBitmapExtension;
+use crate::formula::meteora_dlmm::{quote_exact_in, quote_exact_out};
+use crate::formula::orca_clmm;
+use crate::formula::raydium_clmm;
+use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount, DeserializedConfigAccount, DeserializedDataAccount, DeserializedPoolAccount, DeserializedTokenAccount};
+use crate::r#struct::market::Market;
+use crate::r#struct::pools::{AmmConfig, MeteoraDlmmMarket, OrcaClmmAccount, OrcaClmmMarket, RaydiumClmmAccount, RaydiumClmmMarket};
+use crate::r#struct::resolver::{resolve_pool_account, resolve_pool_config_account};
+use crate::r#struct::token::TokenAccount;
+
+// transactions fetched per poll while waiting for the recorded swap
+const SIGNATURE_LIMIT: usize = 1000;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FixtureAccountKind {
+    Pool,
+    Config,
+    Token,
+    BinArray,
+    BinArrayBitmapExtension,
+    Data,
+}
+
+/// Output of `solana account <pubkey> --output json`
+#[derive(Clone, Debug, Deserialize)]
+pub struct AccountDump {
+    pub pubkey: String,
+    pub account: UiAccountDump,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiAccountDump {
+    pub lamports: u64,
+    pub data: (String, String),
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub space: Option<u64>,
+}
+
+impl AccountDump {
+    pub fn decode(&self) -> Result<(Pubkey, Account), &'static str> {
+        let pubkey = Pubkey::from_str(&self.pubkey).or(Err("invalid pubkey"))?;
+        let owner = Pubkey::from_str(&self.account.owner).or(Err("invalid owner"))?;
+
+        let (data, encoding) = &self.account.data;
+        if encoding != "base64" {
+            return Err("unsupported account data encoding");
+        }
+        let data = BASE64_STANDARD.decode(data).or(Err("invalid base64 account data"))?;
+
+        if let Some(space) = self.account.space {
+            if space as usize != data.len() {
+                return Err("account data length does not match space");
+            }
+        }
+
+        Ok((
+            pubkey,
+            Account {
+                lamports: self.account.lamports,
+                data,
+                owner,
+                executable: self.account.executable,
+                rent_epoch: self.account.rent_epoch,
+            }
+        ))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FixtureAccount {
+    #[serde(default)]
+    pub kind: Option<FixtureAccountKind>,
+    #[serde(flatten)]
+    pub dump: AccountDump,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RecordedSwap {
+    #[serde(default)]
+    pub signature: Option<String>,
+    pub amount: u64,
+    pub a_to_b: bool,
+    pub amount_specified_is_input: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct FixtureFile {
+    market: Market,
+    pool: String,
+    #[serde(default)]
+    slot: u64,
+    #[serde(default)]
+    timestamp: u64,
+    accounts: Vec<FixtureAccount>,
+    #[serde(default)]
+    swaps: Vec<RecordedSwap>,
+}
+
+pub struct AccountFixture {
+    pub market: Market,
+    pub pool: Pubkey,
+    pub slot: u64,
+    pub timestamp: u64,
+    pub accounts: Vec<DeserializedAccount>,
+    pub bin_arrays: HashMap<Pubkey, BinArray>,
+    pub bin_array_bitmap_extension: Option<BinArrayBitmapExtension>,
+    pub swaps: Vec<RecordedSwap>,
+}
+
+impl AccountFixture {
+    pub fn load(path: &Path) -> Result<AccountFixture, &'static str> {
+        let json = fs::read_to_string(path).or(Err("failed to read fixture"))?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<AccountFixture, &'static str> {
+        let file: FixtureFile = serde_json::from_str(json).or(Err("failed to parse fixture"))?;
+        let pool = Pubkey::from_str(&file.pool).or(Err("invalid pool pubkey"))?;
+
+        let mut fixture = AccountFixture {
+            market: file.market,
+            pool,
+            slot: file.slot,
+            timestamp: file.timestamp,
+            accounts: Vec::new(),
+            bin_arrays: HashMap::new(),
+            bin_array_bitmap_extension: None,
+            swaps: file.swaps,
+        };
+
+        for entry in file.accounts.iter() {
+            let (pubkey, account) = entry.dump.decode()?;
+            let kind = match entry.kind {
+                Some(kind) => kind,
+                None => infer_kind(&pool, &pubkey, &account)
+            };
+
+            match kind {
+                FixtureAccountKind::Pool => {
+                    let operation = resolve_pool_account(&fixture.market, &account.data);
+                    fixture.accounts.push(DeserializedAccount::PoolAccount(
+                        DeserializedPoolAccount {
+                            pubkey,
+                            account,
+                            market: fixture.market,
+                            operation,
+                        }
+                    ));
+                }
+                FixtureAccountKind::Config => {
+                    let config = resolve_pool_config_account(&fixture.market, &account.owner, pubkey, &account.data)?;
+                    fixture.accounts.push(DeserializedAccount::ConfigAccount(config));
+                }
+                FixtureAccountKind::Token => {
+                    fixture.accounts.push(DeserializedAccount::TokenAccount(
+                        DeserializedTokenAccount {
+                            pubkey,
+                            token: TokenAccount::unpack_data(&account.data),
+                            account,
+                            market: fixture.market,
+                        }
+                    ));
+                }
+                FixtureAccountKind::BinArray => {
+                    fixture.bin_arrays.insert(pubkey, BinArray::unpack_data(&account.data));
+                }
+                FixtureAccountKind::BinArrayBitmapExtension => {
+                    fixture.bin_array_bitmap_extension = Some(BinArrayBitmapExtension::unpack_data(&account.data));
+                }
+                FixtureAccountKind::Data => {
+                    fixture.accounts.push(DeserializedAccount::Account(
+                        DeserializedDataAccount {
+                            pubkey,
+                            account,
+                            market: fixture.market,
+                        }
+                    ));
+                }
+            }
+        }
+
+        Ok(fixture)
+    }
+
+    pub fn get_pool(&self) -> Option<&DeserializedPoolAccount> {
+        self.accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(pool) if pool.pubkey == self.pool => Some(pool),
+                _ => None
+            }
+        })
+    }
+
+    pub fn get_token_account(&self, pubkey: &Pubkey) -> Option<&DeserializedTokenAccount> {
+        self.accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::TokenAccount(token) if token.pubkey == *pubkey => Some(token),
+                _ => None
+            }
+        })
+    }
+
+    /// Replays a recorded swap against the fixture state and returns (amount_in, amount_out)
+    pub fn quote(&self, swap: &RecordedSwap) -> Result<(u64, u64), &'static str> {
+        let pool = self.get_pool().ok_or("pool account not found in fixture")?;
+        let operation = pool.operation.as_any();
+
+        match self.market {
+            Market::ORCA => self.quote_orca_clmm(swap),
+            Market::RAYDIUM if operation.is::<RaydiumClmmMarket>() => self.quote_raydium_clmm(swap),
+            Market::METEORA if operation.is::<MeteoraDlmmMarket>() => self.quote_meteora_dlmm(swap),
+            _ => self.quote_pool_operation(swap)
+        }
+    }
+
+    /// Replays an exact in swap through `PoolOperation::swap`, the way `Arbitrageur` quotes the pool
+    fn quote_pool_operation(&self, swap: &RecordedSwap) -> Result<(u64, u64), &'static str> {
+        if !swap.amount_specified_is_input {
+            return Err("exact out swaps are only replayed on concentrated liquidity pools");
+        }
+
+        let pool = self.get_pool().ok_or("pool account not found in fixture")?;
+        let pair = pool.operation.get_mint_pair();
+        let mint_in = if swap.a_to_b { pair.pubkey_a } else { pair.pubkey_b };
+        let quote = pool.operation.swap(&self.accounts, &mint_in, swap.amount)?;
+
+        Ok((quote.amount_in, quote.amount_out))
+    }
+
+    fn quote_orca_clmm(&self, swap: &RecordedSwap) -> Result<(u64, u64), &'static str> {
+        let pool = self.get_pool().ok_or("pool account not found in fixture")?;
+        let whirlpool = pool.operation.as_any().downcast_ref::<OrcaClmmMarket>().ok_or("not an orca clmm pool")?;
+
+        let ticks_in_array = TICK_ARRAY_SIZE * whirlpool.tick_spacing as i32;
+        let mut tick_arrays = self.accounts.iter().filter_map(|account| {
+            match account {
+                DeserializedAccount::ConfigAccount(DeserializedConfigAccount::OrcaClmmConfigAccount(OrcaClmmAccount::TickArray(tick_array))) => {
+                    Some(Arc::clone(&tick_array.tick_array))
+                }
+                _ => None
+            }
+        }).filter(|tick_array| {
+            if swap.a_to_b {
+                tick_array.start_tick_index <= whirlpool.tick_current_index
+            } else {
+                tick_array.start_tick_index + ticks_in_array > whirlpool.tick_current_index
+            }
+        }).collect::<Vec<Arc<TickArray>>>();
+
+        if swap.a_to_b {
+            tick_arrays.sort_by(|a, b| b.start_tick_index.cmp(&a.start_tick_index));
+        } else {
+            tick_arrays.sort_by(|a, b| a.start_tick_index.cmp(&b.start_tick_index));
+        }
+
+        let mut tick_arrays = tick_arrays.into_iter();
+        let swap_tick_sequence = SwapTickSequence::new_shared(
+            tick_arrays.next().ok_or("tick array not found in fixture")?,
+            tick_arrays.next(),
+            tick_arrays.next(),
+        );
+
+        let adaptive_fee_info = self.accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::ConfigAccount(DeserializedConfigAccount::OrcaClmmConfigAccount(OrcaClmmAccount::Oracle(oracle))) => {
+                    Some(oracle.oracle.get_adaptive_fee_info())
+                }
+                _ => None
+            }
+        });
+
+        let post_swap = orca_clmm::swap_internal(
+            whirlpool,
+            &swap_tick_sequence,
+            swap.amount,
+            NO_EXPLICIT_SQRT_PRICE_LIMIT,
+            swap.amount_specified_is_input,
+            swap.a_to_b,
+            self.timestamp,
+            &adaptive_fee_info,
+        )?;
+
+        if swap.a_to_b {
+            Ok((post_swap.amount_a, post_swap.amount_b))
+        } else {
+            Ok((post_swap.amount_b, post_swap.amount_a))
+        }
+    }
+
+    fn quote_raydium_clmm(&self, swap: &RecordedSwap) -> Result<(u64, u64), &'static str> {
+        let pool = self.get_pool().ok_or("pool account not found in fixture")?;
+        let mut pool_state = *pool.operation.as_any().downcast_ref::<RaydiumClmmMarket>().ok_or("not a raydium clmm pool")?;
+
+        let mut amm_config: Option<AmmConfig> = None;
+        let mut tick_array_bitmap_extension: Option<TickArrayBitmapExtension> = None;
+        let mut tick_array_states: Vec<TickArrayState> = Vec::new();
+
+        self.accounts.iter().for_each(|account| {
+            if let DeserializedAccount::ConfigAccount(DeserializedConfigAccount::RaydiumClmmConfigAccount(config)) = account {
+                match config {
+                    RaydiumClmmAccount::AmmConfig(amm) => {
+                        amm_config = Some(amm.config)
+                    }
+                    RaydiumClmmAccount::TickArrayState(state) => {
+                        tick_array_states.push(TickArrayState::clone(&state.tick_array_state))
+                    }
+                    RaydiumClmmAccount::TickArrayBitmapExtension(extension) => {
+                        tick_array_bitmap_extension = Some(extension.tick_array_bitmap_extension.clone())
+                    }
+                    RaydiumClmmAccount::ObservationKey => {}
+                }
+            }
+        });
+
+        let amm_config = amm_config.ok_or("amm config not found in fixture")?;
+        let ticks_in_array = TickArrayState::tick_count(pool_state.tick_spacing);
+        let mut tick_array_states = tick_array_states.into_iter().filter(|state| {
+            if swap.a_to_b {
+                state.start_tick_index <= pool_state.tick_current
+            } else {
+                state.start_tick_index + ticks_in_array > pool_state.tick_current
+            }
+        }).collect::<Vec<TickArrayState>>();
+
+        if swap.a_to_b {
+            tick_array_states.sort_by(|a, b| b.start_tick_index.cmp(&a.start_tick_index));
+        } else {
+            tick_array_states.sort_by(|a, b| a.start_tick_index.cmp(&b.start_tick_index));
+        }
+        let mut tick_array_states = tick_array_states.into_iter().collect::<VecDeque<TickArrayState>>();
+
+        let (amount_0, amount_1) = raydium_clmm::swap_internal(
+            &amm_config,
+            &mut pool_state,
+            &mut tick_array_states,
+            &tick_array_bitmap_extension.as_ref(),
+            swap.amount,
+            0,
+            swap.a_to_b,
+            swap.amount_specified_is_input,
+        )?;
+
+        if swap.a_to_b {
+            Ok((amount_0, amount_1))
+        } else {
+            Ok((amount_1, amount_0))
+        }
+    }
+
+    fn quote_meteora_dlmm(&self, swap: &RecordedSwap) -> Result<(u64, u64), &'static str> {
+        let pool = self.get_pool().ok_or("pool account not found in fixture")?;
+        let lb_pair = pool.operation.as_any().downcast_ref::<MeteoraDlmmMarket>().ok_or("not a meteora dlmm pool")?;
+
+        if swap.amount_specified_is_input {
+            let quote = quote_exact_in(
+                self.pool,
+                lb_pair,
+                swap.amount,
+                swap.a_to_b,
+                self.bin_arrays.clone(),
+                self.bin_array_bitmap_extension.as_ref(),
+                self.timestamp,
+                self.slot,
+            )?;
+            Ok((swap.amount, quote.amount_out))
+        } else {
+            let quote = quote_exact_out(
+                self.pool,
+                lb_pair,
+                swap.amount,
+                swap.a_to_b,
+                self.bin_arrays.clone(),
+                self.bin_array_bitmap_extension.as_ref(),
+                self.timestamp,
+                self.slot,
+            )?;
+            Ok((quote.amount_in + quote.fee, swap.amount))
+        }
+    }
+}
+
+fn infer_kind(pool: &Pubkey, pubkey: &Pubkey, account: &Account) -> FixtureAccountKind {
+    if pubkey == pool {
+        return FixtureAccountKind::Pool;
+    }
+
+    if *pubkey == clock::id() {
+        return FixtureAccountKind::Data;
+    }
+
+    let owner = account.owner.to_string();
+    if owner == TOKEN_PROGRAM_PUBKEY || owner == TOKEN_2022_PROGRAM_PUBKEY {
+        // Token-2022 appends the account type after the base token account, 1 for mints
+        let is_mint = account.data.len() < TOKEN_ACCOUNT_DATA_LEN || account.data.get(TOKEN_ACCOUNT_DATA_LEN) == Some(&1);
+        return if is_mint { FixtureAccountKind::Data } else { FixtureAccountKind::Token };
+    }
+
+    if owner == METEORA_DLMM_PROGRAM_PUBKEY {
+        match account.data.len() {
+            METEORA_DLMM_BIN_ARRAY => return FixtureAccountKind::BinArray,
+            METEORA_DLMM_BIN_ARRAY_BITMAP_EXTENSION => return FixtureAccountKind::BinArrayBitmapExtension,
+            _ => {}
+        }
+    }
+
+    FixtureAccountKind::Config
+}
+
+/// Fetches `pool` and the accounts its swaps read, then waits up to `timeout` for the next transaction on the pool
+/// and records it as the fixture's swap.
+///
+/// The accounts are fetched at the latest confirmed slot, so they are the state right before the first transaction
+/// landing on the pool after that slot. That transaction must be a single swap, its amounts are the balance changes of
+/// the recorded token accounts and it is recorded as exact in. The clock is moved to the swap's slot and block time.
+pub fn record(rpc_client: &RpcClient, market: Market, pool: &Pubkey, timeout: Duration) -> Result<String, &'static str> {
+    let account = rpc_client.get_account(pool).or(Err("failed to fetch pool"))?;
+    let pool_account = DeserializedPoolAccount {
+        pubkey: *pool,
+        operation: resolve_pool_account(&market, &account.data),
+        account,
+        market,
+    };
+    let pair = pool_account.operation.get_mint_pair();
+
+    let mut related = pool_account.get_swap_related_pubkeys(Some(rpc_client))?;
+    if !related.iter().any(|(_, pubkey)| pubkey == pool) {
+        related.insert(0, (DeserializedAccount::PoolAccount(DeserializedPoolAccount::default()), *pool));
+    }
+    // the clock dates the fixture
+    if !related.iter().any(|(_, pubkey)| *pubkey == clock::id()) {
+        related.push((DeserializedAccount::Account(DeserializedDataAccount::default()), clock::id()));
+    }
+
+    let pubkeys = related.iter().map(|(_, pubkey)| *pubkey).collect::<Vec<Pubkey>>();
+    let mut slot = 0;
+    let mut fetched = Vec::new();
+    for (i, chunk) in pubkeys.chunks(100).enumerate() {
+        // every chunk is read at the slot of the first one or later, the swap has to land after all of them
+        let response = rpc_client.get_multiple_accounts_with_config(chunk, RpcAccountInfoConfig {
+            commitment: Some(CommitmentConfig::confirmed()),
+            min_context_slot: if i == 0 { None } else { Some(slot) },
+            ..RpcAccountInfoConfig::default()
+        }).or(Err("failed to fetch accounts"))?;
+        slot = slot.max(response.context.slot);
+        fetched.extend(response.value);
+    }
+
+    let (signature, swap_slot, block_time, balance_changes) = wait_for_swap(rpc_client, pool, slot, timeout)?;
+
+    let mut accounts = Vec::new();
+    let (mut amount_a, mut amount_b) = (0i128, 0i128);
+    // accounts that do not exist, such as a missing bitmap extension, are left out
+    for ((template, pubkey), account) in related.iter().zip(fetched.into_iter()) {
+        let mut account = match account {
+            Some(account) => account,
+            None => continue
+        };
+        if *pubkey == clock::id() {
+            let mut clock = bincode::deserialize::<Clock>(&account.data).or(Err("invalid clock"))?;
+            clock.slot = swap_slot;
+            clock.unix_timestamp = block_time;
+            account.data = bincode::serialize(&clock).or(Err("invalid clock"))?;
+        }
+
+        let kind = match template {
+            DeserializedAccount::PoolAccount(_) => FixtureAccountKind::Pool,
+            DeserializedAccount::ConfigAccount(_) => FixtureAccountKind::Config,
+            DeserializedAccount::TokenAccount(_) => FixtureAccountKind::Token,
+            DeserializedAccount::Account(_) => match infer_kind(pool, pubkey, &account) {
+                kind @ (FixtureAccountKind::BinArray | FixtureAccountKind::BinArrayBitmapExtension) => kind,
+                _ => FixtureAccountKind::Data
+            }
+        };
+        if kind == FixtureAccountKind::Token {
+            let mint = TokenAccount::unpack_data(&account.data).mint;
+            let change = balance_changes.get(pubkey).copied().unwrap_or(0);
+            if mint == pair.pubkey_a {
+                amount_a += change;
+            }
+            else if mint == pair.pubkey_b {
+                amount_b += change;
+            }
+        }
+
+        accounts.push(json!({
+            "kind": kind,
+            "pubkey": pubkey.to_string(),
+            "account": {
+                "lamports": account.lamports,
+                "data": [BASE64_STANDARD.encode(&account.data), "base64"],
+                "owner": account.owner.to_string(),
+                "executable": account.executable,
+                "rentEpoch": account.rent_epoch,
+                "space": account.data.len()
+            }
+        }));
+    }
+
+    // the pool receives the input and pays the output
+    let (a_to_b, amount_in, amount_out) = match (amount_a, amount_b) {
+        (a, b) if 0 < a && b < 0 => (true, a as u64, (-b) as u64),
+        (a, b) if a < 0 && 0 < b => (false, b as u64, (-a) as u64),
+        _ => return Err("the first transaction after the snapshot is not a swap, record again")
+    };
+
+    let fixture = json!({
+        "market": format!("{:?}", market),
+        "pool": pool.to_string(),
+        "slot": swap_slot,
+        "timestamp": block_time,
+        "accounts": accounts,
+        "swaps": [{
+            "signature": signature.to_string(),
+            "amount": amount_in,
+            "a_to_b": a_to_b,
+            "amount_specified_is_input": true,
+            "amount_in": amount_in,
+            "amount_out": amount_out
+        }]
+    });
+    serde_json::to_string_pretty(&fixture).or(Err("failed to serialize fixture"))
+}
+
+/// Waits for the first successful transaction on `pool` after `slot`,
+/// returns its signature, slot, block time and the token balance change of every account it touched
+fn wait_for_swap(
+    rpc_client: &RpcClient,
+    pool: &Pubkey,
+    slot: u64,
+    timeout: Duration
+) -> Result<(Signature, u64, i64, HashMap<Pubkey, i128>), &'static str> {
+    let started = Instant::now();
+    loop {
+        let signatures = rpc_client.get_signatures_for_address_with_config(pool, GetConfirmedSignaturesForAddress2Config {
+            before: None,
+            until: None,
+            limit: Some(SIGNATURE_LIMIT),
+            commitment: Some(CommitmentConfig::confirmed()),
+        }).or(Err("failed to fetch pool signatures"))?;
+
+        // newest first, failed transactions leave the pool as it was
+        let landed = signatures.iter().filter(|status| slot < status.slot).collect::<Vec<_>>();
+        if landed.len() == SIGNATURE_LIMIT {
+            return Err("too many transactions since the snapshot, record again");
+        }
+
+        if let Some(status) = landed.iter().rev().find(|status| status.err.is_none()) {
+            let signature = Signature::from_str(&status.signature).or(Err("invalid signature"))?;
+            let transaction = rpc_client.get_transaction_with_config(&signature, RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            }).or(Err("failed to fetch swap transaction"))?;
+
+            let block_time = transaction.block_time.ok_or("swap transaction has no block time")?;
+            let meta = transaction.transaction.meta.as_ref().ok_or("swap transaction has no metadata")?;
+            let message = transaction.transaction.transaction.decode().ok_or("failed to decode swap transaction")?.message;
+
+            // token balances index the static keys followed by the keys loaded from lookup tables
+            let mut account_keys = message.static_account_keys().to_vec();
+            if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+                for pubkey in loaded.writable.iter().chain(loaded.readonly.iter()) {
+                    account_keys.push(Pubkey::from_str(pubkey).or(Err("invalid loaded address"))?);
+                }
+            }
+
+            let mut balance_changes: HashMap<Pubkey, i128> = HashMap::new();
+            for (balances, sign) in [(&meta.pre_token_balances, -1i128), (&meta.post_token_balances, 1i128)] {
+                if let OptionSerializer::Some(balances) = balances {
+                    for balance in balances.iter() {
+                        let pubkey = account_keys.get(balance.account_index as usize).ok_or("token balance out of account keys")?;
+                        let amount = balance.ui_token_amount.amount.parse::<u64>().or(Err("invalid token amount"))?;
+                        *balance_changes.entry(*pubkey).or_default() += sign * amount as i128;
+                    }
+                }
+            }
+
+            return Ok((signature, transaction.slot, block_time, balance_changes));
+        }
+
+        if timeout < started.elapsed() {
+            return Err("no transaction on the pool before the timeout");
+        }
+        sleep(Duration::from_secs(2));
+    }
+}
+
+/// Loads every `*.json` fixture in `dir`, returns an empty list if the directory does not exist
+pub fn load_fixtures(dir: &Path) -> Vec<(PathBuf, Result<AccountFixture, &'static str>)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut paths = entries.filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |extension| extension == "json"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    paths.into_iter().map(|path| {
+        let fixture = AccountFixture::load(&path);
+        (path, fixture)
+    }).collect()
+}
+
+/// Data account of `market` holding `data`, for state built by hand rather than recorded
+pub fn data_account(pubkey: Pubkey, data: Vec<u8>, market: Market) -> DeserializedAccount {
+    DeserializedAccount::Account(DeserializedDataAccount {
+        pubkey,
+        account: Account { data, ..Account::default() },
+        market,
+    })
+}
+
+pub fn token_account(pubkey: Pubkey, amount: u64, market: Market) -> DeserializedAccount {
+    DeserializedAccount::TokenAccount(DeserializedTokenAccount {
+        pubkey,
+        account: Account::default(),
+        token: TokenAccount { amount, ..TokenAccount::default() },
+        market,
+    })
+}
+
+/// SPL mint with `supply`, the only field the pools read
+pub fn mint_account(pubkey: Pubkey, supply: u64, market: Market) -> DeserializedAccount {
+    let mut data = vec![0u8; 82];
+    data[36..44].copy_from_slice(&supply.to_le_bytes());
+    data_account(pubkey, data, market)
+}
+
+pub fn clock_account(clock: Clock, market: Market) -> DeserializedAccount {
+    data_account(clock::id(), bincode::serialize(&clock).unwrap(), market)
+}
+
+/// Pool account of `market` decoded from `data` the way `Probe` decodes it
+pub fn pool_account(pubkey: Pubkey, owner: Pubkey, data: Vec<u8>, market: Market) -> DeserializedAccount {
+    DeserializedAccount::PoolAccount(DeserializedPoolAccount {
+        pubkey,
+        operation: resolve_pool_account(&market, &data),
+        account: Account { data, owner, ..Account::default() },
+        market,
+    })
+}
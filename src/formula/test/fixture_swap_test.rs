@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod fixture_swap_test {
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use base64::Engine;
+    use base64::prelude::BASE64_STANDARD;
+    use serde_json::json;
+    use solana_client::rpc_client::RpcClient;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::constants::TOKEN_PROGRAM_PUBKEY;
+    use crate::formula::test::account_fixture::{AccountFixture, load_fixtures, record};
+    use crate::r#struct::account::DeserializedAccount;
+    use crate::r#struct::market::Market;
+    use crate::utils::get_rpc_url;
+
+    const FIXTURE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/formula/test/fixtures");
+
+    #[test]
+    fn recorded_swaps() {
+        let fixtures = load_fixtures(Path::new(FIXTURE_DIR));
+        assert!(!fixtures.is_empty(), "no fixtures in {}, record some with record_fixture", FIXTURE_DIR);
+
+        for (path, fixture) in fixtures {
+            let fixture = fixture.unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+            assert!(!fixture.swaps.is_empty(), "{}: no swaps recorded", path.display());
+
+            for swap in fixture.swaps.iter() {
+                let (amount_in, amount_out) = fixture.quote(swap)
+                    .unwrap_or_else(|err| panic!("{} ({:?}): {}", path.display(), swap.signature, err));
+
+                assert_eq!(amount_in, swap.amount_in, "{} ({:?}): amount_in", path.display(), swap.signature);
+                assert_eq!(amount_out, swap.amount_out, "{} ({:?}): amount_out", path.display(), swap.signature);
+            }
+        }
+    }
+
+    /// Records `FIXTURE_POOL` of `FIXTURE_MARKET` and its next swap from `RPC_URL` into the fixture directory:
+    /// `FIXTURE_MARKET=ORCA FIXTURE_POOL=<pubkey> cargo test record_fixture -- --ignored`
+    #[test]
+    #[ignore]
+    fn record_fixture() {
+        let market: Market = serde_json::from_value(json!(env::var("FIXTURE_MARKET").expect("FIXTURE_MARKET not set")))
+            .expect("unknown market");
+        let pool = Pubkey::from_str(&env::var("FIXTURE_POOL").expect("FIXTURE_POOL not set")).expect("invalid pool pubkey");
+
+        let rpc_client = RpcClient::new(get_rpc_url());
+        let timeout = env::var("FIXTURE_TIMEOUT_SECS").map_or(120, |secs| secs.parse().expect("invalid FIXTURE_TIMEOUT_SECS"));
+        let fixture = record(&rpc_client, market, &pool, Duration::from_secs(timeout)).unwrap();
+
+        let path = Path::new(FIXTURE_DIR).join(format!("{:?}_{}.json", market, pool).to_lowercase());
+        fs::write(&path, fixture).unwrap();
+        println!("recorded {}", path.display());
+    }
+
+    #[test]
+    fn load_token_account_dump() {
+        let vault = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let mut data = vec![0u8; 165];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&1_234_567u64.to_le_bytes());
+        data[108] = 1; // AccountState::Initialized
+
+        let json = format!(
+            r#"{{
+                "market": "ORCA",
+                "pool": "{}",
+                "accounts": [
+                    {{ "pubkey": "{}", "account": {{ "lamports": 2039280, "data": ["{}", "base64"], "owner": "{}", "executable": false, "rentEpoch": 18446744073709551615, "space": 165 }} }}
+                ]
+            }}"#,
+            Pubkey::new_unique(),
+            vault,
+            BASE64_STANDARD.encode(&data),
+            TOKEN_PROGRAM_PUBKEY
+        );
+
+        let fixture = AccountFixture::from_json(&json).unwrap();
+        assert_eq!(fixture.market, Market::ORCA);
+        assert!(fixture.swaps.is_empty());
+        assert_eq!(fixture.accounts.len(), 1);
+        assert!(matches!(fixture.accounts[0], DeserializedAccount::TokenAccount(_)));
+
+        let token = fixture.get_token_account(&vault).unwrap();
+        assert_eq!(token.token.mint, mint);
+        assert_eq!(token.token.owner, owner);
+        assert_eq!(token.get_amount(), 1_234_567);
+        assert_eq!(token.account.owner, Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap());
+    }
+
+    #[test]
+    fn reject_mismatched_space() {
+        let json = format!(
+            r#"{{
+                "market": "ORCA",
+                "pool": "{}",
+                "accounts": [
+                    {{ "pubkey": "{}", "account": {{ "lamports": 0, "data": ["{}", "base64"], "owner": "{}", "executable": false, "rentEpoch": 0, "space": 165 }} }}
+                ]
+            }}"#,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            BASE64_STANDARD.encode([0u8; 64]),
+            TOKEN_PROGRAM_PUBKEY
+        );
+
+        assert!(AccountFixture::from_json(&json).is_err());
+    }
+}
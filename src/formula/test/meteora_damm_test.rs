@@ -0,0 +1,194 @@
+#[cfg(test)]
+mod meteora_damm_test {
+    use std::str::FromStr;
+
+    use solana_sdk::clock::Clock;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::sysvar::clock;
+
+    use crate::constants::{METEORA_DAMM_DATA_LEN, METEORA_DAMM_V2_DATA_LEN, METEORA_VAULT_DATA_LEN, METEORA_VAULT_PROGRAM_PUBKEY};
+    use crate::formula::base::Formula;
+    use crate::formula::meteora_damm::{swap_curve, VaultShare};
+    use crate::formula::meteora_damm_v2::quote_exact_in;
+    use crate::formula::stable_swap;
+    use crate::formula::test::account_fixture::{clock_account, mint_account, token_account};
+    use crate::r#struct::account::DeserializedAccount;
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::{DammBootstrapping, DammCurveType, DammPoolFees, DammV2BaseFee, LockedProfitTracker, MeteoraDammMarket, MeteoraDammV2Market, MeteoraVault};
+    use crate::r#struct::resolver::{resolve_pool_account, resolve_pool_config_account};
+
+    const Q64: u128 = 1 << 64;
+
+    fn vault_data(vault: &MeteoraVault) -> Vec<u8> {
+        let mut data = vec![0u8; METEORA_VAULT_DATA_LEN];
+        data[8] = vault.enabled;
+        data[11..19].copy_from_slice(&vault.total_amount.to_le_bytes());
+        data[115..147].copy_from_slice(vault.lp_mint.as_ref());
+        data[1203..1211].copy_from_slice(&vault.locked_profit_tracker.last_updated_locked_profit.to_le_bytes());
+        data[1211..1219].copy_from_slice(&vault.locked_profit_tracker.last_report.to_le_bytes());
+        data[1219..1227].copy_from_slice(&vault.locked_profit_tracker.locked_profit_degradation.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn stable_curve() {
+        // a balanced stable pool trades close to 1:1, a constant product pool does not
+        let stable_out = stable_swap::swap_to(100, 1_000_000, 1_000_000_000, 1_000_000_000).unwrap();
+        let constant_product_out = swap_curve(&DammCurveType::ConstantProduct, 1_000_000, 1_000_000_000, 1_000_000_000, true).unwrap();
+        assert!(stable_out < 1_000_000 && stable_out > 999_990, "{}", stable_out);
+        assert_eq!(constant_product_out, 999_000);
+
+        let d = stable_swap::compute_d(100, 1_000_000_000, 1_000_000_000).unwrap();
+        assert!(d >= ruint::aliases::U256::from(1_999_999_999u64) && d <= ruint::aliases::U256::from(2_000_000_001u64));
+        assert_eq!(stable_swap::compute_d(100, 0, 0).unwrap(), ruint::aliases::U256::ZERO);
+
+        // token a has 6 decimals and token b 9, a is upscaled by 1000
+        let mut curve = DammCurveType::Stable { amp: 100, token_multiplier: Default::default(), depeg: Default::default(), last_amp_updated_timestamp: 0 };
+        if let DammCurveType::Stable { token_multiplier, .. } = &mut curve {
+            token_multiplier.token_a_multiplier = 1_000;
+            token_multiplier.token_b_multiplier = 1;
+        }
+        let amount_out = swap_curve(&curve, 1_000, 1_000_000, 1_000_000_000, true).unwrap();
+        assert!(amount_out < 1_000_000 && amount_out > 999_990, "{}", amount_out);
+    }
+
+    #[test]
+    fn vault_locked_profit() {
+        let vault = MeteoraVault {
+            total_amount: 1_100_000,
+            locked_profit_tracker: LockedProfitTracker {
+                last_updated_locked_profit: 100_000,
+                last_report: 1_000,
+                // unlocks fully in 1_000 seconds
+                locked_profit_degradation: 1_000_000_000,
+            },
+            ..MeteoraVault::default()
+        };
+
+        assert_eq!(vault.get_unlocked_amount(1_000).unwrap(), 1_000_000);
+        assert_eq!(vault.get_unlocked_amount(1_500).unwrap(), 1_050_000);
+        assert_eq!(vault.get_unlocked_amount(5_000).unwrap(), 1_100_000);
+
+        let share = VaultShare { vault, pool_lp_amount: 250_000, lp_supply: 1_000_000 };
+        assert_eq!(share.get_pool_amount(1_500).unwrap(), 262_500);
+        assert_eq!(vault.get_unmint_amount(1_500, 10_500, 1_000_000).unwrap(), 10_000);
+    }
+
+    #[test]
+    fn stable_pools_use_stable_swap_formula() {
+        let pool = MeteoraDammMarket::default();
+        assert_eq!(pool.get_formula(), Formula::ConstantProduct);
+
+        let pool = MeteoraDammMarket {
+            curve_type: DammCurveType::Stable { amp: 100, token_multiplier: Default::default(), depeg: Default::default(), last_amp_updated_timestamp: 0 },
+            ..MeteoraDammMarket::default()
+        };
+        assert_eq!(pool.get_formula(), Formula::StableSwap);
+    }
+
+    #[test]
+    fn dynamic_amm_quote_through_vaults() {
+        let pool = MeteoraDammMarket {
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            a_vault: Pubkey::new_unique(),
+            b_vault: Pubkey::new_unique(),
+            a_vault_lp: Pubkey::new_unique(),
+            b_vault_lp: Pubkey::new_unique(),
+            enabled: true,
+            fees: DammPoolFees {
+                trade_fee_numerator: 25,
+                trade_fee_denominator: 10_000,
+                protocol_trade_fee_numerator: 20,
+                protocol_trade_fee_denominator: 100,
+            },
+            bootstrapping: DammBootstrapping { activation_point: 100, activation_type: 0, ..DammBootstrapping::default() },
+            ..MeteoraDammMarket::default()
+        };
+        let vault_program = Pubkey::from_str(METEORA_VAULT_PROGRAM_PUBKEY).unwrap();
+        let (lp_mint_a, lp_mint_b) = (MeteoraVault::lp_mint_key(&pool.a_vault, &vault_program), MeteoraVault::lp_mint_key(&pool.b_vault, &vault_program));
+        let related = pool.get_swap_related_pubkeys().iter().map(|(_, pubkey)| *pubkey).collect::<Vec<Pubkey>>();
+        assert!(related.contains(&lp_mint_a) && related.contains(&lp_mint_b) && related.contains(&clock::id()));
+
+        // the pool holds half of each vault
+        let vault_a = MeteoraVault { total_amount: 1_000_000_000, lp_mint: lp_mint_a, ..MeteoraVault::default() };
+        let vault_b = MeteoraVault { total_amount: 1_000_000_000, lp_mint: lp_mint_b, ..MeteoraVault::default() };
+        let mut accounts = vec![
+            DeserializedAccount::ConfigAccount(resolve_pool_config_account(&Market::METEORA, &vault_program, pool.a_vault, &vault_data(&vault_a)).unwrap()),
+            DeserializedAccount::ConfigAccount(resolve_pool_config_account(&Market::METEORA, &vault_program, pool.b_vault, &vault_data(&vault_b)).unwrap()),
+            token_account(pool.a_vault_lp, 500_000_000, Market::METEORA),
+            token_account(pool.b_vault_lp, 500_000_000, Market::METEORA),
+            mint_account(lp_mint_a, 1_000_000_000, Market::METEORA),
+            mint_account(lp_mint_b, 1_000_000_000, Market::METEORA),
+        ];
+        assert_eq!(pool.swap(&accounts, &pool.token_a_mint, 1_000_000), Err("clock not found"));
+
+        accounts.push(clock_account(Clock { slot: 100, unix_timestamp: 1_700_000_000, ..Clock::default() }, Market::METEORA));
+        // 0.25% trade fee of which 20% to the protocol, 997_500 reaches the curve
+        let quote = pool.swap(&accounts, &pool.token_a_mint, 1_000_000).unwrap();
+        assert_eq!((quote.amount_in, quote.amount_out), (1_000_000, 995_513));
+
+        accounts.pop();
+        accounts.push(clock_account(Clock { slot: 99, unix_timestamp: 1_700_000_000, ..Clock::default() }, Market::METEORA));
+        assert_eq!(pool.swap(&accounts, &pool.token_a_mint, 1_000_000), Err("pool not activated"));
+    }
+
+    #[test]
+    fn damm_v2_fee_scheduler_and_quote() {
+        let linear = DammV2BaseFee { cliff_fee_numerator: 100_000_000, fee_scheduler_mode: 0, number_of_period: 10, period_frequency: 60, reduction_factor: 5_000_000 };
+        assert_eq!(linear.get_fee_numerator(50, 100).unwrap(), 50_000_000);
+        assert_eq!(linear.get_fee_numerator(100, 100).unwrap(), 100_000_000);
+        assert_eq!(linear.get_fee_numerator(250, 100).unwrap(), 90_000_000);
+        assert_eq!(linear.get_fee_numerator(100_000, 100).unwrap(), 50_000_000);
+
+        // halves every period
+        let exponential = DammV2BaseFee { fee_scheduler_mode: 1, reduction_factor: 5_000, ..linear };
+        let fee = exponential.get_fee_numerator(220, 100).unwrap();
+        assert!(fee.abs_diff(25_000_000) <= 1, "{}", fee);
+
+        let mut pool = MeteoraDammV2Market {
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            liquidity: 1_000_000_000 * Q64,
+            sqrt_price: Q64,
+            sqrt_min_price: Q64 / 2,
+            sqrt_max_price: Q64 * 2,
+            activation_type: 1,
+            ..MeteoraDammV2Market::default()
+        };
+        pool.pool_fees.base_fee.cliff_fee_numerator = 2_500_000;
+
+        // fee is taken on the output
+        let result = quote_exact_in(&pool, 1_000_000, true, 0, 0).unwrap();
+        assert_eq!((result.amount_out, result.trading_fee), (996_502, 2_498));
+
+        // fee is taken on token b, here the input
+        pool.collect_fee_mode = 1;
+        let result = quote_exact_in(&pool, 1_000_000, false, 0, 0).unwrap();
+        assert_eq!((result.amount_out, result.trading_fee), (996_505, 2_500));
+
+        assert_eq!(quote_exact_in(&pool, 2_000_000_000, true, 0, 0), Err("price range is violated"));
+        pool.pool_status = 1;
+        assert_eq!(quote_exact_in(&pool, 1_000_000, true, 0, 0), Err("pool disabled"));
+    }
+
+    #[test]
+    fn resolve_by_data_length() {
+        let mut data = vec![0u8; METEORA_DAMM_DATA_LEN];
+        data[233] = 1;
+        data[874] = 1;
+        data[875..883].copy_from_slice(&85u64.to_le_bytes());
+        let pool = resolve_pool_account(&Market::METEORA, &data);
+        let pool = pool.as_any().downcast_ref::<MeteoraDammMarket>().unwrap();
+        assert!(pool.enabled);
+        assert!(matches!(pool.curve_type, DammCurveType::Stable { amp: 85, .. }));
+
+        let mut data = vec![0u8; METEORA_DAMM_V2_DATA_LEN];
+        data[8..16].copy_from_slice(&2_500_000u64.to_le_bytes());
+        data[456..472].copy_from_slice(&Q64.to_le_bytes());
+        let pool = resolve_pool_account(&Market::METEORA, &data);
+        let pool = pool.as_any().downcast_ref::<MeteoraDammV2Market>().unwrap();
+        assert_eq!(pool.sqrt_price, Q64);
+        assert_eq!(pool.get_fee_rate(), Some(2_500));
+    }
+}
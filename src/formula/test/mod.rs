@@ -0,0 +1,11 @@
+pub mod account_fixture;
+mod fixture_swap_test;
+mod raydium_cpmm_test;
+mod meteora_damm_test;
+mod phoenix_test;
+mod openbook_v2_test;
+mod raydium_amm_v4_test;
+mod saber_test;
+mod stake_pool_test;
+mod sanctum_test;
+mod pump_test;
\ No newline at end of file
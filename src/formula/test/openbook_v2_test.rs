@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod openbook_v2_test {
+    use std::str::FromStr;
+
+    use solana_sdk::clock::Clock;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::constants::{OPENBOOK_V2_BOOK_SIDE_DATA_LEN, OPENBOOK_V2_EVENT_HEAP_DATA_LEN, OPENBOOK_V2_MARKET_DATA_LEN, OPENBOOK_V2_PROGRAM_PUBKEY};
+    use crate::formula::openbook::openbook_v2::{get_oracle_price_lots, get_priced_orders, quote_exact_in};
+    use crate::formula::test::account_fixture::clock_account;
+    use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount};
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::{OpenBookV2BookSide, OpenBookV2EventHeap, OpenBookV2Market};
+    use crate::r#struct::resolver::{resolve_pool_account, resolve_pool_config_account};
+
+    /// (price data, sequence number, quantity, time in force, timestamp)
+    type Leaf = (u64, u64, i64, u16, u64);
+
+    fn write_leaf(data: &mut Vec<u8>, handle: usize, leaf: Leaf) {
+        let at = 840 + handle * 88;
+        data[at] = 2;
+        data[at + 2..at + 4].copy_from_slice(&leaf.3.to_le_bytes());
+        let key = (leaf.0 as u128) << 64 | leaf.1 as u128;
+        data[at + 8..at + 24].copy_from_slice(&key.to_le_bytes());
+        data[at + 56..at + 64].copy_from_slice(&leaf.2.to_le_bytes());
+        data[at + 64..at + 72].copy_from_slice(&leaf.4.to_le_bytes());
+        data[at + 72..at + 80].copy_from_slice(&(-1i64).to_le_bytes());
+    }
+
+    /// Fixed tree of an inner node over two leaves, pegged tree of one leaf
+    fn book_side_data(order_tree_type: u8, fixed: [Leaf; 2], pegged: Option<Leaf>) -> Vec<u8> {
+        let mut data = vec![0u8; OPENBOOK_V2_BOOK_SIDE_DATA_LEN];
+        data[8..12].copy_from_slice(&0u32.to_le_bytes());
+        data[12..16].copy_from_slice(&2u32.to_le_bytes());
+        data[312] = order_tree_type;
+
+        data[840] = 1;
+        data[840 + 24..840 + 28].copy_from_slice(&1u32.to_le_bytes());
+        data[840 + 28..840 + 32].copy_from_slice(&2u32.to_le_bytes());
+        write_leaf(&mut data, 1, fixed[0]);
+        write_leaf(&mut data, 2, fixed[1]);
+
+        if let Some(pegged) = pegged {
+            data[16..20].copy_from_slice(&3u32.to_le_bytes());
+            data[20..24].copy_from_slice(&1u32.to_le_bytes());
+            write_leaf(&mut data, 3, pegged);
+        }
+        data
+    }
+
+    fn event_heap_data(count: u16) -> Vec<u8> {
+        let mut data = vec![0u8; OPENBOOK_V2_EVENT_HEAP_DATA_LEN];
+        data[12..14].copy_from_slice(&count.to_le_bytes());
+        data
+    }
+
+    fn pyth_data(price: i64, exponent: i32, publish_slot: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 240];
+        data[0..4].copy_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+        data[20..24].copy_from_slice(&exponent.to_le_bytes());
+        data[208..216].copy_from_slice(&price.to_le_bytes());
+        data[224..228].copy_from_slice(&1u32.to_le_bytes());
+        data[232..240].copy_from_slice(&publish_slot.to_le_bytes());
+        data
+    }
+
+    fn pegged_price_data(offset: i64) -> u64 {
+        (offset as u64) ^ (1 << 63)
+    }
+
+    /// 9 decimals base in lots of 1_000, 6 decimals quote in lots of 10, 4 bps taker fee
+    fn build_market() -> OpenBookV2Market {
+        OpenBookV2Market {
+            base_decimals: 9,
+            quote_decimals: 6,
+            base_lot_size: 1_000,
+            quote_lot_size: 10,
+            taker_fee: 400,
+            bids: Pubkey::new_unique(),
+            asks: Pubkey::new_unique(),
+            event_heap: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            market_base_vault: Pubkey::new_unique(),
+            market_quote_vault: Pubkey::new_unique(),
+            market_authority: Pubkey::new_unique(),
+            ..OpenBookV2Market::default()
+        }
+    }
+
+    /// Asks 10 lots at 100 and 20 at 101, 5 pegged 2 below the oracle. Bids 10 lots at 99 and 10 at 98
+    fn build_books() -> (OpenBookV2BookSide, OpenBookV2BookSide) {
+        let bids = book_side_data(0, [(98, !5, 10, 0, 0), (99, !4, 10, 0, 0)], None);
+        let asks = book_side_data(1, [(100, 1, 10, 0, 0), (101, 2, 20, 0, 0)], Some((pegged_price_data(-2), 3, 5, 0, 0)));
+        (OpenBookV2BookSide::unpack_data(&bids), OpenBookV2BookSide::unpack_data(&asks))
+    }
+
+    #[test]
+    fn decode_book_sides() {
+        let (bids, asks) = build_books();
+        assert!(bids.is_bids() && !asks.is_bids());
+        assert_eq!(bids.fixed.iter().map(|order| order.get_price_data()).collect::<Vec<u64>>(), vec![99, 98]);
+        assert_eq!(asks.fixed.iter().map(|order| order.get_price_data()).collect::<Vec<u64>>(), vec![100, 101]);
+        assert_eq!(asks.oracle_pegged.len(), 1);
+        assert_eq!(asks.oracle_pegged[0].get_peg_offset(), -2);
+
+        // pegged orders join the ladder at the oracle price plus their offset
+        let prices = |oracle| get_priced_orders(&asks, oracle, 0).iter().map(|priced| priced.price_lots).collect::<Vec<i64>>();
+        assert_eq!(prices(None), vec![100, 101]);
+        assert_eq!(prices(Some(100)), vec![98, 100, 101]);
+        assert_eq!(prices(Some(103)), vec![100, 101, 101]);
+    }
+
+    #[test]
+    fn taker_fills() {
+        let market = build_market();
+        let (bids, asks) = build_books();
+        let event_heap = OpenBookV2EventHeap::default();
+
+        // 15 lots: 10 at 99 and 5 at 98 make 1_480 quote lots, 6 of the 14_800 go to the taker fee
+        let (amount_out, fill) = quote_exact_in(&market, &bids, &asks, &event_heap, 15_500, true, None, 0).unwrap();
+        assert_eq!((amount_out, fill.base_lots, fill.quote_lots, fill.orders), (14_794, 15, 1_480, 2));
+
+        // 2_000 quote lots less the fee leave 1_999: 10 lots at 100 then 9 at 101
+        let (amount_out, fill) = quote_exact_in(&market, &bids, &asks, &event_heap, 20_000, false, None, 0).unwrap();
+        assert_eq!((amount_out, fill.quote_lots, fill.orders), (19_000, 1_909, 2));
+
+        // the pegged ask at 98 fills first
+        let (amount_out, fill) = quote_exact_in(&market, &bids, &asks, &event_heap, 20_000, false, Some(100), 0).unwrap();
+        assert_eq!((amount_out, fill.orders), (20_000, 3));
+
+        // one free slot in the event heap, one fill
+        let full = OpenBookV2EventHeap { count: 599, ..OpenBookV2EventHeap::default() };
+        let (amount_out, fill) = quote_exact_in(&market, &bids, &asks, &full, 20_000, false, None, 0).unwrap();
+        assert_eq!((amount_out, fill.orders), (10_000, 1));
+
+        // expired orders are skipped
+        let asks = book_side_data(1, [(100, 1, 10, 10, 1_000), (101, 2, 20, 0, 0)], None);
+        let asks = OpenBookV2BookSide::unpack_data(&asks);
+        let (amount_out, fill) = quote_exact_in(&market, &bids, &asks, &event_heap, 10_200, false, None, 1_010).unwrap();
+        assert_eq!((amount_out, fill.orders, fill.dropped_orders), (10_000, 1, 1));
+
+        let expired = OpenBookV2Market { time_expiry: 1_000, ..market };
+        assert_eq!(quote_exact_in(&expired, &bids, &asks, &event_heap, 10_000, false, None, 1_000), Err("market expired"));
+    }
+
+    #[test]
+    fn oracle_price() {
+        let mut market = build_market();
+        market.oracle_config.max_staleness_slots = 100;
+        market.oracle_config.conf_filter = 0.1;
+        let oracle_a = Pubkey::new_unique();
+        market.oracle_a = Some(oracle_a);
+
+        // 1_000 quote per base is 1 native quote per native base, 100 quote lots per base lot
+        let data = pyth_data(100_000_000_000, -8, 50);
+        assert_eq!(get_oracle_price_lots(&market, Some(&data), None, 100), Some(100));
+        assert_eq!(get_oracle_price_lots(&market, Some(&data), None, 151), None);
+        assert_eq!(get_oracle_price_lots(&market, None, None, 100), None);
+
+        // a over b, b at 2
+        market.oracle_b = Some(Pubkey::new_unique());
+        let data_b = pyth_data(200_000, -5, 50);
+        assert_eq!(get_oracle_price_lots(&market, Some(&data), Some(&data_b), 100), Some(50));
+        assert_eq!(get_oracle_price_lots(&market, Some(&data), None, 100), None);
+    }
+
+    #[test]
+    fn quote_and_build_take_order() {
+        let market = build_market();
+        let program_id = Pubkey::from_str(OPENBOOK_V2_PROGRAM_PUBKEY).unwrap();
+        let mut data = vec![0u8; OPENBOOK_V2_MARKET_DATA_LEN];
+        data[448..456].copy_from_slice(&market.quote_lot_size.to_le_bytes());
+        data[456..464].copy_from_slice(&market.base_lot_size.to_le_bytes());
+        data[488..496].copy_from_slice(&market.taker_fee.to_le_bytes());
+        data[200..232].copy_from_slice(market.bids.as_ref());
+        data[576..608].copy_from_slice(market.base_mint.as_ref());
+        data[608..640].copy_from_slice(market.quote_mint.as_ref());
+        let resolved = resolve_pool_account(&Market::OPENBOOK, &data);
+        let resolved = resolved.as_any().downcast_ref::<OpenBookV2Market>().unwrap();
+        assert_eq!((resolved.quote_lot_size, resolved.base_lot_size, resolved.taker_fee), (10, 1_000, 400));
+        assert_eq!((resolved.bids, resolved.base_mint, resolved.quote_mint), (market.bids, market.base_mint, market.quote_mint));
+        assert_eq!(resolved.get_fee_rate(), Some(400));
+        assert!(resolved.oracle_a.is_none() && resolved.is_tradable());
+
+        // bids, asks and event heap come through the config accounts
+        let bids = book_side_data(0, [(98, !5, 10, 0, 0), (99, !4, 10, 0, 0)], None);
+        let asks = book_side_data(1, [(100, 1, 10, 0, 0), (101, 2, 20, 0, 0)], None);
+        let accounts = vec![
+            DeserializedAccount::ConfigAccount(resolve_pool_config_account(&Market::OPENBOOK, &program_id, market.bids, &bids).unwrap()),
+            DeserializedAccount::ConfigAccount(resolve_pool_config_account(&Market::OPENBOOK, &program_id, market.asks, &asks).unwrap()),
+            DeserializedAccount::ConfigAccount(resolve_pool_config_account(&Market::OPENBOOK, &program_id, market.event_heap, &event_heap_data(0)).unwrap()),
+            clock_account(Clock { slot: 100, unix_timestamp: 1_700_000_000, ..Clock::default() }, Market::OPENBOOK),
+        ];
+        assert_eq!(market.get_swap_related_pubkeys().len(), 4);
+        let quote = market.swap(&accounts, &market.base_mint, 15_500).unwrap();
+        assert_eq!((quote.amount_out, quote.crossings), (14_794, 2));
+        assert_eq!(market.swap(&accounts[1..].to_vec(), &market.base_mint, 15_500), Err("bids not found"));
+
+        let (pubkey, signer, base_account, quote_account) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = market.get_swap_instruction(&pubkey, &signer, &base_account, &quote_account, &market.base_mint, 15_500, None).unwrap();
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.data.len(), 35);
+        // ask at any price, 15 lots, immediate or cancel
+        assert_eq!(instruction.data[8], 1);
+        assert_eq!(instruction.data[9..17], 1i64.to_le_bytes());
+        assert_eq!(instruction.data[17..25], 15i64.to_le_bytes());
+        assert_eq!(instruction.data[33], 1);
+        assert_eq!(instruction.accounts.len(), 16);
+        assert_eq!(instruction.accounts[2].pubkey, pubkey);
+        assert_eq!(instruction.accounts[4].pubkey, market.bids);
+        // no oracles and no open orders admin
+        assert_eq!(instruction.accounts[11].pubkey, program_id);
+        assert_eq!(instruction.accounts[15].pubkey, program_id);
+        assert!(instruction.accounts[0].is_signer);
+
+        let instruction = market.get_swap_instruction(&pubkey, &signer, &base_account, &quote_account, &market.quote_mint, 20_000, Some(101)).unwrap();
+        assert_eq!(instruction.data[8], 0);
+        assert_eq!(instruction.data[9..17], 101i64.to_le_bytes());
+        assert_eq!(instruction.data[25..33], 2_000i64.to_le_bytes());
+        assert!(market.get_swap_instruction(&pubkey, &signer, &base_account, &quote_account, &Pubkey::new_unique(), 1, None).is_err());
+    }
+}
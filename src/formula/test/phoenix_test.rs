@@ -0,0 +1,211 @@
+#[cfg(test)]
+mod phoenix_test {
+    use std::str::FromStr;
+
+    use solana_sdk::clock::Clock;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::sysvar::clock;
+
+    use crate::constants::{PHOENIX_PROGRAM_PUBKEY, TOKEN_PROGRAM_PUBKEY};
+    use crate::executor::fee::estimate_hop_compute_units;
+    use crate::formula::phoenix::{buy_with_quote_lots, compute_fee, quote_exact_in, sell_base_lots};
+    use crate::formula::test::account_fixture::{clock_account, pool_account};
+    use crate::r#struct::account::DeserializedAccount;
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::{PhoenixMarket, PhoenixRestingOrder, PhoenixSide};
+    use crate::r#struct::resolver::resolve_pool_account;
+
+    const BIDS_SIZE: u64 = 4;
+    const ASKS_SIZE: u64 = 4;
+
+    fn order(price_in_ticks: u64, order_sequence_number: u64, num_base_lots: u64) -> PhoenixRestingOrder {
+        PhoenixRestingOrder { price_in_ticks, order_sequence_number, num_base_lots, ..PhoenixRestingOrder::default() }
+    }
+
+    /// Writes `nodes` as (left, right, order) at node addresses 1, 2, ..
+    fn write_tree(data: &mut Vec<u8>, offset: usize, root: u32, nodes: &[(u32, u32, PhoenixRestingOrder)]) {
+        data[offset..offset + 4].copy_from_slice(&root.to_le_bytes());
+        nodes.iter().enumerate().for_each(|(i, (left, right, order))| {
+            let at = offset + 32 + i * 64;
+            data[at..at + 4].copy_from_slice(&left.to_le_bytes());
+            data[at + 4..at + 8].copy_from_slice(&right.to_le_bytes());
+            [
+                order.price_in_ticks, order.order_sequence_number, order.trader_index,
+                order.num_base_lots, order.last_valid_slot, order.last_valid_unix_timestamp_in_seconds
+            ].iter().enumerate().for_each(|(field, value)| {
+                data[at + 16 + field * 8..at + 24 + field * 8].copy_from_slice(&value.to_le_bytes());
+            });
+        });
+    }
+
+    /// SOL/USDC with 0.001 SOL base lots, 1 atom quote lots, 0.001 USDC ticks and a 5 bps taker fee.
+    /// Asks 2 SOL at 150 and 5 SOL at 151, bids 3 SOL at 149 and 10 SOL at 148
+    fn market_data(market: &PhoenixMarket, asks: &[(u32, u32, PhoenixRestingOrder)]) -> Vec<u8> {
+        let bids_offset = 880;
+        let asks_offset = bids_offset + PhoenixMarket::get_tree_len(BIDS_SIZE);
+        let mut data = vec![0u8; asks_offset + PhoenixMarket::get_tree_len(ASKS_SIZE) + 32];
+
+        data[8..16].copy_from_slice(&market.status.to_le_bytes());
+        data[16..24].copy_from_slice(&BIDS_SIZE.to_le_bytes());
+        data[24..32].copy_from_slice(&ASKS_SIZE.to_le_bytes());
+        data[40..44].copy_from_slice(&9u32.to_le_bytes());
+        data[48..80].copy_from_slice(market.base_params.mint_key.as_ref());
+        data[80..112].copy_from_slice(market.base_params.vault_key.as_ref());
+        data[112..120].copy_from_slice(&1_000_000u64.to_le_bytes());
+        data[120..124].copy_from_slice(&6u32.to_le_bytes());
+        data[128..160].copy_from_slice(market.quote_params.mint_key.as_ref());
+        data[160..192].copy_from_slice(market.quote_params.vault_key.as_ref());
+        data[192..200].copy_from_slice(&1u64.to_le_bytes());
+        data[832..840].copy_from_slice(&1_000u64.to_le_bytes());
+        data[840..848].copy_from_slice(&1_000u64.to_le_bytes());
+        data[856..864].copy_from_slice(&5u64.to_le_bytes());
+
+        // node 3 is freed, it is not linked from the root
+        write_tree(&mut data, bids_offset, 2, &[
+            (0, 0, order(148_000, !7, 10_000)),
+            (1, 0, order(149_000, !4, 3_000)),
+            (0, 0, order(1, !1, 1_000_000)),
+        ]);
+        write_tree(&mut data, asks_offset, 1, asks);
+        data
+    }
+
+    fn default_asks() -> Vec<(u32, u32, PhoenixRestingOrder)> {
+        vec![
+            (0, 2, order(150_000, 5, 2_000)),
+            (0, 0, order(151_000, 6, 5_000)),
+        ]
+    }
+
+    fn build_market() -> PhoenixMarket {
+        let mut market = PhoenixMarket { status: 1, ..PhoenixMarket::default() };
+        market.base_params.mint_key = Pubkey::new_unique();
+        market.base_params.vault_key = Pubkey::new_unique();
+        market.quote_params.mint_key = Pubkey::new_unique();
+        market.quote_params.vault_key = Pubkey::new_unique();
+        market
+    }
+
+    fn build_accounts(data: Vec<u8>, slot: u64) -> Vec<DeserializedAccount> {
+        vec![
+            pool_account(Pubkey::new_unique(), Pubkey::default(), data, Market::PHOENIX),
+            clock_account(Clock { slot, unix_timestamp: 1_700_000_000, ..Clock::default() }, Market::PHOENIX),
+        ]
+    }
+
+    #[test]
+    fn decode_market_and_ladder() {
+        let market = build_market();
+        let resolved = resolve_pool_account(&Market::PHOENIX, &market_data(&market, &default_asks()));
+        let resolved = resolved.as_any().downcast_ref::<PhoenixMarket>().unwrap();
+
+        assert_eq!((resolved.base_params.decimals, resolved.quote_params.decimals), (9, 6));
+        assert_eq!((resolved.base_lot_size, resolved.quote_lot_size, resolved.base_lots_per_base_unit), (1_000_000, 1, 1_000));
+        assert_eq!(resolved.get_fee_rate(), Some(500));
+        assert_eq!(resolved.get_mint_pair().pubkey_a, market.base_params.mint_key);
+        assert!(resolved.is_tradable());
+
+        // best price first
+        assert_eq!(resolved.bids.iter().map(|order| order.price_in_ticks).collect::<Vec<u64>>(), vec![149_000, 148_000]);
+        assert_eq!(resolved.asks.iter().map(|order| order.price_in_ticks).collect::<Vec<u64>>(), vec![150_000, 151_000]);
+        assert_eq!(resolved.bids[0].get_side(), PhoenixSide::Bid);
+        assert_eq!(resolved.asks[0].get_side(), PhoenixSide::Ask);
+
+        // a tree cut short keeps what could be read
+        let mut data = market_data(&market, &default_asks());
+        data.truncate(880 + 32 + 64);
+        let truncated = resolve_pool_account(&Market::PHOENIX, &data);
+        let truncated = truncated.as_any().downcast_ref::<PhoenixMarket>().unwrap();
+        assert_eq!(truncated.bids.len(), 0);
+        assert_eq!(truncated.asks.len(), 0);
+    }
+
+    #[test]
+    fn walk_the_ladder() {
+        let market = build_market();
+        let resolved = resolve_pool_account(&Market::PHOENIX, &market_data(&market, &default_asks()));
+        let resolved = resolved.as_any().downcast_ref::<PhoenixMarket>().unwrap();
+
+        assert_eq!(compute_fee(372_500_000, 5).unwrap(), 186_250);
+        assert_eq!(compute_fee(1, 5).unwrap(), 1);
+
+        // 3 SOL at 149 then 1 SOL at 148
+        let fill = sell_base_lots(resolved, 4_000, 0, 0).unwrap();
+        assert_eq!((fill.base_lots, fill.quote_lots, fill.fee_quote_lots, fill.orders), (4_000, 595_000_000, 297_500, 2));
+
+        // 400 USDC buys 2 SOL at 150 and 0.66 SOL at 151, the fee fits in the budget
+        let fill = buy_with_quote_lots(resolved, 400_000_000, 0, 0).unwrap();
+        assert_eq!((fill.base_lots, fill.quote_lots, fill.fee_quote_lots, fill.orders), (2_660, 399_660_000, 199_830, 2));
+        assert!(fill.quote_lots + fill.fee_quote_lots <= 400_000_000);
+
+        // the atoms short of a lot are not sold
+        let (amount_out, fill) = quote_exact_in(resolved, 2_500_000_500, true, 0, 0).unwrap();
+        assert_eq!((amount_out, fill.base_lots), (372_313_750, 2_500));
+
+        // more than the book holds fills what there is
+        let fill = sell_base_lots(resolved, 1_000_000, 0, 0).unwrap();
+        assert_eq!(fill.base_lots, 13_000);
+    }
+
+    #[test]
+    fn quote_through_pool_operation() {
+        let market = build_market();
+        let accounts = build_accounts(market_data(&market, &default_asks()), 100);
+        let related = market.get_swap_related_pubkeys().iter().map(|(_, pubkey)| *pubkey).collect::<Vec<Pubkey>>();
+        assert_eq!(related, vec![clock::id()]);
+
+        // the book of the fetched market account is quoted, not the one the market was loaded with
+        let quote = market.swap(&accounts, &market.quote_params.mint_key, 400_000_000).unwrap();
+        assert_eq!((quote.amount_in, quote.amount_out, quote.crossings), (400_000_000, 2_660_000_000, 2));
+        let quote = market.swap(&accounts, &market.base_params.mint_key, 4_000_000_000).unwrap();
+        assert_eq!(quote.amount_out, 595_000_000 - 297_500);
+        assert!(estimate_hop_compute_units(&market.get_formula(), quote.crossings) > estimate_hop_compute_units(&market.get_formula(), 0));
+
+        // the 150 ask expired at slot 50
+        let mut asks = default_asks();
+        asks[0].2.last_valid_slot = 50;
+        let expired = build_accounts(market_data(&market, &asks), 100);
+        let quote = market.swap(&expired, &market.quote_params.mint_key, 400_000_000).unwrap();
+        assert_eq!((quote.amount_out, quote.crossings), (2_647_000_000, 1));
+
+        assert_eq!(market.swap(&accounts[..1].to_vec(), &market.base_params.mint_key, 1_000_000_000), Err("clock not found"));
+        assert_eq!(market.swap(&accounts, &Pubkey::new_unique(), 1_000_000_000), Err("mint not in market"));
+
+        let paused = PhoenixMarket { status: 3, ..market.clone() };
+        let accounts = build_accounts(market_data(&paused, &default_asks()), 100);
+        assert_eq!(paused.swap(&accounts, &paused.base_params.mint_key, 1_000_000_000), Err("market not active"));
+    }
+
+    #[test]
+    fn build_swap_instruction() {
+        let market = build_market();
+        let resolved = resolve_pool_account(&Market::PHOENIX, &market_data(&market, &default_asks()));
+        let resolved = resolved.as_any().downcast_ref::<PhoenixMarket>().unwrap();
+        let program_id = Pubkey::from_str(PHOENIX_PROGRAM_PUBKEY).unwrap();
+        let (pubkey, trader, base_account, quote_account) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let instruction = resolved.get_swap_instruction(&pubkey, &trader, &base_account, &quote_account, &market.base_params.mint_key, 2_500_000_500, 372_313_750).unwrap();
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.data.len(), 57);
+        // swap, immediate or cancel, ask, no limit price
+        assert_eq!(instruction.data[..4], [0, 2, 1, 0]);
+        assert_eq!(instruction.data[4..12], 2_500u64.to_le_bytes());
+        assert_eq!(instruction.data[12..20], 0u64.to_le_bytes());
+        assert_eq!(instruction.data[28..36], 372_313_750u64.to_le_bytes());
+
+        let accounts = instruction.accounts.iter().map(|meta| meta.pubkey).collect::<Vec<Pubkey>>();
+        assert_eq!(accounts, vec![
+            program_id, PhoenixMarket::log_authority(&program_id), pubkey, trader, base_account, quote_account,
+            market.base_params.vault_key, market.quote_params.vault_key, Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap()
+        ]);
+        assert!(instruction.accounts[3].is_signer);
+        assert!(instruction.accounts[2].is_writable);
+
+        // buying base spends whole quote lots and asks for whole base lots
+        let instruction = resolved.get_swap_instruction(&pubkey, &trader, &base_account, &quote_account, &market.quote_params.mint_key, 400_000_000, 2_660_000_001).unwrap();
+        assert_eq!(instruction.data[2], 0);
+        assert_eq!(instruction.data[12..20], 400_000_000u64.to_le_bytes());
+        assert_eq!(instruction.data[20..28], 2_661u64.to_le_bytes());
+        assert!(resolved.get_swap_instruction(&pubkey, &trader, &base_account, &quote_account, &Pubkey::new_unique(), 1, 1).is_err());
+    }
+}
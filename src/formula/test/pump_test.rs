@@ -0,0 +1,255 @@
+#[cfg(test)]
+mod pump_test {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::config::PoolFilter;
+    use crate::constants::{NATIVE_MINT_PUBKEY, TOKEN_PROGRAM_PUBKEY};
+    use crate::formula::pump::{fee, quote_bonding_curve_buy, quote_bonding_curve_sell, quote_pump_swap_buy, quote_pump_swap_sell};
+    use crate::formula::test::account_fixture::{data_account, token_account};
+    use crate::path::PathFinder;
+    use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount, DeserializedPoolAccount};
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::{PumpFunBondingCurve, PumpFunGlobal, PumpSwapGlobalConfig, PumpSwapPool, RaydiumCpmmMarket};
+    use crate::r#struct::resolver::resolve_pool_account;
+
+    fn curve_data(curve: &PumpFunBondingCurve, with_creator: bool) -> Vec<u8> {
+        let mut data = vec![0u8; 8];
+        data.extend_from_slice(&curve.virtual_token_reserves.to_le_bytes());
+        data.extend_from_slice(&curve.virtual_sol_reserves.to_le_bytes());
+        data.extend_from_slice(&curve.real_token_reserves.to_le_bytes());
+        data.extend_from_slice(&curve.real_sol_reserves.to_le_bytes());
+        data.extend_from_slice(&curve.token_total_supply.to_le_bytes());
+        data.push(curve.complete as u8);
+        if with_creator {
+            data.extend_from_slice(curve.creator.as_ref());
+        }
+        data
+    }
+
+    fn global_data(fee_basis_points: u64, creator_fee_basis_points: u64) -> Vec<u8> {
+        // the live account carries more settings past the creator fee
+        let mut data = vec![0u8; 300];
+        data[8] = 1;
+        data[105..113].copy_from_slice(&fee_basis_points.to_le_bytes());
+        data[154..162].copy_from_slice(&creator_fee_basis_points.to_le_bytes());
+        data
+    }
+
+    fn pool_data(pool: &PumpSwapPool) -> Vec<u8> {
+        let mut data = vec![0u8; 243];
+        data[9..11].copy_from_slice(&pool.index.to_le_bytes());
+        data[43..75].copy_from_slice(pool.base_mint.as_ref());
+        data[75..107].copy_from_slice(pool.quote_mint.as_ref());
+        data[139..171].copy_from_slice(pool.pool_base_token_account.as_ref());
+        data[171..203].copy_from_slice(pool.pool_quote_token_account.as_ref());
+        data[211..243].copy_from_slice(pool.coin_creator.as_ref());
+        data
+    }
+
+    fn config_data(disable_flags: u8) -> Vec<u8> {
+        let mut data = vec![0u8; 353];
+        data[40..48].copy_from_slice(&20u64.to_le_bytes());
+        data[48..56].copy_from_slice(&5u64.to_le_bytes());
+        data[56] = disable_flags;
+        data[313..321].copy_from_slice(&5u64.to_le_bytes());
+        data
+    }
+
+    fn build_curve() -> PumpFunBondingCurve {
+        // a freshly created curve
+        PumpFunBondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+            creator: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            token_program: Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap(),
+        }
+    }
+
+    fn build_pool() -> PumpSwapPool {
+        PumpSwapPool {
+            index: 0,
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap(),
+            pool_base_token_account: Pubkey::new_unique(),
+            pool_quote_token_account: Pubkey::new_unique(),
+            coin_creator: Pubkey::new_unique(),
+            ..PumpSwapPool::default()
+        }
+    }
+
+    #[test]
+    fn decode_bonding_curve() {
+        let curve = build_curve();
+        let resolved = resolve_pool_account(&Market::PUMP_FUN, &curve_data(&curve, true));
+        let resolved = resolved.as_any().downcast_ref::<PumpFunBondingCurve>().unwrap();
+        assert_eq!(resolved.virtual_token_reserves, curve.virtual_token_reserves);
+        assert_eq!(resolved.virtual_sol_reserves, curve.virtual_sol_reserves);
+        assert_eq!(resolved.real_token_reserves, curve.real_token_reserves);
+        assert_eq!(resolved.token_total_supply, curve.token_total_supply);
+        assert_eq!(resolved.creator, curve.creator);
+        assert!(resolved.is_tradable());
+        // the mint is not part of the account
+        assert_eq!(resolved.mint, Pubkey::default());
+
+        let legacy = PumpFunBondingCurve::unpack_data(&curve_data(&PumpFunBondingCurve { complete: true, ..curve }, false));
+        assert_eq!(legacy.creator, Pubkey::default());
+        assert!(!legacy.is_tradable());
+
+        let global = PumpFunGlobal::unpack_data(&global_data(95, 5));
+        assert!(global.initialized);
+        assert_eq!(global.fee_basis_points, 95);
+        assert_eq!(global.creator_fee_basis_points, 5);
+        assert_eq!(legacy.get_creator_fee_bps(&global), 0);
+        assert_eq!(curve.get_creator_fee_bps(&global), 5);
+    }
+
+    #[test]
+    fn quote_bonding_curve() {
+        let curve = build_curve();
+        let global = PumpFunGlobal { fee_basis_points: 95, creator_fee_basis_points: 5, ..PumpFunGlobal::default() };
+
+        let tokens = quote_bonding_curve_buy(&curve, &global, 1_000_000_000).unwrap();
+        assert_eq!(tokens, 34_281_150_028_991);
+        // what the program charges for these tokens, a lamport over the price and both fees rounded up, stays within the lamports
+        let sol_amount = tokens as u128 * curve.virtual_sol_reserves as u128 / (curve.virtual_token_reserves - tokens) as u128 + 1;
+        let sol_amount = sol_amount as u64;
+        assert!(sol_amount + fee(sol_amount, 95) + fee(sol_amount, 5) <= 1_000_000_000);
+
+        let curve = PumpFunBondingCurve {
+            virtual_token_reserves: 1_000_000_000_000_000,
+            virtual_sol_reserves: 32_000_000_000,
+            real_sol_reserves: 2_000_000_000,
+            ..curve
+        };
+        assert_eq!(quote_bonding_curve_sell(&curve, &global, 10_000_000_000_000).unwrap(), 313_663_366);
+        assert_eq!(quote_bonding_curve_sell(&curve, &global, 100_000_000_000_000).unwrap_err(), "insufficient sol reserves");
+
+        // the buy is capped by the tokens left on the curve
+        let almost_sold = PumpFunBondingCurve { real_token_reserves: 1_000, ..curve };
+        assert_eq!(quote_bonding_curve_buy(&almost_sold, &global, 1_000_000_000).unwrap(), 1_000);
+
+        let complete = PumpFunBondingCurve { complete: true, ..curve };
+        assert_eq!(quote_bonding_curve_buy(&complete, &global, 1_000_000_000).unwrap_err(), "bonding curve complete");
+        assert_eq!(quote_bonding_curve_sell(&complete, &global, 1_000).unwrap_err(), "bonding curve complete");
+    }
+
+    #[test]
+    fn swap_through_bonding_curve() {
+        let curve = build_curve();
+        let wsol = Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap();
+        let pair = curve.get_mint_pair();
+        assert!(pair.all(wsol, curve.mint));
+        assert_eq!(curve.get_pool_pair().pubkey_a, PumpFunBondingCurve::bonding_curve(&curve.mint));
+        assert_eq!(curve.get_swap_related_pubkeys()[0].1, PumpFunBondingCurve::global());
+
+        let global = data_account(PumpFunBondingCurve::global(), global_data(95, 5), Market::PUMP_FUN);
+        // a refreshed curve is decoded without its mint, the quote goes by the mint of the edge
+        let fresh = PumpFunBondingCurve::unpack_data(&curve_data(&curve, true));
+        let pool = DeserializedAccount::PoolAccount(DeserializedPoolAccount {
+            pubkey: PumpFunBondingCurve::bonding_curve(&curve.mint),
+            market: Market::PUMP_FUN,
+            operation: Box::new(fresh),
+            ..DeserializedPoolAccount::default()
+        });
+        let accounts = vec![pool, global];
+
+        assert_eq!(curve.swap(&accounts, &wsol, 1_000_000_000).unwrap().amount_out, 34_281_150_028_991);
+        // nothing bought on a fresh curve, nothing to sell against
+        assert_eq!(curve.swap(&accounts, &curve.mint, 1_000_000).unwrap_err(), "insufficient sol reserves");
+        assert_eq!(curve.swap(&accounts, &Pubkey::new_unique(), 1_000).unwrap_err(), "mint not in pool");
+        assert_eq!(curve.swap(&vec![], &wsol, 1_000).unwrap_err(), "global not found");
+    }
+
+    #[test]
+    fn decode_pump_swap_pool() {
+        let pool = build_pool();
+        let resolved = resolve_pool_account(&Market::PUMP_SWAP, &pool_data(&pool));
+        let resolved = resolved.as_any().downcast_ref::<PumpSwapPool>().unwrap();
+        assert_eq!(resolved.base_mint, pool.base_mint);
+        assert_eq!(resolved.quote_mint, pool.quote_mint);
+        assert_eq!(resolved.pool_base_token_account, pool.pool_base_token_account);
+        assert_eq!(resolved.pool_quote_token_account, pool.pool_quote_token_account);
+        assert_eq!(resolved.coin_creator, pool.coin_creator);
+
+        // pools created before coin creator fees
+        let legacy = PumpSwapPool::unpack_data(&pool_data(&pool)[..211].to_vec());
+        assert_eq!(legacy.coin_creator, Pubkey::default());
+
+        let config = PumpSwapGlobalConfig::unpack_data(&config_data(0));
+        assert_eq!(config.lp_fee_basis_points, 20);
+        assert_eq!(config.protocol_fee_basis_points, 5);
+        assert_eq!(config.coin_creator_fee_basis_points, 5);
+        assert_eq!(legacy.get_coin_creator_fee_bps(&config), 0);
+        assert_eq!(pool.get_coin_creator_fee_bps(&config), 5);
+    }
+
+    #[test]
+    fn swap_through_pump_swap_pool() {
+        let pool = build_pool();
+        let accounts = |disable_flags: u8| vec![
+            token_account(pool.pool_base_token_account, 1_000_000_000_000_000, Market::PUMP_SWAP),
+            token_account(pool.pool_quote_token_account, 100_000_000_000, Market::PUMP_SWAP),
+            data_account(PumpSwapPool::global_config(), config_data(disable_flags), Market::PUMP_SWAP),
+        ];
+
+        // lp, protocol and coin creator fees, 30 bps in total
+        assert_eq!(pool.swap(&accounts(0), &pool.quote_mint, 1_000_000_000).unwrap().amount_out, 9_871_668_281_742);
+        assert_eq!(pool.swap(&accounts(0), &pool.base_mint, 10_000_000_000_000).unwrap().amount_out, 987_128_710);
+        assert_eq!(pool.swap(&accounts(0), &Pubkey::new_unique(), 1_000).unwrap_err(), "mint not in pool");
+
+        let without_creator = PumpSwapPool { coin_creator: Pubkey::default(), ..pool };
+        assert_eq!(without_creator.swap(&accounts(0), &pool.quote_mint, 1_000_000_000).unwrap().amount_out, 9_876_543_186_211);
+
+        assert_eq!(pool.swap(&accounts(1 << 3), &pool.quote_mint, 1_000).unwrap_err(), "buy disabled");
+        assert_eq!(pool.swap(&accounts(1 << 4), &pool.base_mint, 1_000).unwrap_err(), "sell disabled");
+        assert_eq!(pool.swap(&accounts(0)[1..].to_vec(), &pool.base_mint, 1_000).unwrap_err(), "base reserve not found");
+    }
+
+    #[test]
+    fn quote_pump_swap_within_the_buy_cost() {
+        let config = PumpSwapGlobalConfig { lp_fee_basis_points: 20, protocol_fee_basis_points: 5, ..PumpSwapGlobalConfig::default() };
+        let (base_reserve, quote_reserve) = (1_000_000_000_000_000u64, 100_000_000_000u64);
+        let base_out = quote_pump_swap_buy(&config, 5, base_reserve, quote_reserve, 1_000_000_000).unwrap();
+
+        // the quote `buy` of base_out takes, fees rounded up, stays within the quote in
+        let quote_in = (quote_reserve as u128 * base_out as u128).div_ceil((base_reserve - base_out) as u128) as u64;
+        assert!(quote_in + fee(quote_in, 20) + fee(quote_in, 5) + fee(quote_in, 5) <= 1_000_000_000);
+        assert_eq!(quote_pump_swap_sell(&config, 0, 0, 0, 0).unwrap_err(), "empty pool");
+    }
+
+    #[test]
+    fn pump_pools_are_path_edges() {
+        let curve = build_curve();
+        let wsol = Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap();
+        let pool = PumpSwapPool { base_mint: curve.mint, ..build_pool() };
+        let raydium = RaydiumCpmmMarket { token_0_mint: wsol, token_1_mint: curve.mint, ..RaydiumCpmmMarket::default() };
+        let pools = vec![
+            DeserializedPoolAccount { pubkey: Pubkey::new_unique(), market: Market::PUMP_FUN, operation: Box::new(curve), ..DeserializedPoolAccount::default() },
+            DeserializedPoolAccount { pubkey: Pubkey::new_unique(), market: Market::PUMP_SWAP, operation: Box::new(pool), ..DeserializedPoolAccount::default() },
+            DeserializedPoolAccount { pubkey: Pubkey::new_unique(), market: Market::RAYDIUM, operation: Box::new(raydium), ..DeserializedPoolAccount::default() },
+        ];
+
+        let path_list = Arc::new(Mutex::new(HashMap::new()));
+        let path_finder = PathFinder {
+            pool_accounts: Arc::new(Mutex::new(pools)),
+            path_list: Arc::clone(&path_list),
+            pool_filter: PoolFilter::default(),
+        };
+        path_finder.resolve_path(wsol);
+
+        // every ordered pair of the three pools trading the token against SOL
+        let paths = path_list.lock().unwrap().get(&wsol).cloned().unwrap();
+        assert_eq!(paths.len(), 6);
+        assert_eq!(paths.iter().filter(|path| path.iter().any(|pool| pool.market == Market::PUMP_FUN)).count(), 4);
+        assert!(paths.iter().any(|path| path[0].market == Market::PUMP_FUN && path[1].market == Market::RAYDIUM));
+    }
+}
@@ -0,0 +1,242 @@
+#[cfg(test)]
+mod raydium_amm_v4_test {
+    use std::str::FromStr;
+
+    use bytemuck::Zeroable;
+    use serum_dex::critbit::{LeafNode, Slab};
+    use serum_dex::fees::FeeTier;
+    use serum_dex::state::{AccountFlag, MarketState, OpenOrders};
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::constants::{OPENBOOK_V1_PROGRAM_PUBKEY, RAYDIUM_OPEN_BOOK_DATA_LEN};
+    use crate::executor::fee::{estimate_hop_compute_units, AMM_V4_CANCEL_ORDER_COMPUTE_UNITS, AMM_V4_SWAP_COMPUTE_UNITS};
+    use crate::formula::openbook::math::Calculator;
+    use crate::formula::test::account_fixture::{pool_account, token_account};
+    use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount, DeserializedConfigAccount};
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::{RaydiumOpenBookAccount, RaydiumOpenBookMarket, SerumBookSide, SerumEventQueue, SerumMarket, SerumOpenOrders};
+    use crate::r#struct::resolver::resolve_pool_config_account;
+
+    const BID_ORDER_ID: u128 = (100u128 << 64) | !1u64 as u128;
+    const ASK_ORDER_ID: u128 = (110u128 << 64) | 2;
+    // filled before the test snapshot, no longer on the book
+    const FILLED_BID_ORDER_ID: u128 = (99u128 << 64) | !3u64 as u128;
+
+    fn serum_account(account_flags: u64, body: &[u8]) -> Vec<u8> {
+        let mut data = b"serum".to_vec();
+        data.extend_from_slice(&account_flags.to_le_bytes());
+        data.extend_from_slice(body);
+        data.extend_from_slice(b"padding");
+        data
+    }
+
+    /// 0.25% swap fee, the pool owes 2_000_000 pc of pnl
+    fn amm_data(amm: &RaydiumOpenBookMarket) -> Vec<u8> {
+        let mut data = vec![0u8; RAYDIUM_OPEN_BOOK_DATA_LEN];
+        data[0..8].copy_from_slice(&amm.status.to_le_bytes());
+        data[176..184].copy_from_slice(&25u64.to_le_bytes());
+        data[184..192].copy_from_slice(&10_000u64.to_le_bytes());
+        data[200..208].copy_from_slice(&2_000_000u64.to_le_bytes());
+        [amm.base_vault, amm.quote_vault, amm.base_mint, amm.quote_mint, amm.lp_mint, amm.open_orders, amm.market_id].iter().enumerate().for_each(|(i, pubkey)| {
+            data[336 + i * 32..368 + i * 32].copy_from_slice(pubkey.as_ref());
+        });
+        data
+    }
+
+    fn build_amm(status: u64) -> RaydiumOpenBookMarket {
+        RaydiumOpenBookMarket {
+            status,
+            base_vault: Pubkey::new_unique(),
+            quote_vault: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+            open_orders: Pubkey::new_unique(),
+            market_id: Pubkey::new_unique(),
+            ..RaydiumOpenBookMarket::default()
+        }
+    }
+
+    /// 10_000_000 coin and 500_000_000 pc in the open orders, a bid, an ask and a filled bid
+    fn open_orders_data(amm: &RaydiumOpenBookMarket) -> Vec<u8> {
+        let mut open_orders = OpenOrders::zeroed();
+        open_orders.account_flags = AccountFlag::Initialized as u64 | AccountFlag::OpenOrders as u64;
+        open_orders.market = bytemuck::cast(amm.market_id.to_bytes());
+        open_orders.native_coin_total = 10_000_000;
+        open_orders.native_pc_total = 500_000_000;
+        open_orders.free_slot_bits = !0b111;
+        open_orders.is_bid_bits = 0b101;
+        let mut orders = open_orders.orders;
+        orders[0] = BID_ORDER_ID;
+        orders[1] = ASK_ORDER_ID;
+        orders[2] = FILLED_BID_ORDER_ID;
+        open_orders.orders = orders;
+
+        let mut data = b"serum".to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(&open_orders));
+        data.extend_from_slice(b"padding");
+        data
+    }
+
+    fn book_side_data(account_flag: AccountFlag, leaves: &[(u128, [u64; 4], u64)]) -> Vec<u8> {
+        let mut aligned = vec![0u64; (32 + 72 * 8) / 8];
+        let slab = Slab::new(bytemuck::cast_slice_mut(&mut aligned));
+        leaves.iter().for_each(|(key, owner, quantity)| {
+            slab.insert_leaf(&LeafNode::new(0, *key, *owner, *quantity, FeeTier::Base, 0)).unwrap();
+        });
+        serum_account(AccountFlag::Initialized as u64 | account_flag as u64, bytemuck::cast_slice(&aligned))
+    }
+
+    /// (flags, owner, native qty paid, native qty received) from head 1 of a queue of 4
+    fn event_queue_data(events: &[(u8, Pubkey, u64, u64)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        [1u64, events.len() as u64, 0].iter().for_each(|value| body.extend_from_slice(&value.to_le_bytes()));
+        let mut slots = vec![[0u8; 88]; 4];
+        events.iter().enumerate().for_each(|(i, (flags, owner, paid, received))| {
+            let slot = &mut slots[(1 + i) % 4];
+            slot[0] = *flags;
+            slot[8..16].copy_from_slice(&received.to_le_bytes());
+            slot[16..24].copy_from_slice(&paid.to_le_bytes());
+            slot[48..80].copy_from_slice(owner.as_ref());
+        });
+        slots.iter().for_each(|slot| body.extend_from_slice(slot));
+        serum_account(AccountFlag::Initialized as u64 | AccountFlag::EventQueue as u64, &body)
+    }
+
+    /// Vaults of 90_000_000 coin and 9_500_000_000 pc, the AMM bid filled for 1_000_000 coin and the crank behind
+    fn build_accounts(amm: &RaydiumOpenBookMarket) -> Vec<DeserializedAccount> {
+        let program_id = Pubkey::from_str(OPENBOOK_V1_PROGRAM_PUBKEY).unwrap();
+        let amm_owner: [u64; 4] = bytemuck::cast(amm.open_orders.to_bytes());
+        let other_owner: [u64; 4] = bytemuck::cast(Pubkey::new_unique().to_bytes());
+        let bids = book_side_data(AccountFlag::Bids, &[(BID_ORDER_ID, amm_owner, 10), ((101u128 << 64) | !4u64 as u128, other_owner, 5)]);
+        let asks = book_side_data(AccountFlag::Asks, &[(ASK_ORDER_ID, amm_owner, 10)]);
+        // fill, bid, maker flags of the event queue
+        let event_queue = event_queue_data(&[
+            (0b1101, amm.open_orders, 100_000_000, 1_000_000),
+            (0b0101, amm.open_orders, 7, 7),
+            (0b1001, Pubkey::new_unique(), 9, 9),
+        ]);
+        let config = |pubkey: Pubkey, data: &Vec<u8>| {
+            DeserializedAccount::ConfigAccount(resolve_pool_config_account(&Market::RAYDIUM, &program_id, pubkey, data).unwrap())
+        };
+
+        vec![
+            pool_account(Pubkey::new_unique(), Pubkey::default(), amm_data(amm), Market::RAYDIUM),
+            token_account(amm.base_vault, 90_000_000, Market::RAYDIUM),
+            token_account(amm.quote_vault, 9_500_000_000, Market::RAYDIUM),
+            config(amm.open_orders, &open_orders_data(amm)),
+            config(Pubkey::new_unique(), &bids),
+            config(Pubkey::new_unique(), &asks),
+            config(Pubkey::new_unique(), &event_queue),
+        ]
+    }
+
+    #[test]
+    fn decode_serum_accounts() {
+        let amm = build_amm(1);
+        let open_orders = SerumOpenOrders::unpack_data(&open_orders_data(&amm));
+        assert_eq!(open_orders.market, amm.market_id);
+        assert_eq!((open_orders.native_coin_total, open_orders.native_pc_total), (10_000_000, 500_000_000));
+        assert_eq!(open_orders.bid_order_ids, vec![BID_ORDER_ID, FILLED_BID_ORDER_ID]);
+        assert_eq!(open_orders.ask_order_ids, vec![ASK_ORDER_ID]);
+
+        let owner: [u64; 4] = bytemuck::cast(amm.open_orders.to_bytes());
+        let bids = SerumBookSide::unpack_data(&book_side_data(AccountFlag::Bids, &[(BID_ORDER_ID, owner, 10), (FILLED_BID_ORDER_ID + 1, owner, 3)]));
+        assert!(bids.is_bids);
+        assert_eq!(bids.orders.iter().map(|order| order.order_id).collect::<Vec<u128>>(), vec![FILLED_BID_ORDER_ID + 1, BID_ORDER_ID]);
+        let order = bids.find_by_order_id(BID_ORDER_ID).unwrap();
+        assert_eq!((order.price, order.quantity, order.owner), (100, 10, amm.open_orders));
+        assert!(bids.find_by_order_id(FILLED_BID_ORDER_ID).is_none());
+
+        // the ring buffer wraps, non fill events are left out
+        let event_queue = SerumEventQueue::unpack_data(&event_queue_data(&[
+            (0b1101, amm.open_orders, 3, 4), (0b0010, amm.open_orders, 0, 0), (0b1001, amm.open_orders, 5, 6), (0b0001, amm.open_orders, 1, 2),
+        ]));
+        assert_eq!(event_queue.fills.len(), 3);
+        assert!(event_queue.fills[0].is_bid && event_queue.fills[0].maker);
+        assert_eq!((event_queue.fills[1].native_qty_paid, event_queue.fills[1].native_qty_received), (5, 6));
+        assert!(!event_queue.fills[2].is_bid && !event_queue.fills[2].maker);
+
+        let mut market = MarketState::zeroed();
+        market.account_flags = AccountFlag::Initialized as u64 | AccountFlag::Market as u64;
+        market.bids = bytemuck::cast(Pubkey::new_unique().to_bytes());
+        market.event_q = bytemuck::cast(Pubkey::new_unique().to_bytes());
+        market.coin_lot_size = 1_000;
+        let mut data = b"serum".to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(&market));
+        data.extend_from_slice(b"padding");
+        let decoded = SerumMarket::unpack_data(&data);
+        assert_eq!(decoded.bids, Pubkey::new_from_array(bytemuck::cast(market.bids)));
+        assert_eq!(decoded.coin_lot_size, 1_000);
+        let related = RaydiumOpenBookMarket::get_market_related_pubkeys(&decoded).iter().map(|(_, pubkey)| *pubkey).collect::<Vec<Pubkey>>();
+        assert_eq!(related, vec![decoded.event_q, decoded.bids, decoded.asks]);
+    }
+
+    #[test]
+    fn reject_unknown_serum_accounts() {
+        let program_id = Pubkey::from_str(OPENBOOK_V1_PROGRAM_PUBKEY).unwrap();
+        let request_queue = serum_account(AccountFlag::Initialized as u64 | AccountFlag::RequestQueue as u64, &[0u8; 64]);
+
+        assert!(RaydiumOpenBookAccount::resolve_account(Pubkey::new_unique(), &request_queue).is_err());
+        assert!(RaydiumOpenBookAccount::resolve_account(Pubkey::new_unique(), &vec![0u8; 8]).is_err());
+        assert!(resolve_pool_config_account(&Market::RAYDIUM, &program_id, Pubkey::new_unique(), &request_queue).is_err());
+    }
+
+    #[test]
+    fn total_without_take_pnl() {
+        let amm = RaydiumOpenBookMarket { quote_need_take_pnl: 2_000_000, ..build_amm(1) };
+        let open_orders = SerumOpenOrders::unpack_data(&open_orders_data(&amm));
+        let event_queue = SerumEventQueue::unpack_data(&event_queue_data(&[(0b1101, amm.open_orders, 100_000_000, 1_000_000)]));
+
+        // the pc the AMM bid paid has left its open orders, the coin is owed to it
+        assert_eq!(Calculator::calc_exact_vault_in_serum(&open_orders, &event_queue, &amm.open_orders), Ok((400_000_000, 11_000_000)));
+        assert_eq!(Calculator::calc_exact_vault_in_serum(&open_orders, &event_queue, &Pubkey::new_unique()), Ok((500_000_000, 10_000_000)));
+        assert_eq!(
+            Calculator::calc_total_without_take_pnl(9_500_000_000, 90_000_000, &open_orders, &amm, &event_queue, &amm.open_orders),
+            Ok((9_898_000_000, 101_000_000))
+        );
+        assert_eq!(Calculator::calc_total_without_take_pnl_no_orderbook(9_500_000_000, 90_000_000, &amm), Ok((9_498_000_000, 90_000_000)));
+        assert!(Calculator::calc_total_without_take_pnl_no_orderbook(1_000_000, 90_000_000, &amm).is_err());
+    }
+
+    #[test]
+    fn swap_with_orderbook() {
+        let amm = build_amm(1);
+        let accounts = build_accounts(&amm);
+        let related = amm.get_swap_related_pubkeys().iter().map(|(_, pubkey)| *pubkey).collect::<Vec<Pubkey>>();
+        assert_eq!(related, vec![amm.base_vault, amm.quote_vault, amm.open_orders]);
+        match &accounts[3] {
+            DeserializedAccount::ConfigAccount(DeserializedConfigAccount::RaydiumOpenBookConfigAccount(RaydiumOpenBookAccount::OpenOrders(account))) => {
+                assert_eq!(account.pubkey, amm.open_orders)
+            }
+            _ => panic!("open orders not resolved")
+        }
+
+        // 2_500 of fee, 997_500 against 101_000_000 coin and 9_898_000_000 pc, the AMM bid is cancelled
+        let quote = amm.swap(&accounts, &amm.base_mint, 1_000_000).unwrap();
+        assert_eq!((quote.amount_out, quote.crossings), (96_798_990, 1));
+        assert_eq!(
+            estimate_hop_compute_units(&amm.get_formula(), quote.crossings),
+            AMM_V4_SWAP_COMPUTE_UNITS + AMM_V4_CANCEL_ORDER_COMPUTE_UNITS
+        );
+
+        let quote = amm.swap(&accounts, &amm.quote_mint, 10_000_000).unwrap();
+        assert_eq!((quote.amount_out, quote.crossings), (101_683, 1));
+
+        assert_eq!(amm.swap(&accounts[..6].to_vec(), &amm.base_mint, 1_000_000), Err("event queue not found"));
+        assert_eq!(amm.swap(&accounts, &Pubkey::new_unique(), 1_000_000), Err("mint not in pool"));
+        assert!(amm.swap(&accounts, &amm.base_mint, 0).is_err());
+    }
+
+    #[test]
+    fn swap_without_orderbook() {
+        // SwapOnly pools keep everything in the vaults
+        let amm = build_amm(6);
+        assert!(amm.is_tradable() && !amm.orderbook_permission());
+        assert_eq!(amm.get_swap_related_pubkeys().len(), 2);
+
+        let accounts = build_accounts(&amm)[..3].to_vec();
+        let quote = amm.swap(&accounts, &amm.base_mint, 1_000_000).unwrap();
+        assert_eq!((quote.amount_out, quote.crossings), (104_115_552, 0));
+    }
+}
@@ -0,0 +1,220 @@
+#[cfg(test)]
+mod raydium_cpmm_test {
+    use std::str::FromStr;
+
+    use base64::Engine;
+    use base64::prelude::BASE64_STANDARD;
+    use solana_sdk::clock::Clock;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::sysvar::clock;
+
+    use crate::constants::{RAYDIUM_CPMM_AMM_CONFIG, RAYDIUM_CPMM_DATA_LEN, RAYDIUM_CPMM_PROGRAM_PUBKEY, TOKEN_2022_PROGRAM_PUBKEY, TOKEN_PROGRAM_PUBKEY};
+    use crate::formula::raydium_cpmm::{get_transfer_fee, swap_base_input, swap_base_output, TransferFee};
+    use crate::formula::test::account_fixture::{clock_account, data_account, pool_account, token_account, AccountFixture};
+    use crate::r#struct::account::{DeserializedAccount, DeserializedConfigAccount};
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::RaydiumCpmmMarket;
+    use crate::r#struct::resolver::{resolve_pool_account, resolve_pool_config_account};
+
+    fn pool_data(market: &RaydiumCpmmMarket) -> Vec<u8> {
+        let mut data = vec![0u8; 8];
+        [
+            market.amm_config, market.pool_creator, market.token_0_vault, market.token_1_vault, market.lp_mint,
+            market.token_0_mint, market.token_1_mint, market.token_0_program, market.token_1_program, market.observation_key
+        ].iter().for_each(|pubkey| data.extend_from_slice(pubkey.as_ref()));
+        data.extend_from_slice(&[market.auth_bump, market.status, 9, 9, 6]);
+        [
+            market.lp_supply, market.protocol_fees_token_0, market.protocol_fees_token_1, market.fund_fees_token_0,
+            market.fund_fees_token_1, market.open_time, market.recent_epoch
+        ].iter().for_each(|value| data.extend_from_slice(&value.to_le_bytes()));
+        data.resize(RAYDIUM_CPMM_DATA_LEN, 0);
+        data
+    }
+
+    fn amm_config_data(trade_fee_rate: u64) -> Vec<u8> {
+        let mut data = vec![0u8; RAYDIUM_CPMM_AMM_CONFIG];
+        data[12..20].copy_from_slice(&trade_fee_rate.to_le_bytes());
+        data
+    }
+
+    fn transfer_fee_mint_data(older: TransferFee, newer: TransferFee) -> Vec<u8> {
+        let mut data = vec![0u8; 165];
+        data.push(1); // AccountType::Mint
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&108u16.to_le_bytes());
+        data.extend_from_slice(&[0u8; 72]);
+        [older, newer].iter().for_each(|fee| {
+            data.extend_from_slice(&fee.epoch.to_le_bytes());
+            data.extend_from_slice(&fee.maximum_fee.to_le_bytes());
+            data.extend_from_slice(&fee.transfer_fee_basis_points.to_le_bytes());
+        });
+        data
+    }
+
+    fn build_market(token_1_program: &str) -> RaydiumCpmmMarket {
+        RaydiumCpmmMarket {
+            amm_config: Pubkey::new_unique(),
+            token_0_vault: Pubkey::new_unique(),
+            token_1_vault: Pubkey::new_unique(),
+            token_0_mint: Pubkey::new_unique(),
+            token_1_mint: Pubkey::new_unique(),
+            token_0_program: Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap(),
+            token_1_program: Pubkey::from_str(token_1_program).unwrap(),
+            observation_key: Pubkey::new_unique(),
+            protocol_fees_token_0: 1_000,
+            fund_fees_token_1: 2_000,
+            recent_epoch: 700,
+            ..RaydiumCpmmMarket::default()
+        }
+    }
+
+    /// Pool, amm config and vaults of `market` holding 1_000_000_000 / 2_000_000_000 after fees, 0.25% trade fee
+    fn build_accounts(market: &RaydiumCpmmMarket) -> Vec<DeserializedAccount> {
+        let program_id = Pubkey::from_str(RAYDIUM_CPMM_PROGRAM_PUBKEY).unwrap();
+
+        vec![
+            pool_account(Pubkey::new_unique(), program_id, pool_data(market), Market::RAYDIUM),
+            DeserializedAccount::ConfigAccount(resolve_pool_config_account(&Market::RAYDIUM, &program_id, market.amm_config, &amm_config_data(2_500)).unwrap()),
+            token_account(market.token_0_vault, 1_000_001_000, Market::RAYDIUM),
+            token_account(market.token_1_vault, 2_000_002_000, Market::RAYDIUM),
+        ]
+    }
+
+    #[test]
+    fn curve_math() {
+        // 0.25% of 1_000_000 is taken before the swap
+        assert_eq!(swap_base_input(1_000_000, 1_000_000_000, 2_000_000_000, 2_500).unwrap(), (2_500, 1_993_011));
+        assert_eq!(swap_base_input(1, 1_000, 1_000, 2_500).unwrap(), (1, 0));
+
+        // the inverse asks for at least what swap_base_input needs to pay out the same amount
+        let (_, amount_in) = swap_base_output(1_993_011, 1_000_000_000, 2_000_000_000, 2_500).unwrap();
+        assert_eq!(amount_in, 1_000_000);
+        assert_eq!(swap_base_input(amount_in, 1_000_000_000, 2_000_000_000, 2_500).unwrap().1, 1_993_011);
+
+        assert!(swap_base_output(2_000_000_000, 1_000_000_000, 2_000_000_000, 2_500).is_err());
+        assert!(swap_base_input(1_000, 1_000_000, 1_000_000, 1_000_000).is_err());
+    }
+
+    #[test]
+    fn transfer_fee_extension() {
+        let older = TransferFee { epoch: 500, maximum_fee: 1_000_000, transfer_fee_basis_points: 100 };
+        let newer = TransferFee { epoch: 800, maximum_fee: 5_000, transfer_fee_basis_points: 50 };
+        let data = transfer_fee_mint_data(older, newer);
+
+        assert_eq!(get_transfer_fee(&data, 700), Some(older));
+        assert_eq!(get_transfer_fee(&data, 800), Some(newer));
+        assert_eq!(get_transfer_fee(&data[..82], 800), None);
+
+        assert_eq!(older.calculate_fee(10_001), 101);
+        assert_eq!(newer.calculate_fee(10_000_000), 5_000);
+        // 10_000 arrives after 1% is withheld from 10_102
+        assert_eq!(older.calculate_inverse_fee(10_000).unwrap(), 102);
+        assert_eq!(newer.calculate_inverse_fee(10_000_000).unwrap(), 5_000);
+    }
+
+    #[test]
+    fn quote_exact_in_and_out() {
+        let market = build_market(TOKEN_PROGRAM_PUBKEY);
+        let accounts = build_accounts(&market);
+        assert_eq!(market.get_swap_related_pubkeys().len(), 3);
+
+        let quote = market.swap(&accounts, &market.token_0_mint, 1_000_000).unwrap();
+        assert_eq!((quote.amount_in, quote.amount_out), (1_000_000, 1_993_011));
+        let quote = market.swap_exact_out(&accounts, &market.token_0_mint, 1_993_011).unwrap();
+        assert_eq!((quote.amount_in, quote.amount_out), (1_000_000, 1_993_011));
+
+        let quote = market.swap(&accounts, &market.token_1_mint, 2_000_000).unwrap();
+        assert_eq!(quote.amount_out, 996_505);
+        assert!(market.swap(&accounts, &Pubkey::new_unique(), 1_000).is_err());
+        assert!(market.swap(&accounts[..3].to_vec(), &market.token_0_mint, 1_000).is_err());
+
+        // from epoch 800 mint 1 withholds 1% on its way out of the pool, the pool last swapped in epoch 700
+        let market = build_market(TOKEN_2022_PROGRAM_PUBKEY);
+        let mut accounts = build_accounts(&market);
+        let older = TransferFee { epoch: 0, maximum_fee: u64::MAX, transfer_fee_basis_points: 0 };
+        let newer = TransferFee { epoch: 800, maximum_fee: u64::MAX, transfer_fee_basis_points: 100 };
+        accounts.push(data_account(market.token_1_mint, transfer_fee_mint_data(older, newer), Market::RAYDIUM));
+        let related_pubkeys = market.get_swap_related_pubkeys().into_iter().map(|(_, pubkey)| pubkey).collect::<Vec<Pubkey>>();
+        assert!(related_pubkeys.contains(&clock::id()));
+        assert_eq!(*related_pubkeys.last().unwrap(), market.token_1_mint);
+        assert_eq!(market.swap(&accounts, &market.token_0_mint, 1_000_000).unwrap_err(), "clock not found");
+
+        accounts.push(clock_account(Clock { epoch: 800, ..Clock::default() }, Market::RAYDIUM));
+
+        let quote = market.swap(&accounts, &market.token_0_mint, 1_000_000).unwrap();
+        assert_eq!(quote.amount_out, 1_993_011 - 19_931);
+        let quote = market.swap_exact_out(&accounts, &market.token_0_mint, 1_993_011 - 19_931).unwrap();
+        assert!(quote.amount_in <= 1_000_000);
+        assert!(market.swap(&accounts, &market.token_0_mint, quote.amount_in).unwrap().amount_out >= 1_993_011 - 19_931);
+    }
+
+    #[test]
+    fn resolve_and_build_swap_instruction() {
+        let market = build_market(TOKEN_2022_PROGRAM_PUBKEY);
+        let program_id = Pubkey::from_str(RAYDIUM_CPMM_PROGRAM_PUBKEY).unwrap();
+        let pool = Pubkey::new_unique();
+        let (payer, input_account, output_account) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let resolved = resolve_pool_account(&Market::RAYDIUM, &pool_data(&market));
+        let resolved = resolved.as_any().downcast_ref::<RaydiumCpmmMarket>().unwrap();
+        assert_eq!(resolved.token_1_program, market.token_1_program);
+        assert_eq!(resolved.fund_fees_token_1, 2_000);
+        assert!(resolved.is_tradable());
+        match resolve_pool_config_account(&Market::RAYDIUM, &program_id, market.amm_config, &amm_config_data(2_500)).unwrap() {
+            DeserializedConfigAccount::RaydiumCpmmConfigAccount(account) => assert_eq!(account.get_pubkey(), market.amm_config),
+            _ => panic!("not a cpmm config")
+        }
+
+        let instruction = market.get_swap_instruction(&pool, &payer, &input_account, &output_account, &market.token_1_mint, 500, 1_000, false).unwrap();
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.data.len(), 24);
+        assert_eq!(instruction.data[8..16], 1_000u64.to_le_bytes());
+        assert_eq!(instruction.data[16..24], 500u64.to_le_bytes());
+
+        let accounts = instruction.accounts.iter().map(|meta| meta.pubkey).collect::<Vec<Pubkey>>();
+        assert_eq!(accounts, vec![
+            payer, RaydiumCpmmMarket::authority(&program_id), market.amm_config, pool, input_account, output_account,
+            market.token_1_vault, market.token_0_vault, market.token_1_program, market.token_0_program,
+            market.token_1_mint, market.token_0_mint, market.observation_key
+        ]);
+        assert!(instruction.accounts[0].is_signer);
+
+        let base_input = market.get_swap_instruction(&pool, &payer, &input_account, &output_account, &market.token_0_mint, 500, 1_000, true).unwrap();
+        assert_ne!(base_input.data[..8], instruction.data[..8]);
+        assert_eq!(base_input.data[8..16], 500u64.to_le_bytes());
+        assert!(market.get_swap_instruction(&pool, &payer, &input_account, &output_account, &Pubkey::new_unique(), 500, 1_000, true).is_err());
+    }
+
+    #[test]
+    fn replay_through_account_fixture() {
+        // the same state as quote_exact_in_and_out, in the format of a recorded fixture
+        let market = build_market(TOKEN_PROGRAM_PUBKEY);
+        let (pool, program_id) = (Pubkey::new_unique(), RAYDIUM_CPMM_PROGRAM_PUBKEY);
+        let vault_data = |amount: u64| {
+            let mut data = vec![0u8; 165];
+            data[64..72].copy_from_slice(&amount.to_le_bytes());
+            data[108] = 1;
+            data
+        };
+        let dump = |pubkey: Pubkey, owner: &str, data: Vec<u8>| {
+            format!(
+                r#"{{ "pubkey": "{}", "account": {{ "lamports": 0, "data": ["{}", "base64"], "owner": "{}", "executable": false, "rentEpoch": 0, "space": {} }} }}"#,
+                pubkey, BASE64_STANDARD.encode(&data), owner, data.len()
+            )
+        };
+        let accounts = [
+            dump(pool, program_id, pool_data(&market)),
+            dump(market.amm_config, program_id, amm_config_data(2_500)),
+            dump(market.token_0_vault, TOKEN_PROGRAM_PUBKEY, vault_data(1_000_001_000)),
+            dump(market.token_1_vault, TOKEN_PROGRAM_PUBKEY, vault_data(2_000_002_000)),
+        ];
+        let json = format!(
+            r#"{{ "market": "RAYDIUM", "pool": "{}", "accounts": [{}], "swaps": [{{ "amount": 1000000, "a_to_b": true, "amount_specified_is_input": true, "amount_in": 1000000, "amount_out": 1993011 }}] }}"#,
+            pool, accounts.join(", ")
+        );
+
+        let fixture = AccountFixture::from_json(&json).unwrap();
+        let swap = &fixture.swaps[0];
+        assert_eq!(fixture.quote(swap).unwrap(), (swap.amount_in, swap.amount_out));
+    }
+}
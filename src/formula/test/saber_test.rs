@@ -0,0 +1,198 @@
+#[cfg(test)]
+mod saber_test {
+    use std::str::FromStr;
+
+    use solana_sdk::clock::Clock;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::constants::{SABER_PROGRAM_PUBKEY, SABER_SWAP_INFO_DATA_LEN, TOKEN_PROGRAM_PUBKEY};
+    use crate::executor::fee::{estimate_hop_compute_units, STABLE_SWAP_COMPUTE_UNITS};
+    use crate::formula::saber::quote_exact_in;
+    use crate::formula::stable_swap;
+    use crate::formula::test::account_fixture::{clock_account, token_account};
+    use crate::r#struct::account::DeserializedAccount;
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::{SaberFees, SaberMarket};
+    use crate::r#struct::resolver::resolve_pool_account;
+
+    fn market_data(pool: &SaberMarket) -> Vec<u8> {
+        let mut data = vec![0u8; SABER_SWAP_INFO_DATA_LEN];
+        data[0] = pool.is_initialized as u8;
+        data[1] = pool.is_paused as u8;
+        data[2] = pool.nonce;
+        data[3..11].copy_from_slice(&pool.initial_amp_factor.to_le_bytes());
+        data[11..19].copy_from_slice(&pool.target_amp_factor.to_le_bytes());
+        data[19..27].copy_from_slice(&pool.start_ramp_ts.to_le_bytes());
+        data[27..35].copy_from_slice(&pool.stop_ramp_ts.to_le_bytes());
+        [
+            pool.token_a, pool.token_b, pool.pool_mint, pool.token_a_mint,
+            pool.token_b_mint, pool.admin_fee_key_a, pool.admin_fee_key_b
+        ].iter().enumerate().for_each(|(i, pubkey)| {
+            data[107 + i * 32..139 + i * 32].copy_from_slice(pubkey.as_ref());
+        });
+        let fees = &pool.fees;
+        [
+            fees.admin_trade_fee_numerator, fees.admin_trade_fee_denominator,
+            fees.admin_withdraw_fee_numerator, fees.admin_withdraw_fee_denominator,
+            fees.trade_fee_numerator, fees.trade_fee_denominator,
+            fees.withdraw_fee_numerator, fees.withdraw_fee_denominator
+        ].iter().enumerate().for_each(|(i, value)| {
+            data[331 + i * 8..339 + i * 8].copy_from_slice(&value.to_le_bytes());
+        });
+        data
+    }
+
+    fn build_pool() -> SaberMarket {
+        SaberMarket {
+            is_initialized: true,
+            nonce: 255,
+            initial_amp_factor: 100,
+            target_amp_factor: 100,
+            token_a: Pubkey::new_unique(),
+            token_b: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            admin_fee_key_a: Pubkey::new_unique(),
+            admin_fee_key_b: Pubkey::new_unique(),
+            fees: SaberFees {
+                admin_trade_fee_numerator: 50,
+                admin_trade_fee_denominator: 100,
+                trade_fee_numerator: 4,
+                trade_fee_denominator: 10_000,
+                ..SaberFees::default()
+            },
+            ..SaberMarket::default()
+        }
+    }
+
+    fn build_accounts(pool: &SaberMarket, amount_a: u64, amount_b: u64, unix_timestamp: i64) -> Vec<DeserializedAccount> {
+        vec![
+            token_account(pool.token_a, amount_a, Market::SABER),
+            token_account(pool.token_b, amount_b, Market::SABER),
+            clock_account(Clock { unix_timestamp, ..Clock::default() }, Market::SABER),
+        ]
+    }
+
+    #[test]
+    fn ramp_amp_factor() {
+        // ramping up from 100 to 200 over 1000 seconds
+        assert_eq!(stable_swap::compute_amp_factor(100, 200, 1_000, 2_000, 1_000), Some(100));
+        assert_eq!(stable_swap::compute_amp_factor(100, 200, 1_000, 2_000, 1_500), Some(150));
+        assert_eq!(stable_swap::compute_amp_factor(100, 200, 1_000, 2_000, 2_000), Some(200));
+        assert_eq!(stable_swap::compute_amp_factor(100, 200, 1_000, 2_000, 5_000), Some(200));
+        // ramping down
+        assert_eq!(stable_swap::compute_amp_factor(200, 100, 1_000, 2_000, 1_250), Some(175));
+        // a ramp not started yet stays at the initial amp
+        assert_eq!(stable_swap::compute_amp_factor(100, 200, 1_000, 2_000, 500), Some(100));
+    }
+
+    #[test]
+    fn newton_iteration_keeps_invariant() {
+        let (amount_a, amount_b) = (1_000_000_000_000u128, 3_000_000_000_000u128);
+        let d = stable_swap::compute_d(100, amount_a, amount_b).unwrap();
+        // D lies between the geometric and arithmetic sums of an imbalanced pool
+        assert!(d < ruint::aliases::U256::from(amount_a + amount_b) && d > ruint::aliases::U256::from(2 * 1_732_050_807_568u128));
+
+        // the balances after a swap keep the invariant, up to the rounding of both iterations
+        let x = amount_a + 1_000_000_000;
+        let y = stable_swap::compute_y(100, x, d).unwrap();
+        let new_d = stable_swap::compute_d(100, x, u128::try_from(y).unwrap()).unwrap();
+        let diff = if new_d > d { new_d - d } else { d - new_d };
+        assert!(diff < ruint::aliases::U256::from(1_000u32), "{} {}", new_d, d);
+
+        // an empty side has no invariant
+        assert!(stable_swap::compute_d(100, amount_a, 0).is_none());
+    }
+
+    #[test]
+    fn decode_swap_info() {
+        let mut pool = build_pool();
+        pool.start_ramp_ts = 1_000;
+        pool.stop_ramp_ts = 2_000;
+        pool.target_amp_factor = 200;
+        let decoded = resolve_pool_account(&Market::SABER, &market_data(&pool));
+        let decoded = decoded.as_any().downcast_ref::<SaberMarket>().unwrap();
+
+        assert!(decoded.is_tradable());
+        assert_eq!(decoded.nonce, 255);
+        assert_eq!((decoded.initial_amp_factor, decoded.target_amp_factor), (100, 200));
+        assert_eq!((decoded.start_ramp_ts, decoded.stop_ramp_ts), (1_000, 2_000));
+        assert_eq!(decoded.pool_mint, pool.pool_mint);
+        assert_eq!(decoded.token_b_mint, pool.token_b_mint);
+        assert_eq!(decoded.admin_fee_key_b, pool.admin_fee_key_b);
+        assert_eq!(decoded.fees.trade_fee_denominator, 10_000);
+        assert_eq!(decoded.get_fee_rate(), Some(400));
+        assert_eq!(decoded.get_mint_pair().pubkey_a, pool.token_a_mint);
+
+        pool.is_paused = true;
+        let paused = resolve_pool_account(&Market::SABER, &market_data(&pool));
+        assert!(!paused.is_tradable());
+    }
+
+    #[test]
+    fn quote_through_pool_operation() {
+        let pool = build_pool();
+        let accounts = build_accounts(&pool, 1_000_000_000_000, 1_000_000_000_000, 0);
+
+        let quote = pool.swap(&accounts, &pool.token_a_mint, 1_000_000_000).unwrap();
+        let dy = stable_swap::swap_to(100, 1_000_000_000, 1_000_000_000_000, 1_000_000_000_000).unwrap() as u64;
+        assert_eq!(quote.amount_out, dy - dy * 4 / 10_000);
+        // a balanced pool trades close to 1:1 before the 4 bps fee
+        assert!(dy < 1_000_000_000 && dy > 999_990_000, "{}", dy);
+        assert_eq!(quote.crossings, 0);
+        assert_eq!(pool.fees.admin_trade_fee(dy * 4 / 10_000).unwrap(), dy * 4 / 10_000 / 2);
+
+        // the reverse direction reads the reserves the other way round
+        let accounts = build_accounts(&pool, 1_200_000_000_000, 800_000_000_000, 0);
+        let a_to_b = pool.swap(&accounts, &pool.token_a_mint, 1_000_000_000).unwrap();
+        let b_to_a = pool.swap(&accounts, &pool.token_b_mint, 1_000_000_000).unwrap();
+        assert!(a_to_b.amount_out < b_to_a.amount_out);
+
+        assert_eq!(pool.swap(&accounts, &Pubkey::new_unique(), 1_000).unwrap_err(), "mint not in pool");
+        assert_eq!(estimate_hop_compute_units(&pool.get_formula(), quote.crossings), STABLE_SWAP_COMPUTE_UNITS);
+    }
+
+    #[test]
+    fn quote_follows_amp_ramp() {
+        let mut pool = build_pool();
+        pool.initial_amp_factor = 10;
+        pool.target_amp_factor = 1_000;
+        pool.start_ramp_ts = 0;
+        pool.stop_ramp_ts = 1_000;
+
+        // a higher amp keeps an imbalanced pool closer to 1:1
+        let early = quote_exact_in(&pool, 1_000_000, 2_000_000_000, 500_000_000, 0).unwrap();
+        let late = quote_exact_in(&pool, 1_000_000, 2_000_000_000, 500_000_000, 1_000).unwrap();
+        assert!(early < late, "{} {}", early, late);
+
+        pool.is_paused = true;
+        assert_eq!(quote_exact_in(&pool, 1_000_000, 2_000_000_000, 500_000_000, 0).unwrap_err(), "pool paused");
+    }
+
+    #[test]
+    fn build_swap_instruction() {
+        let pool = build_pool();
+        let program_id = Pubkey::from_str(SABER_PROGRAM_PUBKEY).unwrap();
+        // find a swap info whose nonce 255 derives a valid authority
+        let (swap_info, authority) = (0..).map(|_| Pubkey::new_unique()).find_map(|swap_info| {
+            Pubkey::create_program_address(&[swap_info.as_ref(), &[255]], &program_id).ok().map(|authority| (swap_info, authority))
+        }).unwrap();
+        let (user, source, destination) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let ix = pool.get_swap_instruction(&swap_info, &user, &source, &destination, &pool.token_b_mint, 1_000, 990).unwrap();
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(ix.data[0], 1);
+        assert_eq!(u64::from_le_bytes(ix.data[1..9].try_into().unwrap()), 1_000);
+        assert_eq!(u64::from_le_bytes(ix.data[9..17].try_into().unwrap()), 990);
+        assert_eq!(ix.accounts.len(), 9);
+        assert_eq!(ix.accounts[1].pubkey, authority);
+        assert!(ix.accounts[2].is_signer);
+        assert_eq!(ix.accounts[4].pubkey, pool.token_b);
+        assert_eq!(ix.accounts[5].pubkey, pool.token_a);
+        assert_eq!(ix.accounts[7].pubkey, pool.admin_fee_key_a);
+        assert_eq!(ix.accounts[8].pubkey, Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap());
+
+        assert!(pool.get_swap_instruction(&swap_info, &user, &source, &destination, &Pubkey::new_unique(), 1_000, 990).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+#[cfg(test)]
+mod sanctum_test {
+    use std::str::FromStr;
+
+    use solana_sdk::clock::Clock;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::constants::{MARINADE_STATE_PUBKEY, NATIVE_MINT_PUBKEY, SANCTUM_FLAT_FEE_PROGRAM_PUBKEY, SANCTUM_MARINADE_CALCULATOR_PUBKEY, SANCTUM_SPL_CALCULATOR_PUBKEYS, SANCTUM_WSOL_CALCULATOR_PUBKEY};
+    use crate::formula::sanctum::{lst_to_sol, sol_to_lst, SolValueCalculatorState};
+    use crate::formula::test::account_fixture::{clock_account, data_account, mint_account, token_account};
+    use crate::r#struct::account::DeserializedAccount;
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::{MarinadeState, SanctumCalculator, SanctumInfinityMarket, SplStakePool, StakePoolFee};
+    use crate::r#struct::resolver::resolve_pool_account;
+
+    struct Lst {
+        mint: Pubkey,
+        calculator: Pubkey,
+        is_input_disabled: bool,
+    }
+
+    fn pool_state_data(total_sol_value: u64, lp_token_mint: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; 176];
+        data[0..8].copy_from_slice(&total_sol_value.to_le_bytes());
+        data[8..10].copy_from_slice(&10u16.to_le_bytes());
+        // rebalancing until a test clears it
+        data[14] = 1;
+        data[112..144].copy_from_slice(Pubkey::from_str(SANCTUM_FLAT_FEE_PROGRAM_PUBKEY).unwrap().as_ref());
+        data[144..176].copy_from_slice(lp_token_mint.as_ref());
+        data
+    }
+
+    fn lst_state_list_data(lsts: &[Lst]) -> Vec<u8> {
+        lsts.iter().flat_map(|lst| {
+            let mut data = vec![0u8; 80];
+            data[0] = lst.is_input_disabled as u8;
+            data[16..48].copy_from_slice(lst.mint.as_ref());
+            data[48..80].copy_from_slice(lst.calculator.as_ref());
+            data
+        }).collect()
+    }
+
+    fn marinade_data(delayed_unstake_fee: u32) -> Vec<u8> {
+        // 10_000 SOL backing 8_000 mSOL
+        let mut data = vec![0u8; 700];
+        data[376..384].copy_from_slice(&9_000_000_000_000u64.to_le_bytes());
+        data[496..504].copy_from_slice(&1_000_000_000_000u64.to_le_bytes());
+        data[504..512].copy_from_slice(&8_000_000_000_000u64.to_le_bytes());
+        data[609..613].copy_from_slice(&delayed_unstake_fee.to_le_bytes());
+        data
+    }
+
+    fn fee_data(input_fee_bps: i16, output_fee_bps: i16) -> Vec<u8> {
+        let mut data = vec![0u8; 8];
+        data[0..2].copy_from_slice(&input_fee_bps.to_le_bytes());
+        data[2..4].copy_from_slice(&output_fee_bps.to_le_bytes());
+        data
+    }
+
+    fn build_market(lsts: &[Lst], lp_mint: &Pubkey) -> Vec<SanctumInfinityMarket> {
+        let pool = resolve_pool_account(&Market::SANCTUM, &pool_state_data(1_000_000_000_000, lp_mint));
+        let pool = pool.as_any().downcast_ref::<SanctumInfinityMarket>().unwrap();
+        pool.get_edges(&lst_state_list_data(lsts), &[])
+    }
+
+    fn build_accounts(lsts: &[Lst], lp_mint: &Pubkey, reserves: u64) -> Vec<DeserializedAccount> {
+        let flat_fee = Pubkey::from_str(SANCTUM_FLAT_FEE_PROGRAM_PUBKEY).unwrap();
+        let pool_state = SanctumInfinityMarket::pool_state();
+        let mut pricing_state = vec![0u8; 34];
+        pricing_state[32..34].copy_from_slice(&10u16.to_le_bytes());
+
+        let mut accounts = vec![
+            data_account(SanctumInfinityMarket::lst_state_list(), lst_state_list_data(lsts), Market::SANCTUM),
+            data_account(SanctumInfinityMarket::pricing_state(&flat_fee), pricing_state, Market::SANCTUM),
+            mint_account(*lp_mint, 900_000_000_000, Market::SANCTUM),
+            clock_account(Clock { epoch: 500, ..Clock::default() }, Market::SANCTUM),
+            data_account(Pubkey::from_str(MARINADE_STATE_PUBKEY).unwrap(), marinade_data(100), Market::SANCTUM),
+        ];
+        // 1 bps in and 2 bps out on every LST
+        lsts.iter().for_each(|lst| {
+            accounts.push(token_account(SanctumInfinityMarket::pool_reserves(&pool_state, &lst.mint), reserves, Market::SANCTUM));
+            accounts.push(data_account(SanctumInfinityMarket::fee_account(&flat_fee, &lst.mint), fee_data(1, 2), Market::SANCTUM));
+        });
+        accounts
+    }
+
+    fn wsol_and_msol(msol_input_disabled: bool) -> Vec<Lst> {
+        vec![
+            Lst { mint: Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap(), calculator: Pubkey::from_str(SANCTUM_WSOL_CALCULATOR_PUBKEY).unwrap(), is_input_disabled: false },
+            Lst { mint: Pubkey::new_unique(), calculator: Pubkey::from_str(SANCTUM_MARINADE_CALCULATOR_PUBKEY).unwrap(), is_input_disabled: msol_input_disabled },
+        ]
+    }
+
+    #[test]
+    fn decode_pool_and_edges() {
+        let lp_mint = Pubkey::new_unique();
+        let jitosol = Pubkey::new_unique();
+        let stake_pool = Pubkey::new_unique();
+        let mut lsts = wsol_and_msol(false);
+        lsts.push(Lst { mint: jitosol, calculator: Pubkey::from_str(SANCTUM_SPL_CALCULATOR_PUBKEYS[0]).unwrap(), is_input_disabled: false });
+        // a calculator the adapter does not know leaves the LST out
+        lsts.push(Lst { mint: Pubkey::new_unique(), calculator: Pubkey::new_unique(), is_input_disabled: false });
+
+        let pool = resolve_pool_account(&Market::SANCTUM, &pool_state_data(1_000_000_000_000, &lp_mint));
+        let pool = pool.as_any().downcast_ref::<SanctumInfinityMarket>().unwrap();
+        assert_eq!(pool.pool.total_sol_value, 1_000_000_000_000);
+        assert_eq!(pool.pool.trading_protocol_fee_bps, 10);
+        assert_eq!(pool.pool.is_rebalancing, 1);
+        assert_eq!(pool.pool.lp_token_mint, lp_mint);
+        assert!(pool.is_tradable());
+
+        let stake_pools = [(stake_pool, SplStakePool { pool_mint: jitosol, ..SplStakePool::default() })];
+        let edges = pool.get_edges(&lst_state_list_data(&lsts), &stake_pools);
+        // 3 LSTs against the LP token and 3 pairs between them
+        assert_eq!(edges.len(), 6);
+        assert_eq!(edges.iter().filter(|edge| edge.get_mint_pair().any(lp_mint)).count(), 3);
+        assert!(edges.iter().any(|edge| edge.get_mint_pair().all(jitosol, lsts[1].mint)));
+        assert!(edges[0].lsts.iter().any(|lst| lst.state.mint == jitosol && lst.calculator == SanctumCalculator::SplStakePool(stake_pool)));
+        // program addresses are derived with the edges
+        let jitosol_lst = edges[0].lsts.iter().find(|lst| lst.state.mint == jitosol).unwrap();
+        assert_eq!(jitosol_lst.reserves, SanctumInfinityMarket::pool_reserves(&SanctumInfinityMarket::pool_state(), &jitosol));
+        assert_eq!(jitosol_lst.fee_account, SanctumInfinityMarket::fee_account(&pool.pool.pricing_program, &jitosol));
+        assert_eq!(edges[0].pricing_state_pubkey, SanctumInfinityMarket::pricing_state(&pool.pool.pricing_program));
+
+        // every edge watches the accounts of all LSTs, the stake pool of jitoSOL among them
+        let related = edges[0].get_swap_related_pubkeys();
+        assert_eq!(related.len(), edges[5].get_swap_related_pubkeys().len());
+        assert!(related.iter().any(|(_, pubkey)| *pubkey == stake_pool));
+
+        // without its stake pool jitoSOL cannot be priced
+        assert_eq!(pool.get_edges(&lst_state_list_data(&lsts), &[]).len(), 3);
+    }
+
+    #[test]
+    fn sol_value_calculators() {
+        let pool = SplStakePool {
+            total_lamports: 1_100_000_000_000,
+            pool_token_supply: 1_000_000_000_000,
+            last_update_epoch: 500,
+            stake_withdrawal_fee: StakePoolFee { denominator: 1_000, numerator: 1 },
+            ..SplStakePool::default()
+        };
+        let spl = SolValueCalculatorState::SplStakePool(pool);
+        // valued as a stake withdrawal, the fee rounds up
+        assert_eq!(lst_to_sol(&spl, 1_000_000_000, 500).unwrap(), 1_098_900_000);
+        assert_eq!(sol_to_lst(&spl, 1_098_900_000, 500).unwrap(), 1_000_000_000);
+        assert_eq!(lst_to_sol(&spl, 1_000_000_000, 501).unwrap_err(), "stake pool not updated");
+
+        let marinade = SolValueCalculatorState::Marinade(MarinadeState {
+            total_active_balance: 9_000_000_000_000,
+            available_reserve_balance: 1_000_000_000_000,
+            msol_supply: 8_000_000_000_000,
+            delayed_unstake_fee: 100,
+            ..MarinadeState::default()
+        });
+        // 1 bp delayed unstake fee
+        assert_eq!(lst_to_sol(&marinade, 1_000_000_000, 500).unwrap(), 1_249_875_000);
+        assert_eq!(sol_to_lst(&marinade, 1_249_875_000, 500).unwrap(), 1_000_000_000);
+        assert_eq!(lst_to_sol(&SolValueCalculatorState::Wsol, 1_000, 500).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn swap_through_pool_operation() {
+        let lp_mint = Pubkey::new_unique();
+        let lsts = wsol_and_msol(false);
+        let (wsol, msol) = (lsts[0].mint, lsts[1].mint);
+        let edges = build_market(&lsts, &lp_mint);
+        let accounts = build_accounts(&lsts, &lp_mint, 1_000_000_000_000);
+        let edge = |pubkey_a: Pubkey, pubkey_b: Pubkey| edges.iter().find(|edge| edge.get_mint_pair().all(pubkey_a, pubkey_b)).unwrap();
+
+        // no swap goes through while the pool rebalances
+        let mut swap = edge(wsol, msol).clone();
+        assert_eq!(swap.swap(&accounts, &wsol, 1_000_000_000).unwrap_err(), "pool rebalancing");
+        swap.pool.is_rebalancing = 0;
+
+        // 1 bps input fee of wSOL and 2 bps output fee of mSOL, then priced at the delayed unstake value of mSOL
+        let quote = swap.swap(&accounts, &wsol, 1_000_000_000).unwrap();
+        assert_eq!(quote.amount_out, 799_839_983);
+
+        let mut add = edge(wsol, lp_mint).clone();
+        add.pool.is_rebalancing = 0;
+        // 900 LP tokens for 1_000 SOL in the pool
+        assert_eq!(add.swap(&accounts, &wsol, 1_000_000_000).unwrap().amount_out, 900_000_000);
+        // 10 bps LP withdrawal fee
+        assert_eq!(add.swap(&accounts, &lp_mint, 900_000_000).unwrap().amount_out, 999_000_000);
+
+        let small_reserves = build_accounts(&lsts, &lp_mint, 1_000);
+        assert_eq!(swap.swap(&small_reserves, &wsol, 1_000_000_000).unwrap_err(), "insufficient reserves");
+        assert_eq!(swap.swap(&accounts, &Pubkey::new_unique(), 1_000).unwrap_err(), "mint not in pool");
+
+        let lsts = wsol_and_msol(true);
+        let disabled = build_accounts(&lsts, &lp_mint, 1_000_000_000_000);
+        let mut swap = build_market(&lsts, &lp_mint).into_iter().find(|edge| edge.get_mint_pair().all(lsts[0].mint, lsts[1].mint)).unwrap();
+        swap.pool.is_rebalancing = 0;
+        assert_eq!(swap.swap(&disabled, &lsts[1].mint, 1_000_000_000).unwrap_err(), "input disabled");
+        assert!(swap.swap(&disabled, &lsts[0].mint, 1_000_000_000).is_ok());
+    }
+}
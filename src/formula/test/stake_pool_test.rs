@@ -0,0 +1,258 @@
+#[cfg(test)]
+mod stake_pool_test {
+    use std::collections::{HashMap, HashSet};
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    use solana_sdk::account::Account;
+    use solana_sdk::clock::Clock;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::sysvar::clock;
+
+    use crate::config::PoolFilter;
+    use crate::constants::NATIVE_MINT_PUBKEY;
+    use crate::formula::base::Formula;
+    use crate::formula::stake_pool::{quote_deposit_sol, quote_marinade_deposit, quote_marinade_liquid_unstake, quote_withdraw_sol};
+    use crate::formula::test::account_fixture::clock_account;
+    use crate::path::{CycleIndex, PathFinder};
+    use crate::r#struct::account::{DeserializedAccount, DeserializedDataAccount, DeserializedPoolAccount};
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::{MarinadeFee, MarinadeState, SaberMarket, SplStakePool, StakePoolFee};
+    use crate::r#struct::resolver::resolve_pool_account;
+
+    const RENT_EXEMPT_RESERVE: u64 = 2_282_880;
+    const RENT_EXEMPT_FOR_TOKEN_ACC: u64 = 2_039_280;
+
+    fn write_fee(data: &mut Vec<u8>, fee: &StakePoolFee) {
+        data.extend_from_slice(&fee.denominator.to_le_bytes());
+        data.extend_from_slice(&fee.numerator.to_le_bytes());
+    }
+
+    fn stake_pool_data(pool: &SplStakePool) -> Vec<u8> {
+        let mut data = vec![0u8; 346];
+        data[0] = pool.account_type;
+        data[130..162].copy_from_slice(pool.reserve_stake.as_ref());
+        data[162..194].copy_from_slice(pool.pool_mint.as_ref());
+        data[258..266].copy_from_slice(&pool.total_lamports.to_le_bytes());
+        data[266..274].copy_from_slice(&pool.pool_token_supply.to_le_bytes());
+        data[274..282].copy_from_slice(&pool.last_update_epoch.to_le_bytes());
+        data[330..338].copy_from_slice(&100u64.to_le_bytes());
+        data[338..346].copy_from_slice(&5u64.to_le_bytes());
+
+        // next epoch fee taking effect in two epochs, then a preferred deposit validator
+        data.push(2);
+        write_fee(&mut data, &StakePoolFee { denominator: 100, numerator: 6 });
+        data.push(1);
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.push(0);
+        write_fee(&mut data, &pool.stake_deposit_fee);
+        write_fee(&mut data, &pool.stake_withdrawal_fee);
+        data.push(0);
+        data.push(pool.stake_referral_fee);
+        data.push(0);
+        write_fee(&mut data, &pool.sol_deposit_fee);
+        data.push(pool.sol_referral_fee);
+        data.push(0);
+        write_fee(&mut data, &pool.sol_withdrawal_fee);
+        data.push(0);
+        data.extend_from_slice(&pool.last_epoch_pool_token_supply.to_le_bytes());
+        data.extend_from_slice(&pool.last_epoch_total_lamports.to_le_bytes());
+        data
+    }
+
+    fn build_stake_pool() -> SplStakePool {
+        SplStakePool {
+            account_type: 1,
+            reserve_stake: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            total_lamports: 1_100_000_000_000,
+            pool_token_supply: 1_000_000_000_000,
+            last_update_epoch: 500,
+            sol_deposit_fee: StakePoolFee { denominator: 1_000, numerator: 1 },
+            sol_withdrawal_fee: StakePoolFee { denominator: 1_000, numerator: 3 },
+            sol_referral_fee: 50,
+            last_epoch_pool_token_supply: 990_000_000_000,
+            last_epoch_total_lamports: 1_080_000_000_000,
+            ..SplStakePool::default()
+        }
+    }
+
+    fn lamports_account(pubkey: Pubkey, lamports: u64, data: Vec<u8>, market: Market) -> DeserializedAccount {
+        DeserializedAccount::Account(DeserializedDataAccount {
+            pubkey,
+            account: Account { lamports, data, ..Account::default() },
+            market,
+        })
+    }
+
+    fn reserve_data() -> Vec<u8> {
+        // StakeStateV2::Initialized with its rent-exempt reserve
+        let mut data = vec![0u8; 200];
+        data[0] = 1;
+        data[4..12].copy_from_slice(&RENT_EXEMPT_RESERVE.to_le_bytes());
+        data
+    }
+
+    fn marinade_data(state: &MarinadeState) -> Vec<u8> {
+        let mut data = vec![0u8; 700];
+        data[8..40].copy_from_slice(state.msol_mint.as_ref());
+        data[138..146].copy_from_slice(&state.rent_exempt_for_token_acc.to_le_bytes());
+        data[376..384].copy_from_slice(&state.total_active_balance.to_le_bytes());
+        data[452..460].copy_from_slice(&state.lp_liquidity_target.to_le_bytes());
+        data[460..464].copy_from_slice(&state.lp_max_fee.basis_points.to_le_bytes());
+        data[464..468].copy_from_slice(&state.lp_min_fee.basis_points.to_le_bytes());
+        data[496..504].copy_from_slice(&state.available_reserve_balance.to_le_bytes());
+        data[504..512].copy_from_slice(&state.msol_supply.to_le_bytes());
+        data[544..552].copy_from_slice(&state.min_deposit.to_le_bytes());
+        data[560..568].copy_from_slice(&state.staking_sol_cap.to_le_bytes());
+        data[608] = state.paused as u8;
+        data
+    }
+
+    fn build_marinade() -> MarinadeState {
+        MarinadeState {
+            msol_mint: Pubkey::new_unique(),
+            rent_exempt_for_token_acc: RENT_EXEMPT_FOR_TOKEN_ACC,
+            total_active_balance: 9_000_000_000_000,
+            available_reserve_balance: 1_000_000_000_000,
+            msol_supply: 8_000_000_000_000,
+            lp_liquidity_target: 10_000_000_000_000,
+            lp_max_fee: MarinadeFee { basis_points: 300 },
+            lp_min_fee: MarinadeFee { basis_points: 30 },
+            min_deposit: 1_000,
+            staking_sol_cap: u64::MAX,
+            ..MarinadeState::default()
+        }
+    }
+
+    #[test]
+    fn decode_stake_pool() {
+        let pool = build_stake_pool();
+        let decoded = resolve_pool_account(&Market::STAKE_POOL, &stake_pool_data(&pool));
+        let decoded = decoded.as_any().downcast_ref::<SplStakePool>().unwrap();
+
+        assert!(decoded.is_tradable());
+        assert_eq!(decoded.get_formula(), Formula::StakePool);
+        assert_eq!(decoded.reserve_stake, pool.reserve_stake);
+        assert_eq!(decoded.get_mint_pair().pubkey_a, Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap());
+        assert_eq!(decoded.get_mint_pair().pubkey_b, pool.pool_mint);
+        assert_eq!((decoded.total_lamports, decoded.pool_token_supply), (1_100_000_000_000, 1_000_000_000_000));
+        assert_eq!(decoded.epoch_fee, StakePoolFee { denominator: 100, numerator: 5 });
+        assert_eq!(decoded.next_epoch_fee, Some(StakePoolFee { denominator: 100, numerator: 6 }));
+        assert!(decoded.preferred_deposit_validator_vote_address.is_some());
+        assert!(decoded.preferred_withdraw_validator_vote_address.is_none());
+        assert_eq!(decoded.sol_deposit_fee, pool.sol_deposit_fee);
+        assert_eq!(decoded.sol_referral_fee, 50);
+        assert_eq!(decoded.sol_withdrawal_fee, pool.sol_withdrawal_fee);
+        assert_eq!(decoded.last_epoch_total_lamports, 1_080_000_000_000);
+    }
+
+    #[test]
+    fn truncated_stake_pool_is_not_tradable() {
+        let pool = build_stake_pool();
+        let mut data = stake_pool_data(&pool);
+        data.truncate(400);
+
+        let decoded = resolve_pool_account(&Market::STAKE_POOL, &data);
+        assert!(!decoded.is_tradable());
+        let accounts = vec![clock_account(Clock { epoch: 500, ..Clock::default() }, Market::STAKE_POOL)];
+        assert_eq!(decoded.swap(&accounts, &pool.pool_mint, 1_000).unwrap_err(), "stake pool not initialized");
+    }
+
+    #[test]
+    fn quote_stake_pool() {
+        let mut pool = build_stake_pool();
+
+        // 1e9 lamports mint 909_090_909 pool tokens, the deposit fee rounds up
+        assert_eq!(quote_deposit_sol(&pool, 1_000_000_000).unwrap(), 909_090_909 - 909_091);
+        // 0.3% of the pool tokens is kept as fee before burning
+        assert_eq!(quote_withdraw_sol(&pool, 1_000_000_000, u64::MAX).unwrap(), 1_096_700_000);
+        assert_eq!(quote_withdraw_sol(&pool, 1_000_000_000, 1_000_000_000).unwrap_err(), "sol withdrawal too large");
+
+        pool.sol_deposit_authority = Some(Pubkey::new_unique());
+        assert_eq!(quote_deposit_sol(&pool, 1_000_000_000).unwrap_err(), "sol deposit requires authority");
+    }
+
+    #[test]
+    fn swap_through_stake_pool() {
+        let pool = build_stake_pool();
+        let wsol = Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap();
+        let reserve = lamports_account(pool.reserve_stake, 2_000_000_000, reserve_data(), Market::STAKE_POOL);
+        let accounts = vec![reserve.clone(), clock_account(Clock { epoch: 500, ..Clock::default() }, Market::STAKE_POOL)];
+
+        let deposit = pool.swap(&accounts, &wsol, 1_000_000_000).unwrap();
+        assert_eq!(deposit.amount_out, 908_181_818);
+        let withdraw = pool.swap(&accounts, &pool.pool_mint, 1_000_000_000).unwrap();
+        assert_eq!(withdraw.amount_out, 1_096_700_000);
+
+        // the reserve keeps its rent-exempt reserve
+        let reserve = lamports_account(pool.reserve_stake, 1_096_700_000 + RENT_EXEMPT_RESERVE - 1, reserve_data(), Market::STAKE_POOL);
+        assert_eq!(pool.swap(&vec![reserve, clock_account(Clock { epoch: 500, ..Clock::default() }, Market::STAKE_POOL)], &pool.pool_mint, 1_000_000_000).unwrap_err(), "sol withdrawal too large");
+
+        let stale = vec![accounts[0].clone(), clock_account(Clock { epoch: 501, ..Clock::default() }, Market::STAKE_POOL)];
+        assert_eq!(pool.swap(&stale, &wsol, 1_000_000_000).unwrap_err(), "stake pool not updated");
+        assert_eq!(pool.swap(&accounts, &Pubkey::new_unique(), 1_000).unwrap_err(), "mint not in pool");
+    }
+
+    #[test]
+    fn swap_through_marinade() {
+        let state = build_marinade();
+        let decoded = resolve_pool_account(&Market::MARINADE, &marinade_data(&state));
+        let decoded = decoded.as_any().downcast_ref::<MarinadeState>().unwrap();
+        assert_eq!(decoded.msol_mint, state.msol_mint);
+        assert_eq!(decoded.total_virtual_staked_lamports(), 10_000_000_000_000);
+        assert_eq!(decoded.lp_max_fee, MarinadeFee { basis_points: 300 });
+        assert!(decoded.is_tradable());
+
+        assert_eq!(quote_marinade_deposit(&state, 1_000_000_000).unwrap(), 800_000_000);
+        assert_eq!(quote_marinade_deposit(&state, 999).unwrap_err(), "deposit below minimum");
+
+        // 4_999 SOL left in the leg against a 10_000 SOL target puts the fee at 166 bps
+        let sol_leg = MarinadeState::liq_pool_sol_leg();
+        let accounts = vec![lamports_account(sol_leg, 5_000_000_000_000 + RENT_EXEMPT_FOR_TOKEN_ACC, vec![], Market::MARINADE)];
+        assert_eq!(decoded.linear_fee(4_999_000_000_000), MarinadeFee { basis_points: 166 });
+        let quote = decoded.swap(&accounts, &state.msol_mint, 800_000_000).unwrap();
+        assert_eq!(quote.amount_out, 983_400_000);
+
+        assert_eq!(quote_marinade_liquid_unstake(&state, 800_000_000, 500_000_000 + RENT_EXEMPT_FOR_TOKEN_ACC).unwrap_err(), "insufficient liquidity");
+        let wsol = Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap();
+        assert_eq!(decoded.swap(&accounts, &wsol, 1_000_000_000).unwrap().amount_out, 800_000_000);
+    }
+
+    #[test]
+    fn stake_pools_are_path_edges() {
+        let state = build_marinade();
+        let wsol = Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap();
+        let saber = SaberMarket { is_initialized: true, token_a_mint: wsol, token_b_mint: state.msol_mint, ..SaberMarket::default() };
+        let pools = vec![
+            DeserializedPoolAccount { pubkey: Pubkey::new_unique(), market: Market::SABER, operation: Box::new(saber), ..DeserializedPoolAccount::default() },
+            DeserializedPoolAccount { pubkey: Pubkey::new_unique(), market: Market::MARINADE, operation: Box::new(state), ..DeserializedPoolAccount::default() },
+        ];
+
+        let path_list = Arc::new(Mutex::new(HashMap::new()));
+        let path_finder = PathFinder {
+            pool_accounts: Arc::new(Mutex::new(pools)),
+            path_list: Arc::clone(&path_list),
+            pool_filter: PoolFilter::default(),
+        };
+        path_finder.resolve_path(wsol);
+
+        // SOL -> mSOL on the dex -> SOL through marinade, and the reverse
+        let paths = path_list.lock().unwrap().get(&wsol).cloned().unwrap();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|path| path.iter().any(|pool| pool.market == Market::MARINADE)));
+    }
+
+    #[test]
+    fn clock_updates_do_not_requote_cycles() {
+        let pool = build_stake_pool();
+        let pubkey = Pubkey::new_unique();
+        let cycle = vec![DeserializedPoolAccount { pubkey, market: Market::STAKE_POOL, operation: Box::new(pool), ..DeserializedPoolAccount::default() }];
+        let related_pubkeys = HashMap::from([(cycle[0].get_edge(), pool.get_swap_related_pubkeys().into_iter().map(|(_, pubkey)| pubkey).collect::<HashSet<Pubkey>>())]);
+        let index = CycleIndex::new(&HashMap::from([(Pubkey::new_unique(), vec![cycle])]), &related_pubkeys);
+
+        assert!(index.get_cycles(&vec![clock::id()]).is_empty());
+        assert_eq!(index.get_cycles(&vec![pool.reserve_stake]).len(), 1);
+        assert_eq!(index.get_cycles(&vec![pubkey]).len(), 1);
+    }
+}
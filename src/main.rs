@@ -1,7 +1,7 @@
 extern crate core;
 
 use std::cmp::PartialEq;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -10,16 +10,26 @@ use log::debug;
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 use tokio::spawn;
-use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::time::sleep;
 
 use path::PathFinder;
 
-use crate::observer::Event;
+use crate::arbitrageur::Arbitrageur;
+use crate::config::read_config;
+use crate::constants::EVENT_BUS_CAPACITY;
+use crate::executor::ledger::Ledger;
+use crate::executor::lookup_table::{get_pool_static_pubkeys, LookupTableManager};
+use crate::executor::submitter::Submitter;
+use crate::executor::tracker::{ConfirmationTracker, TxStatus};
+use crate::executor::wallet::Wallet;
+use crate::metrics::registry::Metrics;
+use crate::metrics::server::serve;
+use crate::observer::{Event, EventBus};
 use crate::probe::Probe;
 use crate::r#struct::account::{DeserializedAccount, DeserializedPoolAccount};
 use crate::r#struct::market::Market;
-use crate::utils::read_pools;
+use crate::utils::{get_rpc_url, get_timestamp_ms, read_pools};
 
 mod probe;
 mod constants;
@@ -30,103 +40,366 @@ mod r#struct;
 mod temp;
 mod arbitrageur;
 mod observer;
+mod config;
+mod executor;
+mod metrics;
+mod test;
 
 #[tokio::main]
 async fn main() {
-    let alchemy = "https://solana-mainnet.g.alchemy.com/v2/76-rZCjoPGCHXLfjHNojk5CiqX8I36AT".to_string();
-    // let get_blocks = "https://go.getblock.io/bd8eab2bbe6e448b84ca2ae3b282b819".to_string();
-    let rpc_url = alchemy;
+    let rpc_url = get_rpc_url();
     let rpc_client = RpcClient::new(rpc_url.clone());
 
+    // read base mints and their input limits
+    let config = read_config("./src/config.json").unwrap();
+
+    // `report [hours]` prints the trades of the ledger summed per base mint, then exits
+    if std::env::args().nth(1).as_deref() == Some("report") {
+        let since = std::env::args().nth(2).and_then(|hours| hours.parse::<u64>().ok()).map_or(0, |hours| {
+            get_timestamp_ms().saturating_sub(hours * 3_600_000)
+        });
+        let ledger = Ledger::open(&config.tracker.ledger_path).unwrap();
+        ledger.get_report(since).unwrap().iter().for_each(|row| {
+            let symbol = config.base_mints.iter().find(|base_mint| base_mint.mint.to_string() == row.base_mint)
+                .map_or(row.base_mint.as_str(), |base_mint| base_mint.symbol.as_str());
+            println!(
+                "{}: {} sent, {} landed, {} failed, {} expired | expected profit: {}, realized profit: {}, fees: {}, tips: {}",
+                symbol, row.sent, row.landed, row.failed, row.expired, row.expected_profit, row.realized_profit, row.fees, row.tips
+            );
+        });
+        return;
+    }
+
     // read pools
     let orca_pools = read_pools("./src/pubkey/orca.json").unwrap();
     let raydium_pools = read_pools("./src/pubkey/raydium.json").unwrap();
     let meteora_pools = read_pools("./src/pubkey/meteora.json").unwrap();
+    let phoenix_pools = read_pools("./src/pubkey/phoenix.json").unwrap();
+    let openbook_pools = read_pools("./src/pubkey/openbook.json").unwrap();
+    let saber_pools = read_pools("./src/pubkey/saber.json").unwrap();
+    let stake_pools = read_pools("./src/pubkey/stake_pool.json").unwrap();
+    let marinade_pools = read_pools("./src/pubkey/marinade.json").unwrap();
+    let sanctum_pools = read_pools("./src/pubkey/sanctum.json").unwrap();
+    let pump_fun_pools = read_pools("./src/pubkey/pump_fun.json").unwrap();
+    let pump_swap_pools = read_pools("./src/pubkey/pump_swap.json").unwrap();
 
     // concatenate all dex pools
     let pool_list = Arc::new(Mutex::new(HashMap::from([
         (Market::ORCA, orca_pools),
         (Market::RAYDIUM, raydium_pools),
-        // (Market::METEORA, meteora_pools),
+        (Market::METEORA, meteora_pools),
+        (Market::PHOENIX, phoenix_pools),
+        (Market::OPENBOOK, openbook_pools),
+        (Market::SABER, saber_pools),
+        // stake pools convert SOL and their LST at the pool rate
+        (Market::STAKE_POOL, stake_pools),
+        (Market::MARINADE, marinade_pools),
+        // infinity prices spl stake pool LSTs from the stake pools listed above
+        (Market::SANCTUM, sanctum_pools),
+        // bonding curves trade new tokens against SOL until they migrate to pump swap
+        (Market::PUMP_FUN, pump_fun_pools),
+        (Market::PUMP_SWAP, pump_swap_pools),
     ])));
 
     // hold pool pubkey
     let pool_account_bin: Arc<Mutex<Vec<DeserializedPoolAccount>>> = Arc::new(Mutex::new(Vec::new()));
-    // hold pubkey in data or pda
-    let shared_account_bin: Arc<Mutex<Vec<DeserializedAccount>>> = Arc::new(Mutex::new(Vec::new()));
+    // hold pubkey in data or pda, replaced as a whole on every fetch
+    let shared_account_bin: Arc<Mutex<Arc<Vec<DeserializedAccount>>>> = Arc::new(Mutex::new(Arc::new(Vec::new())));
     // hold available path list of mint
-    let path_list: Arc<Mutex<HashMap<Pubkey, Vec<DeserializedPoolAccount>>>> = Arc::new(Mutex::new(HashMap::new()));
+    let path_list: Arc<Mutex<HashMap<Pubkey, Vec<Vec<DeserializedPoolAccount>>>>> = Arc::new(Mutex::new(HashMap::new()));
 
-    let mut probe = Probe::new(rpc_url.clone());
+    let metrics = Arc::new(Metrics::new());
+    let mut probe = Probe::new(rpc_url.clone(), Arc::clone(&metrics));
     // fetch pool pubkeys
     probe.fetch_pool_accounts(Arc::clone(&pool_list), Arc::clone(&pool_account_bin));
 
+    // track every mint the pools trade, each held in an associated token account of the wallet
+    let mut mints = pool_account_bin.lock().unwrap().iter().flat_map(|pool| {
+        let pair = pool.operation.get_mint_pair();
+        [pair.pubkey_a, pair.pubkey_b]
+    }).collect::<HashSet<Pubkey>>();
+    mints.extend(config.get_base_mints());
+
+    let mut wallet = match Wallet::new(rpc_url.clone(), config.wallet.clone()) {
+        Ok(wallet) => wallet,
+        Err(err) => {
+            eprintln!("wallet: {}, set `wallet.keypair_path` in the config", err);
+            return;
+        }
+    };
+    wallet.load_mints(&mints.into_iter().collect::<Vec<Pubkey>>()).await.unwrap();
+
+    // `setup` creates the missing token accounts, wraps SOL and fills the lookup tables, then exits
+    if std::env::args().nth(1).as_deref() == Some("setup") {
+        let signatures = wallet.setup().await.unwrap();
+        println!("wallet: setup sent {} transaction(s) for {}", signatures.len(), wallet.get_pubkey());
+
+        let lookup_table_manager = LookupTableManager::new(rpc_url.clone());
+        lookup_table_manager.load(&config.lookup_tables).await.unwrap();
+        let static_pubkeys = pool_account_bin.lock().unwrap().iter().flat_map(|pool| {
+            get_pool_static_pubkeys(pool)
+        }).collect::<Vec<Pubkey>>();
+        let created = lookup_table_manager.extend(wallet.get_keypair(), &static_pubkeys).await.unwrap();
+        created.iter().for_each(|table| {
+            println!("lookup_table: created {}, add it to `lookup_tables` in the config", table);
+        });
+        return;
+    }
+
+    let missing = wallet.refresh().await.unwrap();
+    if !missing.is_empty() {
+        eprintln!("wallet: {} mint(s) have no token account, run `setup` to create them", missing.len());
+    }
+    let wallet = Arc::new(wallet);
+    let refresh_interval = Duration::from_millis(config.wallet.refresh_interval_ms);
+    let poll_interval = Duration::from_millis(config.tracker.poll_interval_ms);
+    let metrics_address = config.metrics.listen_address.clone();
+    let ledger_path = config.tracker.ledger_path.clone();
+    let auto_top_up_wsol = config.wallet.auto_top_up_wsol;
+
     // resolve path
     let pool_accounts = Arc::clone(&pool_account_bin);
     let path_list = Arc::clone(&path_list);
 
     let path_finder = PathFinder {
         pool_accounts: Arc::clone(&pool_accounts),
-        path_list: Arc::clone(&path_list)
+        path_list: Arc::clone(&path_list),
+        pool_filter: config.pool_filter.clone()
     };
 
-    let mint = Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap();
-    path_finder.resolve_path(mint);
+    path_finder.resolve_paths(&config.get_base_mints());
 
-    let (tx, mut rx) = broadcast::channel(10);
-    tx.send(Event::Initialized).expect("broadcast: failed to broadcast");
+    let event_bus = EventBus::new(EVENT_BUS_CAPACITY);
 
     //////////////////////////////////////////////////////////////////////////////////////////////////////////
 
     // collect swap-related pubkeys from pool accounts
-    probe.start_watching(Arc::clone(&pool_account_bin), Arc::clone(&shared_account_bin), tx.clone());
+    let related_pubkeys = probe.resolve_swap_related_pubkeys(Arc::clone(&pool_account_bin));
+
+    // transactions are compiled against the lookup tables created by `setup`
+    let lookup_table_manager = LookupTableManager::new(rpc_url.clone());
+    if let Err(err) = lookup_table_manager.load(&config.lookup_tables).await {
+        eprintln!("lookup_table: failed to load lookup tables: {}", err);
+    }
+    let submitter = Submitter::new(
+        rpc_url.clone(),
+        &config,
+        Arc::clone(&wallet),
+        pool_account_bin.lock().unwrap().clone(),
+        lookup_table_manager,
+        Arc::clone(&metrics)
+    );
 
     // setup and run arbitrage
-    let shared_pool_account_bin = Arc::clone(&shared_account_bin);
-    let path_list = Arc::clone(&path_list);
-    let rpc_client = RpcClient::new(rpc_url.clone());
-    let mut rx1 = tx.subscribe();
+    let arbitrageur = Arbitrageur::new(
+        config,
+        Arc::clone(&pool_account_bin),
+        Arc::clone(&shared_account_bin),
+        Arc::clone(&path_list),
+        &related_pubkeys,
+        Arc::clone(&wallet.inventory),
+        Arc::clone(&metrics)
+    );
+
+    // expose metrics, counting opportunities and transactions from the events
+    match serve(Arc::clone(&metrics), &metrics_address).await {
+        Ok((address, _)) => debug!("metrics: serving on http://{}/metrics", address),
+        Err(err) => eprintln!("metrics: failed to listen on {}: {}", metrics_address, err)
+    }
+    let event_metrics = Arc::clone(&metrics);
+    event_bus.subscribe_with("metrics", move |event| {
+        event_metrics.observe_event(&event);
+        async {}
+    });
+
+    // record submitted transactions and follow them until they settle
+    let ledger = Arc::new(Ledger::open(&ledger_path).unwrap());
+    let tracker = Arc::new(ConfirmationTracker::new(rpc_url.clone()));
+    let (submission_ledger, submission_tracker) = (Arc::clone(&ledger), Arc::clone(&tracker));
+    event_bus.subscribe_with("tracker", move |event| {
+        let (ledger, tracker) = (Arc::clone(&submission_ledger), Arc::clone(&submission_tracker));
+        async move {
+            if let Event::TxSubmitted { signature, opportunity, last_valid_block_height, tip } = event {
+                if let Err(err) = ledger.record_sent(&signature, &opportunity, tip, get_timestamp_ms()) {
+                    eprintln!("tracker: failed to record {}: {}", signature, err);
+                }
+                tracker.track(signature, last_valid_block_height);
+            }
+        }
+    });
+
+    // send the best opportunity of those found together, the tracker follows what was submitted
+    let mut executor_rx = event_bus.subscribe();
+    let publisher = event_bus.clone();
     spawn(async move {
         loop {
-            if !rx1.is_empty() {
-                match rx1.recv().await {
-                    Ok(event) => {
-                        debug!("broadcast: event received: {:?}", event);
-                        match event {
-                            Event::Initialized => {}
-                            Event::UpdateAccounts => {
-                                let path_list = path_list.lock().unwrap().clone();
-
-                                let target = path_list.iter().find(|path| {
-                                    *path.0 == mint
-                                }).expect(format!("path: path not found for mint: {}", mint).as_str());
-
-                                target.1.iter().for_each(|pool| {
-                                    let related_pubkeys = pool.get_swap_related_pubkeys(Some(&rpc_client)).unwrap();
-
-                                    let related_accounts = shared_pool_account_bin.lock().unwrap().clone().into_iter().filter(|account| {
-                                        related_pubkeys.iter().find(|(_, pubkey)| {
-                                            *pubkey == account.get_pubkey()
-                                        }).is_some()
-                                    }).collect::<Vec<DeserializedAccount>>();
-
-                                    target.1.iter().for_each(|pool| {
-                                        pool.operation.swap(&related_accounts);
-                                    });
-                                });
-                            }
-                        }
+            let mut opportunities = match executor_rx.recv().await {
+                Ok(Event::OpportunityFound(opportunity)) => vec![opportunity],
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    // skipped opportunities are stale by now
+                    debug!("executor: skipped {} event(s)", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => {
+                    break;
+                }
+            };
+            // the arbitrageur publishes an evaluation's opportunities best first, one after another
+            while let Ok(event) = executor_rx.try_recv() {
+                if let Event::OpportunityFound(opportunity) = event {
+                    opportunities.push(opportunity);
+                }
+            }
+
+            match submitter.submit(&opportunities).await {
+                Ok(Some((index, submission))) => {
+                    publisher.publish(Event::TxSubmitted {
+                        signature: submission.signature,
+                        opportunity: opportunities.swap_remove(index),
+                        last_valid_block_height: submission.last_valid_block_height,
+                        tip: submission.tip
+                    });
+                }
+                Ok(None) => debug!("executor: none of {} opportunities could be sent", opportunities.len()),
+                Err(err) => eprintln!("executor: failed to submit an opportunity: {}", err)
+            }
+        }
+    });
+
+    let owner = wallet.get_pubkey();
+    let inventory = Arc::clone(&wallet.inventory);
+    let publisher = event_bus.clone();
+    spawn(async move {
+        loop {
+            sleep(poll_interval).await;
+            let changes = match tracker.poll().await {
+                Ok(changes) => changes,
+                Err(err) => {
+                    eprintln!("tracker: failed to poll signature statuses: {}", err);
+                    continue;
+                }
+            };
+
+            for (signature, status) in changes {
+                if let Err(err) = ledger.record_status(&signature, &status, get_timestamp_ms()) {
+                    eprintln!("tracker: failed to record {}: {}", signature, err);
+                }
+
+                let slot = match &status {
+                    TxStatus::Confirmed { slot } | TxStatus::Finalized { slot } | TxStatus::Failed { slot, .. } => Some(*slot),
+                    TxStatus::Expired => None,
+                    _ => continue
+                };
+
+                // a trade settles once, whichever status is seen first, even if its result cannot be fetched
+                match ledger.mark_settled(&signature, get_timestamp_ms()) {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(err) => {
+                        eprintln!("tracker: failed to settle {}: {}", signature, err);
+                        continue;
+                    }
+                }
+                let trade = match ledger.get_trade(&signature) {
+                    Ok(Some(trade)) => trade,
+                    _ => continue
+                };
+                // the input set aside when the trade was sent is spendable again, or spent and gone at the next refresh
+                let base_mint = Pubkey::from_str(&trade.base_mint).unwrap();
+                inventory.release(&base_mint, trade.amount_in);
+
+                match (&status, slot) {
+                    (TxStatus::Failed { reason, .. }, _) => publisher.publish(Event::TxFailed { signature, reason: reason.clone() }),
+                    (_, Some(slot)) => publisher.publish(Event::TxLanded { signature, slot }),
+                    (_, None) => {
+                        publisher.publish(Event::TxFailed { signature, reason: "blockhash expired".to_string() });
+                        continue;
                     }
-                    Err(_) => {
-                        eprintln!("broadcast: nothing to receive");
+                };
+
+                match tracker.get_trade_result(&signature, &owner, &base_mint).await {
+                    Ok(Some(result)) => {
+                        if let Err(err) = ledger.record_result(&signature, &result) {
+                            eprintln!("tracker: failed to record {}: {}", signature, err);
+                        }
                     }
+                    Ok(None) => {}
+                    Err(err) => eprintln!("tracker: failed to fetch {}: {}", signature, err)
                 }
             }
+        }
+    });
 
-            sleep(Duration::from_secs(1)).await;
+    // keep the inventory and, if enabled, the wSOL account up to date
+    spawn(async move {
+        loop {
+            sleep(refresh_interval).await;
+            if let Err(err) = wallet.refresh().await {
+                eprintln!("wallet: failed to refresh balances: {}", err);
+                continue;
+            }
+            if !auto_top_up_wsol {
+                continue;
+            }
+            if let Err(err) = wallet.top_up_wsol().await {
+                eprintln!("wallet: failed to top up wSOL: {}", err);
+            }
         }
     });
 
+    let mut rx = event_bus.subscribe();
+    let publisher = event_bus.clone();
+    spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(Event::AccountsUpdated { pubkeys, slot }) => {
+                    debug!("observer: {} account(s) updated at slot {}", pubkeys.len(), slot);
+                    arbitrageur.arbitrage_changed(&pubkeys).await.into_iter().for_each(|opportunity| {
+                        publisher.publish(Event::OpportunityFound(opportunity));
+                    });
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    // the changes carried by skipped events are lost, re-evaluate every cycle
+                    debug!("observer: arbitrageur skipped {} event(s)", skipped);
+                    arbitrageur.arbitrage().await.into_iter().for_each(|opportunity| {
+                        publisher.publish(Event::OpportunityFound(opportunity));
+                    });
+                }
+                Err(RecvError::Closed) => {
+                    break;
+                }
+            }
+        }
+    });
+
+    event_bus.subscribe_with("logger", |event| async move {
+        match event {
+            Event::OpportunityFound(opportunity) => {
+                println!(
+                    "arbitrageur: {} -> {} {} (profit: {}, in quote: {:?}) via [{}]",
+                    opportunity.amount_in,
+                    opportunity.amount_out,
+                    opportunity.base_mint,
+                    opportunity.profit,
+                    opportunity.quote_profit,
+                    opportunity.path.iter().map(|pool| pool.to_string()).collect::<Vec<String>>().join(", ")
+                );
+            }
+            Event::TxFailed { signature, reason } => {
+                eprintln!("executor: {} failed: {}", signature, reason);
+            }
+            event => {
+                debug!("observer: event received: {:?}", event);
+            }
+        }
+    });
+
+    // start fetching once every subscriber is set up
+    probe.start_watching(Arc::clone(&pool_account_bin), &related_pubkeys, Arc::clone(&shared_account_bin), event_bus.clone());
+    event_bus.publish(Event::Initialized);
+
     // spawn(async move {
     //     loop {
     //         let path_list = path_list.lock().unwrap().clone();
@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use prometheus::{exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder};
+use tokio::time::Instant;
+
+use crate::arbitrageur::Opportunity;
+use crate::observer::Event;
+use crate::r#struct::market::Market;
+
+const NAMESPACE: &str = "sol_arb";
+
+/// Gap between a quoted and a simulated swap output, in basis points
+const QUOTE_ERROR_BUCKETS: [f64; 9] = [0.0, 1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 500.0, 1000.0];
+
+/// Every metric exposed on `/metrics`, registered in a registry of their own
+pub struct Metrics {
+    registry: Registry,
+    /// latency of an rpc request, by method
+    pub rpc_duration: HistogramVec,
+    /// failed rpc requests, by method
+    pub rpc_errors: IntCounterVec,
+    /// slots between the last account snapshot and the cluster
+    pub account_staleness: IntGauge,
+    /// cycles quoted, its rate is the cycles evaluated per second
+    pub cycles_evaluated: IntCounter,
+    /// latency of quoting one swap, by market
+    pub quote_duration: HistogramVec,
+    /// best profit of an opportunity so far, in base mint, by base mint
+    pub best_profit: IntGaugeVec,
+    pub opportunities_found: IntCounter,
+    pub transactions_sent: IntCounter,
+    pub transactions_landed: IntCounter,
+    pub transactions_failed: IntCounter,
+    /// gap between a quoted and a simulated swap output in basis points, by market
+    pub quote_error: HistogramVec,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        let registry = Registry::new();
+
+        let rpc_duration = HistogramVec::new(
+            HistogramOpts::new("rpc_request_duration_seconds", "Latency of rpc requests").namespace(NAMESPACE),
+            &["method"]
+        ).unwrap();
+        let rpc_errors = IntCounterVec::new(
+            Opts::new("rpc_request_errors_total", "Failed rpc requests").namespace(NAMESPACE),
+            &["method"]
+        ).unwrap();
+        let account_staleness = IntGauge::with_opts(
+            Opts::new("account_staleness_slots", "Slots between the account snapshot and the cluster").namespace(NAMESPACE)
+        ).unwrap();
+        let cycles_evaluated = IntCounter::with_opts(
+            Opts::new("cycles_evaluated_total", "Cycles quoted").namespace(NAMESPACE)
+        ).unwrap();
+        let quote_duration = HistogramVec::new(
+            HistogramOpts::new("quote_duration_seconds", "Latency of quoting one swap")
+                .namespace(NAMESPACE)
+                .buckets(exponential_buckets(0.00001, 4.0, 10).unwrap()),
+            &["market"]
+        ).unwrap();
+        let best_profit = IntGaugeVec::new(
+            Opts::new("best_profit", "Best opportunity profit seen, in base mint").namespace(NAMESPACE),
+            &["base_mint"]
+        ).unwrap();
+        let opportunities_found = IntCounter::with_opts(
+            Opts::new("opportunities_found_total", "Profitable opportunities found").namespace(NAMESPACE)
+        ).unwrap();
+        let transactions_sent = IntCounter::with_opts(
+            Opts::new("transactions_sent_total", "Arbitrage transactions submitted").namespace(NAMESPACE)
+        ).unwrap();
+        let transactions_landed = IntCounter::with_opts(
+            Opts::new("transactions_landed_total", "Arbitrage transactions landed").namespace(NAMESPACE)
+        ).unwrap();
+        let transactions_failed = IntCounter::with_opts(
+            Opts::new("transactions_failed_total", "Arbitrage transactions failed or expired").namespace(NAMESPACE)
+        ).unwrap();
+        let quote_error = HistogramVec::new(
+            HistogramOpts::new("quote_simulation_error_bps", "Gap between quoted and simulated swap outputs, in basis points")
+                .namespace(NAMESPACE)
+                .buckets(QUOTE_ERROR_BUCKETS.to_vec()),
+            &["market"]
+        ).unwrap();
+
+        registry.register(Box::new(rpc_duration.clone())).unwrap();
+        registry.register(Box::new(rpc_errors.clone())).unwrap();
+        registry.register(Box::new(account_staleness.clone())).unwrap();
+        registry.register(Box::new(cycles_evaluated.clone())).unwrap();
+        registry.register(Box::new(quote_duration.clone())).unwrap();
+        registry.register(Box::new(best_profit.clone())).unwrap();
+        registry.register(Box::new(opportunities_found.clone())).unwrap();
+        registry.register(Box::new(transactions_sent.clone())).unwrap();
+        registry.register(Box::new(transactions_landed.clone())).unwrap();
+        registry.register(Box::new(transactions_failed.clone())).unwrap();
+        registry.register(Box::new(quote_error.clone())).unwrap();
+
+        Metrics {
+            registry,
+            rpc_duration,
+            rpc_errors,
+            account_staleness,
+            cycles_evaluated,
+            quote_duration,
+            best_profit,
+            opportunities_found,
+            transactions_sent,
+            transactions_landed,
+            transactions_failed,
+            quote_error,
+        }
+    }
+
+    /// Records the latency of an rpc request started at `start`, and whether it failed
+    pub fn observe_rpc<T, E>(&self, method: &str, start: Instant, result: &Result<T, E>) {
+        self.rpc_duration.with_label_values(&[method]).observe(start.elapsed().as_secs_f64());
+        if result.is_err() {
+            self.rpc_errors.with_label_values(&[method]).inc();
+        }
+    }
+
+    pub fn observe_quote(&self, market: &Market, elapsed: Duration) {
+        self.quote_duration.with_label_values(&[&format!("{:?}", market)]).observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_quote_error(&self, market: &Market, diff_bps: u64) {
+        self.quote_error.with_label_values(&[&format!("{:?}", market)]).observe(diff_bps as f64);
+    }
+
+    pub fn observe_opportunity(&self, opportunity: &Opportunity) {
+        self.opportunities_found.inc();
+
+        let best_profit = self.best_profit.with_label_values(&[&opportunity.base_mint.to_string()]);
+        let profit = i64::try_from(opportunity.profit).unwrap_or(i64::MAX);
+        if profit > best_profit.get() {
+            best_profit.set(profit);
+        }
+    }
+
+    /// Counts the opportunities and transactions an event reports
+    pub fn observe_event(&self, event: &Event) {
+        match event {
+            Event::OpportunityFound(opportunity) => {
+                self.observe_opportunity(opportunity);
+            }
+            Event::TxSubmitted { .. } => {
+                self.transactions_sent.inc();
+            }
+            Event::TxLanded { .. } => {
+                self.transactions_landed.inc();
+            }
+            Event::TxFailed { .. } => {
+                self.transactions_failed.inc();
+            }
+            _ => {}
+        }
+    }
+
+    /// Every metric in the Prometheus text format
+    pub fn encode(&self) -> String {
+        let mut buffer: Vec<u8> = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer).unwrap();
+
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
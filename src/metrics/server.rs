@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use log::debug;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::spawn;
+use tokio::task::JoinHandle;
+
+use crate::metrics::registry::Metrics;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Serves `metrics` on `GET /metrics` at `listen_address`.
+/// Returns the address bound, which tells the port when `listen_address` asks for any
+pub async fn serve(metrics: Arc<Metrics>, listen_address: &str) -> std::io::Result<(String, JoinHandle<()>)> {
+    let listener = TcpListener::bind(listen_address).await?;
+    let address = listener.local_addr()?.to_string();
+
+    let handle = spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let metrics = Arc::clone(&metrics);
+            spawn(async move {
+                if let Err(err) = respond(stream, &metrics).await {
+                    debug!("metrics: failed to respond: {}", err);
+                }
+            });
+        }
+    });
+
+    Ok((address, handle))
+}
+
+async fn respond(stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+
+    // the request is answered from its first line, the headers are drained and ignored
+    let mut header = String::new();
+    while reader.read_line(&mut header).await? > 2 {
+        header.clear();
+    }
+
+    let (status, content_type, body) = match request_line.split_whitespace().take(2).collect::<Vec<&str>>()[..] {
+        ["GET", "/metrics"] => ("200 OK", CONTENT_TYPE, metrics.encode()),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body
+    );
+    let mut stream = reader.into_inner();
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod metrics_test {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use tokio::time::Instant;
+
+    use crate::arbitrageur::Opportunity;
+    use crate::metrics::registry::Metrics;
+    use crate::metrics::server::serve;
+    use crate::observer::Event;
+    use crate::r#struct::market::Market;
+
+    fn build_opportunity(base_mint: Pubkey, profit: u64) -> Opportunity {
+        Opportunity {
+            base_mint,
+            path: Vec::new(),
+            mints_out: Vec::new(),
+            markets: Vec::new(),
+            quotes: Vec::new(),
+            amount_in: 1_000_000,
+            amount_out: 1_000_000 + profit,
+            profit,
+            quote_profit: None,
+            lamport_profit: None,
+            compute_units: 200_000,
+        }
+    }
+
+    #[test]
+    fn events_are_counted() {
+        let metrics = Metrics::new();
+        let base_mint = Pubkey::new_unique();
+
+        metrics.observe_event(&Event::OpportunityFound(build_opportunity(base_mint, 30_000)));
+        metrics.observe_event(&Event::OpportunityFound(build_opportunity(base_mint, 10_000)));
+        metrics.observe_event(&Event::TxSubmitted {
+            signature: Signature::new_unique(),
+            opportunity: build_opportunity(base_mint, 30_000),
+            last_valid_block_height: 100,
+            tip: 1_000,
+        });
+        metrics.observe_event(&Event::TxLanded { signature: Signature::new_unique(), slot: 10 });
+        metrics.observe_event(&Event::TxFailed { signature: Signature::new_unique(), reason: "expired".to_string() });
+
+        assert_eq!(metrics.opportunities_found.get(), 2);
+        assert_eq!(metrics.best_profit.with_label_values(&[&base_mint.to_string()]).get(), 30_000);
+        assert_eq!(metrics.transactions_sent.get(), 1);
+        assert_eq!(metrics.transactions_landed.get(), 1);
+        assert_eq!(metrics.transactions_failed.get(), 1);
+    }
+
+    #[test]
+    fn rpc_and_quote_observations() {
+        let metrics = Metrics::new();
+
+        metrics.observe_rpc("getMultipleAccounts", Instant::now(), &Ok::<(), ()>(()));
+        metrics.observe_rpc("getMultipleAccounts", Instant::now(), &Err::<(), ()>(()));
+        metrics.observe_quote(&Market::ORCA, Duration::from_micros(50));
+        metrics.observe_quote_error(&Market::RAYDIUM, 12);
+
+        assert_eq!(metrics.rpc_duration.with_label_values(&["getMultipleAccounts"]).get_sample_count(), 2);
+        assert_eq!(metrics.rpc_errors.with_label_values(&["getMultipleAccounts"]).get(), 1);
+        assert_eq!(metrics.quote_duration.with_label_values(&["ORCA"]).get_sample_count(), 1);
+        assert_eq!(metrics.quote_error.with_label_values(&["RAYDIUM"]).get_sample_sum(), 12.0);
+
+        let encoded = metrics.encode();
+        assert!(encoded.contains("sol_arb_rpc_request_errors_total{method=\"getMultipleAccounts\"} 1"));
+        assert!(encoded.contains("sol_arb_quote_duration_seconds_count{market=\"ORCA\"} 1"));
+        assert!(encoded.contains("sol_arb_quote_simulation_error_bps_bucket{market=\"RAYDIUM\",le=\"25\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn serve_metrics_endpoint() {
+        let metrics = Arc::new(Metrics::new());
+        metrics.cycles_evaluated.inc_by(42);
+        let (address, _) = serve(Arc::clone(&metrics), "127.0.0.1:0").await.unwrap();
+
+        let response = reqwest::get(format!("http://{}/metrics", address)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.text().await.unwrap().contains("sol_arb_cycles_evaluated_total 42"));
+
+        let response = reqwest::get(format!("http://{}/", address)).await.unwrap();
+        assert_eq!(response.status(), 404);
+    }
+}
@@ -1,41 +1,89 @@
-use std::collections::HashMap;
-use std::fmt::{Display, Formatter};
+use std::future::Future;
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+use log::warn;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tokio::spawn;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+
+use crate::arbitrageur::Opportunity;
+
+#[derive(Clone, Debug)]
 pub enum Event {
     Initialized,
-    UpdateAccounts
+    /// accounts whose data was fetched, and the slot they were fetched at
+    AccountsUpdated {
+        pubkeys: Vec<Pubkey>,
+        slot: u64
+    },
+    OpportunityFound(Opportunity),
+    TxSubmitted {
+        signature: Signature,
+        opportunity: Opportunity,
+        /// last block height the transaction's blockhash is valid at
+        last_valid_block_height: u64,
+        /// lamports
+        tip: u64
+    },
+    TxLanded {
+        signature: Signature,
+        slot: u64
+    },
+    TxFailed {
+        signature: Signature,
+        reason: String
+    }
 }
 
-pub type Subscriber = fn();
-
-#[derive(Default, Clone)]
-pub struct Publisher {
-    events: HashMap<Event, Vec<Subscriber>>
+/// Broadcasts events to every subscriber.
+/// Subscribers either `recv().await` on their own receiver or register an async handler
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<Event>
 }
 
-impl Publisher {
-    pub fn subscribe(&mut self, event: Event, listener: Subscriber) {
-        self.events.entry(event.clone()).or_default();
-        if let Some(events) = self.events.get_mut(&event) {
-            events.push(listener);
+impl EventBus {
+    pub fn new(capacity: usize) -> EventBus {
+        let (tx, _) = broadcast::channel(capacity);
+        EventBus {
+            tx
         }
     }
 
-    // pub fn unsubscribe(&mut self, event: Event, listener: Subscriber) {
-    //     self.events.get_mut(&event).unwrap().retain(|&subscriber| {
-    //         subscriber != listener
-    //     })
-    // }
-
-    pub fn notify(&self, event: Event) {
-        if let Some(listeners) = &self.events.get(&event) {
-            listeners.iter().for_each(|&subscriber| {
-                subscriber()
-            })
-        }
-        else {
+    /// Returns the number of subscribers the event reached
+    pub fn publish(&self, event: Event) -> usize {
+        // no subscriber is not an error, the event is just dropped
+        self.tx.send(event).unwrap_or(0)
+    }
 
-        }
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
     }
-}
\ No newline at end of file
+
+    /// Runs `handler` for every event until the bus is dropped.
+    /// Events are handled one at a time, in order
+    pub fn subscribe_with<F, Fut>(&self, name: &'static str, handler: F) -> JoinHandle<()>
+    where
+        F: Fn(Event) -> Fut + Send + 'static,
+        Fut: Future<Output=()> + Send
+    {
+        let mut rx = self.subscribe();
+        spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => {
+                        handler(event).await
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("observer: {} lagged behind, {} event(s) skipped", name, skipped);
+                    }
+                    Err(RecvError::Closed) => {
+                        break;
+                    }
+                }
+            }
+        })
+    }
+}
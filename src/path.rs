@@ -1,54 +1,68 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar::clock;
 use tokio::time::Instant;
+use crate::config::PoolFilter;
 use crate::constants::MAX_DEPTH;
-use crate::r#struct::account::DeserializedPoolAccount;
-use crate::r#struct::market::Market;
+use crate::r#struct::account::{DeserializedPoolAccount, PoolEdge};
 
 pub struct PathFinder {
     pub pool_accounts: Arc<Mutex<Vec<DeserializedPoolAccount>>>,
-    pub path_list: Arc<Mutex<HashMap<Pubkey, Vec<DeserializedPoolAccount>>>>,
+    pub path_list: Arc<Mutex<HashMap<Pubkey, Vec<Vec<DeserializedPoolAccount>>>>>,
+    pub pool_filter: PoolFilter,
 }
 
 impl PathFinder {
+    pub fn resolve_paths(&self, mints: &Vec<Pubkey>) {
+        mints.iter().for_each(|mint| {
+            self.resolve_path(*mint)
+        })
+    }
+
     pub fn resolve_path(&self, mint: Pubkey) {
         let t = Instant::now();
+        (*self.path_list.lock().unwrap()).remove(&mint);
+
+        let pools = (*self.pool_accounts.lock().unwrap()).iter().filter(|pool| {
+            self.pool_filter.accept(pool)
+        }).cloned().collect::<Vec<DeserializedPoolAccount>>();
+        let len = pools.len();
+        let pools = Arc::new(Mutex::new(pools));
+
         let path: Rc<RefCell<Vec<DeserializedPoolAccount>>> = Rc::new(RefCell::new(Vec::new()));
-        let len = (*Arc::clone(&self.pool_accounts).lock().unwrap()).len();
-        for i in 2..len + 1 {
+        for i in 2..len.min(MAX_DEPTH) + 1 {
             Self::find_path(
                 Arc::clone(&self.path_list),
-                Arc::clone(&self.pool_accounts),
+                Arc::clone(&pools),
                 Rc::clone(&path),
-                0,
                 i,
                 mint,
                 mint
             )
         }
 
-        println!("path: path resolved ({:?})", t.elapsed());
+        let count = (*self.path_list.lock().unwrap()).get(&mint).map_or(0, |paths| paths.len());
+        println!("path: {} path(s) resolved for {} ({:?})", count, mint, t.elapsed());
     }
 
     fn find_path(
-        path_list: Arc<Mutex<HashMap<Pubkey, Vec<DeserializedPoolAccount>>>>,
+        path_list: Arc<Mutex<HashMap<Pubkey, Vec<Vec<DeserializedPoolAccount>>>>>,
         pools: Arc<Mutex<Vec<DeserializedPoolAccount>>>,
         path: Rc<RefCell<Vec<DeserializedPoolAccount>>>,
-        start: usize,
         r: usize,
         next_mint: Pubkey,
         target_mint: Pubkey
     ) {
         if r == 0 {
             let tmp_path = Rc::clone(&path);
-            if Self::validate_path(&tmp_path, &target_mint) {
+            if Self::validate_path(&tmp_path, &next_mint, &target_mint) {
                 // println!("[{}]", tmp_path.borrow().iter().map(|x| {
                 //     format!("({}({}) - ({}, {}))", x.market.name(), x.pubkey, x.operation.get_mint_pair().pubkey_a, x.operation.get_mint_pair().pubkey_b)
                 // }).collect::<Vec<String>>().join(","));
-                (*path_list.lock().unwrap()).insert(target_mint, tmp_path.take());
+                (*path_list.lock().unwrap()).entry(target_mint).or_default().push(tmp_path.borrow().clone());
             }
             return;
         }
@@ -56,13 +70,12 @@ impl PathFinder {
             let tmp_path = Rc::clone(&path);
             let pools = Arc::clone(&pools);
 
-            let len = (*pools.lock().unwrap()).len();
-            for i in start..len {
-                let accounts = (*pools.lock().unwrap()).clone();
-
+            let accounts = (*pools.lock().unwrap()).clone();
+            for i in 0..accounts.len() {
+                // amm pools, order book markets and stake pools alike are edges between the two mints they trade
                 let account = accounts[i].clone();
                 let pair = account.operation.get_mint_pair();
-                if !pair.any(next_mint) || Self::contains_dex(&account.market, &tmp_path.borrow()) {
+                if !pair.any(next_mint) || Self::contains_pool(&account, &tmp_path.borrow()) {
                     continue;
                 }
 
@@ -74,7 +87,7 @@ impl PathFinder {
                     pair.pubkey_a
                 };
 
-                Self::find_path(Arc::clone(&path_list), Arc::clone(&pools), Rc::clone(&path), i+1, r-1, next_mint, target_mint);
+                Self::find_path(Arc::clone(&path_list), Arc::clone(&pools), Rc::clone(&path), r-1, next_mint, target_mint);
                 tmp_path.borrow_mut().pop();
 
                 // basic
@@ -86,8 +99,9 @@ impl PathFinder {
         }
     }
 
-    fn validate_path(path: &Rc<RefCell<Vec<DeserializedPoolAccount>>>, target_mint: &Pubkey) -> bool {
-        if MAX_DEPTH < path.borrow().len() {
+    fn validate_path(path: &Rc<RefCell<Vec<DeserializedPoolAccount>>>, last_mint: &Pubkey, target_mint: &Pubkey) -> bool {
+        // the cycle must end with the mint it started from
+        if MAX_DEPTH < path.borrow().len() || last_mint != target_mint {
             false
         }
         else {
@@ -102,9 +116,68 @@ impl PathFinder {
         }
     }
 
-    fn contains_dex(market: &Market, accounts: &Vec<DeserializedPoolAccount>) -> bool {
-        accounts.iter().find(|account| {
-            account.market.eq(market)
-        }).is_some()
+    // an edge can be used once per path, pools of the same dex and edges of the same multi-asset pool can be chained
+    fn contains_pool(pool: &DeserializedPoolAccount, accounts: &Vec<DeserializedPoolAccount>) -> bool {
+        accounts.iter().any(|account| {
+            account.equals(pool)
+        })
+    }
+}
+
+/// Identifies a cycle by its base mint and its index in the path list of that mint
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct CycleId {
+    pub base_mint: Pubkey,
+    pub index: usize,
+}
+
+/// Reverse index from an account to the pool edges whose quote depends on it, and from an edge to the cycles containing it
+#[derive(Default)]
+pub struct CycleIndex {
+    account_pools: HashMap<Pubkey, HashSet<PoolEdge>>,
+    pool_cycles: HashMap<PoolEdge, HashSet<CycleId>>,
+}
+
+impl CycleIndex {
+    pub fn new(
+        path_list: &HashMap<Pubkey, Vec<Vec<DeserializedPoolAccount>>>,
+        related_pubkeys: &HashMap<PoolEdge, HashSet<Pubkey>>
+    ) -> CycleIndex {
+        let mut index = CycleIndex::default();
+
+        related_pubkeys.iter().for_each(|(pool, related_pubkeys)| {
+            // a pool always depends on its own account
+            index.account_pools.entry(pool.pubkey).or_default().insert(*pool);
+            // the clock changes every slot, pools reading it are re-quoted with the clock of the snapshot when their own state changes
+            related_pubkeys.iter().filter(|pubkey| **pubkey != clock::id()).for_each(|pubkey| {
+                index.account_pools.entry(*pubkey).or_default().insert(*pool);
+            });
+        });
+
+        path_list.iter().for_each(|(base_mint, paths)| {
+            paths.iter().enumerate().for_each(|(i, path)| {
+                path.iter().for_each(|pool| {
+                    index.pool_cycles.entry(pool.get_edge()).or_default().insert(CycleId {
+                        base_mint: *base_mint,
+                        index: i,
+                    });
+                });
+            });
+        });
+
+        index
+    }
+
+    pub fn get_pools(&self, pubkeys: &Vec<Pubkey>) -> HashSet<PoolEdge> {
+        pubkeys.iter().filter_map(|pubkey| {
+            self.account_pools.get(pubkey)
+        }).flatten().copied().collect::<HashSet<PoolEdge>>()
     }
-}
\ No newline at end of file
+
+    /// Cycles to re-quote after `pubkeys` changed
+    pub fn get_cycles(&self, pubkeys: &Vec<Pubkey>) -> HashSet<CycleId> {
+        self.get_pools(pubkeys).iter().filter_map(|pool| {
+            self.pool_cycles.get(pool)
+        }).flatten().copied().collect::<HashSet<CycleId>>()
+    }
+}
@@ -1,30 +1,40 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use log::debug;
 
+use serde_json::json;
 use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::{Response, RpcKeyedAccount};
 use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
 use tokio::spawn;
-use tokio::sync::broadcast::Sender;
 use tokio::time::{Instant, sleep};
 
-use crate::observer::{Event};
-use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount, DeserializedDataAccount, DeserializedPoolAccount, DeserializedTokenAccount};
+use crate::constants::{TOKEN_2022_PROGRAM_PUBKEY, TOKEN_PROGRAM_PUBKEY};
+use crate::executor::wallet::get_associated_token_address;
+use crate::metrics::registry::Metrics;
+use crate::observer::{Event, EventBus};
+use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount, DeserializedDataAccount, DeserializedPoolAccount, DeserializedTokenAccount, PoolEdge};
 use crate::r#struct::market::Market;
+use crate::r#struct::pools::{PumpFunBondingCurve, SanctumInfinityMarket, SplStakePool};
 use crate::r#struct::resolver::{resolve_pool_account, resolve_pool_config_account};
 use crate::r#struct::token::TokenAccount;
 
 pub struct Probe {
-    pub rpc_url: String
+    pub rpc_url: String,
+    metrics: Arc<Metrics>
 }
 
 impl Probe {
-    pub fn new(rpc_url: String) -> Probe {
+    pub fn new(rpc_url: String, metrics: Arc<Metrics>) -> Probe {
         Probe {
-            rpc_url
+            rpc_url,
+            metrics
         }
     }
 
@@ -38,7 +48,7 @@ impl Probe {
 
         debug!("probe: fetching market pools...");
         let fetched_markets = pools.lock().unwrap().iter().map(|pools| {
-            let accounts = Self::_fetch_accounts(&rpc_client, pools.1);
+            let (accounts, _) = Self::_fetch_accounts(&rpc_client, pools.1, &self.metrics);
 
             let valid_accounts = accounts.iter().enumerate().filter(|(index, account)| {
                 account.is_some()
@@ -57,44 +67,166 @@ impl Probe {
 
             valid_accounts
         }).collect::<Vec<Vec<DeserializedPoolAccount>>>().into_iter().flatten().collect::<Vec<DeserializedPoolAccount>>();
+        let fetched_markets = Self::expand_multi_asset_pools(&rpc_client, fetched_markets, &self.metrics);
+        let fetched_markets = Self::resolve_bonding_curve_mints(&rpc_client, fetched_markets, &self.metrics);
 
         *pool_account_bin.lock().unwrap() = fetched_markets;
     }
 
+    // a multi-asset pool is one account, replace it with an edge per pair of mints it trades
+    fn expand_multi_asset_pools(
+        rpc_client: &RpcClient,
+        pools: Vec<DeserializedPoolAccount>,
+        metrics: &Metrics
+    ) -> Vec<DeserializedPoolAccount> {
+        let stake_pools = pools.iter().filter_map(|pool| {
+            pool.operation.as_any().downcast_ref::<SplStakePool>().map(|stake_pool| (pool.pubkey, *stake_pool))
+        }).collect::<Vec<(Pubkey, SplStakePool)>>();
+
+        pools.into_iter().flat_map(|pool| {
+            let edges = pool.operation.as_any().downcast_ref::<SanctumInfinityMarket>().map(|market| {
+                let (accounts, _) = Self::_fetch_accounts(rpc_client, &vec![SanctumInfinityMarket::lst_state_list()], metrics);
+                match accounts.into_iter().next().flatten() {
+                    Some(lst_state_list) => market.get_edges(&lst_state_list.data, &stake_pools),
+                    None => {
+                        eprintln!("probe: failed to fetch lst state list of {}", pool.pubkey);
+                        Vec::new()
+                    }
+                }
+            });
+
+            match edges {
+                Some(edges) => edges.into_iter().map(|edge| {
+                    DeserializedPoolAccount {
+                        pubkey: pool.pubkey,
+                        account: pool.account.clone(),
+                        market: pool.market,
+                        operation: Box::new(edge),
+                    }
+                }).collect::<Vec<DeserializedPoolAccount>>(),
+                None => vec![pool]
+            }
+        }).collect::<Vec<DeserializedPoolAccount>>()
+    }
+
+    // a bonding curve does not hold its mint, take it from the associated token account of the curve
+    fn resolve_bonding_curve_mints(
+        rpc_client: &RpcClient,
+        pools: Vec<DeserializedPoolAccount>,
+        metrics: &Metrics
+    ) -> Vec<DeserializedPoolAccount> {
+        pools.into_iter().filter_map(|mut pool| {
+            let curve = match pool.operation.as_any().downcast_ref::<PumpFunBondingCurve>() {
+                Some(curve) => *curve,
+                None => return Some(pool)
+            };
+
+            match Self::_fetch_associated_mint(rpc_client, &pool.pubkey, metrics) {
+                Some((mint, token_program)) if PumpFunBondingCurve::bonding_curve(&mint) == pool.pubkey => {
+                    pool.operation = Box::new(PumpFunBondingCurve { mint, token_program, ..curve });
+                    Some(pool)
+                }
+                _ => {
+                    eprintln!("probe: failed to resolve mint of bonding curve {}", pool.pubkey);
+                    None
+                }
+            }
+        }).collect::<Vec<DeserializedPoolAccount>>()
+    }
+
+    // mint and token program of the associated token account `owner` holds, legacy and Token-2022 mints alike.
+    // getTokenAccountsByOwner only returns the few accounts of the owner, base64 keeps them decodable as raw accounts
+    fn _fetch_associated_mint(
+        rpc_client: &RpcClient,
+        owner: &Pubkey,
+        metrics: &Metrics
+    ) -> Option<(Pubkey, Pubkey)> {
+        [TOKEN_PROGRAM_PUBKEY, TOKEN_2022_PROGRAM_PUBKEY].iter().find_map(|token_program| {
+            let token_program = Pubkey::from_str(token_program).unwrap();
+            let params = json!([
+                owner.to_string(),
+                { "programId": token_program.to_string() },
+                { "encoding": "base64", "commitment": "confirmed" }
+            ]);
+
+            let start = Instant::now();
+            let response = rpc_client.send::<Response<Vec<RpcKeyedAccount>>>(RpcRequest::GetTokenAccountsByOwner, params);
+            metrics.observe_rpc("getTokenAccountsByOwner", start, &response);
+
+            // anyone can open token accounts for the curve, only the associated one is its own
+            response.ok()?.value.into_iter().find_map(|keyed_account| {
+                let pubkey = Pubkey::from_str(&keyed_account.pubkey).ok()?;
+                let account = keyed_account.account.decode::<Account>()?;
+                let mint = Pubkey::new_from_array(account.data.get(0..32)?.try_into().unwrap());
+                (get_associated_token_address(owner, &mint, &token_program) == pubkey).then_some((mint, token_program))
+            })
+        })
+    }
+
     // fetch accounts one time
     pub fn fetch_multiple_accounts(
         &self,
         items: Vec<(Market, DeserializedAccount, Pubkey)>,
-        bin: Arc<Mutex<Vec<DeserializedAccount>>>
+        bin: Arc<Mutex<Arc<Vec<DeserializedAccount>>>>
     ) {
         let rpc_client = RpcClient::new(self.rpc_url.clone());
-        Self::_fetch_multiple_accounts(&rpc_client, items, bin, None)
+        Self::_fetch_multiple_accounts(&rpc_client, items, bin, &mut HashMap::new(), None, &self.metrics)
+    }
+
+    // resolve swap-related pubkeys of every pool one time
+    pub fn resolve_swap_related_pubkeys(
+        &self,
+        pool_account_bin: Arc<Mutex<Vec<DeserializedPoolAccount>>>
+    ) -> HashMap<PoolEdge, Vec<(DeserializedAccount, Pubkey)>> {
+        let rpc_client = RpcClient::new(self.rpc_url.clone());
+
+        pool_account_bin.lock().unwrap().iter().filter_map(|account| {
+            match account.get_swap_related_pubkeys(Some(&rpc_client)) {
+                Ok(related_pubkeys) => {
+                    Some((account.get_edge(), related_pubkeys))
+                }
+                Err(err) => {
+                    eprintln!("probe: failed to resolve swap-related pubkeys of {}: {}", account.pubkey, err);
+                    None
+                }
+            }
+        }).collect::<HashMap<PoolEdge, Vec<(DeserializedAccount, Pubkey)>>>()
     }
 
     // fetch accounts periodically
     pub fn start_watching(
         &self,
         pool_account_bin: Arc<Mutex<Vec<DeserializedPoolAccount>>>,
-        bin: Arc<Mutex<Vec<DeserializedAccount>>>,
-        tx: Sender<Event>
+        related_pubkeys: &HashMap<PoolEdge, Vec<(DeserializedAccount, Pubkey)>>,
+        bin: Arc<Mutex<Arc<Vec<DeserializedAccount>>>>,
+        event_bus: EventBus
     ) {
         let get_blocks = self.rpc_url.clone();
         let rpc_client = RpcClient::new(get_blocks);
-        let tx = tx.clone();
 
-        let items = Arc::clone(&pool_account_bin).lock().unwrap().iter().map(|account| {
-            account.get_swap_related_pubkeys(Some(&rpc_client)).unwrap().into_iter().map(|item| {
-                (account.market, item.0, item.1)
-            }).collect::<Vec<(Market, DeserializedAccount, Pubkey)>>()
-        }).into_iter().flatten().collect::<Vec<(Market, DeserializedAccount, Pubkey)>>();
+        let items = Arc::clone(&pool_account_bin).lock().unwrap().iter().filter_map(|account| {
+            related_pubkeys.get(&account.get_edge()).map(|related_pubkeys| {
+                related_pubkeys.iter().map(|item| {
+                    (account.market, item.0.clone(), item.1)
+                }).collect::<Vec<(Market, DeserializedAccount, Pubkey)>>()
+            })
+        }).flatten().collect::<Vec<(Market, DeserializedAccount, Pubkey)>>();
+        // pools sharing accounts, e.g. the clock or the edges of a multi-asset pool, fetch them once
+        let mut seen: HashSet<(Market, Pubkey)> = HashSet::new();
+        let items = items.into_iter().filter(|item| seen.insert((item.0, item.2))).collect::<Vec<(Market, DeserializedAccount, Pubkey)>>();
 
+        let metrics = Arc::clone(&self.metrics);
         spawn(async move {
+            // raw accounts of the previous fetch, to report only changed accounts
+            let mut last_fetched: HashMap<Pubkey, Account> = HashMap::new();
             loop {
                 Self::_fetch_multiple_accounts(
                     &rpc_client,
                     items.clone(),
                     Arc::clone(&bin),
-                    Some(tx.clone())
+                    &mut last_fetched,
+                    Some(event_bus.clone()),
+                    &metrics
                 );
 
                 let _ = sleep(Duration::from_secs(10)).await;
@@ -105,79 +237,119 @@ impl Probe {
     fn _fetch_multiple_accounts(
         rpc_client: &RpcClient,
         items: Vec<(Market, DeserializedAccount, Pubkey)>,
-        bin: Arc<Mutex<Vec<DeserializedAccount>>>,
-        tx: Option<Sender<Event>>
+        bin: Arc<Mutex<Arc<Vec<DeserializedAccount>>>>,
+        last_fetched: &mut HashMap<Pubkey, Account>,
+        event_bus: Option<EventBus>,
+        metrics: &Metrics
     ) {
         let time = Instant::now();
         let pubkeys = items.iter().map(|item| { item.2 }).collect::<Vec<Pubkey>>();
-        let accounts = Self::_fetch_accounts(&rpc_client, &pubkeys);
+        let (accounts, slot) = Self::_fetch_accounts(&rpc_client, &pubkeys, metrics);
+
+        // how far the snapshot trails the cluster
+        let start = Instant::now();
+        let cluster_slot = rpc_client.get_slot_with_commitment(CommitmentConfig::processed());
+        metrics.observe_rpc("getSlot", start, &cluster_slot);
+        if let (Ok(cluster_slot), true) = (cluster_slot, slot > 0) {
+            metrics.account_staleness.set(cluster_slot.saturating_sub(slot) as i64);
+        }
+
+        let mut changed_pubkeys: Vec<Pubkey> = Vec::new();
+        accounts.iter().enumerate().for_each(|(index, account)| {
+            if let Some(account) = account {
+                if last_fetched.get(&pubkeys[index]) != Some(account) && !changed_pubkeys.contains(&pubkeys[index]) {
+                    changed_pubkeys.push(pubkeys[index]);
+                    last_fetched.insert(pubkeys[index], account.clone());
+                }
+            }
+        });
 
         let fetched_accounts = accounts.iter().enumerate().filter(|(index, account)| {
             account.is_some()
-        }).map(|(index, account)| {
+        }).filter_map(|(index, account)| {
             let account = account.clone().unwrap();
 
             match items[index].1 {
                 DeserializedAccount::Account(_) => {
-                    DeserializedAccount::Account(DeserializedDataAccount {
+                    Some(DeserializedAccount::Account(DeserializedDataAccount {
                         pubkey: items[index].2,
                         account,
                         market: items[index].0,
-                    })
+                    }))
                 }
                 DeserializedAccount::PoolAccount(_) => {
                     let market_operation = resolve_pool_account(&items[index].0, &account.data);
-                    DeserializedAccount::PoolAccount(
+                    Some(DeserializedAccount::PoolAccount(
                         DeserializedPoolAccount {
                             pubkey: items[index].2,
                             account,
                             market: items[index].0,
                             operation: market_operation,
                         }
-                    )
+                    ))
                 }
                 DeserializedAccount::TokenAccount(_) => {
-                    DeserializedAccount::TokenAccount(DeserializedTokenAccount {
+                    Some(DeserializedAccount::TokenAccount(DeserializedTokenAccount {
                         pubkey: pubkeys[index],
                         account: account.clone(),
                         token: TokenAccount::unpack_data(&account.data),
                         market: items[index].0,
-                    })
+                    }))
                 }
                 DeserializedAccount::ConfigAccount(_) => {
-                    DeserializedAccount::ConfigAccount(
-                        resolve_pool_config_account(&items[index].0, &account.owner, pubkeys[index], &account.data)
-                    )
+                    // an account the market does not recognize is left out, the pools reading it fail to quote
+                    match resolve_pool_config_account(&items[index].0, &account.owner, pubkeys[index], &account.data) {
+                        Ok(config) => Some(DeserializedAccount::ConfigAccount(config)),
+                        Err(err) => {
+                            eprintln!("probe: skipped {}: {}", pubkeys[index], err);
+                            None
+                        }
+                    }
                 }
             }
         }).collect::<Vec<DeserializedAccount>>();
 
-        // todo: replace not overwrite
-        *bin.lock().unwrap() = fetched_accounts;
-        if let Some(tx) = tx {
-            tx.send(Event::UpdateAccounts).expect("broadcast: failed to broadcast Event::UpdateAccounts");
+        // swap in a new snapshot, readers keep the one they hold
+        *bin.lock().unwrap() = Arc::new(fetched_accounts);
+        if let Some(event_bus) = event_bus {
+            if !changed_pubkeys.is_empty() {
+                event_bus.publish(Event::AccountsUpdated {
+                    pubkeys: changed_pubkeys.clone(),
+                    slot
+                });
+            }
         }
 
-        println!("probe: accounts fetched ({:?})", time.elapsed());
+        println!("probe: accounts fetched, {} changed ({:?})", changed_pubkeys.len(), time.elapsed());
     }
 
+    // returns accounts in the order of pubkeys, and the lowest slot the chunks were fetched at
     fn _fetch_accounts(
         rpc_client: &RpcClient,
-        pubkeys: &Vec<Pubkey>
-    ) -> Vec<Option<Account>> {
+        pubkeys: &Vec<Pubkey>,
+        metrics: &Metrics
+    ) -> (Vec<Option<Account>>, u64) {
         let mut vec: Vec<Option<Account>> = Vec::new();
+        let mut slot: Option<u64> = None;
 
         pubkeys.chunks(99).for_each(|pubkeys| {
-            match rpc_client.get_multiple_accounts(pubkeys) {
-                Ok(mut accounts) => {
-                    vec.append(accounts.as_mut())
+            let start = Instant::now();
+            let response = rpc_client.get_multiple_accounts_with_commitment(pubkeys, CommitmentConfig::confirmed());
+            metrics.observe_rpc("getMultipleAccounts", start, &response);
+
+            match response {
+                Ok(mut response) => {
+                    slot = Some(slot.map_or(response.context.slot, |slot| slot.min(response.context.slot)));
+                    vec.append(response.value.as_mut())
                 }
                 Err(err) => {
                     eprintln!("probe: failed to fetch pubkeys: {}", err);
+                    // keep indexes aligned with pubkeys
+                    vec.append(&mut vec![None; pubkeys.len()]);
                 }
             }
         });
 
-        vec
+        (vec, slot.unwrap_or_default())
     }
 }
\ No newline at end of file
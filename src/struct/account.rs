@@ -1,13 +1,15 @@
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar::clock;
 use crate::formula::base::Formula;
 
 use crate::formula::clmm::constant::TICK_ARRAY_SEED;
 use crate::formula::clmm::orca_swap_state::{get_tick_array_public_keys_with_start_tick_index, TICK_ARRAY_SIZE, TickArray, TickArrayAccount};
 use crate::formula::clmm::raydium_tick_array::{TickArrayBitmapExtension, TickArrayBitmapExtensionAccount, TickArrayState, TickArrayStateAccount};
 use crate::r#struct::market::{Market, PoolOperation};
-use crate::r#struct::pools::{OrcaClmmAccount, OrcaClmmMarket, RaydiumClmmAccount, RaydiumClmmMarket};
+use crate::r#struct::pools::{MeteoraAccount, MeteoraDlmmMarket, OpenBookV2Account, OrcaClmmAccount, OrcaClmmMarket, RaydiumClmmAccount, RaydiumClmmMarket, RaydiumCpmmAccount, RaydiumOpenBookAccount, RaydiumOpenBookMarket, SerumMarket, WhirlpoolOracle, WhirlpoolOracleAccount};
 use crate::r#struct::resolver::resolve_pool_account;
 use crate::r#struct::token::TokenAccount;
 
@@ -60,7 +62,11 @@ impl DeserializedAccount {
 #[derive(Clone, Default, PartialEq)]
 pub enum DeserializedConfigAccount {
     RaydiumClmmConfigAccount(RaydiumClmmAccount),
+    RaydiumCpmmConfigAccount(RaydiumCpmmAccount),
     OrcaClmmConfigAccount(OrcaClmmAccount),
+    MeteoraConfigAccount(MeteoraAccount),
+    OpenBookConfigAccount(OpenBookV2Account),
+    RaydiumOpenBookConfigAccount(RaydiumOpenBookAccount),
     #[default]
     EmptyConfigAccount
 }
@@ -71,9 +77,21 @@ impl DeserializedConfigAccount {
             DeserializedConfigAccount::RaydiumClmmConfigAccount(account) => {
                 account.get_pubkey()
             }
+            DeserializedConfigAccount::RaydiumCpmmConfigAccount(account) => {
+                account.get_pubkey()
+            }
             DeserializedConfigAccount::OrcaClmmConfigAccount(account) => {
                 account.get_pubkey()
             }
+            DeserializedConfigAccount::MeteoraConfigAccount(account) => {
+                account.get_pubkey()
+            }
+            DeserializedConfigAccount::OpenBookConfigAccount(account) => {
+                account.get_pubkey()
+            }
+            DeserializedConfigAccount::RaydiumOpenBookConfigAccount(account) => {
+                account.get_pubkey()
+            }
             _ => {
                 Pubkey::default()
             }
@@ -85,9 +103,21 @@ impl DeserializedConfigAccount {
             DeserializedConfigAccount::RaydiumClmmConfigAccount(account) => {
                 account.get_market()
             }
+            DeserializedConfigAccount::RaydiumCpmmConfigAccount(account) => {
+                account.get_market()
+            }
             DeserializedConfigAccount::OrcaClmmConfigAccount(account) => {
                 account.get_market()
             }
+            DeserializedConfigAccount::MeteoraConfigAccount(account) => {
+                account.get_market()
+            }
+            DeserializedConfigAccount::OpenBookConfigAccount(account) => {
+                account.get_market()
+            }
+            DeserializedConfigAccount::RaydiumOpenBookConfigAccount(account) => {
+                account.get_market()
+            }
             _ => {
                 Market::UNKNOWN
             }
@@ -103,6 +133,15 @@ pub struct DeserializedPoolAccount {
     pub operation: Box<dyn PoolOperation>
 }
 
+/// Identifies an edge of the path graph, a pool and the two mints it trades.
+/// Edges of a multi-asset pool share its pubkey and differ by their mints
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct PoolEdge {
+    pub pubkey: Pubkey,
+    /// mints of the pair, the lower first
+    pub mints: (Pubkey, Pubkey),
+}
+
 impl DeserializedPoolAccount {
     pub fn get_swap_related_pubkeys(&self, rpc_client: Option<&RpcClient>) -> Result<Vec<(DeserializedAccount, Pubkey)>, &'static str> {
         match self.market {
@@ -114,9 +153,9 @@ impl DeserializedPoolAccount {
 
                 if self.operation.get_formula() == Formula::ConcentratedLiquidity {
                     // let accounts = rpc_client.unwrap().get_multiple_accounts(&[self.pubkey, tick_array_bitmap_extension_pubkey]).expect("failed to fetch accounts");
-                    let pool_account = rpc_client.unwrap().get_account(&self.pubkey).expect("failed to fetch pool");
+                    let pool_account = rpc_client.ok_or("rpc client not given")?.get_account(&self.pubkey).or(Err("failed to fetch pool"))?;
                     let pool = resolve_pool_account(&Market::ORCA, &pool_account.data);
-                    let market = pool.as_any().downcast_ref::<OrcaClmmMarket>().expect("failed to downcast");
+                    let market = pool.as_any().downcast_ref::<OrcaClmmMarket>().ok_or("failed to downcast")?;
 
                     for i in 0..2 {
                         let zero_for_one: bool = if i % 2 == 0 { true } else { false };
@@ -133,6 +172,13 @@ impl DeserializedPoolAccount {
                             ));
                         });
                     }
+
+                    if market.is_initialized_with_adaptive_fee() {
+                        vec.push((
+                            DeserializedAccount::ConfigAccount(DeserializedConfigAccount::OrcaClmmConfigAccount(OrcaClmmAccount::Oracle(WhirlpoolOracleAccount::default()))),
+                            WhirlpoolOracle::key(&self.account.owner, &self.pubkey)
+                        ));
+                    }
                 }
 
                 Ok(vec)
@@ -146,23 +192,23 @@ impl DeserializedPoolAccount {
                 // since this step does not know swap direction, find both ways of tick array states pubkeys
                 if self.operation.get_formula() == Formula::ConcentratedLiquidity {
                     // get tick array states
-                    let tick_array_bitmap_extension_pubkey = TickArrayBitmapExtension::key(&self.account.owner, &self.pubkey).expect("failed to get tick_array_bitmap_extension pubkey");
-                    let accounts = rpc_client.unwrap().get_multiple_accounts(&[self.pubkey, tick_array_bitmap_extension_pubkey]).expect("failed to fetch accounts");
+                    let tick_array_bitmap_extension_pubkey = TickArrayBitmapExtension::key(&self.account.owner, &self.pubkey).ok_or("failed to get tick_array_bitmap_extension pubkey")?;
+                    let accounts = rpc_client.ok_or("rpc client not given")?.get_multiple_accounts(&[self.pubkey, tick_array_bitmap_extension_pubkey]).or(Err("failed to fetch accounts"))?;
 
-                    let tick_array_bitmap_extension_account = accounts[1].to_owned().expect("failed to fetch tick_array_bitmap_extension");
+                    let tick_array_bitmap_extension_account = accounts[1].to_owned().ok_or("failed to fetch tick_array_bitmap_extension")?;
                     let tick_array_bitmap_extension = TickArrayBitmapExtension::unpack_data(&tick_array_bitmap_extension_account.data);
                     vec.push((DeserializedAccount::ConfigAccount(DeserializedConfigAccount::RaydiumClmmConfigAccount(RaydiumClmmAccount::TickArrayBitmapExtension(TickArrayBitmapExtensionAccount::default()))), tick_array_bitmap_extension_pubkey));
 
-                    let pool_account = accounts[0].to_owned().expect("failed to fetch pool");
+                    let pool_account = accounts[0].to_owned().ok_or("failed to fetch pool")?;
                     let pool = resolve_pool_account(&Market::RAYDIUM, &pool_account.data);
-                    let market = pool.as_any().downcast_ref::<RaydiumClmmMarket>().expect("failed to downcast");
+                    let market = pool.as_any().downcast_ref::<RaydiumClmmMarket>().ok_or("failed to downcast")?;
 
                     for i in 0..2 {
                         let zero_for_one = if i % 2 == 0 { true } else { false };
 
                         let (_, mut current_valid_tick_array_start_index) = market.get_first_initialized_tick_array(
                             &Some(&tick_array_bitmap_extension), true
-                        ).unwrap();
+                        )?;
                         let current_tick_array_state = TickArrayState::key(
                             &self.account.owner,
                             &[
@@ -170,7 +216,7 @@ impl DeserializedPoolAccount {
                                 &self.pubkey.as_ref(),
                                 &current_valid_tick_array_start_index.to_be_bytes()
                             ]
-                        ).expect("failed to get current_tick_array_state");
+                        ).ok_or("failed to get current_tick_array_state")?;
                         vec.push((DeserializedAccount::ConfigAccount(DeserializedConfigAccount::RaydiumClmmConfigAccount(RaydiumClmmAccount::TickArrayState(TickArrayStateAccount::default()))), current_tick_array_state));
 
                         for _ in 0..3 {
@@ -178,12 +224,12 @@ impl DeserializedPoolAccount {
                                 &Some(&tick_array_bitmap_extension),
                                 current_valid_tick_array_start_index,
                                 zero_for_one
-                            ).expect("failed to get next_tick_array_index");
+                            )?;
 
-                            if next_tick_array_index.is_none() {
-                                break;
-                            }
-                            current_valid_tick_array_start_index = next_tick_array_index.unwrap();
+                            current_valid_tick_array_start_index = match next_tick_array_index {
+                                Some(next_tick_array_index) => next_tick_array_index,
+                                None => break
+                            };
                             let tick_array_state = TickArrayState::key(
                                 &self.account.owner,
                                 &[
@@ -191,22 +237,60 @@ impl DeserializedPoolAccount {
                                     &self.pubkey.as_ref(),
                                     &current_valid_tick_array_start_index.to_be_bytes()
                                 ]
-                            ).expect("failed to get tick_array_state");
+                            ).ok_or("failed to get tick_array_state")?;
                             // todo tick_array_state array does not need to have 5 items
                             vec.push((DeserializedAccount::ConfigAccount(DeserializedConfigAccount::RaydiumClmmConfigAccount(RaydiumClmmAccount::TickArrayState(TickArrayStateAccount::default()))), tick_array_state));
                         }
                     }
                 }
 
+                // the AMM's funds on the book are read through the market's event queue, bids and asks
+                if self.operation.get_formula() == Formula::OpenBook {
+                    let pool = self.operation.as_any().downcast_ref::<RaydiumOpenBookMarket>().ok_or("failed to downcast")?;
+                    if pool.orderbook_permission() {
+                        let market_account = rpc_client.ok_or("rpc client not given")?.get_account(&pool.market_id).or(Err("failed to fetch market"))?;
+                        let market = SerumMarket::unpack_data(&market_account.data);
+                        vec.append(&mut RaydiumOpenBookMarket::get_market_related_pubkeys(&market));
+                    }
+                }
+
                 Ok(vec)
             }
-            Market::METEORA | Market::LIFINITY => { todo!() }
+            Market::METEORA => {
+                let mut vec = vec![
+                    (DeserializedAccount::PoolAccount(DeserializedPoolAccount::default()), self.pubkey)
+                ];
+                vec.append(&mut self.operation.get_swap_related_pubkeys());
+
+                if let Some(lb_pair) = self.operation.as_any().downcast_ref::<MeteoraDlmmMarket>() {
+                    vec.append(&mut lb_pair.get_bin_array_related_pubkeys(&self.pubkey));
+                }
+
+                Ok(vec)
+            }
+            Market::PHOENIX | Market::OPENBOOK | Market::SABER | Market::STAKE_POOL | Market::MARINADE | Market::SANCTUM | Market::PUMP_FUN | Market::PUMP_SWAP => {
+                let mut vec = vec![
+                    (DeserializedAccount::PoolAccount(DeserializedPoolAccount::default()), self.pubkey)
+                ];
+                vec.append(&mut self.operation.get_swap_related_pubkeys());
+
+                Ok(vec)
+            }
+            Market::LIFINITY => { todo!() }
             Market::UNKNOWN => { Err("unknown market") }
         }
     }
 
+    pub fn get_edge(&self) -> PoolEdge {
+        let pair = self.operation.get_mint_pair();
+        PoolEdge {
+            pubkey: self.pubkey,
+            mints: (pair.pubkey_a.min(pair.pubkey_b), pair.pubkey_a.max(pair.pubkey_b)),
+        }
+    }
+
     pub fn equals(&self, to: &DeserializedPoolAccount) -> bool {
-        self.pubkey == to.pubkey
+        self.get_edge() == to.get_edge()
     }
 }
 
@@ -231,6 +315,18 @@ impl DeserializedTokenAccount {
     }
 }
 
+/// Clock sysvar among `accounts`, pools quoting against time or slot list it in their swap-related pubkeys
+pub fn get_clock(accounts: &Vec<DeserializedAccount>) -> Option<Clock> {
+    accounts.iter().find_map(|account| {
+        match account {
+            DeserializedAccount::Account(account) if account.pubkey == clock::id() => {
+                bincode::deserialize::<Clock>(&account.account.data).ok()
+            }
+            _ => None
+        }
+    })
+}
+
 pub trait AccountDataSerializer {
     fn unpack_data(data: &Vec<u8>) -> Self;
 }
\ No newline at end of file
@@ -5,10 +5,11 @@ use dyn_clone::DynClone;
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use solana_sdk::account::Account;
+use solana_sdk::instruction::Instruction;
 use solana_sdk::pubkey::Pubkey;
 
 use crate::r#struct::account::{DeserializedAccount};
-use crate::formula::base::Formula;
+use crate::formula::base::{Formula, SwapQuote};
 use crate::utils::PubkeyPair;
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Default, Deserialize)]
@@ -17,6 +18,14 @@ pub enum Market {
     RAYDIUM,
     METEORA,
     LIFINITY,
+    PHOENIX,
+    OPENBOOK,
+    SABER,
+    STAKE_POOL,
+    MARINADE,
+    SANCTUM,
+    PUMP_FUN,
+    PUMP_SWAP,
     #[default]
     UNKNOWN
 }
@@ -28,6 +37,14 @@ impl Market {
             Market::RAYDIUM => Market::RAYDIUM,
             Market::METEORA => Market::METEORA,
             Market::LIFINITY => Market::LIFINITY,
+            Market::PHOENIX => Market::PHOENIX,
+            Market::OPENBOOK => Market::OPENBOOK,
+            Market::SABER => Market::SABER,
+            Market::STAKE_POOL => Market::STAKE_POOL,
+            Market::MARINADE => Market::MARINADE,
+            Market::SANCTUM => Market::SANCTUM,
+            Market::PUMP_FUN => Market::PUMP_FUN,
+            Market::PUMP_SWAP => Market::PUMP_SWAP,
             Market::UNKNOWN => Market::UNKNOWN,
         }
     }
@@ -40,6 +57,14 @@ impl Market {
             Market::RAYDIUM => String::from("RAYDIUM"),
             Market::METEORA => String::from("METEORA"),
             Market::LIFINITY => String::from("LIFINITY"),
+            Market::PHOENIX => String::from("PHOENIX"),
+            Market::OPENBOOK => String::from("OPENBOOK"),
+            Market::SABER => String::from("SABER"),
+            Market::STAKE_POOL => String::from("STAKE_POOL"),
+            Market::MARINADE => String::from("MARINADE"),
+            Market::SANCTUM => String::from("SANCTUM"),
+            Market::PUMP_FUN => String::from("PUMP_FUN"),
+            Market::PUMP_SWAP => String::from("PUMP_SWAP"),
             Market::UNKNOWN => String::from("UNKNOWN"),
         }
     }
@@ -51,8 +76,46 @@ pub trait PoolOperation: DynClone + Sync + Send {
     fn get_mint_pair(&self) -> PubkeyPair;
     fn get_pool_pair(&self) -> PubkeyPair;
     fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)>;
+    /// Accounts every swap through the pool passes besides the pool itself, e.g. vaults, configs and oracles.
+    /// Tick and bin arrays move with the price and are left out
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        let pool_pair = self.get_pool_pair();
+        vec![pool_pair.pubkey_a, pool_pair.pubkey_b]
+    }
     fn get_formula(&self) -> Formula;
-    fn swap(&self, accounts: &Vec<DeserializedAccount>);
+    /// Fee rate in hundredths of a basis point (1_000_000 = 100%), `None` if the pool account does not hold it
+    fn get_fee_rate(&self) -> Option<u32> {
+        None
+    }
+    /// Active liquidity held by the pool account, `None` if the formula has no such value
+    fn get_liquidity(&self) -> Option<u128> {
+        None
+    }
+    /// Whether the pool currently accepts swaps
+    fn is_tradable(&self) -> bool {
+        true
+    }
+    /// Quotes an exact-in swap of `amount_in` of `mint_in` against the given swap-related accounts
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str>;
+    /// Whether `get_swap_instructions` is implemented, pools without it can be quoted but not traded
+    fn has_swap_instructions(&self) -> bool {
+        false
+    }
+    /// Instructions swapping exactly `amount_in` of `mint_in` through the pool account `pool` for at least `minimum_amount_out`,
+    /// `user` signing and paying from `source_token_account` into `destination_token_account`.
+    /// Markets without an instruction builder return an error
+    fn get_swap_instructions(
+        &self,
+        _pool: &Pubkey,
+        _user: &Pubkey,
+        _source_token_account: &Pubkey,
+        _destination_token_account: &Pubkey,
+        _mint_in: &Pubkey,
+        _amount_in: u64,
+        _minimum_amount_out: u64
+    ) -> Result<Vec<Instruction>, &'static str> {
+        Err("swap instructions not supported")
+    }
     fn as_any(&self) -> &dyn Any;
 }
 
@@ -92,7 +155,9 @@ impl PoolOperation for DefaultMarket {
         Formula::default()
     }
 
-    fn swap(&self, accounts: &Vec<DeserializedAccount>) {}
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        Err("unknown market")
+    }
 
     fn as_any(&self) -> &dyn Any {
         self
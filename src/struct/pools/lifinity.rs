@@ -3,7 +3,7 @@ use arrayref::{array_ref, array_refs};
 use solana_sdk::pubkey::Pubkey;
 
 use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount, DeserializedTokenAccount};
-use crate::formula::base::Formula;
+use crate::formula::base::{Formula, SwapQuote};
 use crate::formula::base::Formula::ConcentratedLiquidity;
 use crate::r#struct::market::PoolOperation;
 use crate::utils::PubkeyPair;
@@ -107,8 +107,19 @@ impl PoolOperation for LifinityMarket {
         ConcentratedLiquidity
     }
 
-    fn swap(&self, accounts: &Vec<DeserializedAccount>) {
-        todo!()
+    fn get_fee_rate(&self) -> Option<u32> {
+        if self.fees.trade_fee_denominator == 0 {
+            return None;
+        }
+        u32::try_from(self.fees.trade_fee_numerator as u128 * 1_000_000 / self.fees.trade_fee_denominator as u128).ok()
+    }
+
+    fn is_tradable(&self) -> bool {
+        self.freeze_trade == 0
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        Err("LifinityMarket: swap is not supported")
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -0,0 +1,230 @@
+use std::any::Any;
+use std::str::FromStr;
+
+use arrayref::{array_ref, array_refs};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::constants::{MARINADE_LIQ_POOL_SOL_LEG_SEED, MARINADE_PROGRAM_PUBKEY, MARINADE_STATE_PUBKEY, NATIVE_MINT_PUBKEY};
+use crate::formula::base::{Formula, SwapQuote};
+use crate::formula::stake_pool;
+use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount, DeserializedDataAccount};
+use crate::r#struct::market::PoolOperation;
+use crate::utils::PubkeyPair;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MarinadeFee { // 4
+    pub basis_points: u32, // 4
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MarinadeState {
+    pub msol_mint: Pubkey, // 32
+    pub admin_authority: Pubkey, // 32
+    pub operational_sol_account: Pubkey, // 32
+    pub treasury_msol_account: Pubkey, // 32
+    pub reserve_bump_seed: u8, // 1
+    pub msol_mint_authority_bump_seed: u8, // 1
+    pub rent_exempt_for_token_acc: u64, // 8
+    pub reward_fee: MarinadeFee, // 4
+    // stake system, 114
+    pub stake_list: Pubkey, // 32
+    pub delayed_unstake_cooling_down: u64, // 8
+    // validator system, 121
+    pub validator_list: Pubkey, // 32
+    pub total_active_balance: u64, // 8
+    // liquidity pool, 111
+    pub lp_mint: Pubkey, // 32
+    pub msol_leg: Pubkey, // 32
+    pub lp_liquidity_target: u64, // 8
+    pub lp_max_fee: MarinadeFee, // 4
+    pub lp_min_fee: MarinadeFee, // 4
+    pub treasury_cut: MarinadeFee, // 4
+    pub lp_supply: u64, // 8
+    pub lent_from_sol_leg: u64, // 8
+    pub liquidity_sol_cap: u64, // 8
+    pub available_reserve_balance: u64, // 8
+    pub msol_supply: u64, // 8
+    pub msol_price: u64, // 8
+    pub circulating_ticket_count: u64, // 8
+    pub circulating_ticket_balance: u64, // 8
+    pub lent_from_reserve: u64, // 8
+    pub min_deposit: u64, // 8
+    pub min_withdraw: u64, // 8
+    pub staking_sol_cap: u64, // 8
+    pub emergency_cooling_down: u64, // 8
+    pub pause_authority: Pubkey, // 32
+    pub paused: bool, // 1
+    /// hundredths of a basis point, taken from mSOL redeemed through delayed unstake tickets
+    pub delayed_unstake_fee: u32, // 4
+}
+
+impl AccountDataSerializer for MarinadeState {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 613];
+        let (
+            _discriminator,
+            msol_mint,
+            admin_authority,
+            operational_sol_account,
+            treasury_msol_account,
+            reserve_bump_seed,
+            msol_mint_authority_bump_seed,
+            rent_exempt_for_token_acc,
+            reward_fee,
+            stake_list,
+            _stake_list_rest,
+            delayed_unstake_cooling_down,
+            _stake_system_rest,
+            validator_list,
+            _validator_list_rest,
+            _manager_authority_and_score,
+            total_active_balance,
+            _auto_add_validator_enabled,
+            lp_mint,
+            _liq_pool_bump_seeds,
+            msol_leg,
+            lp_liquidity_target,
+            lp_max_fee,
+            lp_min_fee,
+            treasury_cut,
+            lp_supply,
+            lent_from_sol_leg,
+            liquidity_sol_cap,
+            available_reserve_balance,
+            msol_supply,
+            msol_price,
+            circulating_ticket_count,
+            circulating_ticket_balance,
+            lent_from_reserve,
+            min_deposit,
+            min_withdraw,
+            staking_sol_cap,
+            emergency_cooling_down,
+            pause_authority,
+            paused,
+            delayed_unstake_fee
+        ) = array_refs![
+            src, 8, 32, 32, 32, 32, 1, 1, 8, 4,
+            32, 44, 8, 30,
+            32, 44, 36, 8, 1,
+            32, 3, 32, 8, 4, 4, 4, 8, 8, 8,
+            8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 32, 1, 4
+        ];
+
+        MarinadeState {
+            msol_mint: Pubkey::new_from_array(*msol_mint),
+            admin_authority: Pubkey::new_from_array(*admin_authority),
+            operational_sol_account: Pubkey::new_from_array(*operational_sol_account),
+            treasury_msol_account: Pubkey::new_from_array(*treasury_msol_account),
+            reserve_bump_seed: reserve_bump_seed[0],
+            msol_mint_authority_bump_seed: msol_mint_authority_bump_seed[0],
+            rent_exempt_for_token_acc: u64::from_le_bytes(*rent_exempt_for_token_acc),
+            reward_fee: MarinadeFee { basis_points: u32::from_le_bytes(*reward_fee) },
+            stake_list: Pubkey::new_from_array(*stake_list),
+            delayed_unstake_cooling_down: u64::from_le_bytes(*delayed_unstake_cooling_down),
+            validator_list: Pubkey::new_from_array(*validator_list),
+            total_active_balance: u64::from_le_bytes(*total_active_balance),
+            lp_mint: Pubkey::new_from_array(*lp_mint),
+            msol_leg: Pubkey::new_from_array(*msol_leg),
+            lp_liquidity_target: u64::from_le_bytes(*lp_liquidity_target),
+            lp_max_fee: MarinadeFee { basis_points: u32::from_le_bytes(*lp_max_fee) },
+            lp_min_fee: MarinadeFee { basis_points: u32::from_le_bytes(*lp_min_fee) },
+            treasury_cut: MarinadeFee { basis_points: u32::from_le_bytes(*treasury_cut) },
+            lp_supply: u64::from_le_bytes(*lp_supply),
+            lent_from_sol_leg: u64::from_le_bytes(*lent_from_sol_leg),
+            liquidity_sol_cap: u64::from_le_bytes(*liquidity_sol_cap),
+            available_reserve_balance: u64::from_le_bytes(*available_reserve_balance),
+            msol_supply: u64::from_le_bytes(*msol_supply),
+            msol_price: u64::from_le_bytes(*msol_price),
+            circulating_ticket_count: u64::from_le_bytes(*circulating_ticket_count),
+            circulating_ticket_balance: u64::from_le_bytes(*circulating_ticket_balance),
+            lent_from_reserve: u64::from_le_bytes(*lent_from_reserve),
+            min_deposit: u64::from_le_bytes(*min_deposit),
+            min_withdraw: u64::from_le_bytes(*min_withdraw),
+            staking_sol_cap: u64::from_le_bytes(*staking_sol_cap),
+            emergency_cooling_down: u64::from_le_bytes(*emergency_cooling_down),
+            pause_authority: Pubkey::new_from_array(*pause_authority),
+            paused: paused[0] != 0,
+            delayed_unstake_fee: u32::from_le_bytes(*delayed_unstake_fee),
+        }
+    }
+}
+
+impl MarinadeState {
+    /// System account holding the SOL side of the liquidity pool, liquid unstakes are paid from it
+    pub fn liq_pool_sol_leg() -> Pubkey {
+        let program_id = Pubkey::from_str(MARINADE_PROGRAM_PUBKEY).unwrap();
+        let state = Pubkey::from_str(MARINADE_STATE_PUBKEY).unwrap();
+        Pubkey::find_program_address(&[state.as_ref(), MARINADE_LIQ_POOL_SOL_LEG_SEED.as_bytes()], &program_id).0
+    }
+}
+
+impl PoolOperation for MarinadeState {
+    fn get_mint_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap(),
+            pubkey_b: self.msol_mint,
+        }
+    }
+
+    fn get_pool_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: Self::liq_pool_sol_leg(),
+            pubkey_b: self.msol_leg,
+        }
+    }
+
+    fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
+        // lamports of the sol leg bound and price liquid unstakes
+        vec![
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), Self::liq_pool_sol_leg()),
+        ]
+    }
+
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        vec![
+            Self::liq_pool_sol_leg(),
+            self.msol_leg,
+            self.msol_mint,
+            self.treasury_msol_account,
+        ]
+    }
+
+    fn get_formula(&self) -> Formula {
+        Formula::StakePool
+    }
+
+    fn is_tradable(&self) -> bool {
+        !self.paused
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let state = accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(account) => account.operation.as_any().downcast_ref::<MarinadeState>(),
+                _ => None
+            }
+        }).unwrap_or(self);
+
+        let amount_out = if *mint_in == Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap() {
+            stake_pool::quote_marinade_deposit(state, amount_in)?
+        } else if *mint_in == state.msol_mint {
+            let sol_leg = Self::liq_pool_sol_leg();
+            let sol_leg_lamports = accounts.iter().find_map(|account| {
+                match account {
+                    DeserializedAccount::Account(account) if account.pubkey == sol_leg => Some(account.account.lamports),
+                    _ => None
+                }
+            }).ok_or("liq pool sol leg not found")?;
+            stake_pool::quote_marinade_liquid_unstake(state, amount_in, sol_leg_lamports)?
+        } else {
+            return Err("mint not in pool");
+        };
+
+        Ok(SwapQuote { amount_in, amount_out, crossings: 0 })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
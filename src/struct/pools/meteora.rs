@@ -1,9 +1,13 @@
 use std::any::Any;
+use std::collections::HashMap;
 use std::ops::{BitXor, Shl, Shr};
+use std::str::FromStr;
 use arrayref::{array_ref, array_refs};
 use ruint::aliases::U1024;
 use solana_sdk::pubkey::Pubkey;
-use crate::formula::base::Formula;
+use solana_sdk::sysvar::clock;
+use crate::constants::{METEORA_DLMM_BIN_ARRAY, METEORA_DLMM_BIN_ARRAY_BITMAP_EXTENSION, METEORA_VAULT_DATA_LEN, METEORA_VAULT_PROGRAM_PUBKEY};
+use crate::formula::base::{Formula, SwapQuote};
 use crate::formula::base::Formula::DynamicLiquidity;
 use crate::formula::dlmm::bin::{BinArray};
 use crate::formula::dlmm::bin_array_bitmap_extension::BinArrayBitmapExtension;
@@ -11,9 +15,12 @@ use crate::formula::dlmm::constant::{BASIS_POINT_MAX, BIN_ARRAY_BITMAP_SIZE, FEE
 use crate::formula::dlmm::safe_math::SafeMath;
 use crate::formula::dlmm::u128x128_math::Rounding;
 use crate::formula::dlmm::utils_math::{one, safe_mul_div_cast};
-use crate::formula::meteora_dlmm::{PairStatus, PairType};
-use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount, DeserializedConfigAccount, DeserializedTokenAccount};
-use crate::r#struct::market::{PoolOperation};
+use crate::formula::meteora_damm::VaultShare;
+use crate::formula::meteora_dlmm::{derive_bin_array_bitmap_extension_pda, get_bin_array_pubkeys_for_swap, quote_exact_in, PairStatus, PairType};
+use crate::formula::{meteora_damm, meteora_damm_v2};
+use crate::r#struct::account::{get_clock, AccountDataSerializer, DeserializedAccount, DeserializedConfigAccount, DeserializedDataAccount, DeserializedTokenAccount};
+use crate::r#struct::account::DeserializedConfigAccount::MeteoraConfigAccount;
+use crate::r#struct::market::{Market, PoolOperation};
 use crate::utils::{PubkeyPair};
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -117,8 +124,8 @@ impl PoolOperation for MeteoraClmmMarket {
         DynamicLiquidity
     }
 
-    fn swap(&self, accounts: &Vec<DeserializedAccount>) {
-        todo!()
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        Err("MeteoraClmmMarket: swap is not supported")
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -464,15 +471,79 @@ impl PoolOperation for MeteoraDlmmMarket {
     }
 
     fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
-        todo!()
+        // the bin arrays depend on the pool pubkey, see get_bin_array_related_pubkeys
+        vec![
+            // the volatility decays and the activation point passes with the chain time
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), clock::id()),
+        ]
     }
 
     fn get_formula(&self) -> Formula {
         DynamicLiquidity
     }
 
-    fn swap(&self, accounts: &Vec<DeserializedAccount>) {
-        todo!()
+    fn get_fee_rate(&self) -> Option<u32> {
+        // base fee is in FEE_PRECISION (1e9)
+        self.get_base_fee().ok().and_then(|base_fee| u32::try_from(base_fee / 1000).ok())
+    }
+
+    fn is_tradable(&self) -> bool {
+        // PairStatus::Enabled
+        self.status == 0
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let swap_for_y = if *mint_in == self.token_x_mint {
+            true
+        } else if *mint_in == self.token_y_mint {
+            false
+        } else {
+            return Err("mint not in pool");
+        };
+
+        let mut lb_pair = *self;
+        let mut lb_pair_pubkey: Option<Pubkey> = None;
+        let mut bin_arrays: HashMap<Pubkey, BinArray> = HashMap::new();
+        let mut bitmap_extension: Option<BinArrayBitmapExtension> = None;
+
+        accounts.iter().for_each(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(pool) => {
+                    if let Some(market) = pool.operation.as_any().downcast_ref::<MeteoraDlmmMarket>() {
+                        lb_pair = *market;
+                        lb_pair_pubkey = Some(pool.pubkey);
+                    }
+                }
+                DeserializedAccount::Account(account) => {
+                    match account.account.data.len() {
+                        METEORA_DLMM_BIN_ARRAY => {
+                            bin_arrays.insert(account.pubkey, BinArray::unpack_data(&account.account.data));
+                        }
+                        METEORA_DLMM_BIN_ARRAY_BITMAP_EXTENSION => {
+                            bitmap_extension = Some(BinArrayBitmapExtension::unpack_data(&account.account.data));
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        });
+
+        // bin arrays are derived from the pool pubkey
+        let lb_pair_pubkey = lb_pair_pubkey.ok_or("pool account not found")?;
+        let clock = get_clock(accounts).ok_or("clock not found")?;
+        let quote = quote_exact_in(
+            lb_pair_pubkey,
+            &lb_pair,
+            amount_in,
+            swap_for_y,
+            bin_arrays,
+            bitmap_extension.as_ref(),
+            clock.unix_timestamp as u64,
+            clock.slot,
+        )?;
+
+        Ok(SwapQuote { amount_in, amount_out: quote.amount_out, crossings: quote.bins_crossed })
     }
 
     fn as_any(&self) -> &dyn Any { self }
@@ -507,6 +578,23 @@ impl MeteoraDlmmMarket {
         Ok(pair_type)
     }
 
+    /// Bitmap extension and the bin arrays holding liquidity next to the active bin, in both swap directions.
+    /// Bin arrays past the internal bitmap are only found once the extension is known, so they are not listed
+    pub fn get_bin_array_related_pubkeys(&self, lb_pair_pubkey: &Pubkey) -> Vec<(DeserializedAccount, Pubkey)> {
+        let mut pubkeys = vec![derive_bin_array_bitmap_extension_pda(*lb_pair_pubkey).0];
+        [true, false].iter().for_each(|swap_for_y| {
+            get_bin_array_pubkeys_for_swap(*lb_pair_pubkey, self, None, *swap_for_y, 3).unwrap_or_default().into_iter().for_each(|pubkey| {
+                if !pubkeys.contains(&pubkey) {
+                    pubkeys.push(pubkey);
+                }
+            });
+        });
+
+        pubkeys.into_iter().map(|pubkey| {
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), pubkey)
+        }).collect()
+    }
+
     pub fn swap_for_y(&self, out_token_mint: Pubkey) -> bool {
         out_token_mint.eq(&self.token_y_mint)
     }
@@ -818,4 +906,563 @@ impl MeteoraDlmmMarket {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+/////////////////////////////////////////////////////////////////////////////////////////////////
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DammPoolFees { // 32
+    pub trade_fee_numerator: u64, // 8
+    pub trade_fee_denominator: u64, // 8
+    pub protocol_trade_fee_numerator: u64, // 8
+    pub protocol_trade_fee_denominator: u64, // 8
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DammBootstrapping { // 73
+    pub activation_point: u64, // 8
+    pub whitelisted_vault: Pubkey, // 32
+    pub pool_creator: Pubkey, // 32
+    pub activation_type: u8, // 1
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DammTokenMultiplier { // 17
+    pub token_a_multiplier: u64, // 8
+    pub token_b_multiplier: u64, // 8
+    pub precision_factor: u8, // 1
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DammDepeg { // 17
+    pub base_virtual_price: u64, // 8
+    pub base_cache_updated: u64, // 8
+    pub depeg_type: u8, // 1
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub enum DammCurveType {
+    #[default]
+    ConstantProduct,
+    Stable {
+        amp: u64,
+        token_multiplier: DammTokenMultiplier,
+        depeg: DammDepeg,
+        last_amp_updated_timestamp: u64,
+    },
+}
+
+impl DammCurveType {
+    pub fn unpack_data(data: &[u8; 51]) -> DammCurveType {
+        let (tag, amp, token_a_multiplier, token_b_multiplier, precision_factor, base_virtual_price, base_cache_updated, depeg_type, last_amp_updated_timestamp) =
+            array_refs![data, 1, 8, 8, 8, 1, 8, 8, 1, 8];
+
+        match tag[0] {
+            1 => DammCurveType::Stable {
+                amp: u64::from_le_bytes(*amp),
+                token_multiplier: DammTokenMultiplier {
+                    token_a_multiplier: u64::from_le_bytes(*token_a_multiplier),
+                    token_b_multiplier: u64::from_le_bytes(*token_b_multiplier),
+                    precision_factor: precision_factor[0],
+                },
+                depeg: DammDepeg {
+                    base_virtual_price: u64::from_le_bytes(*base_virtual_price),
+                    base_cache_updated: u64::from_le_bytes(*base_cache_updated),
+                    depeg_type: depeg_type[0],
+                },
+                last_amp_updated_timestamp: u64::from_le_bytes(*last_amp_updated_timestamp),
+            },
+            _ => DammCurveType::ConstantProduct
+        }
+    }
+}
+
+/// Meteora Dynamic AMM pool, its liquidity is held as LP of two dynamic vaults
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MeteoraDammMarket { // 944
+    pub lp_mint: Pubkey, // 32
+    pub token_a_mint: Pubkey, // 32
+    pub token_b_mint: Pubkey, // 32
+    pub a_vault: Pubkey, // 32
+    pub b_vault: Pubkey, // 32
+    pub a_vault_lp: Pubkey, // 32
+    pub b_vault_lp: Pubkey, // 32
+    pub a_vault_lp_bump: u8, // 1
+    pub enabled: bool, // 1
+    pub protocol_token_a_fee: Pubkey, // 32
+    pub protocol_token_b_fee: Pubkey, // 32
+    pub fee_last_updated_at: u64, // 8
+    pub fees: DammPoolFees, // 32
+    pub pool_type: u8, // 1
+    pub stake: Pubkey, // 32
+    pub total_locked_lp: u64, // 8
+    pub bootstrapping: DammBootstrapping, // 73
+    pub curve_type: DammCurveType, // 51
+}
+
+impl AccountDataSerializer for MeteoraDammMarket {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 925];
+        let (discriminator, lp_mint, token_a_mint, token_b_mint, a_vault, b_vault, a_vault_lp, b_vault_lp, a_vault_lp_bump, enabled, protocol_token_a_fee, protocol_token_b_fee, fee_last_updated_at, _padding0, fees, pool_type, stake, total_locked_lp, bootstrapping, _partner_info, _padding, curve_type) =
+            array_refs![src, 8, 32, 32, 32, 32, 32, 32, 32, 1, 1, 32, 32, 8, 24, 32, 1, 32, 8, 73, 56, 342, 51];
+        let (trade_fee_numerator, trade_fee_denominator, protocol_trade_fee_numerator, protocol_trade_fee_denominator) =
+            array_refs![fees, 8, 8, 8, 8];
+        let (activation_point, whitelisted_vault, pool_creator, activation_type) =
+            array_refs![bootstrapping, 8, 32, 32, 1];
+
+        MeteoraDammMarket {
+            lp_mint: Pubkey::new_from_array(*lp_mint),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            a_vault: Pubkey::new_from_array(*a_vault),
+            b_vault: Pubkey::new_from_array(*b_vault),
+            a_vault_lp: Pubkey::new_from_array(*a_vault_lp),
+            b_vault_lp: Pubkey::new_from_array(*b_vault_lp),
+            a_vault_lp_bump: u8::from_le_bytes(*a_vault_lp_bump),
+            enabled: enabled[0] != 0,
+            protocol_token_a_fee: Pubkey::new_from_array(*protocol_token_a_fee),
+            protocol_token_b_fee: Pubkey::new_from_array(*protocol_token_b_fee),
+            fee_last_updated_at: u64::from_le_bytes(*fee_last_updated_at),
+            fees: DammPoolFees {
+                trade_fee_numerator: u64::from_le_bytes(*trade_fee_numerator),
+                trade_fee_denominator: u64::from_le_bytes(*trade_fee_denominator),
+                protocol_trade_fee_numerator: u64::from_le_bytes(*protocol_trade_fee_numerator),
+                protocol_trade_fee_denominator: u64::from_le_bytes(*protocol_trade_fee_denominator),
+            },
+            pool_type: u8::from_le_bytes(*pool_type),
+            stake: Pubkey::new_from_array(*stake),
+            total_locked_lp: u64::from_le_bytes(*total_locked_lp),
+            bootstrapping: DammBootstrapping {
+                activation_point: u64::from_le_bytes(*activation_point),
+                whitelisted_vault: Pubkey::new_from_array(*whitelisted_vault),
+                pool_creator: Pubkey::new_from_array(*pool_creator),
+                activation_type: u8::from_le_bytes(*activation_type),
+            },
+            curve_type: DammCurveType::unpack_data(curve_type),
+        }
+    }
+}
+
+impl PoolOperation for MeteoraDammMarket {
+    fn get_mint_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.token_a_mint,
+            pubkey_b: self.token_b_mint,
+        }
+    }
+
+    fn get_pool_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.a_vault,
+            pubkey_b: self.b_vault,
+        }
+    }
+
+    fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
+        let vault_program = Pubkey::from_str(METEORA_VAULT_PROGRAM_PUBKEY).unwrap();
+
+        vec![
+            (DeserializedAccount::ConfigAccount(DeserializedConfigAccount::default()), self.a_vault),
+            (DeserializedAccount::ConfigAccount(DeserializedConfigAccount::default()), self.b_vault),
+            (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.a_vault_lp),
+            (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.b_vault_lp),
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), MeteoraVault::lp_mint_key(&self.a_vault, &vault_program)),
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), MeteoraVault::lp_mint_key(&self.b_vault, &vault_program)),
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), clock::id()),
+        ]
+    }
+
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        let vault_program = Pubkey::from_str(METEORA_VAULT_PROGRAM_PUBKEY).unwrap();
+
+        vec![
+            self.a_vault,
+            self.b_vault,
+            self.a_vault_lp,
+            self.b_vault_lp,
+            MeteoraVault::lp_mint_key(&self.a_vault, &vault_program),
+            MeteoraVault::lp_mint_key(&self.b_vault, &vault_program),
+            MeteoraVault::token_vault_key(&self.a_vault, &vault_program),
+            MeteoraVault::token_vault_key(&self.b_vault, &vault_program),
+            self.protocol_token_a_fee,
+            self.protocol_token_b_fee,
+            vault_program,
+        ]
+    }
+
+    fn get_formula(&self) -> Formula {
+        match self.curve_type {
+            DammCurveType::ConstantProduct => Formula::ConstantProduct,
+            DammCurveType::Stable { .. } => Formula::StableSwap
+        }
+    }
+
+    fn get_fee_rate(&self) -> Option<u32> {
+        if self.fees.trade_fee_denominator == 0 {
+            return None;
+        }
+        u32::try_from(self.fees.trade_fee_numerator as u128 * 1_000_000 / self.fees.trade_fee_denominator as u128).ok()
+    }
+
+    fn is_tradable(&self) -> bool {
+        self.enabled
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let a_to_b = if *mint_in == self.token_a_mint {
+            true
+        } else if *mint_in == self.token_b_mint {
+            false
+        } else {
+            return Err("mint not in pool");
+        };
+
+        let mut pool = *self;
+        let (mut vault_a, mut vault_b): (Option<MeteoraVault>, Option<MeteoraVault>) = (None, None);
+        let (mut pool_lp_a, mut pool_lp_b): (Option<u64>, Option<u64>) = (None, None);
+        let mut lp_mint_data: Vec<(Pubkey, &Vec<u8>)> = Vec::new();
+
+        accounts.iter().for_each(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(account) => {
+                    if let Some(market) = account.operation.as_any().downcast_ref::<MeteoraDammMarket>() {
+                        pool = *market;
+                    }
+                }
+                DeserializedAccount::ConfigAccount(MeteoraConfigAccount(MeteoraAccount::Vault(vault))) => {
+                    if vault.pubkey == pool.a_vault {
+                        vault_a = Some(vault.vault)
+                    } else if vault.pubkey == pool.b_vault {
+                        vault_b = Some(vault.vault)
+                    }
+                }
+                DeserializedAccount::TokenAccount(token) => {
+                    if token.pubkey == pool.a_vault_lp {
+                        pool_lp_a = Some(token.get_amount())
+                    } else if token.pubkey == pool.b_vault_lp {
+                        pool_lp_b = Some(token.get_amount())
+                    }
+                }
+                DeserializedAccount::Account(account) => {
+                    lp_mint_data.push((account.pubkey, &account.account.data))
+                }
+                _ => {}
+            }
+        });
+
+        let clock = get_clock(accounts).ok_or("clock not found")?;
+        let vault_a = vault_a.ok_or("vault a not found")?;
+        let vault_b = vault_b.ok_or("vault b not found")?;
+        let get_lp_supply = |vault: &MeteoraVault| -> Result<u64, &'static str> {
+            let (_, data) = lp_mint_data.iter().find(|(pubkey, _)| *pubkey == vault.lp_mint).ok_or("vault lp mint not found")?;
+            // supply follows the optional mint authority
+            Ok(u64::from_le_bytes(*array_ref![data.get(36..44).ok_or("invalid mint data")?, 0, 8]))
+        };
+
+        let amount_out = meteora_damm::quote_exact_in(
+            &pool,
+            &VaultShare { vault: vault_a, pool_lp_amount: pool_lp_a.ok_or("vault a lp not found")?, lp_supply: get_lp_supply(&vault_a)? },
+            &VaultShare { vault: vault_b, pool_lp_amount: pool_lp_b.ok_or("vault b lp not found")?, lp_supply: get_lp_supply(&vault_b)? },
+            amount_in,
+            a_to_b,
+            clock.unix_timestamp as u64,
+            clock.slot
+        )?;
+
+        Ok(SwapQuote { amount_in, amount_out, crossings: 0 })
+    }
+
+    fn as_any(&self) -> &dyn Any { self }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct LockedProfitTracker { // 24
+    pub last_updated_locked_profit: u64, // 8
+    pub last_report: u64, // 8
+    pub locked_profit_degradation: u64, // 8
+}
+
+/// Meteora dynamic vault, lends its tokens out to strategies and issues LP for deposits
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MeteoraVault { // 1227
+    pub enabled: u8, // 1
+    pub vault_bump: u8, // 1
+    pub token_vault_bump: u8, // 1
+    pub total_amount: u64, // 8
+    pub token_vault: Pubkey, // 32
+    pub fee_vault: Pubkey, // 32
+    pub token_mint: Pubkey, // 32
+    pub lp_mint: Pubkey, // 32
+    // strategies (960), base, admin and operator are not needed to quote
+    pub locked_profit_tracker: LockedProfitTracker, // 24
+}
+
+impl AccountDataSerializer for MeteoraVault {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 1227];
+        let (discriminator, enabled, vault_bump, token_vault_bump, total_amount, token_vault, fee_vault, token_mint, lp_mint, _strategies, _base, _admin, _operator, locked_profit_tracker) =
+            array_refs![src, 8, 1, 1, 1, 8, 32, 32, 32, 32, 960, 32, 32, 32, 24];
+        let (last_updated_locked_profit, last_report, locked_profit_degradation) =
+            array_refs![locked_profit_tracker, 8, 8, 8];
+
+        MeteoraVault {
+            enabled: u8::from_le_bytes(*enabled),
+            vault_bump: u8::from_le_bytes(*vault_bump),
+            token_vault_bump: u8::from_le_bytes(*token_vault_bump),
+            total_amount: u64::from_le_bytes(*total_amount),
+            token_vault: Pubkey::new_from_array(*token_vault),
+            fee_vault: Pubkey::new_from_array(*fee_vault),
+            token_mint: Pubkey::new_from_array(*token_mint),
+            lp_mint: Pubkey::new_from_array(*lp_mint),
+            locked_profit_tracker: LockedProfitTracker {
+                last_updated_locked_profit: u64::from_le_bytes(*last_updated_locked_profit),
+                last_report: u64::from_le_bytes(*last_report),
+                locked_profit_degradation: u64::from_le_bytes(*locked_profit_degradation),
+            },
+        }
+    }
+}
+
+impl MeteoraVault {
+    pub fn lp_mint_key(vault: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"lp_mint", vault.as_ref()], program_id).0
+    }
+
+    pub fn token_vault_key(vault: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"token_vault", vault.as_ref()], program_id).0
+    }
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct MeteoraVaultAccount {
+    pub pubkey: Pubkey,
+    pub vault: MeteoraVault,
+    pub market: Market,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum MeteoraAccount {
+    Vault(MeteoraVaultAccount),
+}
+
+impl MeteoraAccount {
+    pub fn get_pubkey(&self) -> Pubkey {
+        match self {
+            MeteoraAccount::Vault(account) => account.pubkey
+        }
+    }
+
+    pub fn get_market(&self) -> Market {
+        Market::METEORA
+    }
+
+    pub fn resolve_account(pubkey: Pubkey, data: &Vec<u8>) -> MeteoraAccount {
+        match data.len() {
+            METEORA_VAULT_DATA_LEN => {
+                MeteoraAccount::Vault(MeteoraVaultAccount {
+                    pubkey,
+                    vault: MeteoraVault::unpack_data(data),
+                    market: Market::METEORA,
+                })
+            }
+            _ => {
+                panic!("could not resolve account from data: pubkey({})", pubkey)
+            }
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////////////
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DammV2BaseFee { // 40
+    pub cliff_fee_numerator: u64, // 8
+    pub fee_scheduler_mode: u8, // 1
+    pub number_of_period: u16, // 2
+    pub period_frequency: u64, // 8
+    pub reduction_factor: u64, // 8
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DammV2DynamicFee { // 96
+    pub initialized: u8, // 1
+    pub max_volatility_accumulator: u32, // 4
+    pub variable_fee_control: u32, // 4
+    pub bin_step: u16, // 2
+    pub filter_period: u16, // 2
+    pub decay_period: u16, // 2
+    pub reduction_factor: u16, // 2
+    pub last_update_timestamp: u64, // 8
+    pub bin_step_u128: u128, // 16
+    pub sqrt_price_reference: u128, // 16
+    pub volatility_accumulator: u128, // 16
+    pub volatility_reference: u128, // 16
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DammV2PoolFees { // 160
+    pub base_fee: DammV2BaseFee, // 40
+    pub protocol_fee_percent: u8, // 1
+    pub partner_fee_percent: u8, // 1
+    pub referral_fee_percent: u8, // 1
+    pub dynamic_fee: DammV2DynamicFee, // 96
+}
+
+impl DammV2PoolFees {
+    pub fn unpack_data(data: &[u8; 160]) -> DammV2PoolFees {
+        let (cliff_fee_numerator, fee_scheduler_mode, _padding_0, number_of_period, period_frequency, base_reduction_factor, _padding_1, protocol_fee_percent, partner_fee_percent, referral_fee_percent, _padding_2, initialized, _padding_3, max_volatility_accumulator, variable_fee_control, bin_step, filter_period, decay_period, reduction_factor, last_update_timestamp, bin_step_u128, sqrt_price_reference, volatility_accumulator, volatility_reference, _padding_4) =
+            array_refs![data, 8, 1, 5, 2, 8, 8, 8, 1, 1, 1, 5, 1, 7, 4, 4, 2, 2, 2, 2, 8, 16, 16, 16, 16, 16];
+
+        DammV2PoolFees {
+            base_fee: DammV2BaseFee {
+                cliff_fee_numerator: u64::from_le_bytes(*cliff_fee_numerator),
+                fee_scheduler_mode: u8::from_le_bytes(*fee_scheduler_mode),
+                number_of_period: u16::from_le_bytes(*number_of_period),
+                period_frequency: u64::from_le_bytes(*period_frequency),
+                reduction_factor: u64::from_le_bytes(*base_reduction_factor),
+            },
+            protocol_fee_percent: u8::from_le_bytes(*protocol_fee_percent),
+            partner_fee_percent: u8::from_le_bytes(*partner_fee_percent),
+            referral_fee_percent: u8::from_le_bytes(*referral_fee_percent),
+            dynamic_fee: DammV2DynamicFee {
+                initialized: u8::from_le_bytes(*initialized),
+                max_volatility_accumulator: u32::from_le_bytes(*max_volatility_accumulator),
+                variable_fee_control: u32::from_le_bytes(*variable_fee_control),
+                bin_step: u16::from_le_bytes(*bin_step),
+                filter_period: u16::from_le_bytes(*filter_period),
+                decay_period: u16::from_le_bytes(*decay_period),
+                reduction_factor: u16::from_le_bytes(*reduction_factor),
+                last_update_timestamp: u64::from_le_bytes(*last_update_timestamp),
+                bin_step_u128: u128::from_le_bytes(*bin_step_u128),
+                sqrt_price_reference: u128::from_le_bytes(*sqrt_price_reference),
+                volatility_accumulator: u128::from_le_bytes(*volatility_accumulator),
+                volatility_reference: u128::from_le_bytes(*volatility_reference),
+            },
+        }
+    }
+}
+
+/// Meteora DAMM v2 (cp-amm) pool, constant product liquidity within a single sqrt price range
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MeteoraDammV2Market { // 1112
+    pub pool_fees: DammV2PoolFees, // 160
+    pub token_a_mint: Pubkey, // 32
+    pub token_b_mint: Pubkey, // 32
+    pub token_a_vault: Pubkey, // 32
+    pub token_b_vault: Pubkey, // 32
+    pub whitelisted_vault: Pubkey, // 32
+    pub partner: Pubkey, // 32
+    pub liquidity: u128, // 16
+    pub protocol_a_fee: u64, // 8
+    pub protocol_b_fee: u64, // 8
+    pub partner_a_fee: u64, // 8
+    pub partner_b_fee: u64, // 8
+    pub sqrt_min_price: u128, // 16
+    pub sqrt_max_price: u128, // 16
+    pub sqrt_price: u128, // 16
+    pub activation_point: u64, // 8
+    pub activation_type: u8, // 1
+    pub pool_status: u8, // 1
+    pub token_a_flag: u8, // 1
+    pub token_b_flag: u8, // 1
+    pub collect_fee_mode: u8, // 1
+    pub pool_type: u8, // 1
+    pub permanent_lock_liquidity: u128, // 16
+    pub creator: Pubkey, // 32
+}
+
+impl AccountDataSerializer for MeteoraDammV2Market {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 1112];
+        let (discriminator, pool_fees, token_a_mint, token_b_mint, token_a_vault, token_b_vault, whitelisted_vault, partner, liquidity, _padding, protocol_a_fee, protocol_b_fee, partner_a_fee, partner_b_fee, sqrt_min_price, sqrt_max_price, sqrt_price, activation_point, activation_type, pool_status, token_a_flag, token_b_flag, collect_fee_mode, pool_type, _padding_0, _fee_a_per_liquidity, _fee_b_per_liquidity, permanent_lock_liquidity, _metrics, creator, _padding_1, _reward_infos) =
+            array_refs![src, 8, 160, 32, 32, 32, 32, 32, 32, 16, 16, 8, 8, 8, 8, 16, 16, 16, 8, 1, 1, 1, 1, 1, 1, 2, 32, 32, 16, 80, 32, 48, 384];
+
+        MeteoraDammV2Market {
+            pool_fees: DammV2PoolFees::unpack_data(pool_fees),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            token_a_vault: Pubkey::new_from_array(*token_a_vault),
+            token_b_vault: Pubkey::new_from_array(*token_b_vault),
+            whitelisted_vault: Pubkey::new_from_array(*whitelisted_vault),
+            partner: Pubkey::new_from_array(*partner),
+            liquidity: u128::from_le_bytes(*liquidity),
+            protocol_a_fee: u64::from_le_bytes(*protocol_a_fee),
+            protocol_b_fee: u64::from_le_bytes(*protocol_b_fee),
+            partner_a_fee: u64::from_le_bytes(*partner_a_fee),
+            partner_b_fee: u64::from_le_bytes(*partner_b_fee),
+            sqrt_min_price: u128::from_le_bytes(*sqrt_min_price),
+            sqrt_max_price: u128::from_le_bytes(*sqrt_max_price),
+            sqrt_price: u128::from_le_bytes(*sqrt_price),
+            activation_point: u64::from_le_bytes(*activation_point),
+            activation_type: u8::from_le_bytes(*activation_type),
+            pool_status: u8::from_le_bytes(*pool_status),
+            token_a_flag: u8::from_le_bytes(*token_a_flag),
+            token_b_flag: u8::from_le_bytes(*token_b_flag),
+            collect_fee_mode: u8::from_le_bytes(*collect_fee_mode),
+            pool_type: u8::from_le_bytes(*pool_type),
+            permanent_lock_liquidity: u128::from_le_bytes(*permanent_lock_liquidity),
+            creator: Pubkey::new_from_array(*creator),
+        }
+    }
+}
+
+impl PoolOperation for MeteoraDammV2Market {
+    fn get_mint_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.token_a_mint,
+            pubkey_b: self.token_b_mint,
+        }
+    }
+
+    fn get_pool_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.token_a_vault,
+            pubkey_b: self.token_b_vault,
+        }
+    }
+
+    fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
+        // the pool account holds the liquidity, only the fee scheduler needs the clock
+        vec![
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), clock::id()),
+        ]
+    }
+
+    fn get_formula(&self) -> Formula {
+        Formula::ConcentratedLiquidity
+    }
+
+    fn get_fee_rate(&self) -> Option<u32> {
+        // cliff fee is over 1e9
+        u32::try_from(self.pool_fees.base_fee.cliff_fee_numerator / 1000).ok()
+    }
+
+    fn get_liquidity(&self) -> Option<u128> {
+        Some(self.liquidity)
+    }
+
+    fn is_tradable(&self) -> bool {
+        self.pool_status == 0
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let a_to_b = if *mint_in == self.token_a_mint {
+            true
+        } else if *mint_in == self.token_b_mint {
+            false
+        } else {
+            return Err("mint not in pool");
+        };
+
+        let pool = accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(account) => account.operation.as_any().downcast_ref::<MeteoraDammV2Market>(),
+                _ => None
+            }
+        }).unwrap_or(self);
+        let clock = get_clock(accounts).ok_or("clock not found")?;
+
+        let result = meteora_damm_v2::quote_exact_in(pool, amount_in, a_to_b, clock.unix_timestamp as u64, clock.slot)?;
+
+        Ok(SwapQuote { amount_in, amount_out: result.amount_out, crossings: 0 })
+    }
+
+    fn as_any(&self) -> &dyn Any { self }
+}
@@ -2,7 +2,21 @@ pub mod orca;
 pub mod meteora;
 pub mod raydium;
 pub mod lifinity;
+pub mod phoenix;
+pub mod openbook;
+pub mod saber;
+pub mod stake_pool;
+pub mod marinade;
+pub mod sanctum;
+pub mod pump;
 
 pub use orca::*;
 pub use meteora::*;
-pub use raydium::*;
\ No newline at end of file
+pub use raydium::*;
+pub use phoenix::*;
+pub use openbook::*;
+pub use saber::*;
+pub use stake_pool::*;
+pub use marinade::*;
+pub use sanctum::*;
+pub use pump::*;
\ No newline at end of file
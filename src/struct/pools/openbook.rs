@@ -0,0 +1,529 @@
+use std::any::Any;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrayref::{array_ref, array_refs};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+use solana_sdk::sysvar::clock;
+
+use crate::constants::{OPENBOOK_V2_BOOK_SIDE_DATA_LEN, OPENBOOK_V2_EVENT_HEAP_DATA_LEN, OPENBOOK_V2_PROGRAM_PUBKEY, TOKEN_PROGRAM_PUBKEY};
+use crate::formula::base::{Formula, SwapQuote};
+use crate::formula::openbook::openbook_v2;
+use crate::formula::openbook::openbook_v2::MATCH_LIMIT;
+use crate::r#struct::account::{get_clock, AccountDataSerializer, DeserializedAccount, DeserializedConfigAccount, DeserializedDataAccount};
+use crate::r#struct::account::DeserializedConfigAccount::OpenBookConfigAccount;
+use crate::r#struct::market::{Market, PoolOperation};
+use crate::utils::{get_discriminator, PubkeyPair};
+
+// roots, reserved roots, reserved and the node header of a book side, after the discriminator
+const BOOK_SIDE_NODES_OFFSET: usize = 840;
+const MAX_ORDER_TREE_NODES: usize = 1024;
+const ORDER_TREE_NODE_LEN: usize = 88;
+const INNER_NODE_TAG: u8 = 1;
+const LEAF_NODE_TAG: u8 = 2;
+pub const MAX_NUM_EVENTS: u16 = 600;
+
+const IMMEDIATE_OR_CANCEL: u8 = 1;
+
+fn unpack_pubkey_option(data: &[u8; 32]) -> Option<Pubkey> {
+    let pubkey = Pubkey::new_from_array(*data);
+    if pubkey == Pubkey::default() { None } else { Some(pubkey) }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct OpenBookV2OracleConfig { // 88
+    pub conf_filter: f64, // 8
+    pub max_staleness_slots: i64, // 8
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct OpenBookV2Market { // 848
+    pub bump: u8, // 1
+    pub base_decimals: u8, // 1
+    pub quote_decimals: u8, // 1
+    pub market_authority: Pubkey, // 32
+    pub time_expiry: i64, // 8
+    pub collect_fee_admin: Pubkey, // 32
+    pub open_orders_admin: Option<Pubkey>, // 32
+    pub consume_events_admin: Option<Pubkey>, // 32
+    pub close_market_admin: Option<Pubkey>, // 32
+    pub name: [u8; 16], // 16
+    pub bids: Pubkey, // 32
+    pub asks: Pubkey, // 32
+    pub event_heap: Pubkey, // 32
+    pub oracle_a: Option<Pubkey>, // 32
+    pub oracle_b: Option<Pubkey>, // 32
+    pub oracle_config: OpenBookV2OracleConfig, // 88
+    pub quote_lot_size: i64, // 8
+    pub base_lot_size: i64, // 8
+    pub seq_num: u64, // 8
+    pub registration_time: i64, // 8
+    pub maker_fee: i64, // 8
+    pub taker_fee: i64, // 8
+    pub fees_accrued: u128, // 16
+    pub fees_to_referrers: u128, // 16
+    pub referrer_rebates_accrued: u64, // 8
+    pub fees_available: u64, // 8
+    pub maker_volume: u128, // 16
+    pub taker_volume_wo_oo: u128, // 16
+    pub base_mint: Pubkey, // 32
+    pub quote_mint: Pubkey, // 32
+    pub market_base_vault: Pubkey, // 32
+    pub base_deposit_total: u64, // 8
+    pub market_quote_vault: Pubkey, // 32
+    pub quote_deposit_total: u64, // 8
+}
+
+impl AccountDataSerializer for OpenBookV2Market {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 848];
+        let (_discriminator, bump, base_decimals, quote_decimals, _padding1, market_authority, time_expiry, collect_fee_admin, open_orders_admin, consume_events_admin, close_market_admin, name, bids, asks, event_heap, oracle_a, oracle_b, oracle_config, quote_lot_size, base_lot_size, seq_num, registration_time, maker_fee, taker_fee, fees_accrued, fees_to_referrers, referrer_rebates_accrued, fees_available, maker_volume, taker_volume_wo_oo, base_mint, quote_mint, market_base_vault, base_deposit_total, market_quote_vault, quote_deposit_total, _reserved) =
+            array_refs![src, 8, 1, 1, 1, 5, 32, 8, 32, 32, 32, 32, 16, 32, 32, 32, 32, 32, 88, 8, 8, 8, 8, 8, 8, 16, 16, 8, 8, 16, 16, 32, 32, 32, 8, 32, 8, 128];
+        let (conf_filter, max_staleness_slots, _reserved) = array_refs![oracle_config, 8, 8, 72];
+
+        OpenBookV2Market {
+            bump: u8::from_le_bytes(*bump),
+            base_decimals: u8::from_le_bytes(*base_decimals),
+            quote_decimals: u8::from_le_bytes(*quote_decimals),
+            market_authority: Pubkey::new_from_array(*market_authority),
+            time_expiry: i64::from_le_bytes(*time_expiry),
+            collect_fee_admin: Pubkey::new_from_array(*collect_fee_admin),
+            open_orders_admin: unpack_pubkey_option(open_orders_admin),
+            consume_events_admin: unpack_pubkey_option(consume_events_admin),
+            close_market_admin: unpack_pubkey_option(close_market_admin),
+            name: *name,
+            bids: Pubkey::new_from_array(*bids),
+            asks: Pubkey::new_from_array(*asks),
+            event_heap: Pubkey::new_from_array(*event_heap),
+            oracle_a: unpack_pubkey_option(oracle_a),
+            oracle_b: unpack_pubkey_option(oracle_b),
+            oracle_config: OpenBookV2OracleConfig {
+                conf_filter: f64::from_le_bytes(*conf_filter),
+                max_staleness_slots: i64::from_le_bytes(*max_staleness_slots),
+            },
+            quote_lot_size: i64::from_le_bytes(*quote_lot_size),
+            base_lot_size: i64::from_le_bytes(*base_lot_size),
+            seq_num: u64::from_le_bytes(*seq_num),
+            registration_time: i64::from_le_bytes(*registration_time),
+            maker_fee: i64::from_le_bytes(*maker_fee),
+            taker_fee: i64::from_le_bytes(*taker_fee),
+            fees_accrued: u128::from_le_bytes(*fees_accrued),
+            fees_to_referrers: u128::from_le_bytes(*fees_to_referrers),
+            referrer_rebates_accrued: u64::from_le_bytes(*referrer_rebates_accrued),
+            fees_available: u64::from_le_bytes(*fees_available),
+            maker_volume: u128::from_le_bytes(*maker_volume),
+            taker_volume_wo_oo: u128::from_le_bytes(*taker_volume_wo_oo),
+            base_mint: Pubkey::new_from_array(*base_mint),
+            quote_mint: Pubkey::new_from_array(*quote_mint),
+            market_base_vault: Pubkey::new_from_array(*market_base_vault),
+            base_deposit_total: u64::from_le_bytes(*base_deposit_total),
+            market_quote_vault: Pubkey::new_from_array(*market_quote_vault),
+            quote_deposit_total: u64::from_le_bytes(*quote_deposit_total),
+        }
+    }
+}
+
+impl PoolOperation for OpenBookV2Market {
+    fn get_mint_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.base_mint,
+            pubkey_b: self.quote_mint,
+        }
+    }
+
+    fn get_pool_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.market_base_vault,
+            pubkey_b: self.market_quote_vault,
+        }
+    }
+
+    fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
+        let mut vec = vec![
+            (DeserializedAccount::ConfigAccount(DeserializedConfigAccount::default()), self.bids),
+            (DeserializedAccount::ConfigAccount(DeserializedConfigAccount::default()), self.asks),
+            (DeserializedAccount::ConfigAccount(DeserializedConfigAccount::default()), self.event_heap),
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), clock::id()),
+        ];
+        // oracles price the pegged orders
+        [self.oracle_a, self.oracle_b].iter().flatten().for_each(|oracle| {
+            vec.push((DeserializedAccount::Account(DeserializedDataAccount::default()), *oracle));
+        });
+
+        vec
+    }
+
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        let mut pubkeys = vec![self.market_base_vault, self.market_quote_vault, self.market_authority, self.bids, self.asks, self.event_heap];
+        pubkeys.extend([self.oracle_a, self.oracle_b].iter().flatten());
+        pubkeys
+    }
+
+    fn get_formula(&self) -> Formula {
+        Formula::OrderBook
+    }
+
+    fn get_fee_rate(&self) -> Option<u32> {
+        // taker fee is over 1e6 already
+        u32::try_from(self.taker_fee).ok()
+    }
+
+    /// Markets with an open orders admin take orders co-signed by the admin only
+    fn is_tradable(&self) -> bool {
+        self.open_orders_admin.is_none()
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let base_in = if *mint_in == self.base_mint {
+            true
+        } else if *mint_in == self.quote_mint {
+            false
+        } else {
+            return Err("mint not in market");
+        };
+
+        let mut market = *self;
+        let (mut bids, mut asks, mut event_heap) = (None, None, None);
+        let (mut oracle_a, mut oracle_b): (Option<&[u8]>, Option<&[u8]>) = (None, None);
+        accounts.iter().for_each(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(pool) => {
+                    if let Some(openbook_market) = pool.operation.as_any().downcast_ref::<OpenBookV2Market>() {
+                        market = *openbook_market;
+                    }
+                }
+                DeserializedAccount::ConfigAccount(OpenBookConfigAccount(OpenBookV2Account::BookSide(book_side))) => {
+                    if book_side.pubkey == self.bids {
+                        bids = Some(Arc::clone(&book_side.book_side))
+                    } else if book_side.pubkey == self.asks {
+                        asks = Some(Arc::clone(&book_side.book_side))
+                    }
+                }
+                DeserializedAccount::ConfigAccount(OpenBookConfigAccount(OpenBookV2Account::EventHeap(heap))) => {
+                    event_heap = Some(heap.event_heap)
+                }
+                DeserializedAccount::Account(account) => {
+                    if Some(account.pubkey) == self.oracle_a {
+                        oracle_a = Some(&account.account.data)
+                    } else if Some(account.pubkey) == self.oracle_b {
+                        oracle_b = Some(&account.account.data)
+                    }
+                }
+                _ => {}
+            }
+        });
+
+        let bids = bids.ok_or("bids not found")?;
+        let asks = asks.ok_or("asks not found")?;
+        let event_heap = event_heap.ok_or("event heap not found")?;
+        let clock = get_clock(accounts).ok_or("clock not found")?;
+        let oracle_price_lots = openbook_v2::get_oracle_price_lots(&market, oracle_a, oracle_b, clock.slot);
+
+        let (amount_out, fill) = openbook_v2::quote_exact_in(
+            &market, &bids, &asks, &event_heap, amount_in, base_in, oracle_price_lots, clock.unix_timestamp as u64
+        )?;
+
+        Ok(SwapQuote { amount_in, amount_out, crossings: fill.orders })
+    }
+
+    fn has_swap_instructions(&self) -> bool {
+        true
+    }
+
+    fn get_swap_instructions(
+        &self,
+        pool: &Pubkey,
+        user: &Pubkey,
+        source_token_account: &Pubkey,
+        destination_token_account: &Pubkey,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64
+    ) -> Result<Vec<Instruction>, &'static str> {
+        if self.base_lot_size <= 0 || self.quote_lot_size <= 0 {
+            return Err("invalid lot size");
+        }
+        let (base_lot_size, quote_lot_size) = (self.base_lot_size as u64, self.quote_lot_size as u64);
+        // the average price `minimum_amount_out` implies bounds the worst price matched, rounded in favor of the fill
+        let (user_base_account, user_quote_account, limit_price_lots) = if *mint_in == self.base_mint {
+            let base_lots = (amount_in / base_lot_size).max(1);
+            (source_token_account, destination_token_account, (minimum_amount_out / quote_lot_size / base_lots).max(1))
+        } else {
+            let quote_lots = amount_in / quote_lot_size;
+            let base_lots = minimum_amount_out.div_ceil(base_lot_size).max(1);
+            (destination_token_account, source_token_account, quote_lots.div_ceil(base_lots).max(1))
+        };
+        let limit_price_lots = i64::try_from(limit_price_lots).or(Err("limit price overflow"))?;
+
+        let instruction = self.get_swap_instruction(
+            pool, user, user_base_account, user_quote_account, mint_in, amount_in, Some(limit_price_lots)
+        )?;
+        Ok(vec![instruction])
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl OpenBookV2Market {
+    /// Builds `place_take_order` selling `amount_in` of `mint_in` immediately or cancelling the rest.
+    /// `limit_price_lots` is the worst price to match at, any price if `None`
+    pub fn get_swap_instruction(
+        &self,
+        market: &Pubkey,
+        signer: &Pubkey,
+        user_base_account: &Pubkey,
+        user_quote_account: &Pubkey,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        limit_price_lots: Option<i64>
+    ) -> Result<Instruction, &'static str> {
+        if self.base_lot_size <= 0 || self.quote_lot_size <= 0 {
+            return Err("invalid lot size");
+        }
+        let amount_in = i64::try_from(amount_in).or(Err("amount in overflow"))?;
+        // (side, price_lots, max_base_lots, max_quote_lots_including_fees)
+        let order = if *mint_in == self.base_mint {
+            (1u8, limit_price_lots.unwrap_or(1), amount_in / self.base_lot_size, i64::MAX)
+        } else if *mint_in == self.quote_mint {
+            (0u8, limit_price_lots.unwrap_or(i64::MAX), i64::MAX, amount_in / self.quote_lot_size)
+        } else {
+            return Err("mint not in market");
+        };
+
+        let mut data = get_discriminator("global", "place_take_order").to_vec();
+        data.push(order.0);
+        [order.1, order.2, order.3].iter().for_each(|value| data.extend_from_slice(&value.to_le_bytes()));
+        data.push(IMMEDIATE_OR_CANCEL);
+        data.push(MATCH_LIMIT);
+
+        // absent optional accounts are passed as the program id
+        let program_id = Pubkey::from_str(OPENBOOK_V2_PROGRAM_PUBKEY).unwrap();
+        let optional = |pubkey: Option<Pubkey>| pubkey.unwrap_or(program_id);
+        Ok(Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new(*signer, true),
+                AccountMeta::new(*signer, true),
+                AccountMeta::new(*market, false),
+                AccountMeta::new_readonly(self.market_authority, false),
+                AccountMeta::new(self.bids, false),
+                AccountMeta::new(self.asks, false),
+                AccountMeta::new(self.market_base_vault, false),
+                AccountMeta::new(self.market_quote_vault, false),
+                AccountMeta::new(self.event_heap, false),
+                AccountMeta::new(*user_base_account, false),
+                AccountMeta::new(*user_quote_account, false),
+                AccountMeta::new_readonly(optional(self.oracle_a), false),
+                AccountMeta::new_readonly(optional(self.oracle_b), false),
+                AccountMeta::new_readonly(Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(optional(self.open_orders_admin), false),
+            ]
+        ))
+    }
+}
+
+/// Order resting on a book side, a leaf node of its order tree
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct OpenBookV2Order { // 88
+    pub owner_slot: u8, // 1
+    pub time_in_force: u16, // 2
+    /// price data in the upper 64 bits, sequence number in the lower, negated for bids
+    pub key: u128, // 16
+    pub owner: Pubkey, // 32
+    pub quantity: i64, // 8
+    pub timestamp: u64, // 8
+    pub peg_limit: i64, // 8
+    pub client_order_id: u64, // 8
+}
+
+impl OpenBookV2Order {
+    pub fn get_price_data(&self) -> u64 {
+        (self.key >> 64) as u64
+    }
+
+    /// Price offset of an oracle pegged order, stored with its sign bit flipped to keep the key order
+    pub fn get_peg_offset(&self) -> i64 {
+        (self.get_price_data() ^ (1 << 63)) as i64
+    }
+
+    pub fn is_expired(&self, now_ts: u64) -> bool {
+        self.time_in_force > 0 && now_ts >= self.timestamp + self.time_in_force as u64
+    }
+}
+
+/// Bids or asks of a market, orders of the fixed and the oracle pegged trees by priority
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpenBookV2BookSide {
+    /// 0 for bids, 1 for asks
+    pub order_tree_type: u8,
+    pub fixed: Vec<OpenBookV2Order>,
+    pub oracle_pegged: Vec<OpenBookV2Order>,
+}
+
+impl AccountDataSerializer for OpenBookV2BookSide {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 840];
+        let (_discriminator, roots, _reserved_roots, _reserved, order_tree_type, _node_header) =
+            array_refs![src, 8, 16, 32, 256, 1, 527];
+        let (fixed_root, fixed_leaf_count, pegged_root, pegged_leaf_count) = array_refs![roots, 4, 4, 4, 4];
+        let nodes = &data[BOOK_SIDE_NODES_OFFSET..];
+
+        let order_tree_type = u8::from_le_bytes(*order_tree_type);
+        let mut fixed = Self::unpack_orders(nodes, u32::from_le_bytes(*fixed_root), u32::from_le_bytes(*fixed_leaf_count));
+        let mut oracle_pegged = Self::unpack_orders(nodes, u32::from_le_bytes(*pegged_root), u32::from_le_bytes(*pegged_leaf_count));
+        // the key orders by price then by time, bids from the highest
+        [&mut fixed, &mut oracle_pegged].into_iter().for_each(|orders| {
+            if order_tree_type == 0 {
+                orders.sort_by(|a, b| b.key.cmp(&a.key));
+            } else {
+                orders.sort_by(|a, b| a.key.cmp(&b.key));
+            }
+        });
+
+        OpenBookV2BookSide {
+            order_tree_type,
+            fixed,
+            oracle_pegged,
+        }
+    }
+}
+
+impl OpenBookV2BookSide {
+    pub fn is_bids(&self) -> bool {
+        self.order_tree_type == 0
+    }
+
+    /// Leaves of the tree at `root`, walked through the inner nodes
+    fn unpack_orders(nodes: &[u8], root: u32, leaf_count: u32) -> Vec<OpenBookV2Order> {
+        let mut orders = Vec::new();
+        if leaf_count == 0 {
+            return orders;
+        }
+
+        let mut stack = vec![root];
+        let mut visited = 0;
+        while let Some(handle) = stack.pop() {
+            visited += 1;
+            if handle as usize >= MAX_ORDER_TREE_NODES || visited > MAX_ORDER_TREE_NODES {
+                break;
+            }
+            let Some(node) = nodes.get(handle as usize * ORDER_TREE_NODE_LEN..(handle as usize + 1) * ORDER_TREE_NODE_LEN) else { break };
+            let node = array_ref![node, 0, 88];
+
+            match node[0] {
+                INNER_NODE_TAG => {
+                    let (_tag, _prefix, _key, left, right, _expiry) = array_refs![node, 4, 4, 16, 4, 4, 56];
+                    stack.push(u32::from_le_bytes(*right));
+                    stack.push(u32::from_le_bytes(*left));
+                }
+                LEAF_NODE_TAG => {
+                    let (_tag, owner_slot, time_in_force, _padding, key, owner, quantity, timestamp, peg_limit, client_order_id) =
+                        array_refs![node, 1, 1, 2, 4, 16, 32, 8, 8, 8, 8];
+                    orders.push(OpenBookV2Order {
+                        owner_slot: u8::from_le_bytes(*owner_slot),
+                        time_in_force: u16::from_le_bytes(*time_in_force),
+                        key: u128::from_le_bytes(*key),
+                        owner: Pubkey::new_from_array(*owner),
+                        quantity: i64::from_le_bytes(*quantity),
+                        timestamp: u64::from_le_bytes(*timestamp),
+                        peg_limit: i64::from_le_bytes(*peg_limit),
+                        client_order_id: u64::from_le_bytes(*client_order_id),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        orders
+    }
+}
+
+/// Header of the event heap, fills and outs wait there for the crank
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct OpenBookV2EventHeap {
+    pub free_head: u16, // 2
+    pub used_head: u16, // 2
+    pub count: u16, // 2
+    pub seq_num: u64, // 8
+}
+
+impl AccountDataSerializer for OpenBookV2EventHeap {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 24];
+        let (_discriminator, free_head, used_head, count, _padding, seq_num) = array_refs![src, 8, 2, 2, 2, 2, 8];
+
+        OpenBookV2EventHeap {
+            free_head: u16::from_le_bytes(*free_head),
+            used_head: u16::from_le_bytes(*used_head),
+            count: u16::from_le_bytes(*count),
+            seq_num: u64::from_le_bytes(*seq_num),
+        }
+    }
+}
+
+impl OpenBookV2EventHeap {
+    pub fn get_free_space(&self) -> u16 {
+        MAX_NUM_EVENTS.saturating_sub(self.count)
+    }
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct OpenBookV2BookSideAccount {
+    pub pubkey: Pubkey,
+    pub market: Market,
+    pub book_side: Arc<OpenBookV2BookSide>,
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct OpenBookV2EventHeapAccount {
+    pub pubkey: Pubkey,
+    pub market: Market,
+    pub event_heap: OpenBookV2EventHeap,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum OpenBookV2Account {
+    BookSide(OpenBookV2BookSideAccount),
+    EventHeap(OpenBookV2EventHeapAccount),
+}
+
+impl OpenBookV2Account {
+    pub fn get_pubkey(&self) -> Pubkey {
+        match self {
+            OpenBookV2Account::BookSide(account) => account.pubkey,
+            OpenBookV2Account::EventHeap(account) => account.pubkey,
+        }
+    }
+
+    pub fn get_market(&self) -> Market {
+        Market::OPENBOOK
+    }
+
+    pub fn resolve_account(pubkey: Pubkey, data: &Vec<u8>) -> OpenBookV2Account {
+        match data.len() {
+            OPENBOOK_V2_BOOK_SIDE_DATA_LEN => {
+                OpenBookV2Account::BookSide(OpenBookV2BookSideAccount {
+                    pubkey,
+                    market: Market::OPENBOOK,
+                    book_side: Arc::new(OpenBookV2BookSide::unpack_data(data)),
+                })
+            }
+            OPENBOOK_V2_EVENT_HEAP_DATA_LEN => {
+                OpenBookV2Account::EventHeap(OpenBookV2EventHeapAccount {
+                    pubkey,
+                    market: Market::OPENBOOK,
+                    event_heap: OpenBookV2EventHeap::unpack_data(data),
+                })
+            }
+            _ => {
+                panic!("could not resolve account from data: pubkey({})", pubkey)
+            }
+        }
+    }
+}
@@ -1,14 +1,17 @@
 use std::any::Any;
-use std::collections::VecDeque;
+use std::sync::Arc;
 
 use arrayref::{array_ref, array_refs};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar::clock;
 
 use crate::constants::*;
-use crate::formula::base::Formula;
+use crate::formula::base::{Formula, SwapQuote};
 use crate::formula::base::Formula::ConcentratedLiquidity;
-use crate::formula::clmm::orca_swap_state::{ProxiedTickArray, TickArray, TickArrayAccount};
-use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount, DeserializedConfigAccount, DeserializedTokenAccount};
+use crate::formula::clmm::orca_adaptive_fee::AdaptiveFeeInfo;
+use crate::formula::clmm::orca_swap_state::{NO_EXPLICIT_SQRT_PRICE_LIMIT, SwapTickSequence, TICK_ARRAY_SIZE, TickArray, TickArrayAccount};
+use crate::formula::orca_clmm::swap_internal;
+use crate::r#struct::account::{get_clock, AccountDataSerializer, DeserializedAccount, DeserializedConfigAccount, DeserializedDataAccount, DeserializedTokenAccount};
 use crate::r#struct::market::{Market, PoolOperation};
 use crate::utils::PubkeyPair;
 
@@ -65,6 +68,13 @@ impl AccountDataSerializer for OrcaClmmMarket {
     }
 }
 
+impl OrcaClmmMarket {
+    /// Adaptive fee pools are seeded by their fee tier index instead of the tick spacing
+    pub fn is_initialized_with_adaptive_fee(&self) -> bool {
+        u16::from_le_bytes(self.tick_spacing_seed) != self.tick_spacing
+    }
+}
+
 impl PoolOperation for OrcaClmmMarket {
     fn get_mint_pair(&self) -> PubkeyPair {
         PubkeyPair {
@@ -85,6 +95,8 @@ impl PoolOperation for OrcaClmmMarket {
             (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.token_vault_a),
             (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.token_vault_b),
             (DeserializedAccount::ConfigAccount(DeserializedConfigAccount::default()), self.whirlpools_config),
+            // rewards and the adaptive fee move with the chain time
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), clock::id()),
         ]
     }
 
@@ -92,14 +104,26 @@ impl PoolOperation for OrcaClmmMarket {
         ConcentratedLiquidity
     }
 
-    fn swap(&self, accounts: &Vec<DeserializedAccount>) {
-        let amount = 0u64;
-        let a_to_b = true; // equivalent to zero_for_one
-        let amount_specified_is_input = true; // equivalent to is_base_input
+    fn get_fee_rate(&self) -> Option<u32> {
+        Some(self.fee_rate as u32)
+    }
+
+    fn get_liquidity(&self) -> Option<u128> {
+        Some(self.liquidity)
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let a_to_b = if *mint_in == self.token_mint_a {
+            true
+        } else if *mint_in == self.token_mint_b {
+            false
+        } else {
+            return Err("mint not in pool");
+        };
 
-        let mut market = OrcaClmmMarket::default();
-        let mut tick_array_list: Vec<ProxiedTickArray> = Vec::new();
-        let mut whirl_pools_config = WhirlpoolsConfig::default();
+        let mut market = *self;
+        let mut tick_array_list: Vec<Arc<TickArray>> = Vec::new();
+        let mut oracle: Option<WhirlpoolOracle> = None;
 
         accounts.iter().for_each(|account| {
             match account {
@@ -108,48 +132,73 @@ impl PoolOperation for OrcaClmmMarket {
                         market = *orca_clmm_market;
                     }
                 }
-                DeserializedAccount::ConfigAccount(config) => {
-                    match config {
-                        DeserializedConfigAccount::OrcaClmmConfigAccount(orca_config) => {
-                            match orca_config {
-                                OrcaClmmAccount::WhirlpoolsConfig(whirl_pools) => {
-                                    whirl_pools_config = whirl_pools.config;
-                                }
-                                OrcaClmmAccount::TickArray(tick_array) => {
-                                    // todo: push only directed tick array state
-                                    tick_array_list.push(
-                                        ProxiedTickArray::new_initialized(tick_array.tick_array.clone())
-                                    );
-                                }
-                            }
-                        }
-                        DeserializedConfigAccount::RaydiumClmmConfigAccount(_) => {}
-                        DeserializedConfigAccount::EmptyConfigAccount => {}
-                    }
+                DeserializedAccount::ConfigAccount(DeserializedConfigAccount::OrcaClmmConfigAccount(OrcaClmmAccount::TickArray(tick_array))) => {
+                    tick_array_list.push(Arc::clone(&tick_array.tick_array));
                 }
-                DeserializedAccount::TokenAccount(_) => {}
-                DeserializedAccount::Account(_) => {}
+                DeserializedAccount::ConfigAccount(DeserializedConfigAccount::OrcaClmmConfigAccount(OrcaClmmAccount::Oracle(oracle_account))) => {
+                    oracle = Some(oracle_account.oracle);
+                }
+                _ => {}
             }
         });
 
-        let mut tick_array_list = tick_array_list.into_iter().filter(|tick_array_state| {
+        // keep the tick arrays on the swap direction, ordered from the current tick
+        let ticks_in_array = TICK_ARRAY_SIZE * market.tick_spacing as i32;
+        tick_array_list.retain(|tick_array| {
             if a_to_b {
-                tick_array_state.start_tick_index() >= market.tick_current_index
+                tick_array.start_tick_index <= market.tick_current_index
             }
             else {
-                tick_array_state.start_tick_index() <= market.tick_current_index
+                tick_array.start_tick_index + ticks_in_array > market.tick_current_index
+            }
+        });
+        if a_to_b {
+            tick_array_list.sort_by(|a, b| b.start_tick_index.cmp(&a.start_tick_index));
+        }
+        else {
+            tick_array_list.sort_by(|a, b| a.start_tick_index.cmp(&b.start_tick_index));
+        }
+
+        let mut tick_arrays = tick_array_list.iter().cloned();
+        let swap_tick_sequence = SwapTickSequence::new_shared(
+            tick_arrays.next().ok_or("tick array not found")?,
+            tick_arrays.next(),
+            tick_arrays.next(),
+        );
+
+        let timestamp = get_clock(accounts).ok_or("clock not found")?.unix_timestamp as u64;
+        let adaptive_fee_info = if market.is_initialized_with_adaptive_fee() {
+            let oracle = oracle.ok_or("oracle not found")?;
+            if timestamp < oracle.trade_enable_timestamp {
+                return Err("trade not enabled");
             }
-        }).collect::<VecDeque<ProxiedTickArray>>();
-
-        // swap_internal(
-        //     market,
-        //     SwapTickSequence::new(),
-        //     amount,
-        //     market.sqrt_price,
-        //     amount_specified_is_input,
-        //     a_to_b,
-        //     064
-        // ).expect("swap failed");
+            Some(oracle.get_adaptive_fee_info())
+        } else {
+            None
+        };
+        let post_swap = swap_internal(
+            &market,
+            &swap_tick_sequence,
+            amount_in,
+            NO_EXPLICIT_SQRT_PRICE_LIMIT,
+            true,
+            a_to_b,
+            timestamp,
+            &adaptive_fee_info,
+        )?;
+
+        let lower_tick_index = market.tick_current_index.min(post_swap.next_tick_index);
+        let upper_tick_index = market.tick_current_index.max(post_swap.next_tick_index);
+        let crossings = tick_array_list.iter().take(3).map(|tick_array| {
+            tick_array.count_initialized_ticks(market.tick_spacing, lower_tick_index, upper_tick_index)
+        }).sum();
+
+        if a_to_b {
+            Ok(SwapQuote { amount_in: post_swap.amount_a, amount_out: post_swap.amount_b, crossings })
+        }
+        else {
+            Ok(SwapQuote { amount_in: post_swap.amount_b, amount_out: post_swap.amount_a, crossings })
+        }
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -230,10 +279,108 @@ impl AccountDataSerializer for WhirlpoolsConfig {
     }
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct WhirlpoolOracleAccount {
+    pub pubkey: Pubkey,
+    pub oracle: WhirlpoolOracle,
+    pub market: Market,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct WhirlpoolOracle {
+    pub whirlpool: Pubkey, // 32
+    pub trade_enable_timestamp: u64, // 8
+    pub adaptive_fee_constants: AdaptiveFeeConstants, // 34
+    pub adaptive_fee_variables: AdaptiveFeeVariables, // 44
+}
+
+impl AccountDataSerializer for WhirlpoolOracle {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 254]; // 254
+        let (discriminator, whirlpool, trade_enable_timestamp, adaptive_fee_constants, adaptive_fee_variables, _) =
+            array_refs![src, 8, 32, 8, 34, 44, 128];
+
+        WhirlpoolOracle {
+            whirlpool: Pubkey::new_from_array(*whirlpool),
+            trade_enable_timestamp: u64::from_le_bytes(*trade_enable_timestamp),
+            adaptive_fee_constants: AdaptiveFeeConstants::unpack_data(&adaptive_fee_constants.to_vec()),
+            adaptive_fee_variables: AdaptiveFeeVariables::unpack_data(&adaptive_fee_variables.to_vec()),
+        }
+    }
+}
+
+impl WhirlpoolOracle {
+    pub fn key(program_id: &Pubkey, whirlpool: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[ORCA_CLMM_ORACLE_SEED.as_bytes(), whirlpool.as_ref()], program_id).0
+    }
+
+    pub fn get_adaptive_fee_info(&self) -> AdaptiveFeeInfo {
+        AdaptiveFeeInfo {
+            constants: self.adaptive_fee_constants,
+            variables: self.adaptive_fee_variables,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct AdaptiveFeeConstants {
+    pub filter_period: u16, // 2
+    pub decay_period: u16, // 2
+    pub reduction_factor: u16, // 2
+    pub adaptive_fee_control_factor: u32, // 4
+    pub max_volatility_accumulator: u32, // 4
+    pub tick_group_size: u16, // 2
+    pub major_swap_threshold_ticks: u16, // 2
+}
+
+impl AccountDataSerializer for AdaptiveFeeConstants {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 34]; // 34
+        let (filter_period, decay_period, reduction_factor, adaptive_fee_control_factor, max_volatility_accumulator, tick_group_size, major_swap_threshold_ticks, _) =
+            array_refs![src, 2, 2, 2, 4, 4, 2, 2, 16];
+
+        AdaptiveFeeConstants {
+            filter_period: u16::from_le_bytes(*filter_period),
+            decay_period: u16::from_le_bytes(*decay_period),
+            reduction_factor: u16::from_le_bytes(*reduction_factor),
+            adaptive_fee_control_factor: u32::from_le_bytes(*adaptive_fee_control_factor),
+            max_volatility_accumulator: u32::from_le_bytes(*max_volatility_accumulator),
+            tick_group_size: u16::from_le_bytes(*tick_group_size),
+            major_swap_threshold_ticks: u16::from_le_bytes(*major_swap_threshold_ticks),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct AdaptiveFeeVariables {
+    pub last_reference_update_timestamp: u64, // 8
+    pub last_major_swap_timestamp: u64, // 8
+    pub volatility_reference: u32, // 4
+    pub tick_group_index_reference: i32, // 4
+    pub volatility_accumulator: u32, // 4
+}
+
+impl AccountDataSerializer for AdaptiveFeeVariables {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 44]; // 44
+        let (last_reference_update_timestamp, last_major_swap_timestamp, volatility_reference, tick_group_index_reference, volatility_accumulator, _) =
+            array_refs![src, 8, 8, 4, 4, 4, 16];
+
+        AdaptiveFeeVariables {
+            last_reference_update_timestamp: u64::from_le_bytes(*last_reference_update_timestamp),
+            last_major_swap_timestamp: u64::from_le_bytes(*last_major_swap_timestamp),
+            volatility_reference: u32::from_le_bytes(*volatility_reference),
+            tick_group_index_reference: i32::from_le_bytes(*tick_group_index_reference),
+            volatility_accumulator: u32::from_le_bytes(*volatility_accumulator),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub enum OrcaClmmAccount {
     WhirlpoolsConfig(WhirlpoolsConfigAccount),
-    TickArray(TickArrayAccount)
+    TickArray(TickArrayAccount),
+    Oracle(WhirlpoolOracleAccount)
 }
 
 impl OrcaClmmAccount {
@@ -245,6 +392,9 @@ impl OrcaClmmAccount {
             OrcaClmmAccount::TickArray(account) => {
                 account.pubkey
             }
+            OrcaClmmAccount::Oracle(account) => {
+                account.pubkey
+            }
         }
     }
 
@@ -258,7 +408,7 @@ impl OrcaClmmAccount {
                 OrcaClmmAccount::TickArray(TickArrayAccount {
                     pubkey,
                     market: Market::ORCA,
-                    tick_array: TickArray::unpack_data(data),
+                    tick_array: Arc::new(TickArray::unpack_data(data)),
                 })
             }
             ORCA_CLMM_WHIRLPOOL_CONFIG => {
@@ -268,6 +418,21 @@ impl OrcaClmmAccount {
                     config: WhirlpoolsConfig::unpack_data(data),
                 })
             }
+            ORCA_CLMM_ORACLE => {
+                OrcaClmmAccount::Oracle(WhirlpoolOracleAccount {
+                    pubkey,
+                    market: Market::ORCA,
+                    oracle: WhirlpoolOracle::unpack_data(data),
+                })
+            }
+            len if (ORCA_CLMM_DYNAMIC_TICK_ARRAY_MIN_LEN..=ORCA_CLMM_DYNAMIC_TICK_ARRAY_MAX_LEN).contains(&len)
+                && (len - ORCA_CLMM_DYNAMIC_TICK_ARRAY_MIN_LEN) % ORCA_CLMM_DYNAMIC_TICK_DATA == 0 => {
+                OrcaClmmAccount::TickArray(TickArrayAccount {
+                    pubkey,
+                    market: Market::ORCA,
+                    tick_array: Arc::new(TickArray::unpack_data(data)),
+                })
+            }
             _ => {
                 panic!("could not resolve account from data: pubkey({})", pubkey)
             }
@@ -367,6 +532,7 @@ pub mod whirlpool_builder {
                 tick_current_index: self.tick_current_index,
                 sqrt_price: self.sqrt_price,
                 tick_spacing: self.tick_spacing,
+                tick_spacing_seed: self.tick_spacing.to_le_bytes(),
                 fee_growth_global_a: self.fee_growth_global_a,
                 fee_growth_global_b: self.fee_growth_global_b,
                 fee_rate: self.fee_rate,
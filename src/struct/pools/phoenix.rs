@@ -0,0 +1,345 @@
+use std::any::Any;
+use std::str::FromStr;
+
+use arrayref::{array_ref, array_refs};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar::clock;
+
+use crate::constants::{PHOENIX_LOG_AUTHORITY_SEED, PHOENIX_MARKET_HEADER_LEN, PHOENIX_PROGRAM_PUBKEY, TOKEN_PROGRAM_PUBKEY};
+use crate::formula::base::{Formula, SwapQuote};
+use crate::formula::phoenix;
+use crate::r#struct::account::{get_clock, AccountDataSerializer, DeserializedAccount, DeserializedDataAccount};
+use crate::r#struct::market::PoolOperation;
+use crate::utils::PubkeyPair;
+
+// FIFOMarket fields between the header and the bids tree
+const PHOENIX_FIFO_MARKET_LEN: usize = 304;
+// root and padding of a red-black tree, then size, bump index and free list head of its node allocator
+const PHOENIX_TREE_HEADER_LEN: usize = 32;
+// 4 registers (left, right, parent, color), order id (16) and resting order (32)
+const PHOENIX_ORDER_NODE_LEN: usize = 64;
+
+const PHOENIX_SWAP_TAG: u8 = 0;
+const PHOENIX_IMMEDIATE_OR_CANCEL: u8 = 2;
+const PHOENIX_SELF_TRADE_CANCEL_PROVIDE: u8 = 1;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum PhoenixSide {
+    #[default]
+    Bid,
+    Ask
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PhoenixTokenParams { // 72
+    pub decimals: u32, // 4
+    pub vault_bump: u32, // 4
+    pub mint_key: Pubkey, // 32
+    pub vault_key: Pubkey, // 32
+}
+
+impl PhoenixTokenParams {
+    fn unpack_data(data: &[u8; 72]) -> Self {
+        let (decimals, vault_bump, mint_key, vault_key) = array_refs![data, 4, 4, 32, 32];
+
+        PhoenixTokenParams {
+            decimals: u32::from_le_bytes(*decimals),
+            vault_bump: u32::from_le_bytes(*vault_bump),
+            mint_key: Pubkey::new_from_array(*mint_key),
+            vault_key: Pubkey::new_from_array(*vault_key),
+        }
+    }
+}
+
+/// Order resting on the book, as a node of the bids or asks tree
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PhoenixRestingOrder { // 48
+    pub price_in_ticks: u64, // 8
+    pub order_sequence_number: u64, // 8
+    pub trader_index: u64, // 8
+    pub num_base_lots: u64, // 8
+    pub last_valid_slot: u64, // 8
+    pub last_valid_unix_timestamp_in_seconds: u64, // 8
+}
+
+impl PhoenixRestingOrder {
+    /// Expired orders are skipped by the matching engine, 0 means no expiry
+    pub fn is_expired(&self, current_slot: u64, current_timestamp: u64) -> bool {
+        (self.last_valid_slot != 0 && self.last_valid_slot < current_slot)
+            || (self.last_valid_unix_timestamp_in_seconds != 0 && self.last_valid_unix_timestamp_in_seconds < current_timestamp)
+    }
+
+    /// Bid sequence numbers are stored bitwise negated
+    pub fn get_side(&self) -> PhoenixSide {
+        if self.order_sequence_number >> 63 == 1 { PhoenixSide::Bid } else { PhoenixSide::Ask }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PhoenixMarket {
+    // MarketHeader, 576
+    pub discriminant: u64, // 8
+    pub status: u64, // 8
+    pub bids_size: u64, // 8
+    pub asks_size: u64, // 8
+    pub num_seats: u64, // 8
+    pub base_params: PhoenixTokenParams, // 72
+    pub base_lot_size: u64, // 8
+    pub quote_params: PhoenixTokenParams, // 72
+    pub quote_lot_size: u64, // 8
+    pub tick_size_in_quote_atoms_per_base_unit: u64, // 8
+    pub authority: Pubkey, // 32
+    pub fee_recipient: Pubkey, // 32
+    pub market_sequence_number: u64, // 8
+    pub successor: Pubkey, // 32
+    pub raw_base_units_per_base_unit: u32, // 4
+    // FIFOMarket, 304 before the trees
+    pub base_lots_per_base_unit: u64, // 8
+    pub tick_size_in_quote_lots_per_base_unit: u64, // 8
+    pub order_sequence_number: u64, // 8
+    pub taker_fee_bps: u64, // 8
+    pub collected_quote_lot_fees: u64, // 8
+    pub unclaimed_quote_lot_fees: u64, // 8
+    /// best price first, then by time priority
+    pub bids: Vec<PhoenixRestingOrder>,
+    pub asks: Vec<PhoenixRestingOrder>,
+}
+
+impl AccountDataSerializer for PhoenixMarket {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 880];
+        let (header, fifo_market) = array_refs![src, 576, 304];
+        let (discriminant, status, bids_size, asks_size, num_seats, base_params, base_lot_size, quote_params, quote_lot_size, tick_size_in_quote_atoms_per_base_unit, authority, fee_recipient, market_sequence_number, successor, raw_base_units_per_base_unit, _padding1, _padding2) =
+            array_refs![header, 8, 8, 8, 8, 8, 72, 8, 72, 8, 8, 32, 32, 8, 32, 4, 4, 256];
+        let (_padding, base_lots_per_base_unit, tick_size_in_quote_lots_per_base_unit, order_sequence_number, taker_fee_bps, collected_quote_lot_fees, unclaimed_quote_lot_fees) =
+            array_refs![fifo_market, 256, 8, 8, 8, 8, 8, 8];
+
+        let bids_size = u64::from_le_bytes(*bids_size);
+        let asks_size = u64::from_le_bytes(*asks_size);
+        let bids_offset = PHOENIX_MARKET_HEADER_LEN + PHOENIX_FIFO_MARKET_LEN;
+        let asks_offset = bids_offset + Self::get_tree_len(bids_size);
+
+        let mut bids = Self::unpack_orders(data, bids_offset, bids_size);
+        let mut asks = Self::unpack_orders(data, asks_offset, asks_size);
+        // same price orders fill the oldest first
+        bids.sort_by(|a, b| b.price_in_ticks.cmp(&a.price_in_ticks).then((!a.order_sequence_number).cmp(&!b.order_sequence_number)));
+        asks.sort_by(|a, b| a.price_in_ticks.cmp(&b.price_in_ticks).then(a.order_sequence_number.cmp(&b.order_sequence_number)));
+
+        PhoenixMarket {
+            discriminant: u64::from_le_bytes(*discriminant),
+            status: u64::from_le_bytes(*status),
+            bids_size,
+            asks_size,
+            num_seats: u64::from_le_bytes(*num_seats),
+            base_params: PhoenixTokenParams::unpack_data(base_params),
+            base_lot_size: u64::from_le_bytes(*base_lot_size),
+            quote_params: PhoenixTokenParams::unpack_data(quote_params),
+            quote_lot_size: u64::from_le_bytes(*quote_lot_size),
+            tick_size_in_quote_atoms_per_base_unit: u64::from_le_bytes(*tick_size_in_quote_atoms_per_base_unit),
+            authority: Pubkey::new_from_array(*authority),
+            fee_recipient: Pubkey::new_from_array(*fee_recipient),
+            market_sequence_number: u64::from_le_bytes(*market_sequence_number),
+            successor: Pubkey::new_from_array(*successor),
+            raw_base_units_per_base_unit: u32::from_le_bytes(*raw_base_units_per_base_unit),
+            base_lots_per_base_unit: u64::from_le_bytes(*base_lots_per_base_unit),
+            tick_size_in_quote_lots_per_base_unit: u64::from_le_bytes(*tick_size_in_quote_lots_per_base_unit),
+            order_sequence_number: u64::from_le_bytes(*order_sequence_number),
+            taker_fee_bps: u64::from_le_bytes(*taker_fee_bps),
+            collected_quote_lot_fees: u64::from_le_bytes(*collected_quote_lot_fees),
+            unclaimed_quote_lot_fees: u64::from_le_bytes(*unclaimed_quote_lot_fees),
+            bids,
+            asks,
+        }
+    }
+}
+
+impl PhoenixMarket {
+    pub fn get_tree_len(capacity: u64) -> usize {
+        PHOENIX_TREE_HEADER_LEN + PHOENIX_ORDER_NODE_LEN * capacity as usize
+    }
+
+    /// Orders of the tree at `offset`, walked from the root so that freed nodes are left out.
+    /// A tree cut short by the account data yields the orders read so far
+    fn unpack_orders(data: &[u8], offset: usize, capacity: u64) -> Vec<PhoenixRestingOrder> {
+        let read_u32 = |at: usize| data.get(at..at + 4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()));
+        let read_u64 = |at: usize| data.get(at..at + 8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()));
+
+        let mut orders = Vec::new();
+        let mut stack = match read_u32(offset) {
+            Some(root) if root != 0 => vec![root],
+            _ => return orders
+        };
+        // node addresses start from 1, 0 being the sentinel
+        while let Some(node) = stack.pop() {
+            if node as u64 > capacity || orders.len() as u64 >= capacity {
+                break;
+            }
+            let at = offset + PHOENIX_TREE_HEADER_LEN + (node as usize - 1) * PHOENIX_ORDER_NODE_LEN;
+            let (Some(left), Some(right)) = (read_u32(at), read_u32(at + 4)) else { break };
+            let fields = (16..64).step_by(8).map(|field| read_u64(at + field)).collect::<Option<Vec<u64>>>();
+            let Some(fields) = fields else { break };
+
+            orders.push(PhoenixRestingOrder {
+                price_in_ticks: fields[0],
+                order_sequence_number: fields[1],
+                trader_index: fields[2],
+                num_base_lots: fields[3],
+                last_valid_slot: fields[4],
+                last_valid_unix_timestamp_in_seconds: fields[5],
+            });
+            [left, right].iter().filter(|child| **child != 0).for_each(|child| stack.push(*child));
+        }
+
+        orders
+    }
+
+    pub fn log_authority(program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[PHOENIX_LOG_AUTHORITY_SEED.as_bytes()], program_id).0
+    }
+
+    /// Builds `Swap` with an immediate-or-cancel order selling `amount_in` of `mint_in`.
+    /// Amounts are rounded to lots, down for the input and up for `minimum_amount_out`
+    pub fn get_swap_instruction(
+        &self,
+        market: &Pubkey,
+        trader: &Pubkey,
+        base_account: &Pubkey,
+        quote_account: &Pubkey,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64
+    ) -> Result<Instruction, &'static str> {
+        if self.base_lot_size == 0 || self.quote_lot_size == 0 {
+            return Err("zero lot size");
+        }
+        // (side, num_base_lots, num_quote_lots, min_base_lots_to_fill, min_quote_lots_to_fill)
+        let order = if *mint_in == self.base_params.mint_key {
+            (PhoenixSide::Ask, amount_in / self.base_lot_size, 0, 0, minimum_amount_out.div_ceil(self.quote_lot_size))
+        } else if *mint_in == self.quote_params.mint_key {
+            (PhoenixSide::Bid, 0, amount_in / self.quote_lot_size, minimum_amount_out.div_ceil(self.base_lot_size), 0)
+        } else {
+            return Err("mint not in market");
+        };
+
+        let mut data = vec![PHOENIX_SWAP_TAG, PHOENIX_IMMEDIATE_OR_CANCEL, order.0 as u8];
+        data.push(0); // price_in_ticks: None, take any price
+        [order.1, order.2, order.3, order.4].iter().for_each(|value| data.extend_from_slice(&value.to_le_bytes()));
+        data.push(PHOENIX_SELF_TRADE_CANCEL_PROVIDE);
+        data.push(0); // match_limit: None
+        data.extend_from_slice(&0u128.to_le_bytes()); // client_order_id
+        data.push(0); // use_only_deposited_funds
+        data.extend_from_slice(&[0, 0]); // last_valid_slot, last_valid_unix_timestamp_in_seconds: None
+
+        let program_id = Pubkey::from_str(PHOENIX_PROGRAM_PUBKEY).unwrap();
+        Ok(Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new_readonly(Self::log_authority(&program_id), false),
+                AccountMeta::new(*market, false),
+                AccountMeta::new_readonly(*trader, true),
+                AccountMeta::new(*base_account, false),
+                AccountMeta::new(*quote_account, false),
+                AccountMeta::new(self.base_params.vault_key, false),
+                AccountMeta::new(self.quote_params.vault_key, false),
+                AccountMeta::new_readonly(Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap(), false),
+            ]
+        ))
+    }
+}
+
+impl PoolOperation for PhoenixMarket {
+    fn get_mint_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.base_params.mint_key,
+            pubkey_b: self.quote_params.mint_key,
+        }
+    }
+
+    fn get_pool_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.base_params.vault_key,
+            pubkey_b: self.quote_params.vault_key,
+        }
+    }
+
+    fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
+        // the market account holds the book, the clock expires resting orders
+        vec![
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), clock::id()),
+        ]
+    }
+
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        let program_id = Pubkey::from_str(PHOENIX_PROGRAM_PUBKEY).unwrap();
+        vec![self.base_params.vault_key, self.quote_params.vault_key, Self::log_authority(&program_id)]
+    }
+
+    fn get_formula(&self) -> Formula {
+        Formula::OrderBook
+    }
+
+    fn get_fee_rate(&self) -> Option<u32> {
+        u32::try_from(self.taker_fee_bps * 100).ok()
+    }
+
+    /// Takers are matched only while the market is active
+    fn is_tradable(&self) -> bool {
+        self.status == 1
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let base_in = if *mint_in == self.base_params.mint_key {
+            true
+        } else if *mint_in == self.quote_params.mint_key {
+            false
+        } else {
+            return Err("mint not in market");
+        };
+
+        let market = accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(account) => account.operation.as_any().downcast_ref::<PhoenixMarket>(),
+                _ => None
+            }
+        }).unwrap_or(self);
+        if !market.is_tradable() {
+            return Err("market not active");
+        }
+        let clock = get_clock(accounts).ok_or("clock not found")?;
+
+        let (amount_out, fill) = phoenix::quote_exact_in(market, amount_in, base_in, clock.slot, clock.unix_timestamp as u64)?;
+
+        Ok(SwapQuote { amount_in, amount_out, crossings: fill.orders })
+    }
+
+    fn has_swap_instructions(&self) -> bool {
+        true
+    }
+
+    fn get_swap_instructions(
+        &self,
+        pool: &Pubkey,
+        user: &Pubkey,
+        source_token_account: &Pubkey,
+        destination_token_account: &Pubkey,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64
+    ) -> Result<Vec<Instruction>, &'static str> {
+        let (base_account, quote_account) = if *mint_in == self.base_params.mint_key {
+            (source_token_account, destination_token_account)
+        } else {
+            (destination_token_account, source_token_account)
+        };
+
+        let instruction = self.get_swap_instruction(pool, user, base_account, quote_account, mint_in, amount_in, minimum_amount_out)?;
+        Ok(vec![instruction])
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
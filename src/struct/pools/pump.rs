@@ -0,0 +1,378 @@
+use std::any::Any;
+use std::str::FromStr;
+
+use arrayref::{array_ref, array_refs};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::constants::{NATIVE_MINT_PUBKEY, PUMP_FUN_BONDING_CURVE_SEED, PUMP_FUN_GLOBAL_SEED, PUMP_FUN_PROGRAM_PUBKEY, PUMP_SWAP_GLOBAL_CONFIG_SEED, PUMP_SWAP_PROGRAM_PUBKEY, TOKEN_PROGRAM_PUBKEY};
+use crate::executor::wallet::get_associated_token_address;
+use crate::formula::base::{Formula, SwapQuote};
+use crate::formula::pump;
+use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount, DeserializedDataAccount, DeserializedTokenAccount};
+use crate::r#struct::market::PoolOperation;
+use crate::utils::PubkeyPair;
+
+/// Finds the data of `pubkey` among raw accounts
+fn get_account_data<'a>(accounts: &'a Vec<DeserializedAccount>, pubkey: &Pubkey) -> Option<&'a Vec<u8>> {
+    accounts.iter().find_map(|account| {
+        match account {
+            DeserializedAccount::Account(account) if account.pubkey == *pubkey => Some(&account.account.data),
+            _ => None
+        }
+    })
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PumpFunGlobal { // 162
+    pub initialized: bool, // 1
+    pub authority: Pubkey, // 32
+    pub fee_recipient: Pubkey, // 32
+    pub initial_virtual_token_reserves: u64, // 8
+    pub initial_virtual_sol_reserves: u64, // 8
+    pub initial_real_token_reserves: u64, // 8
+    pub token_total_supply: u64, // 8
+    pub fee_basis_points: u64, // 8
+    pub withdraw_authority: Pubkey, // 32
+    pub enable_migrate: bool, // 1
+    pub pool_migration_fee: u64, // 8
+    pub creator_fee_basis_points: u64, // 8
+}
+
+impl AccountDataSerializer for PumpFunGlobal {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 162];
+        let (
+            _discriminator,
+            initialized,
+            authority,
+            fee_recipient,
+            initial_virtual_token_reserves,
+            initial_virtual_sol_reserves,
+            initial_real_token_reserves,
+            token_total_supply,
+            fee_basis_points,
+            withdraw_authority,
+            enable_migrate,
+            pool_migration_fee,
+            creator_fee_basis_points
+        ) = array_refs![src, 8, 1, 32, 32, 8, 8, 8, 8, 8, 32, 1, 8, 8];
+
+        PumpFunGlobal {
+            initialized: initialized[0] != 0,
+            authority: Pubkey::new_from_array(*authority),
+            fee_recipient: Pubkey::new_from_array(*fee_recipient),
+            initial_virtual_token_reserves: u64::from_le_bytes(*initial_virtual_token_reserves),
+            initial_virtual_sol_reserves: u64::from_le_bytes(*initial_virtual_sol_reserves),
+            initial_real_token_reserves: u64::from_le_bytes(*initial_real_token_reserves),
+            token_total_supply: u64::from_le_bytes(*token_total_supply),
+            fee_basis_points: u64::from_le_bytes(*fee_basis_points),
+            withdraw_authority: Pubkey::new_from_array(*withdraw_authority),
+            enable_migrate: enable_migrate[0] != 0,
+            pool_migration_fee: u64::from_le_bytes(*pool_migration_fee),
+            creator_fee_basis_points: u64::from_le_bytes(*creator_fee_basis_points),
+        }
+    }
+}
+
+/// Pump.fun bonding curve of one token, trading native SOL against the token at virtual reserves until it completes and migrates.
+/// The account does not hold its mint, `mint` and `token_program` are filled in from the token account the curve owns
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PumpFunBondingCurve { // 49, 81 with the creator
+    pub virtual_token_reserves: u64, // 8
+    pub virtual_sol_reserves: u64, // 8
+    pub real_token_reserves: u64, // 8
+    pub real_sol_reserves: u64, // 8
+    pub token_total_supply: u64, // 8
+    pub complete: bool, // 1
+    pub creator: Pubkey, // 32
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl AccountDataSerializer for PumpFunBondingCurve {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 49];
+        let (
+            _discriminator,
+            virtual_token_reserves,
+            virtual_sol_reserves,
+            real_token_reserves,
+            real_sol_reserves,
+            token_total_supply,
+            complete
+        ) = array_refs![src, 8, 8, 8, 8, 8, 8, 1];
+        // curves created before creator fees end at `complete`
+        let creator = data.get(49..81).map_or(Pubkey::default(), |creator| Pubkey::new_from_array(creator.try_into().unwrap()));
+
+        PumpFunBondingCurve {
+            virtual_token_reserves: u64::from_le_bytes(*virtual_token_reserves),
+            virtual_sol_reserves: u64::from_le_bytes(*virtual_sol_reserves),
+            real_token_reserves: u64::from_le_bytes(*real_token_reserves),
+            real_sol_reserves: u64::from_le_bytes(*real_sol_reserves),
+            token_total_supply: u64::from_le_bytes(*token_total_supply),
+            complete: complete[0] != 0,
+            creator,
+            ..PumpFunBondingCurve::default()
+        }
+    }
+}
+
+impl PumpFunBondingCurve {
+    pub fn bonding_curve(mint: &Pubkey) -> Pubkey {
+        let program_id = Pubkey::from_str(PUMP_FUN_PROGRAM_PUBKEY).unwrap();
+        Pubkey::find_program_address(&[PUMP_FUN_BONDING_CURVE_SEED.as_bytes(), mint.as_ref()], &program_id).0
+    }
+
+    pub fn global() -> Pubkey {
+        let program_id = Pubkey::from_str(PUMP_FUN_PROGRAM_PUBKEY).unwrap();
+        Pubkey::find_program_address(&[PUMP_FUN_GLOBAL_SEED.as_bytes()], &program_id).0
+    }
+
+    /// Token account of the curve holding the tokens left to sell
+    pub fn associated_bonding_curve(&self) -> Pubkey {
+        get_associated_token_address(&Self::bonding_curve(&self.mint), &self.mint, &self.token_program)
+    }
+
+    /// Creator fees are charged once the curve has a creator
+    pub fn get_creator_fee_bps(&self, global: &PumpFunGlobal) -> u64 {
+        if self.creator == Pubkey::default() { 0 } else { global.creator_fee_basis_points }
+    }
+}
+
+impl PoolOperation for PumpFunBondingCurve {
+    fn get_mint_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap(),
+            pubkey_b: self.mint,
+        }
+    }
+
+    fn get_pool_pair(&self) -> PubkeyPair {
+        // SOL is held as lamports of the curve itself
+        PubkeyPair {
+            pubkey_a: Self::bonding_curve(&self.mint),
+            pubkey_b: self.associated_bonding_curve(),
+        }
+    }
+
+    fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
+        // reserves live in the curve, fees in the global account
+        vec![
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), Self::global()),
+        ]
+    }
+
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        let pool_pair = self.get_pool_pair();
+        vec![pool_pair.pubkey_a, pool_pair.pubkey_b, Self::global(), self.mint, self.token_program]
+    }
+
+    fn get_formula(&self) -> Formula {
+        Formula::ConstantProduct
+    }
+
+    fn is_tradable(&self) -> bool {
+        !self.complete
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        // the fresh curve is decoded from data alone, the mint stays the one of this curve
+        let curve = accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(account) => account.operation.as_any().downcast_ref::<PumpFunBondingCurve>(),
+                _ => None
+            }
+        }).unwrap_or(self);
+        let global = PumpFunGlobal::unpack_data(get_account_data(accounts, &Self::global()).ok_or("global not found")?);
+
+        let amount_out = if *mint_in == Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap() {
+            pump::quote_bonding_curve_buy(curve, &global, amount_in)?
+        } else if *mint_in == self.mint {
+            pump::quote_bonding_curve_sell(curve, &global, amount_in)?
+        } else {
+            return Err("mint not in pool");
+        };
+
+        Ok(SwapQuote { amount_in, amount_out, crossings: 0 })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PumpSwapGlobalConfig { // 321
+    pub admin: Pubkey, // 32
+    pub lp_fee_basis_points: u64, // 8
+    pub protocol_fee_basis_points: u64, // 8
+    pub disable_flags: u8, // 1
+    pub protocol_fee_recipients: [Pubkey; 8], // 256
+    pub coin_creator_fee_basis_points: u64, // 8
+}
+
+impl AccountDataSerializer for PumpSwapGlobalConfig {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 321];
+        let (
+            _discriminator,
+            admin,
+            lp_fee_basis_points,
+            protocol_fee_basis_points,
+            disable_flags,
+            protocol_fee_recipients,
+            coin_creator_fee_basis_points
+        ) = array_refs![src, 8, 32, 8, 8, 1, 256, 8];
+
+        let mut recipients = [Pubkey::default(); 8];
+        protocol_fee_recipients.chunks_exact(32).enumerate().for_each(|(index, recipient)| {
+            recipients[index] = Pubkey::new_from_array(recipient.try_into().unwrap());
+        });
+
+        PumpSwapGlobalConfig {
+            admin: Pubkey::new_from_array(*admin),
+            lp_fee_basis_points: u64::from_le_bytes(*lp_fee_basis_points),
+            protocol_fee_basis_points: u64::from_le_bytes(*protocol_fee_basis_points),
+            disable_flags: disable_flags[0],
+            protocol_fee_recipients: recipients,
+            coin_creator_fee_basis_points: u64::from_le_bytes(*coin_creator_fee_basis_points),
+        }
+    }
+}
+
+/// PumpSwap constant product pool, where Pump.fun curves migrate once complete
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PumpSwapPool { // 211, 243 with the coin creator
+    pub pool_bump: u8, // 1
+    pub index: u16, // 2
+    pub creator: Pubkey, // 32
+    pub base_mint: Pubkey, // 32
+    pub quote_mint: Pubkey, // 32
+    pub lp_mint: Pubkey, // 32
+    pub pool_base_token_account: Pubkey, // 32
+    pub pool_quote_token_account: Pubkey, // 32
+    pub lp_supply: u64, // 8
+    pub coin_creator: Pubkey, // 32
+}
+
+impl AccountDataSerializer for PumpSwapPool {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 211];
+        let (
+            _discriminator,
+            pool_bump,
+            index,
+            creator,
+            base_mint,
+            quote_mint,
+            lp_mint,
+            pool_base_token_account,
+            pool_quote_token_account,
+            lp_supply
+        ) = array_refs![src, 8, 1, 2, 32, 32, 32, 32, 32, 32, 8];
+        // pools created before coin creator fees end at `lp_supply`
+        let coin_creator = data.get(211..243).map_or(Pubkey::default(), |coin_creator| Pubkey::new_from_array(coin_creator.try_into().unwrap()));
+
+        PumpSwapPool {
+            pool_bump: pool_bump[0],
+            index: u16::from_le_bytes(*index),
+            creator: Pubkey::new_from_array(*creator),
+            base_mint: Pubkey::new_from_array(*base_mint),
+            quote_mint: Pubkey::new_from_array(*quote_mint),
+            lp_mint: Pubkey::new_from_array(*lp_mint),
+            pool_base_token_account: Pubkey::new_from_array(*pool_base_token_account),
+            pool_quote_token_account: Pubkey::new_from_array(*pool_quote_token_account),
+            lp_supply: u64::from_le_bytes(*lp_supply),
+            coin_creator,
+        }
+    }
+}
+
+impl PumpSwapPool {
+    pub fn global_config() -> Pubkey {
+        let program_id = Pubkey::from_str(PUMP_SWAP_PROGRAM_PUBKEY).unwrap();
+        Pubkey::find_program_address(&[PUMP_SWAP_GLOBAL_CONFIG_SEED.as_bytes()], &program_id).0
+    }
+
+    /// Coin creator fees are charged once the pool has a coin creator
+    pub fn get_coin_creator_fee_bps(&self, config: &PumpSwapGlobalConfig) -> u64 {
+        if self.coin_creator == Pubkey::default() { 0 } else { config.coin_creator_fee_basis_points }
+    }
+}
+
+impl PoolOperation for PumpSwapPool {
+    fn get_mint_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.base_mint,
+            pubkey_b: self.quote_mint,
+        }
+    }
+
+    fn get_pool_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.pool_base_token_account,
+            pubkey_b: self.pool_quote_token_account,
+        }
+    }
+
+    fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
+        vec![
+            (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.pool_base_token_account),
+            (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.pool_quote_token_account),
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), Self::global_config()),
+        ]
+    }
+
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        vec![
+            self.pool_base_token_account,
+            self.pool_quote_token_account,
+            Self::global_config(),
+            self.base_mint,
+            self.quote_mint,
+            Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap(),
+        ]
+    }
+
+    fn get_formula(&self) -> Formula {
+        Formula::ConstantProduct
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let pool = accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(account) => account.operation.as_any().downcast_ref::<PumpSwapPool>(),
+                _ => None
+            }
+        }).unwrap_or(self);
+        let config = PumpSwapGlobalConfig::unpack_data(get_account_data(accounts, &Self::global_config()).ok_or("global config not found")?);
+
+        let (mut base_reserve, mut quote_reserve): (Option<u64>, Option<u64>) = (None, None);
+        accounts.iter().for_each(|account| {
+            if let DeserializedAccount::TokenAccount(token) = account {
+                if token.pubkey == pool.pool_base_token_account {
+                    base_reserve = Some(token.get_amount())
+                } else if token.pubkey == pool.pool_quote_token_account {
+                    quote_reserve = Some(token.get_amount())
+                }
+            }
+        });
+        let base_reserve = base_reserve.ok_or("base reserve not found")?;
+        let quote_reserve = quote_reserve.ok_or("quote reserve not found")?;
+        let coin_creator_fee_bps = pool.get_coin_creator_fee_bps(&config);
+
+        let amount_out = if *mint_in == pool.quote_mint {
+            pump::quote_pump_swap_buy(&config, coin_creator_fee_bps, base_reserve, quote_reserve, amount_in)?
+        } else if *mint_in == pool.base_mint {
+            pump::quote_pump_swap_sell(&config, coin_creator_fee_bps, base_reserve, quote_reserve, amount_in)?
+        } else {
+            return Err("mint not in pool");
+        };
+
+        Ok(SwapQuote { amount_in, amount_out, crossings: 0 })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
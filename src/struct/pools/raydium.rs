@@ -1,23 +1,37 @@
 use std::any::Any;
 use std::collections::VecDeque;
+use std::mem::size_of;
 use std::ops::BitXor;
+use std::str::FromStr;
+use std::sync::Arc;
 
 use arrayref::{array_ref, array_refs};
 use serde::Deserialize;
+use serum_dex::critbit::Slab;
+use serum_dex::matching::Side;
+use serum_dex::state::{AccountFlag, Event, EventQueueHeader, EventView, MarketState, OpenOrders, QueueHeader};
 use solana_sdk::account::Account;
 use solana_sdk::account_info::AccountInfo;
+use solana_sdk::instruction::{AccountMeta, Instruction};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar::clock;
 
 use crate::constants::*;
-use crate::formula::base::Formula;
+use crate::formula::base::{Formula, SwapQuote};
 use crate::formula::base::Formula::ConcentratedLiquidity;
 use crate::formula::clmm::constant::{MAX_TICK, MIN_TICK, POOL_SEED, REWARD_NUM, TICK_ARRAY_BITMAP_SIZE};
 use crate::formula::clmm::raydium_tick_array::{check_current_tick_array_is_initialized, max_tick_in_tick_array_bitmap, next_initialized_tick_array_start_index, TickArrayBitmapExtension, TickArrayBitmapExtensionAccount, TickArrayState, TickArrayStateAccount};
 use crate::formula::clmm::u256_math::U1024;
-use crate::r#struct::account::{AccountDataSerializer, DeserializedAccount, DeserializedConfigAccount, DeserializedTokenAccount};
-use crate::r#struct::account::DeserializedConfigAccount::RaydiumClmmConfigAccount;
+use crate::formula::raydium_clmm::swap_internal;
+use crate::formula::openbook::math::SwapDirection;
+use crate::formula::raydium_cpmm;
+use crate::formula::raydium_cpmm::{get_transfer_fee, TransferFee};
+use crate::formula::raydium_openbook;
+use crate::formula::raydium_openbook::SerumState;
+use crate::r#struct::account::{get_clock, AccountDataSerializer, DeserializedAccount, DeserializedConfigAccount, DeserializedDataAccount, DeserializedTokenAccount};
+use crate::r#struct::account::DeserializedConfigAccount::{RaydiumClmmConfigAccount, RaydiumCpmmConfigAccount, RaydiumOpenBookConfigAccount};
 use crate::r#struct::market::{Market, PoolOperation};
-use crate::utils::PubkeyPair;
+use crate::utils::{get_discriminator, PubkeyPair};
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct RaydiumClmmMarket {
@@ -141,19 +155,36 @@ impl PoolOperation for RaydiumClmmMarket {
         ]
     }
 
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        vec![self.token_vault_0, self.token_vault_1, self.amm_config, self.observation_key]
+    }
+
     fn get_formula(&self) -> Formula {
         ConcentratedLiquidity
     }
 
-    fn swap(&self, accounts: &Vec<DeserializedAccount>) {
-        let amount = 0u64;
-        let zero_for_one = true; // equivalent to a_to_b
-        let is_base_input = true; // equivalent to amount_specified_is_input
+    fn get_liquidity(&self) -> Option<u128> {
+        Some(self.liquidity)
+    }
+
+    fn is_tradable(&self) -> bool {
+        // bit 4 of status disables swap
+        self.status & (1 << 4) == 0
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let zero_for_one = if *mint_in == self.token_mint_0 {
+            true
+        } else if *mint_in == self.token_mint_1 {
+            false
+        } else {
+            return Err("mint not in pool");
+        };
 
-        let mut market = RaydiumClmmMarket::default();
-        let mut amm_config = AmmConfig::default();
-        let mut tick_array_states: VecDeque<TickArrayState> = VecDeque::new();
-        let mut tick_array_bitmap_extension = TickArrayBitmapExtension::default();
+        let mut market = *self;
+        let mut amm_config: Option<AmmConfig> = None;
+        let mut tick_array_states: Vec<Arc<TickArrayState>> = Vec::new();
+        let mut tick_array_bitmap_extension: Option<&TickArrayBitmapExtension> = None;
 
         accounts.iter().for_each(|account| {
             match account {
@@ -162,52 +193,69 @@ impl PoolOperation for RaydiumClmmMarket {
                         market = *raydium_clmm_market;
                     }
                 }
-                DeserializedAccount::ConfigAccount(config) => {
-                    match config {
-                        RaydiumClmmConfigAccount(raydium_config) => {
-                            match raydium_config {
-                                RaydiumClmmAccount::AmmConfig(amm) => {
-                                    amm_config = amm.config
-                                }
-                                RaydiumClmmAccount::TickArrayState(state) => {
-                                    // todo: push only directed tick array state
-                                    tick_array_states.push_back(state.tick_array_state.clone())
-                                }
-                                RaydiumClmmAccount::TickArrayBitmapExtension(extension) => {
-                                    tick_array_bitmap_extension = extension.tick_array_bitmap_extension.clone()
-                                }
-                                RaydiumClmmAccount::ObservationKey => {}
-                            }
+                DeserializedAccount::ConfigAccount(RaydiumClmmConfigAccount(raydium_config)) => {
+                    match raydium_config {
+                        RaydiumClmmAccount::AmmConfig(amm) => {
+                            amm_config = Some(amm.config)
+                        }
+                        RaydiumClmmAccount::TickArrayState(state) => {
+                            tick_array_states.push(Arc::clone(&state.tick_array_state))
+                        }
+                        RaydiumClmmAccount::TickArrayBitmapExtension(extension) => {
+                            tick_array_bitmap_extension = Some(&extension.tick_array_bitmap_extension)
                         }
-                        _ => {}
+                        RaydiumClmmAccount::ObservationKey => {}
                     }
                 }
-                DeserializedAccount::Account(_) => {}
-                DeserializedAccount::TokenAccount(_) => {}
+                _ => {}
             }
         });
 
-        let mut tick_array_states = tick_array_states.into_iter().filter(|tick_array_state| {
+        let amm_config = amm_config.ok_or("amm config not found")?;
+
+        // keep the tick array states on the swap direction, ordered from the current tick
+        let ticks_in_array = TickArrayState::tick_count(market.tick_spacing);
+        tick_array_states.retain(|tick_array_state| {
             if zero_for_one {
-                tick_array_state.start_tick_index >= market.tick_current
+                tick_array_state.start_tick_index <= market.tick_current
             }
             else {
-                tick_array_state.start_tick_index <= market.tick_current
+                tick_array_state.start_tick_index + ticks_in_array > market.tick_current
             }
-        }).collect::<VecDeque<TickArrayState>>();
+        });
+        if zero_for_one {
+            tick_array_states.sort_by(|a, b| b.start_tick_index.cmp(&a.start_tick_index));
+        }
+        else {
+            tick_array_states.sort_by(|a, b| a.start_tick_index.cmp(&b.start_tick_index));
+        }
+        let tick_current = market.tick_current;
+        let mut swap_tick_array_states = tick_array_states.iter().cloned().collect::<VecDeque<Arc<TickArrayState>>>();
 
-        let sqrt_price_x64 = market.sqrt_price_x64;
+        let (amount_0, amount_1) = swap_internal(
+            &amm_config,
+            &mut market,
+            &mut swap_tick_array_states,
+            &tick_array_bitmap_extension,
+            amount_in,
+            0,
+            zero_for_one,
+            true
+        )?;
 
-        // swap_internal(
-        //     &amm_config,
-        //     &mut market,
-        //     &mut tick_array_states,
-        //     &Some(&tick_array_bitmap_extension),
-        //     amount,
-        //     sqrt_price_x64,
-        //     zero_for_one,
-        //     is_base_input
-        // ).expect("swap failed");
+        // swap_internal moved the pool to the tick the swap ended at
+        let lower_tick_index = tick_current.min(market.tick_current);
+        let upper_tick_index = tick_current.max(market.tick_current);
+        let crossings = tick_array_states.iter().map(|tick_array_state| {
+            tick_array_state.count_initialized_ticks(lower_tick_index, upper_tick_index)
+        }).sum();
+
+        if zero_for_one {
+            Ok(SwapQuote { amount_in: amount_0, amount_out: amount_1, crossings })
+        }
+        else {
+            Ok(SwapQuote { amount_in: amount_1, amount_out: amount_0, crossings })
+        }
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -546,7 +594,7 @@ impl RaydiumClmmAccount {
                 RaydiumClmmAccount::TickArrayState(TickArrayStateAccount {
                     pubkey,
                     market: Market::RAYDIUM,
-                    tick_array_state: TickArrayState::unpack_data(data)
+                    tick_array_state: Arc::new(TickArrayState::unpack_data(data))
                 })
             }
             _ => {
@@ -697,52 +745,101 @@ impl PoolOperation for RaydiumOpenBookMarket {
     }
 
     fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
-        vec![
+        let mut vec = vec![
             (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.base_vault),
             (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.quote_vault),
-        ]
+        ];
+        // part of the reserves sits in the open orders, the market's event queue and book sides are added with the market fetched
+        if self.orderbook_permission() {
+            vec.push((DeserializedAccount::ConfigAccount(DeserializedConfigAccount::default()), self.open_orders));
+        }
+
+        vec
+    }
+
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        vec![self.base_vault, self.quote_vault, self.open_orders, self.target_orders, self.market_id, self.market_program_id]
     }
 
     fn get_formula(&self) -> Formula {
         Formula::OpenBook
     }
 
-    fn swap(&self, accounts: &Vec<DeserializedAccount>) {
-        todo!()
-        // if !accounts.is_empty() {
-        //     let mut raydium_pool= &DeserializedPoolAccount::default();
-        //     let mut base_vault= &DeserializedTokenAccount::default();
-        //     let mut quote_vault= &DeserializedTokenAccount::default();
-        //
-        //     accounts.iter().for_each(|account| {
-        //         match account {
-        //             DeserializedAccount::Account(_) => {}
-        //             DeserializedAccount::PoolAccount(pool) => {
-        //                 raydium_pool = &pool
-        //             }
-        //             DeserializedAccount::TokenAccount(token) => {
-        //                 if token.pubkey == self.base_vault {
-        //                     base_vault = &token
-        //                 }
-        //                 else if token.pubkey == self.quote_vault {
-        //                     quote_vault = &token
-        //                 }
-        //             }
-        //             DeserializedAccount::ConfigAccount(_) => {}
-        //         }
-        //     });
-        //
-        //     let cpmm = DefaultConstantProduct {
-        //         token_a_amount: base_vault.get_amount(),
-        //         token_b_amount: quote_vault.get_amount(),
-        //         decimal_diff: (self.base_decimal - self.quote_decimal) as i32,
-        //         swap_fee_numerator: self.swap_fee_numerator,
-        //         swap_fee_denominator: self.swap_fee_denominator
-        //     };
-        //
-        //     let res = cpmm.swap(1000000000u64, true);
-        //     println!("{}", res);
-        // }
+    fn get_fee_rate(&self) -> Option<u32> {
+        if self.swap_fee_denominator == 0 {
+            return None;
+        }
+        u32::try_from(self.swap_fee_numerator as u128 * 1_000_000 / self.swap_fee_denominator as u128).ok()
+    }
+
+    fn is_tradable(&self) -> bool {
+        // Initialized, SwapOnly, WaitingTrade
+        matches!(self.status, 1 | 6 | 7)
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let swap_direction = if *mint_in == self.base_mint {
+            SwapDirection::Coin2PC
+        } else if *mint_in == self.quote_mint {
+            SwapDirection::PC2Coin
+        } else {
+            return Err("mint not in pool");
+        };
+
+        let mut amm = *self;
+        let (mut coin_vault_amount, mut pc_vault_amount) = (None, None);
+        let (mut open_orders, mut event_queue, mut bids, mut asks) = (None, None, None, None);
+        accounts.iter().for_each(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(pool) => {
+                    if let Some(raydium_pool) = pool.operation.as_any().downcast_ref::<RaydiumOpenBookMarket>() {
+                        amm = *raydium_pool;
+                    }
+                }
+                DeserializedAccount::TokenAccount(token) => {
+                    if token.pubkey == self.base_vault {
+                        coin_vault_amount = Some(token.get_amount())
+                    } else if token.pubkey == self.quote_vault {
+                        pc_vault_amount = Some(token.get_amount())
+                    }
+                }
+                DeserializedAccount::ConfigAccount(RaydiumOpenBookConfigAccount(serum_account)) => {
+                    match serum_account {
+                        RaydiumOpenBookAccount::OpenOrders(account) => {
+                            if account.pubkey == self.open_orders {
+                                open_orders = Some(&account.open_orders)
+                            }
+                        }
+                        RaydiumOpenBookAccount::EventQueue(account) => {
+                            event_queue = Some(Arc::clone(&account.event_queue))
+                        }
+                        RaydiumOpenBookAccount::BookSide(account) => {
+                            if account.book_side.is_bids {
+                                bids = Some(Arc::clone(&account.book_side))
+                            } else {
+                                asks = Some(Arc::clone(&account.book_side))
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        });
+
+        let coin_vault_amount = coin_vault_amount.ok_or("base vault not found")?;
+        let pc_vault_amount = pc_vault_amount.ok_or("quote vault not found")?;
+        let (amount_out, cancelled_orders) = if amm.orderbook_permission() {
+            let open_orders = open_orders.ok_or("open orders not found")?;
+            let event_queue = event_queue.ok_or("event queue not found")?;
+            let bids = bids.ok_or("bids not found")?;
+            let asks = asks.ok_or("asks not found")?;
+            let serum = SerumState { open_orders, event_queue: &event_queue, bids: &bids, asks: &asks };
+            raydium_openbook::swap_base_in(&amm, coin_vault_amount, pc_vault_amount, Some(serum), amount_in, swap_direction)?
+        } else {
+            raydium_openbook::swap_base_in(&amm, coin_vault_amount, pc_vault_amount, None, amount_in, swap_direction)?
+        };
+
+        Ok(SwapQuote { amount_in, amount_out, crossings: cancelled_orders })
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -750,7 +847,688 @@ impl PoolOperation for RaydiumOpenBookMarket {
     }
 }
 
-#[derive(Clone, Deserialize)]
+impl RaydiumOpenBookMarket {
+    /// Initialized and OrderBookOnly pools keep orders on the OpenBook market
+    pub fn orderbook_permission(&self) -> bool {
+        matches!(self.status, 1 | 5)
+    }
+
+    /// Event queue, bids and asks of the pool's OpenBook market
+    pub fn get_market_related_pubkeys(market: &SerumMarket) -> Vec<(DeserializedAccount, Pubkey)> {
+        [market.event_q, market.bids, market.asks].iter().map(|pubkey| {
+            (DeserializedAccount::ConfigAccount(DeserializedConfigAccount::default()), *pubkey)
+        }).collect()
+    }
+}
+
+// serum_dex accounts are framed by "serum" and "padding"
+const SERUM_ACCOUNT_HEAD_PADDING: usize = 5;
+const SERUM_ACCOUNT_TAIL_PADDING: usize = 7;
+
+fn strip_serum_padding(data: &Vec<u8>) -> &[u8] {
+    &data[SERUM_ACCOUNT_HEAD_PADDING..data.len() - SERUM_ACCOUNT_TAIL_PADDING]
+}
+
+fn get_serum_account_flags(data: &Vec<u8>) -> u64 {
+    u64::from_le_bytes(*array_ref![data, SERUM_ACCOUNT_HEAD_PADDING, 8])
+}
+
+/// OpenBook v1 market an AMM v4 pool places its orders on
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SerumMarket {
+    pub own_address: Pubkey,
+    pub vault_signer_nonce: u64,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub req_q: Pubkey,
+    pub event_q: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub fee_rate_bps: u64,
+}
+
+impl AccountDataSerializer for SerumMarket {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        // permissioned markets extend the same layout
+        let market = bytemuck::pod_read_unaligned::<MarketState>(&strip_serum_padding(data)[..size_of::<MarketState>()]);
+        let pubkey = |words: [u64; 4]| Pubkey::new_from_array(bytemuck::cast(words));
+
+        SerumMarket {
+            own_address: pubkey(market.own_address),
+            vault_signer_nonce: market.vault_signer_nonce,
+            coin_mint: pubkey(market.coin_mint),
+            pc_mint: pubkey(market.pc_mint),
+            coin_vault: pubkey(market.coin_vault),
+            pc_vault: pubkey(market.pc_vault),
+            req_q: pubkey(market.req_q),
+            event_q: pubkey(market.event_q),
+            bids: pubkey(market.bids),
+            asks: pubkey(market.asks),
+            coin_lot_size: market.coin_lot_size,
+            pc_lot_size: market.pc_lot_size,
+            fee_rate_bps: market.fee_rate_bps,
+        }
+    }
+}
+
+/// Open orders of the AMM, its funds on the market and the ids of its resting orders
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SerumOpenOrders {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub native_coin_free: u64,
+    pub native_coin_total: u64,
+    pub native_pc_free: u64,
+    pub native_pc_total: u64,
+    pub bid_order_ids: Vec<u128>,
+    pub ask_order_ids: Vec<u128>,
+}
+
+impl AccountDataSerializer for SerumOpenOrders {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let open_orders = bytemuck::pod_read_unaligned::<OpenOrders>(&strip_serum_padding(data)[..size_of::<OpenOrders>()]);
+        let (free_slot_bits, is_bid_bits, orders) = (open_orders.free_slot_bits, open_orders.is_bid_bits, open_orders.orders);
+
+        let (mut bid_order_ids, mut ask_order_ids) = (Vec::new(), Vec::new());
+        (0..orders.len()).filter(|slot| free_slot_bits & (1u128 << slot) == 0).for_each(|slot| {
+            if is_bid_bits & (1u128 << slot) != 0 {
+                bid_order_ids.push(orders[slot])
+            } else {
+                ask_order_ids.push(orders[slot])
+            }
+        });
+
+        SerumOpenOrders {
+            market: Pubkey::new_from_array(bytemuck::cast(open_orders.market)),
+            owner: Pubkey::new_from_array(bytemuck::cast(open_orders.owner)),
+            native_coin_free: open_orders.native_coin_free,
+            native_coin_total: open_orders.native_coin_total,
+            native_pc_free: open_orders.native_pc_free,
+            native_pc_total: open_orders.native_pc_total,
+            bid_order_ids,
+            ask_order_ids,
+        }
+    }
+}
+
+/// Resting order of a book side, leaf of its critbit slab
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SerumOrder {
+    pub order_id: u128,
+    pub owner: Pubkey,
+    /// price in pc lots per coin lot
+    pub price: u64,
+    /// coin lots
+    pub quantity: u64,
+    pub client_order_id: u64,
+}
+
+/// Bids or asks of a market, orders by ascending order id
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SerumBookSide {
+    pub is_bids: bool,
+    pub orders: Vec<SerumOrder>,
+}
+
+impl AccountDataSerializer for SerumBookSide {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        // the slab is read in place by serum_dex, which needs it 8 byte aligned
+        let slab_data = &strip_serum_padding(data)[8..];
+        let mut aligned = vec![0u64; slab_data.len().div_ceil(8)];
+        let bytes = &mut bytemuck::cast_slice_mut::<u64, u8>(&mut aligned)[..slab_data.len()];
+        bytes.copy_from_slice(slab_data);
+
+        let slab = Slab::new(bytes);
+        let mut orders = Vec::new();
+        while let Some(leaf) = slab.remove_min() {
+            orders.push(SerumOrder {
+                order_id: leaf.order_id(),
+                owner: Pubkey::new_from_array(bytemuck::cast(leaf.owner())),
+                price: leaf.price().get(),
+                quantity: leaf.quantity(),
+                client_order_id: leaf.client_order_id(),
+            });
+        }
+
+        SerumBookSide {
+            is_bids: get_serum_account_flags(data) & AccountFlag::Bids as u64 != 0,
+            orders,
+        }
+    }
+}
+
+impl SerumBookSide {
+    pub fn find_by_order_id(&self, order_id: u128) -> Option<SerumOrder> {
+        self.orders.binary_search_by_key(&order_id, |order| order.order_id).ok().map(|index| self.orders[index])
+    }
+}
+
+/// Fill of the event queue the crank has not consumed yet
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SerumFill {
+    /// open orders of the side
+    pub owner: Pubkey,
+    pub is_bid: bool,
+    pub maker: bool,
+    pub native_qty_paid: u64,
+    pub native_qty_received: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SerumEventQueue {
+    pub fills: Vec<SerumFill>,
+}
+
+impl AccountDataSerializer for SerumEventQueue {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = strip_serum_padding(data);
+        let header = bytemuck::pod_read_unaligned::<EventQueueHeader>(&src[..size_of::<EventQueueHeader>()]);
+        let events = &src[size_of::<EventQueueHeader>()..];
+        let capacity = (events.len() / size_of::<Event>()) as u64;
+        if capacity == 0 {
+            return SerumEventQueue::default();
+        }
+
+        // ring buffer of `count` events from `head`
+        let fills = (0..header.count().min(capacity)).filter_map(|i| {
+            let index = ((header.head() + i) % capacity) as usize * size_of::<Event>();
+            let event = bytemuck::pod_read_unaligned::<Event>(&events[index..index + size_of::<Event>()]);
+            match event.as_view().ok()? {
+                EventView::Fill { side, maker, native_qty_paid, native_qty_received, owner, .. } => {
+                    Some(SerumFill {
+                        owner: Pubkey::new_from_array(bytemuck::cast(owner)),
+                        is_bid: side == Side::Bid,
+                        maker,
+                        native_qty_paid,
+                        native_qty_received,
+                    })
+                }
+                _ => None
+            }
+        }).collect::<Vec<SerumFill>>();
+
+        SerumEventQueue {
+            fills
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct SerumOpenOrdersAccount {
+    pub pubkey: Pubkey,
+    pub market: Market,
+    pub open_orders: SerumOpenOrders,
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct SerumBookSideAccount {
+    pub pubkey: Pubkey,
+    pub market: Market,
+    pub book_side: Arc<SerumBookSide>,
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct SerumEventQueueAccount {
+    pub pubkey: Pubkey,
+    pub market: Market,
+    pub event_queue: Arc<SerumEventQueue>,
+}
+
+/// OpenBook v1 accounts of an AMM v4 pool
+#[derive(Clone, PartialEq)]
 pub enum RaydiumOpenBookAccount {
-    Unknown
-}
\ No newline at end of file
+    OpenOrders(SerumOpenOrdersAccount),
+    BookSide(SerumBookSideAccount),
+    EventQueue(SerumEventQueueAccount),
+}
+
+impl RaydiumOpenBookAccount {
+    pub fn get_pubkey(&self) -> Pubkey {
+        match self {
+            RaydiumOpenBookAccount::OpenOrders(account) => account.pubkey,
+            RaydiumOpenBookAccount::BookSide(account) => account.pubkey,
+            RaydiumOpenBookAccount::EventQueue(account) => account.pubkey,
+        }
+    }
+
+    pub fn get_market(&self) -> Market {
+        Market::RAYDIUM
+    }
+
+    /// Resolves by the serum account flags rather than by length, book sides and event queues are sized per market
+    pub fn resolve_account(pubkey: Pubkey, data: &Vec<u8>) -> Result<RaydiumOpenBookAccount, &'static str> {
+        let account_flags = if data.len() > SERUM_ACCOUNT_HEAD_PADDING + SERUM_ACCOUNT_TAIL_PADDING + 8 {
+            get_serum_account_flags(data)
+        } else {
+            0
+        };
+
+        if account_flags & AccountFlag::OpenOrders as u64 != 0 {
+            Ok(RaydiumOpenBookAccount::OpenOrders(SerumOpenOrdersAccount {
+                pubkey,
+                market: Market::RAYDIUM,
+                open_orders: SerumOpenOrders::unpack_data(data),
+            }))
+        } else if account_flags & (AccountFlag::Bids as u64 | AccountFlag::Asks as u64) != 0 {
+            Ok(RaydiumOpenBookAccount::BookSide(SerumBookSideAccount {
+                pubkey,
+                market: Market::RAYDIUM,
+                book_side: Arc::new(SerumBookSide::unpack_data(data)),
+            }))
+        } else if account_flags & AccountFlag::EventQueue as u64 != 0 {
+            Ok(RaydiumOpenBookAccount::EventQueue(SerumEventQueueAccount {
+                pubkey,
+                market: Market::RAYDIUM,
+                event_queue: Arc::new(SerumEventQueue::unpack_data(data)),
+            }))
+        } else {
+            Err("unknown serum account flags")
+        }
+    }
+}
+/////////////////////////////////////////////////////////////////////////////////////////////////
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RaydiumCpmmMarket { // 637
+    pub amm_config: Pubkey, // 32
+    pub pool_creator: Pubkey, // 32
+    pub token_0_vault: Pubkey, // 32
+    pub token_1_vault: Pubkey, // 32
+    pub lp_mint: Pubkey, // 32
+    pub token_0_mint: Pubkey, // 32
+    pub token_1_mint: Pubkey, // 32
+    pub token_0_program: Pubkey, // 32
+    pub token_1_program: Pubkey, // 32
+    pub observation_key: Pubkey, // 32
+    pub auth_bump: u8, // 1
+    pub status: u8, // 1
+    pub lp_mint_decimals: u8, // 1
+    pub mint_0_decimals: u8, // 1
+    pub mint_1_decimals: u8, // 1
+    pub lp_supply: u64, // 8
+    pub protocol_fees_token_0: u64, // 8
+    pub protocol_fees_token_1: u64, // 8
+    pub fund_fees_token_0: u64, // 8
+    pub fund_fees_token_1: u64, // 8
+    pub open_time: u64, // 8
+    pub recent_epoch: u64, // 8
+    pub padding: [u64; 31], // 248
+}
+
+impl AccountDataSerializer for RaydiumCpmmMarket {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 637];
+        let (discriminator, amm_config, pool_creator, token_0_vault, token_1_vault, lp_mint, token_0_mint, token_1_mint, token_0_program, token_1_program, observation_key, auth_bump, status, lp_mint_decimals, mint_0_decimals, mint_1_decimals, lp_supply, protocol_fees_token_0, protocol_fees_token_1, fund_fees_token_0, fund_fees_token_1, open_time, recent_epoch, padding) =
+            array_refs![src, 8, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 1, 1, 1, 1, 1, 8, 8, 8, 8, 8, 8, 8, 248];
+
+        RaydiumCpmmMarket {
+            amm_config: Pubkey::new_from_array(*amm_config),
+            pool_creator: Pubkey::new_from_array(*pool_creator),
+            token_0_vault: Pubkey::new_from_array(*token_0_vault),
+            token_1_vault: Pubkey::new_from_array(*token_1_vault),
+            lp_mint: Pubkey::new_from_array(*lp_mint),
+            token_0_mint: Pubkey::new_from_array(*token_0_mint),
+            token_1_mint: Pubkey::new_from_array(*token_1_mint),
+            token_0_program: Pubkey::new_from_array(*token_0_program),
+            token_1_program: Pubkey::new_from_array(*token_1_program),
+            observation_key: Pubkey::new_from_array(*observation_key),
+            auth_bump: u8::from_le_bytes(*auth_bump),
+            status: u8::from_le_bytes(*status),
+            lp_mint_decimals: u8::from_le_bytes(*lp_mint_decimals),
+            mint_0_decimals: u8::from_le_bytes(*mint_0_decimals),
+            mint_1_decimals: u8::from_le_bytes(*mint_1_decimals),
+            lp_supply: u64::from_le_bytes(*lp_supply),
+            protocol_fees_token_0: u64::from_le_bytes(*protocol_fees_token_0),
+            protocol_fees_token_1: u64::from_le_bytes(*protocol_fees_token_1),
+            fund_fees_token_0: u64::from_le_bytes(*fund_fees_token_0),
+            fund_fees_token_1: u64::from_le_bytes(*fund_fees_token_1),
+            open_time: u64::from_le_bytes(*open_time),
+            recent_epoch: u64::from_le_bytes(*recent_epoch),
+            padding: bytemuck::cast(*padding)
+        }
+    }
+}
+
+impl PoolOperation for RaydiumCpmmMarket {
+    fn get_mint_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.token_0_mint,
+            pubkey_b: self.token_1_mint
+        }
+    }
+
+    fn get_pool_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.token_0_vault,
+            pubkey_b: self.token_1_vault
+        }
+    }
+
+    fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
+        let mut vec = vec![
+            (DeserializedAccount::ConfigAccount(DeserializedConfigAccount::default()), self.amm_config),
+            (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.token_0_vault),
+            (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.token_1_vault),
+        ];
+
+        // Token-2022 mints may withhold a transfer fee on the way in and out of the pool, the fee in effect depends on the epoch
+        let token_2022_program = Pubkey::from_str(TOKEN_2022_PROGRAM_PUBKEY).unwrap();
+        if self.token_0_program == token_2022_program || self.token_1_program == token_2022_program {
+            vec.push((DeserializedAccount::Account(DeserializedDataAccount::default()), clock::id()));
+        }
+        if self.token_0_program == token_2022_program {
+            vec.push((DeserializedAccount::Account(DeserializedDataAccount::default()), self.token_0_mint));
+        }
+        if self.token_1_program == token_2022_program {
+            vec.push((DeserializedAccount::Account(DeserializedDataAccount::default()), self.token_1_mint));
+        }
+
+        vec
+    }
+
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        vec![
+            self.token_0_vault,
+            self.token_1_vault,
+            self.amm_config,
+            self.observation_key,
+            self.token_0_mint,
+            self.token_1_mint,
+            self.token_0_program,
+            self.token_1_program
+        ]
+    }
+
+    fn get_formula(&self) -> Formula {
+        Formula::ConstantProduct
+    }
+
+    fn is_tradable(&self) -> bool {
+        // bit 2 of status disables swap
+        self.status & (1 << 2) == 0
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let state = RaydiumCpmmSwapState::from_accounts(self, accounts, mint_in)?;
+
+        let transfer_fee_in = state.transfer_fee_in.map_or(0, |fee| fee.calculate_fee(amount_in));
+        let (_, amount_out) = raydium_cpmm::swap_base_input(
+            amount_in - transfer_fee_in,
+            state.source_amount,
+            state.destination_amount,
+            state.trade_fee_rate
+        )?;
+        let transfer_fee_out = state.transfer_fee_out.map_or(0, |fee| fee.calculate_fee(amount_out));
+
+        Ok(SwapQuote { amount_in, amount_out: amount_out - transfer_fee_out, crossings: 0 })
+    }
+
+    fn has_swap_instructions(&self) -> bool {
+        true
+    }
+
+    fn get_swap_instructions(
+        &self,
+        pool: &Pubkey,
+        user: &Pubkey,
+        source_token_account: &Pubkey,
+        destination_token_account: &Pubkey,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64
+    ) -> Result<Vec<Instruction>, &'static str> {
+        let instruction = self.get_swap_instruction(
+            pool, user, source_token_account, destination_token_account, mint_in, amount_in, minimum_amount_out, true
+        )?;
+        Ok(vec![instruction])
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl RaydiumCpmmMarket {
+    /// Quotes an exact-out swap receiving `amount_out` of the other mint for `mint_in`
+    pub fn swap_exact_out(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_out: u64) -> Result<SwapQuote, &'static str> {
+        let state = RaydiumCpmmSwapState::from_accounts(self, accounts, mint_in)?;
+
+        let transfer_fee_out = state.transfer_fee_out.map_or(Ok(0), |fee| fee.calculate_inverse_fee(amount_out))?;
+        let (_, amount_in) = raydium_cpmm::swap_base_output(
+            amount_out + transfer_fee_out,
+            state.source_amount,
+            state.destination_amount,
+            state.trade_fee_rate
+        )?;
+        let transfer_fee_in = state.transfer_fee_in.map_or(Ok(0), |fee| fee.calculate_inverse_fee(amount_in))?;
+
+        Ok(SwapQuote { amount_in: amount_in + transfer_fee_in, amount_out, crossings: 0 })
+    }
+
+    pub fn authority(program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[RAYDIUM_CPMM_AUTH_SEED.as_bytes()], program_id).0
+    }
+
+    /// Builds `swap_base_input` when `base_input`, otherwise `swap_base_output`.
+    /// `amount` is the exact side and `other_amount_threshold` the minimum out or the maximum in
+    pub fn get_swap_instruction(
+        &self,
+        pool: &Pubkey,
+        payer: &Pubkey,
+        input_token_account: &Pubkey,
+        output_token_account: &Pubkey,
+        mint_in: &Pubkey,
+        amount: u64,
+        other_amount_threshold: u64,
+        base_input: bool
+    ) -> Result<Instruction, &'static str> {
+        let (input_vault, output_vault, input_program, output_program, output_mint) = if *mint_in == self.token_0_mint {
+            (self.token_0_vault, self.token_1_vault, self.token_0_program, self.token_1_program, self.token_1_mint)
+        } else if *mint_in == self.token_1_mint {
+            (self.token_1_vault, self.token_0_vault, self.token_1_program, self.token_0_program, self.token_0_mint)
+        } else {
+            return Err("mint not in pool");
+        };
+
+        let program_id = Pubkey::from_str(RAYDIUM_CPMM_PROGRAM_PUBKEY).unwrap();
+        let name = if base_input { "swap_base_input" } else { "swap_base_output" };
+        // swap_base_input takes (amount_in, minimum_amount_out), swap_base_output (max_amount_in, amount_out)
+        let (first, second) = if base_input { (amount, other_amount_threshold) } else { (other_amount_threshold, amount) };
+        let mut data = get_discriminator("global", name).to_vec();
+        data.extend_from_slice(&first.to_le_bytes());
+        data.extend_from_slice(&second.to_le_bytes());
+
+        Ok(Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new_readonly(*payer, true),
+                AccountMeta::new_readonly(Self::authority(&program_id), false),
+                AccountMeta::new_readonly(self.amm_config, false),
+                AccountMeta::new(*pool, false),
+                AccountMeta::new(*input_token_account, false),
+                AccountMeta::new(*output_token_account, false),
+                AccountMeta::new(input_vault, false),
+                AccountMeta::new(output_vault, false),
+                AccountMeta::new_readonly(input_program, false),
+                AccountMeta::new_readonly(output_program, false),
+                AccountMeta::new_readonly(*mint_in, false),
+                AccountMeta::new_readonly(output_mint, false),
+                AccountMeta::new(self.observation_key, false),
+            ]
+        ))
+    }
+}
+
+/// Reserves, fee rate and transfer fees of a cpmm pool in the swap direction
+struct RaydiumCpmmSwapState {
+    source_amount: u64,
+    destination_amount: u64,
+    trade_fee_rate: u64,
+    transfer_fee_in: Option<TransferFee>,
+    transfer_fee_out: Option<TransferFee>,
+}
+
+impl RaydiumCpmmSwapState {
+    fn from_accounts(market: &RaydiumCpmmMarket, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey) -> Result<RaydiumCpmmSwapState, &'static str> {
+        let zero_for_one = if *mint_in == market.token_0_mint {
+            true
+        } else if *mint_in == market.token_1_mint {
+            false
+        } else {
+            return Err("mint not in pool");
+        };
+
+        let mut market = *market;
+        let mut amm_config: Option<CpmmAmmConfig> = None;
+        let (mut vault_0, mut vault_1): (Option<u64>, Option<u64>) = (None, None);
+        let (mut mint_0_data, mut mint_1_data): (Option<&Vec<u8>>, Option<&Vec<u8>>) = (None, None);
+
+        accounts.iter().for_each(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(pool) => {
+                    if let Some(raydium_cpmm_market) = pool.operation.as_any().downcast_ref::<RaydiumCpmmMarket>() {
+                        market = *raydium_cpmm_market;
+                    }
+                }
+                DeserializedAccount::ConfigAccount(RaydiumCpmmConfigAccount(RaydiumCpmmAccount::AmmConfig(config))) => {
+                    amm_config = Some(config.config)
+                }
+                DeserializedAccount::TokenAccount(token) => {
+                    if token.pubkey == market.token_0_vault {
+                        vault_0 = Some(token.get_amount())
+                    } else if token.pubkey == market.token_1_vault {
+                        vault_1 = Some(token.get_amount())
+                    }
+                }
+                DeserializedAccount::Account(account) => {
+                    if account.pubkey == market.token_0_mint {
+                        mint_0_data = Some(&account.account.data)
+                    } else if account.pubkey == market.token_1_mint {
+                        mint_1_data = Some(&account.account.data)
+                    }
+                }
+                _ => {}
+            }
+        });
+
+        let amm_config = amm_config.ok_or("amm config not found")?;
+        // fees owed to the protocol and the fund stay in the vaults but are not part of the reserves
+        let amount_0 = vault_0.ok_or("token 0 vault not found")?
+            .checked_sub(market.protocol_fees_token_0 + market.fund_fees_token_0).ok_or("vault 0 below fees")?;
+        let amount_1 = vault_1.ok_or("token 1 vault not found")?
+            .checked_sub(market.protocol_fees_token_1 + market.fund_fees_token_1).ok_or("vault 1 below fees")?;
+        // the pool only records the epoch of its last swap, the current one comes from the clock
+        let epoch = if mint_0_data.is_some() || mint_1_data.is_some() {
+            get_clock(accounts).ok_or("clock not found")?.epoch
+        } else {
+            0
+        };
+        let transfer_fee_0 = mint_0_data.and_then(|data| get_transfer_fee(data, epoch));
+        let transfer_fee_1 = mint_1_data.and_then(|data| get_transfer_fee(data, epoch));
+
+        Ok(if zero_for_one {
+            RaydiumCpmmSwapState {
+                source_amount: amount_0,
+                destination_amount: amount_1,
+                trade_fee_rate: amm_config.trade_fee_rate,
+                transfer_fee_in: transfer_fee_0,
+                transfer_fee_out: transfer_fee_1,
+            }
+        } else {
+            RaydiumCpmmSwapState {
+                source_amount: amount_1,
+                destination_amount: amount_0,
+                trade_fee_rate: amm_config.trade_fee_rate,
+                transfer_fee_in: transfer_fee_1,
+                transfer_fee_out: transfer_fee_0,
+            }
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub struct CpmmAmmConfig { // 236
+    pub bump: u8, // 1
+    pub disable_create_pool: bool, // 1
+    pub index: u16, // 2
+    pub trade_fee_rate: u64, // 8
+    pub protocol_fee_rate: u64, // 8
+    pub fund_fee_rate: u64, // 8
+    pub create_pool_fee: u64, // 8
+    pub protocol_owner: Pubkey, // 32
+    pub fund_owner: Pubkey, // 32
+    pub padding: [u64; 16], // 128
+}
+
+impl AccountDataSerializer for CpmmAmmConfig {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 236];
+        let (discriminator, bump, disable_create_pool, index, trade_fee_rate, protocol_fee_rate, fund_fee_rate, create_pool_fee, protocol_owner, fund_owner, padding) =
+            array_refs![src, 8, 1, 1, 2, 8, 8, 8, 8, 32, 32, 128];
+
+        CpmmAmmConfig {
+            bump: u8::from_le_bytes(*bump),
+            disable_create_pool: disable_create_pool[0] != 0,
+            index: u16::from_le_bytes(*index),
+            trade_fee_rate: u64::from_le_bytes(*trade_fee_rate),
+            protocol_fee_rate: u64::from_le_bytes(*protocol_fee_rate),
+            fund_fee_rate: u64::from_le_bytes(*fund_fee_rate),
+            create_pool_fee: u64::from_le_bytes(*create_pool_fee),
+            protocol_owner: Pubkey::new_from_array(*protocol_owner),
+            fund_owner: Pubkey::new_from_array(*fund_owner),
+            padding: bytemuck::cast(*padding)
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Default)]
+pub struct CpmmAmmConfigAccount {
+    pub pubkey: Pubkey,
+    pub config: CpmmAmmConfig,
+    pub market: Market,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum RaydiumCpmmAccount {
+    AmmConfig(CpmmAmmConfigAccount),
+    ObservationState,
+}
+
+impl RaydiumCpmmAccount {
+    pub fn get_pubkey(&self) -> Pubkey {
+        match self {
+            RaydiumCpmmAccount::AmmConfig(account) => {
+                account.pubkey
+            }
+            _ => {
+                Pubkey::default()
+            }
+        }
+    }
+
+    pub fn get_market(&self) -> Market {
+        Market::RAYDIUM
+    }
+
+    pub fn resolve_account(pubkey: Pubkey, data: &Vec<u8>) -> RaydiumCpmmAccount {
+        match data.len() {
+            RAYDIUM_CPMM_AMM_CONFIG => {
+                RaydiumCpmmAccount::AmmConfig(CpmmAmmConfigAccount {
+                    pubkey,
+                    config: CpmmAmmConfig::unpack_data(data),
+                    market: Market::RAYDIUM
+                })
+            }
+            RAYDIUM_CPMM_OBSERVATION_STATE => {
+                RaydiumCpmmAccount::ObservationState
+            }
+            _ => {
+                panic!("could not resolve account from data: pubkey({})", pubkey)
+            }
+        }
+    }
+}
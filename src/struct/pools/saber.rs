@@ -0,0 +1,266 @@
+use std::any::Any;
+use std::str::FromStr;
+
+use arrayref::{array_ref, array_refs};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar::clock;
+
+use crate::constants::{SABER_PROGRAM_PUBKEY, SABER_SWAP_INFO_DATA_LEN, TOKEN_PROGRAM_PUBKEY};
+use crate::formula::base::{Formula, SwapQuote};
+use crate::formula::saber;
+use crate::r#struct::account::{get_clock, AccountDataSerializer, DeserializedAccount, DeserializedDataAccount, DeserializedTokenAccount};
+use crate::r#struct::market::PoolOperation;
+use crate::utils::PubkeyPair;
+
+const SABER_SWAP_TAG: u8 = 1;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SaberFees { // 64
+    pub admin_trade_fee_numerator: u64, // 8
+    pub admin_trade_fee_denominator: u64, // 8
+    pub admin_withdraw_fee_numerator: u64, // 8
+    pub admin_withdraw_fee_denominator: u64, // 8
+    pub trade_fee_numerator: u64, // 8
+    pub trade_fee_denominator: u64, // 8
+    pub withdraw_fee_numerator: u64, // 8
+    pub withdraw_fee_denominator: u64, // 8
+}
+
+impl AccountDataSerializer for SaberFees {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 64];
+        let fields = array_refs![src, 8, 8, 8, 8, 8, 8, 8, 8];
+
+        SaberFees {
+            admin_trade_fee_numerator: u64::from_le_bytes(*fields.0),
+            admin_trade_fee_denominator: u64::from_le_bytes(*fields.1),
+            admin_withdraw_fee_numerator: u64::from_le_bytes(*fields.2),
+            admin_withdraw_fee_denominator: u64::from_le_bytes(*fields.3),
+            trade_fee_numerator: u64::from_le_bytes(*fields.4),
+            trade_fee_denominator: u64::from_le_bytes(*fields.5),
+            withdraw_fee_numerator: u64::from_le_bytes(*fields.6),
+            withdraw_fee_denominator: u64::from_le_bytes(*fields.7),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SaberMarket { // 395
+    pub is_initialized: bool, // 1
+    pub is_paused: bool, // 1
+    pub nonce: u8, // 1
+    pub initial_amp_factor: u64, // 8
+    pub target_amp_factor: u64, // 8
+    pub start_ramp_ts: i64, // 8
+    pub stop_ramp_ts: i64, // 8
+    pub future_admin_deadline: i64, // 8
+    pub future_admin_key: Pubkey, // 32
+    pub admin_key: Pubkey, // 32
+    pub token_a: Pubkey, // 32
+    pub token_b: Pubkey, // 32
+    pub pool_mint: Pubkey, // 32
+    pub token_a_mint: Pubkey, // 32
+    pub token_b_mint: Pubkey, // 32
+    pub admin_fee_key_a: Pubkey, // 32
+    pub admin_fee_key_b: Pubkey, // 32
+    pub fees: SaberFees, // 64
+}
+
+impl AccountDataSerializer for SaberMarket {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, SABER_SWAP_INFO_DATA_LEN];
+        let (
+            is_initialized,
+            is_paused,
+            nonce,
+            initial_amp_factor,
+            target_amp_factor,
+            start_ramp_ts,
+            stop_ramp_ts,
+            future_admin_deadline,
+            future_admin_key,
+            admin_key,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            admin_fee_key_a,
+            admin_fee_key_b,
+            fees
+        ) = array_refs![src, 1, 1, 1, 8, 8, 8, 8, 8, 32, 32, 32, 32, 32, 32, 32, 32, 32, 64];
+
+        SaberMarket {
+            is_initialized: is_initialized[0] != 0,
+            is_paused: is_paused[0] != 0,
+            nonce: nonce[0],
+            initial_amp_factor: u64::from_le_bytes(*initial_amp_factor),
+            target_amp_factor: u64::from_le_bytes(*target_amp_factor),
+            start_ramp_ts: i64::from_le_bytes(*start_ramp_ts),
+            stop_ramp_ts: i64::from_le_bytes(*stop_ramp_ts),
+            future_admin_deadline: i64::from_le_bytes(*future_admin_deadline),
+            future_admin_key: Pubkey::new_from_array(*future_admin_key),
+            admin_key: Pubkey::new_from_array(*admin_key),
+            token_a: Pubkey::new_from_array(*token_a),
+            token_b: Pubkey::new_from_array(*token_b),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            admin_fee_key_a: Pubkey::new_from_array(*admin_fee_key_a),
+            admin_fee_key_b: Pubkey::new_from_array(*admin_fee_key_b),
+            fees: SaberFees::unpack_data(&fees.to_vec()),
+        }
+    }
+}
+
+impl SaberMarket {
+    /// Authority of the swap reserves, derived from the swap info with its nonce
+    pub fn get_authority(&self, swap_info: &Pubkey) -> Result<Pubkey, &'static str> {
+        let program_id = Pubkey::from_str(SABER_PROGRAM_PUBKEY).unwrap();
+        Pubkey::create_program_address(&[swap_info.as_ref(), &[self.nonce]], &program_id).or(Err("invalid swap authority nonce"))
+    }
+
+    /// Builds `Swap` selling `amount_in` of `mint_in`, the admin share of the trade fee goes to the admin fee account of the output token
+    pub fn get_swap_instruction(
+        &self,
+        swap_info: &Pubkey,
+        user: &Pubkey,
+        source_token_account: &Pubkey,
+        destination_token_account: &Pubkey,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64
+    ) -> Result<Instruction, &'static str> {
+        let (swap_source, swap_destination, admin_fee_destination) = if *mint_in == self.token_a_mint {
+            (self.token_a, self.token_b, self.admin_fee_key_b)
+        } else if *mint_in == self.token_b_mint {
+            (self.token_b, self.token_a, self.admin_fee_key_a)
+        } else {
+            return Err("mint not in pool");
+        };
+
+        let mut data = vec![SABER_SWAP_TAG];
+        data.extend_from_slice(&amount_in.to_le_bytes());
+        data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+
+        Ok(Instruction::new_with_bytes(
+            Pubkey::from_str(SABER_PROGRAM_PUBKEY).unwrap(),
+            &data,
+            vec![
+                AccountMeta::new_readonly(*swap_info, false),
+                AccountMeta::new_readonly(self.get_authority(swap_info)?, false),
+                AccountMeta::new_readonly(*user, true),
+                AccountMeta::new(*source_token_account, false),
+                AccountMeta::new(swap_source, false),
+                AccountMeta::new(swap_destination, false),
+                AccountMeta::new(*destination_token_account, false),
+                AccountMeta::new(admin_fee_destination, false),
+                AccountMeta::new_readonly(Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap(), false),
+            ]
+        ))
+    }
+}
+
+impl PoolOperation for SaberMarket {
+    fn get_mint_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.token_a_mint,
+            pubkey_b: self.token_b_mint,
+        }
+    }
+
+    fn get_pool_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.token_a,
+            pubkey_b: self.token_b,
+        }
+    }
+
+    fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
+        // the clock moves the amplification coefficient while it is ramping
+        vec![
+            (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.token_a),
+            (DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), self.token_b),
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), clock::id()),
+        ]
+    }
+
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        vec![self.token_a, self.token_b, self.admin_fee_key_a, self.admin_fee_key_b]
+    }
+
+    fn get_formula(&self) -> Formula {
+        Formula::StableSwap
+    }
+
+    fn get_fee_rate(&self) -> Option<u32> {
+        if self.fees.trade_fee_denominator == 0 {
+            return None;
+        }
+        u32::try_from(self.fees.trade_fee_numerator as u128 * 1_000_000 / self.fees.trade_fee_denominator as u128).ok()
+    }
+
+    fn is_tradable(&self) -> bool {
+        self.is_initialized && !self.is_paused
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let pool = accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(account) => account.operation.as_any().downcast_ref::<SaberMarket>(),
+                _ => None
+            }
+        }).unwrap_or(self);
+
+        let (mut amount_a, mut amount_b): (Option<u64>, Option<u64>) = (None, None);
+        accounts.iter().for_each(|account| {
+            if let DeserializedAccount::TokenAccount(token) = account {
+                if token.pubkey == pool.token_a {
+                    amount_a = Some(token.get_amount())
+                } else if token.pubkey == pool.token_b {
+                    amount_b = Some(token.get_amount())
+                }
+            }
+        });
+        let amount_a = amount_a.ok_or("token a reserves not found")?;
+        let amount_b = amount_b.ok_or("token b reserves not found")?;
+
+        let (source_amount, destination_amount) = if *mint_in == pool.token_a_mint {
+            (amount_a, amount_b)
+        } else if *mint_in == pool.token_b_mint {
+            (amount_b, amount_a)
+        } else {
+            return Err("mint not in pool");
+        };
+        let clock = get_clock(accounts).ok_or("clock not found")?;
+
+        let amount_out = saber::quote_exact_in(pool, amount_in, source_amount, destination_amount, clock.unix_timestamp)?;
+
+        Ok(SwapQuote { amount_in, amount_out, crossings: 0 })
+    }
+
+    fn has_swap_instructions(&self) -> bool {
+        true
+    }
+
+    fn get_swap_instructions(
+        &self,
+        pool: &Pubkey,
+        user: &Pubkey,
+        source_token_account: &Pubkey,
+        destination_token_account: &Pubkey,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64
+    ) -> Result<Vec<Instruction>, &'static str> {
+        let instruction = self.get_swap_instruction(
+            pool, user, source_token_account, destination_token_account, mint_in, amount_in, minimum_amount_out
+        )?;
+        Ok(vec![instruction])
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,418 @@
+use std::any::Any;
+use std::str::FromStr;
+
+use arrayref::{array_ref, array_refs};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar::clock;
+
+use crate::constants::{SANCTUM_FLAT_FEE_PROGRAM_PUBKEY, SANCTUM_INFINITY_PROGRAM_PUBKEY, SANCTUM_MARINADE_CALCULATOR_PUBKEY, SANCTUM_SPL_CALCULATOR_PUBKEYS, SANCTUM_WSOL_CALCULATOR_PUBKEY, MARINADE_STATE_PUBKEY, TOKEN_PROGRAM_PUBKEY};
+use crate::executor::wallet::get_associated_token_address;
+use crate::formula::base::{Formula, SwapQuote};
+use crate::formula::sanctum;
+use crate::formula::sanctum::{LstQuoteState, SolValueCalculatorState};
+use crate::r#struct::account::{get_clock, AccountDataSerializer, DeserializedAccount, DeserializedDataAccount, DeserializedTokenAccount};
+use crate::r#struct::market::PoolOperation;
+use crate::r#struct::pools::{MarinadeState, SplStakePool};
+use crate::utils::PubkeyPair;
+
+const SANCTUM_STATE_SEED: &str = "state";
+const SANCTUM_LST_STATE_LIST_SEED: &str = "lst-state-list";
+const SANCTUM_FEE_ACCOUNT_SEED: &str = "fee";
+const SANCTUM_LST_STATE_LEN: usize = 80;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SanctumPoolState { // 176
+    pub total_sol_value: u64, // 8
+    pub trading_protocol_fee_bps: u16, // 2
+    pub lp_protocol_fee_bps: u16, // 2
+    pub version: u8, // 1
+    pub is_disabled: u8, // 1
+    pub is_rebalancing: u8, // 1
+    pub admin: Pubkey, // 32
+    pub rebalance_authority: Pubkey, // 32
+    pub protocol_fee_beneficiary: Pubkey, // 32
+    pub pricing_program: Pubkey, // 32
+    pub lp_token_mint: Pubkey, // 32
+}
+
+impl AccountDataSerializer for SanctumPoolState {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 176];
+        let (
+            total_sol_value,
+            trading_protocol_fee_bps,
+            lp_protocol_fee_bps,
+            version,
+            is_disabled,
+            is_rebalancing,
+            _padding,
+            admin,
+            rebalance_authority,
+            protocol_fee_beneficiary,
+            pricing_program,
+            lp_token_mint
+        ) = array_refs![src, 8, 2, 2, 1, 1, 1, 1, 32, 32, 32, 32, 32];
+
+        SanctumPoolState {
+            total_sol_value: u64::from_le_bytes(*total_sol_value),
+            trading_protocol_fee_bps: u16::from_le_bytes(*trading_protocol_fee_bps),
+            lp_protocol_fee_bps: u16::from_le_bytes(*lp_protocol_fee_bps),
+            version: version[0],
+            is_disabled: is_disabled[0],
+            is_rebalancing: is_rebalancing[0],
+            admin: Pubkey::new_from_array(*admin),
+            rebalance_authority: Pubkey::new_from_array(*rebalance_authority),
+            protocol_fee_beneficiary: Pubkey::new_from_array(*protocol_fee_beneficiary),
+            pricing_program: Pubkey::new_from_array(*pricing_program),
+            lp_token_mint: Pubkey::new_from_array(*lp_token_mint),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SanctumLstState { // 80
+    pub is_input_disabled: u8, // 1
+    pub pool_reserves_bump: u8, // 1
+    pub protocol_fee_accumulator_bump: u8, // 1
+    pub sol_value: u64, // 8
+    pub mint: Pubkey, // 32
+    pub sol_value_calculator: Pubkey, // 32
+}
+
+impl AccountDataSerializer for SanctumLstState {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, SANCTUM_LST_STATE_LEN];
+        let (is_input_disabled, pool_reserves_bump, protocol_fee_accumulator_bump, _padding, sol_value, mint, sol_value_calculator) =
+            array_refs![src, 1, 1, 1, 5, 8, 32, 32];
+
+        SanctumLstState {
+            is_input_disabled: is_input_disabled[0],
+            pool_reserves_bump: pool_reserves_bump[0],
+            protocol_fee_accumulator_bump: protocol_fee_accumulator_bump[0],
+            sol_value: u64::from_le_bytes(*sol_value),
+            mint: Pubkey::new_from_array(*mint),
+            sol_value_calculator: Pubkey::new_from_array(*sol_value_calculator),
+        }
+    }
+}
+
+impl SanctumLstState {
+    /// LST states packed back to back in the LST state list account
+    pub fn unpack_list(data: &[u8]) -> Vec<SanctumLstState> {
+        data.chunks_exact(SANCTUM_LST_STATE_LEN).map(|chunk| SanctumLstState::unpack_data(&chunk.to_vec())).collect()
+    }
+}
+
+/// Fees the flat fee pricing program charges on an LST, in basis points
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SanctumFeeAccount { // 8
+    pub input_fee_bps: i16, // 2
+    pub output_fee_bps: i16, // 2
+    pub bump: u8, // 1
+}
+
+impl AccountDataSerializer for SanctumFeeAccount {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 8];
+        let (input_fee_bps, output_fee_bps, bump, _padding) = array_refs![src, 2, 2, 1, 3];
+
+        SanctumFeeAccount {
+            input_fee_bps: i16::from_le_bytes(*input_fee_bps),
+            output_fee_bps: i16::from_le_bytes(*output_fee_bps),
+            bump: bump[0],
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SanctumPricingState { // 34
+    pub manager: Pubkey, // 32
+    pub lp_withdrawal_fee_bps: u16, // 2
+}
+
+impl AccountDataSerializer for SanctumPricingState {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 34];
+        let (manager, lp_withdrawal_fee_bps) = array_refs![src, 32, 2];
+
+        SanctumPricingState {
+            manager: Pubkey::new_from_array(*manager),
+            lp_withdrawal_fee_bps: u16::from_le_bytes(*lp_withdrawal_fee_bps),
+        }
+    }
+}
+
+/// SOL value calculator of an LST and the account it reads
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum SanctumCalculator {
+    Wsol,
+    SplStakePool(Pubkey),
+    Marinade,
+    #[default]
+    Unsupported
+}
+
+impl SanctumCalculator {
+    /// `stake_pools` maps pool mints to their stake pool accounts, SPL calculators take the stake pool as an account
+    pub fn resolve(lst_state: &SanctumLstState, stake_pools: &[(Pubkey, SplStakePool)]) -> SanctumCalculator {
+        let program = lst_state.sol_value_calculator.to_string();
+        if program == SANCTUM_WSOL_CALCULATOR_PUBKEY {
+            SanctumCalculator::Wsol
+        } else if program == SANCTUM_MARINADE_CALCULATOR_PUBKEY {
+            SanctumCalculator::Marinade
+        } else if SANCTUM_SPL_CALCULATOR_PUBKEYS.contains(&program.as_str()) {
+            stake_pools.iter().find(|(_, pool)| pool.pool_mint == lst_state.mint)
+                .map_or(SanctumCalculator::Unsupported, |(pubkey, _)| SanctumCalculator::SplStakePool(*pubkey))
+        } else {
+            SanctumCalculator::Unsupported
+        }
+    }
+
+    pub fn get_state_pubkey(&self) -> Option<Pubkey> {
+        match self {
+            SanctumCalculator::SplStakePool(stake_pool) => Some(*stake_pool),
+            SanctumCalculator::Marinade => Some(Pubkey::from_str(MARINADE_STATE_PUBKEY).unwrap()),
+            _ => None
+        }
+    }
+}
+
+/// A supported LST of the Infinity pool with the accounts its quotes read
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SanctumLst {
+    pub state: SanctumLstState,
+    pub calculator: SanctumCalculator,
+    pub reserves: Pubkey,
+    pub fee_account: Pubkey,
+}
+
+/// An edge of the Infinity pool, between two of its LSTs or between an LST and the LP token.
+/// Every edge lists the accounts of all supported LSTs so that edges of the same pool share their swap-related pubkeys.
+/// The program addresses are derived once in `get_edges`
+#[derive(Clone, Debug, Default)]
+pub struct SanctumInfinityMarket {
+    pub pool: SanctumPoolState,
+    pub lsts: Vec<SanctumLst>,
+    pub mint_pair: PubkeyPair,
+    pub reserves_pair: PubkeyPair,
+    pub lst_state_list_pubkey: Pubkey,
+    pub pricing_state_pubkey: Pubkey,
+}
+
+impl AccountDataSerializer for SanctumInfinityMarket {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        SanctumInfinityMarket {
+            pool: SanctumPoolState::unpack_data(data),
+            ..SanctumInfinityMarket::default()
+        }
+    }
+}
+
+impl SanctumInfinityMarket {
+    pub fn pool_state() -> Pubkey {
+        let program_id = Pubkey::from_str(SANCTUM_INFINITY_PROGRAM_PUBKEY).unwrap();
+        Pubkey::find_program_address(&[SANCTUM_STATE_SEED.as_bytes()], &program_id).0
+    }
+
+    pub fn lst_state_list() -> Pubkey {
+        let program_id = Pubkey::from_str(SANCTUM_INFINITY_PROGRAM_PUBKEY).unwrap();
+        Pubkey::find_program_address(&[SANCTUM_LST_STATE_LIST_SEED.as_bytes()], &program_id).0
+    }
+
+    pub fn pool_reserves(pool_state: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address(pool_state, mint, &Pubkey::from_str(TOKEN_PROGRAM_PUBKEY).unwrap())
+    }
+
+    pub fn fee_account(pricing_program: &Pubkey, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[SANCTUM_FEE_ACCOUNT_SEED.as_bytes(), mint.as_ref()], pricing_program).0
+    }
+
+    pub fn pricing_state(pricing_program: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[SANCTUM_STATE_SEED.as_bytes()], pricing_program).0
+    }
+
+    /// One edge per pair of supported LSTs and per supported LST against the LP token
+    pub fn get_edges(&self, lst_state_list: &[u8], stake_pools: &[(Pubkey, SplStakePool)]) -> Vec<SanctumInfinityMarket> {
+        let pool_state = Self::pool_state();
+        let lsts = SanctumLstState::unpack_list(lst_state_list).into_iter().map(|state| {
+            SanctumLst {
+                state,
+                calculator: SanctumCalculator::resolve(&state, stake_pools),
+                reserves: Self::pool_reserves(&pool_state, &state.mint),
+                fee_account: Self::fee_account(&self.pool.pricing_program, &state.mint),
+            }
+        }).filter(|lst| lst.calculator != SanctumCalculator::Unsupported).collect::<Vec<_>>();
+        let lst_state_list_pubkey = Self::lst_state_list();
+        let pricing_state_pubkey = Self::pricing_state(&self.pool.pricing_program);
+        let lp_reserves = Self::pool_reserves(&pool_state, &self.pool.lp_token_mint);
+
+        let mut pairs = Vec::new();
+        for i in 0..lsts.len() {
+            pairs.push((lsts[i].state.mint, self.pool.lp_token_mint, lsts[i].reserves, lp_reserves));
+            for j in i + 1..lsts.len() {
+                pairs.push((lsts[i].state.mint, lsts[j].state.mint, lsts[i].reserves, lsts[j].reserves));
+            }
+        }
+
+        pairs.into_iter().map(|(pubkey_a, pubkey_b, reserves_a, reserves_b)| {
+            SanctumInfinityMarket {
+                pool: self.pool,
+                lsts: lsts.clone(),
+                mint_pair: PubkeyPair { pubkey_a, pubkey_b },
+                reserves_pair: PubkeyPair { pubkey_a: reserves_a, pubkey_b: reserves_b },
+                lst_state_list_pubkey,
+                pricing_state_pubkey,
+            }
+        }).collect()
+    }
+
+    fn get_quote_state(
+        &self,
+        lst_states: &[SanctumLstState],
+        mint: &Pubkey,
+        accounts: &Vec<DeserializedAccount>
+    ) -> Result<LstQuoteState, &'static str> {
+        let lst_state = *lst_states.iter().find(|lst_state| lst_state.mint == *mint).ok_or("lst not in pool")?;
+        let lst = self.lsts.iter().find(|lst| lst.state.mint == *mint).ok_or("lst not supported")?;
+
+        let get_data = |pubkey: Pubkey| accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::Account(account) if account.pubkey == pubkey => Some(&account.account.data),
+                // stake pools and the marinade state may be held as pools of their own markets
+                DeserializedAccount::PoolAccount(account) if account.pubkey == pubkey => Some(&account.account.data),
+                _ => None
+            }
+        });
+        let calculator = match lst.calculator {
+            SanctumCalculator::Wsol => SolValueCalculatorState::Wsol,
+            SanctumCalculator::SplStakePool(stake_pool) => {
+                SolValueCalculatorState::SplStakePool(SplStakePool::unpack_data(get_data(stake_pool).ok_or("stake pool not found")?))
+            }
+            SanctumCalculator::Marinade => {
+                let state = Pubkey::from_str(MARINADE_STATE_PUBKEY).unwrap();
+                SolValueCalculatorState::Marinade(MarinadeState::unpack_data(get_data(state).ok_or("marinade state not found")?))
+            }
+            SanctumCalculator::Unsupported => return Err("lst not supported")
+        };
+        let fee = SanctumFeeAccount::unpack_data(get_data(lst.fee_account).ok_or("fee account not found")?);
+        let reserves = accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::TokenAccount(token) if token.pubkey == lst.reserves => Some(token.get_amount()),
+                _ => None
+            }
+        }).ok_or("pool reserves not found")?;
+
+        Ok(LstQuoteState {
+            lst_state,
+            calculator,
+            input_fee_bps: fee.input_fee_bps,
+            output_fee_bps: fee.output_fee_bps,
+            reserves,
+        })
+    }
+}
+
+impl PoolOperation for SanctumInfinityMarket {
+    fn get_mint_pair(&self) -> PubkeyPair {
+        self.mint_pair
+    }
+
+    fn get_pool_pair(&self) -> PubkeyPair {
+        self.reserves_pair
+    }
+
+    fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
+        let mut vec = vec![
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), self.lst_state_list_pubkey),
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), self.pricing_state_pubkey),
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), self.pool.lp_token_mint),
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), clock::id()),
+        ];
+
+        self.lsts.iter().for_each(|lst| {
+            vec.push((DeserializedAccount::TokenAccount(DeserializedTokenAccount::default()), lst.reserves));
+            vec.push((DeserializedAccount::Account(DeserializedDataAccount::default()), lst.fee_account));
+            if let Some(state) = lst.calculator.get_state_pubkey() {
+                if !vec.iter().any(|(_, pubkey)| *pubkey == state) {
+                    vec.push((DeserializedAccount::Account(DeserializedDataAccount::default()), state));
+                }
+            }
+        });
+
+        vec
+    }
+
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        vec![
+            self.reserves_pair.pubkey_a,
+            self.reserves_pair.pubkey_b,
+            self.lst_state_list_pubkey,
+            self.pricing_state_pubkey,
+            self.pool.lp_token_mint,
+        ]
+    }
+
+    fn get_formula(&self) -> Formula {
+        Formula::StakePool
+    }
+
+    fn is_tradable(&self) -> bool {
+        self.pool.is_disabled == 0 && self.pool.pricing_program == Pubkey::from_str(SANCTUM_FLAT_FEE_PROGRAM_PUBKEY).unwrap()
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let mint_out = if *mint_in == self.mint_pair.pubkey_a {
+            self.mint_pair.pubkey_b
+        } else if *mint_in == self.mint_pair.pubkey_b {
+            self.mint_pair.pubkey_a
+        } else {
+            return Err("mint not in pool");
+        };
+
+        let pool = accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(account) => account.operation.as_any().downcast_ref::<SanctumInfinityMarket>().map(|market| market.pool),
+                _ => None
+            }
+        }).unwrap_or(self.pool);
+        if pool.pricing_program != Pubkey::from_str(SANCTUM_FLAT_FEE_PROGRAM_PUBKEY).unwrap() {
+            return Err("unsupported pricing program");
+        }
+
+        let get_data = |pubkey: Pubkey| accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::Account(account) if account.pubkey == pubkey => Some(&account.account.data),
+                _ => None
+            }
+        });
+        // sol values and input flags of the LSTs move with every trade
+        let lst_states = SanctumLstState::unpack_list(get_data(self.lst_state_list_pubkey).ok_or("lst state list not found")?);
+        let lp_supply = || -> Result<u64, &'static str> {
+            let data = get_data(pool.lp_token_mint).ok_or("lp mint not found")?;
+            // supply follows the optional mint authority
+            Ok(u64::from_le_bytes(*array_ref![data.get(36..44).ok_or("invalid mint data")?, 0, 8]))
+        };
+        let epoch = get_clock(accounts).ok_or("clock not found")?.epoch;
+
+        let amount_out = if *mint_in == pool.lp_token_mint {
+            let output = self.get_quote_state(&lst_states, &mint_out, accounts)?;
+            let pricing_state = SanctumPricingState::unpack_data(
+                get_data(self.pricing_state_pubkey).ok_or("pricing state not found")?
+            );
+            sanctum::quote_remove_liquidity(&pool, &output, lp_supply()?, pricing_state.lp_withdrawal_fee_bps, amount_in, epoch)?
+        } else if mint_out == pool.lp_token_mint {
+            let input = self.get_quote_state(&lst_states, mint_in, accounts)?;
+            sanctum::quote_add_liquidity(&pool, &input, lp_supply()?, amount_in, epoch)?
+        } else {
+            let input = self.get_quote_state(&lst_states, mint_in, accounts)?;
+            let output = self.get_quote_state(&lst_states, &mint_out, accounts)?;
+            sanctum::quote_swap_exact_in(&pool, &input, &output, amount_in, epoch)?
+        };
+
+        Ok(SwapQuote { amount_in, amount_out, crossings: 0 })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,269 @@
+use std::any::Any;
+use std::str::FromStr;
+
+use arrayref::{array_ref, array_refs};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar::clock;
+
+use crate::constants::NATIVE_MINT_PUBKEY;
+use crate::formula::base::{Formula, SwapQuote};
+use crate::formula::stake_pool;
+use crate::r#struct::account::{get_clock, AccountDataSerializer, DeserializedAccount, DeserializedDataAccount};
+use crate::r#struct::market::PoolOperation;
+use crate::utils::PubkeyPair;
+
+const STAKE_POOL_ACCOUNT_TYPE: u8 = 1;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct StakePoolFee { // 16
+    pub denominator: u64, // 8
+    pub numerator: u64, // 8
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct StakePoolLockup { // 48
+    pub unix_timestamp: i64, // 8
+    pub epoch: u64, // 8
+    pub custodian: Pubkey, // 32
+}
+
+/// Borsh reader for the fields of `StakePool` past its fixed-size head, where options and future fees change the length
+struct BorshReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> BorshReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.offset..self.offset + len)?;
+        self.offset += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn pubkey(&mut self) -> Option<Pubkey> {
+        self.take(32).map(|bytes| Pubkey::new_from_array(bytes.try_into().unwrap()))
+    }
+
+    fn option_pubkey(&mut self) -> Option<Option<Pubkey>> {
+        match self.u8()? {
+            0 => Some(None),
+            _ => Some(Some(self.pubkey()?))
+        }
+    }
+
+    fn fee(&mut self) -> Option<StakePoolFee> {
+        Some(StakePoolFee { denominator: self.u64()?, numerator: self.u64()? })
+    }
+
+    /// `FutureEpoch<Fee>`, a fee taking effect after one or two epoch boundaries
+    fn future_fee(&mut self) -> Option<Option<StakePoolFee>> {
+        match self.u8()? {
+            0 => Some(None),
+            _ => Some(Some(self.fee()?))
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SplStakePool {
+    pub account_type: u8, // 1
+    pub manager: Pubkey, // 32
+    pub staker: Pubkey, // 32
+    pub stake_deposit_authority: Pubkey, // 32
+    pub stake_withdraw_bump_seed: u8, // 1
+    pub validator_list: Pubkey, // 32
+    pub reserve_stake: Pubkey, // 32
+    pub pool_mint: Pubkey, // 32
+    pub manager_fee_account: Pubkey, // 32
+    pub token_program_id: Pubkey, // 32
+    pub total_lamports: u64, // 8
+    pub pool_token_supply: u64, // 8
+    pub last_update_epoch: u64, // 8
+    pub lockup: StakePoolLockup, // 48
+    pub epoch_fee: StakePoolFee, // 16
+    pub next_epoch_fee: Option<StakePoolFee>, // 1 + 16
+    pub preferred_deposit_validator_vote_address: Option<Pubkey>, // 1 + 32
+    pub preferred_withdraw_validator_vote_address: Option<Pubkey>, // 1 + 32
+    pub stake_deposit_fee: StakePoolFee, // 16
+    pub stake_withdrawal_fee: StakePoolFee, // 16
+    pub next_stake_withdrawal_fee: Option<StakePoolFee>, // 1 + 16
+    pub stake_referral_fee: u8, // 1
+    pub sol_deposit_authority: Option<Pubkey>, // 1 + 32
+    pub sol_deposit_fee: StakePoolFee, // 16
+    pub sol_referral_fee: u8, // 1
+    pub sol_withdraw_authority: Option<Pubkey>, // 1 + 32
+    pub sol_withdrawal_fee: StakePoolFee, // 16
+    pub next_sol_withdrawal_fee: Option<StakePoolFee>, // 1 + 16
+    pub last_epoch_pool_token_supply: u64, // 8
+    pub last_epoch_total_lamports: u64, // 8
+}
+
+impl AccountDataSerializer for SplStakePool {
+    fn unpack_data(data: &Vec<u8>) -> Self {
+        let src = array_ref![data, 0, 346];
+        let (
+            account_type,
+            manager,
+            staker,
+            stake_deposit_authority,
+            stake_withdraw_bump_seed,
+            validator_list,
+            reserve_stake,
+            pool_mint,
+            manager_fee_account,
+            token_program_id,
+            total_lamports,
+            pool_token_supply,
+            last_update_epoch,
+            lockup_unix_timestamp,
+            lockup_epoch,
+            lockup_custodian,
+            epoch_fee_denominator,
+            epoch_fee_numerator
+        ) = array_refs![src, 1, 32, 32, 32, 1, 32, 32, 32, 32, 32, 8, 8, 8, 8, 8, 32, 8, 8];
+
+        let mut pool = SplStakePool {
+            account_type: account_type[0],
+            manager: Pubkey::new_from_array(*manager),
+            staker: Pubkey::new_from_array(*staker),
+            stake_deposit_authority: Pubkey::new_from_array(*stake_deposit_authority),
+            stake_withdraw_bump_seed: stake_withdraw_bump_seed[0],
+            validator_list: Pubkey::new_from_array(*validator_list),
+            reserve_stake: Pubkey::new_from_array(*reserve_stake),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            manager_fee_account: Pubkey::new_from_array(*manager_fee_account),
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            total_lamports: u64::from_le_bytes(*total_lamports),
+            pool_token_supply: u64::from_le_bytes(*pool_token_supply),
+            last_update_epoch: u64::from_le_bytes(*last_update_epoch),
+            lockup: StakePoolLockup {
+                unix_timestamp: i64::from_le_bytes(*lockup_unix_timestamp),
+                epoch: u64::from_le_bytes(*lockup_epoch),
+                custodian: Pubkey::new_from_array(*lockup_custodian),
+            },
+            epoch_fee: StakePoolFee {
+                denominator: u64::from_le_bytes(*epoch_fee_denominator),
+                numerator: u64::from_le_bytes(*epoch_fee_numerator),
+            },
+            ..SplStakePool::default()
+        };
+        // a tail that does not decode leaves an uninitialized pool, which is not tradable
+        match pool.unpack_tail(&data[346..]) {
+            Some(()) => pool,
+            None => SplStakePool::default()
+        }
+    }
+}
+
+impl SplStakePool {
+    fn unpack_tail(&mut self, data: &[u8]) -> Option<()> {
+        let mut reader = BorshReader { data, offset: 0 };
+        self.next_epoch_fee = reader.future_fee()?;
+        self.preferred_deposit_validator_vote_address = reader.option_pubkey()?;
+        self.preferred_withdraw_validator_vote_address = reader.option_pubkey()?;
+        self.stake_deposit_fee = reader.fee()?;
+        self.stake_withdrawal_fee = reader.fee()?;
+        self.next_stake_withdrawal_fee = reader.future_fee()?;
+        self.stake_referral_fee = reader.u8()?;
+        self.sol_deposit_authority = reader.option_pubkey()?;
+        self.sol_deposit_fee = reader.fee()?;
+        self.sol_referral_fee = reader.u8()?;
+        self.sol_withdraw_authority = reader.option_pubkey()?;
+        self.sol_withdrawal_fee = reader.fee()?;
+        self.next_sol_withdrawal_fee = reader.future_fee()?;
+        self.last_epoch_pool_token_supply = reader.u64()?;
+        self.last_epoch_total_lamports = reader.u64()?;
+
+        Some(())
+    }
+
+    /// Lamports `WithdrawSol` can take from the reserve stake, which keeps its rent-exempt reserve
+    pub fn get_reserve_available(reserve_lamports: u64, reserve_data: &[u8]) -> u64 {
+        // StakeStateV2 tag (4) then Meta, starting with rent_exempt_reserve
+        let rent_exempt_reserve = reserve_data.get(4..12).map_or(0, |bytes| u64::from_le_bytes(bytes.try_into().unwrap()));
+        reserve_lamports.saturating_sub(rent_exempt_reserve)
+    }
+}
+
+impl PoolOperation for SplStakePool {
+    fn get_mint_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap(),
+            pubkey_b: self.pool_mint,
+        }
+    }
+
+    fn get_pool_pair(&self) -> PubkeyPair {
+        PubkeyPair {
+            pubkey_a: self.reserve_stake,
+            pubkey_b: self.manager_fee_account,
+        }
+    }
+
+    fn get_swap_related_pubkeys(&self) -> Vec<(DeserializedAccount, Pubkey)> {
+        // the reserve bounds sol withdrawals, the clock tells whether the pool was updated this epoch
+        vec![
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), self.reserve_stake),
+            (DeserializedAccount::Account(DeserializedDataAccount::default()), clock::id()),
+        ]
+    }
+
+    fn get_static_pubkeys(&self) -> Vec<Pubkey> {
+        vec![self.reserve_stake, self.manager_fee_account, self.pool_mint, self.validator_list]
+    }
+
+    fn get_formula(&self) -> Formula {
+        Formula::StakePool
+    }
+
+    fn is_tradable(&self) -> bool {
+        self.account_type == STAKE_POOL_ACCOUNT_TYPE
+    }
+
+    fn swap(&self, accounts: &Vec<DeserializedAccount>, mint_in: &Pubkey, amount_in: u64) -> Result<SwapQuote, &'static str> {
+        let pool = accounts.iter().find_map(|account| {
+            match account {
+                DeserializedAccount::PoolAccount(account) => account.operation.as_any().downcast_ref::<SplStakePool>(),
+                _ => None
+            }
+        }).unwrap_or(self);
+        if !pool.is_tradable() {
+            return Err("stake pool not initialized");
+        }
+
+        // deposits and withdrawals fail until the pool is updated for the current epoch
+        let clock = get_clock(accounts).ok_or("clock not found")?;
+        if pool.last_update_epoch < clock.epoch {
+            return Err("stake pool not updated");
+        }
+
+        let amount_out = if *mint_in == Pubkey::from_str(NATIVE_MINT_PUBKEY).unwrap() {
+            stake_pool::quote_deposit_sol(pool, amount_in)?
+        } else if *mint_in == pool.pool_mint {
+            let reserve = accounts.iter().find_map(|account| {
+                match account {
+                    DeserializedAccount::Account(account) if account.pubkey == pool.reserve_stake => Some(&account.account),
+                    _ => None
+                }
+            }).ok_or("reserve stake not found")?;
+            let reserve_available = Self::get_reserve_available(reserve.lamports, &reserve.data);
+            stake_pool::quote_withdraw_sol(pool, amount_in, reserve_available)?
+        } else {
+            return Err("mint not in pool");
+        };
+
+        Ok(SwapQuote { amount_in, amount_out, crossings: 0 })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
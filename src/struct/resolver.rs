@@ -4,10 +4,16 @@ use solana_sdk::pubkey::Pubkey;
 use crate::r#struct::account::{AccountDataSerializer, DeserializedConfigAccount};
 use crate::constants::RAYDIUM_CLMM_DATA_LEN;
 use crate::constants::RAYDIUM_CLMM_PROGRAM_PUBKEY;
+use crate::constants::{RAYDIUM_CPMM_DATA_LEN, RAYDIUM_CPMM_PROGRAM_PUBKEY};
+use crate::constants::{METEORA_DAMM_DATA_LEN, METEORA_DAMM_V2_DATA_LEN, METEORA_VAULT_PROGRAM_PUBKEY};
 use crate::constants::RAYDIUM_OPEN_BOOK_PROGRAM_PUBKEY;
+use crate::constants::{OPENBOOK_V1_PROGRAM_PUBKEY, OPENBOOK_V2_PROGRAM_PUBKEY};
 use crate::r#struct::market::{Market, PoolOperation};
-use crate::r#struct::pools::{MeteoraClmmMarket, OrcaClmmAccount, OrcaClmmMarket, RaydiumClmmAccount, RaydiumClmmMarket, RaydiumOpenBookMarket, WhirlpoolsConfig, WhirlpoolsConfigAccount};
+use crate::r#struct::pools::{MeteoraAccount, MeteoraDammMarket, MeteoraDammV2Market, MeteoraDlmmMarket, OrcaClmmAccount, OrcaClmmMarket, RaydiumClmmAccount, RaydiumClmmMarket, RaydiumCpmmAccount, RaydiumCpmmMarket, RaydiumOpenBookAccount, RaydiumOpenBookMarket, WhirlpoolsConfig, WhirlpoolsConfigAccount};
 use crate::r#struct::pools::lifinity::LifinityMarket;
+use crate::r#struct::pools::{OpenBookV2Account, OpenBookV2Market, PhoenixMarket, SaberMarket};
+use crate::r#struct::pools::{MarinadeState, SanctumInfinityMarket, SplStakePool};
+use crate::r#struct::pools::{PumpFunBondingCurve, PumpSwapPool};
 
 pub fn resolve_pool_account(market: &Market, data: &Vec<u8>) -> Box<dyn PoolOperation> {
     match market {
@@ -18,52 +24,134 @@ pub fn resolve_pool_account(market: &Market, data: &Vec<u8>) -> Box<dyn PoolOper
             if data.len() == RAYDIUM_CLMM_DATA_LEN {
                 Box::new(RaydiumClmmMarket::unpack_data(data))
             }
+            else if data.len() == RAYDIUM_CPMM_DATA_LEN {
+                Box::new(RaydiumCpmmMarket::unpack_data(data))
+            }
             else {
                 Box::new(RaydiumOpenBookMarket::unpack_data(data))
             }
         }
         Market::METEORA => {
-            Box::new(MeteoraClmmMarket::unpack_data(data))
+            if data.len() == METEORA_DAMM_DATA_LEN {
+                Box::new(MeteoraDammMarket::unpack_data(data))
+            }
+            else if data.len() == METEORA_DAMM_V2_DATA_LEN {
+                Box::new(MeteoraDammV2Market::unpack_data(data))
+            }
+            else {
+                Box::new(MeteoraDlmmMarket::unpack_data(data))
+            }
         }
         Market::LIFINITY => {
             Box::new(LifinityMarket::unpack_data(data))
         }
+        Market::PHOENIX => {
+            Box::new(PhoenixMarket::unpack_data(data))
+        }
+        Market::OPENBOOK => {
+            Box::new(OpenBookV2Market::unpack_data(data))
+        }
+        Market::SABER => {
+            Box::new(SaberMarket::unpack_data(data))
+        }
+        Market::STAKE_POOL => {
+            Box::new(SplStakePool::unpack_data(data))
+        }
+        Market::MARINADE => {
+            Box::new(MarinadeState::unpack_data(data))
+        }
+        Market::SANCTUM => {
+            Box::new(SanctumInfinityMarket::unpack_data(data))
+        }
+        Market::PUMP_FUN => {
+            Box::new(PumpFunBondingCurve::unpack_data(data))
+        }
+        Market::PUMP_SWAP => {
+            Box::new(PumpSwapPool::unpack_data(data))
+        }
         _ => {
             panic!("unknown pool")
         }
     }
 }
 
-pub fn resolve_pool_config_account(market: &Market, owner_pubkey: &Pubkey, account_pubkey: Pubkey, data: &Vec<u8>) -> DeserializedConfigAccount {
+pub fn resolve_pool_config_account(market: &Market, owner_pubkey: &Pubkey, account_pubkey: Pubkey, data: &Vec<u8>) -> Result<DeserializedConfigAccount, &'static str> {
     match market {
         Market::ORCA => {
-            DeserializedConfigAccount::OrcaClmmConfigAccount(
+            Ok(DeserializedConfigAccount::OrcaClmmConfigAccount(
                 OrcaClmmAccount::resolve_account(account_pubkey, data)
-            )
+            ))
         }
         Market::RAYDIUM => {
             match owner_pubkey.to_string().as_str() {
                 RAYDIUM_CLMM_PROGRAM_PUBKEY => {
-                    DeserializedConfigAccount::RaydiumClmmConfigAccount(
+                    Ok(DeserializedConfigAccount::RaydiumClmmConfigAccount(
                         RaydiumClmmAccount::resolve_account(account_pubkey, data)
-                    )
+                    ))
+                }
+                RAYDIUM_CPMM_PROGRAM_PUBKEY => {
+                    Ok(DeserializedConfigAccount::RaydiumCpmmConfigAccount(
+                        RaydiumCpmmAccount::resolve_account(account_pubkey, data)
+                    ))
                 }
                 RAYDIUM_OPEN_BOOK_PROGRAM_PUBKEY => {
-                    panic!("unknown account: RaydiumOpenBookAccount")
+                    Err("unknown account: RaydiumOpenBookAccount")
+                }
+                OPENBOOK_V1_PROGRAM_PUBKEY => {
+                    Ok(DeserializedConfigAccount::RaydiumOpenBookConfigAccount(
+                        RaydiumOpenBookAccount::resolve_account(account_pubkey, data)?
+                    ))
                 }
                 _ => {
-                    DeserializedConfigAccount::EmptyConfigAccount
+                    Ok(DeserializedConfigAccount::EmptyConfigAccount)
                 }
             }
         }
         Market::METEORA => {
-            todo!()
+            match owner_pubkey.to_string().as_str() {
+                METEORA_VAULT_PROGRAM_PUBKEY => {
+                    Ok(DeserializedConfigAccount::MeteoraConfigAccount(
+                        MeteoraAccount::resolve_account(account_pubkey, data)
+                    ))
+                }
+                _ => {
+                    Ok(DeserializedConfigAccount::EmptyConfigAccount)
+                }
+            }
         }
         Market::LIFINITY => {
-            todo!()
+            Err("unknown account: Lifinity")
+        }
+        Market::PHOENIX => {
+            // the book lives in the market account
+            Ok(DeserializedConfigAccount::EmptyConfigAccount)
+        }
+        Market::OPENBOOK => {
+            match owner_pubkey.to_string().as_str() {
+                OPENBOOK_V2_PROGRAM_PUBKEY => {
+                    Ok(DeserializedConfigAccount::OpenBookConfigAccount(
+                        OpenBookV2Account::resolve_account(account_pubkey, data)
+                    ))
+                }
+                _ => {
+                    Ok(DeserializedConfigAccount::EmptyConfigAccount)
+                }
+            }
+        }
+        Market::SABER => {
+            // reserves are token accounts and the curve parameters live in the swap info
+            Ok(DeserializedConfigAccount::EmptyConfigAccount)
+        }
+        Market::STAKE_POOL | Market::MARINADE | Market::SANCTUM => {
+            // reserves, legs and calculator states are read as raw accounts
+            Ok(DeserializedConfigAccount::EmptyConfigAccount)
+        }
+        Market::PUMP_FUN | Market::PUMP_SWAP => {
+            // fee settings are read from the global accounts as raw accounts
+            Ok(DeserializedConfigAccount::EmptyConfigAccount)
         }
         _ => {
-            todo!()
+            Err("unknown market")
         }
     }
 }
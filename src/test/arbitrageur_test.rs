@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod arbitrageur_test {
+    use std::collections::HashMap;
+
+    use solana_sdk::clock::Clock;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::arbitrageur::{Arbitrageur, Opportunity};
+    use crate::formula::test::account_fixture::{clock_account, token_account};
+    use crate::r#struct::account::{DeserializedAccount, DeserializedPoolAccount, PoolEdge};
+    use crate::r#struct::market::Market;
+    use crate::r#struct::pools::{SaberFees, SaberMarket};
+
+    /// Saber pool of `mint` against `quote_mint` with balanced reserves and a trade fee of `fee_numerator` basis points
+    fn quote_pool(mint: Pubkey, quote_mint: Pubkey, fee_numerator: u64) -> (DeserializedPoolAccount, Vec<DeserializedAccount>) {
+        let pool = SaberMarket {
+            is_initialized: true,
+            initial_amp_factor: 100,
+            target_amp_factor: 100,
+            token_a: Pubkey::new_unique(),
+            token_b: Pubkey::new_unique(),
+            token_a_mint: mint,
+            token_b_mint: quote_mint,
+            fees: SaberFees {
+                trade_fee_numerator: fee_numerator,
+                trade_fee_denominator: 10_000,
+                ..SaberFees::default()
+            },
+            ..SaberMarket::default()
+        };
+        let accounts = vec![
+            token_account(pool.token_a, 1_000_000_000_000, Market::SABER),
+            token_account(pool.token_b, 1_000_000_000_000, Market::SABER),
+            clock_account(Clock::default(), Market::SABER),
+        ];
+
+        (DeserializedPoolAccount { pubkey: Pubkey::new_unique(), market: Market::SABER, operation: Box::new(pool), ..DeserializedPoolAccount::default() }, accounts)
+    }
+
+    fn build_quote_pools(pools: Vec<(DeserializedPoolAccount, Vec<DeserializedAccount>)>) -> (Vec<DeserializedPoolAccount>, HashMap<PoolEdge, Vec<DeserializedAccount>>) {
+        let related_accounts = pools.iter().map(|(pool, accounts)| (pool.get_edge(), accounts.clone())).collect();
+        (pools.into_iter().map(|(pool, _)| pool).collect(), related_accounts)
+    }
+
+    fn build_opportunity(base_mint: Pubkey, profit: u64, quote_profit: Option<u64>) -> Opportunity {
+        Opportunity {
+            base_mint,
+            path: Vec::new(),
+            mints_out: Vec::new(),
+            markets: Vec::new(),
+            quotes: Vec::new(),
+            amount_in: 0,
+            amount_out: profit,
+            profit,
+            quote_profit,
+            lamport_profit: None,
+            compute_units: 0,
+        }
+    }
+
+    #[test]
+    fn quote_amount_takes_the_best_direct_pool() {
+        let (mint, quote_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (quote_pools, related_accounts) = build_quote_pools(vec![
+            quote_pool(mint, quote_mint, 100),
+            quote_pool(mint, quote_mint, 4),
+            quote_pool(Pubkey::new_unique(), quote_mint, 0),
+        ]);
+        let best = quote_pools[1].operation.swap(&related_accounts[&quote_pools[1].get_edge()], &mint, 1_000_000).unwrap().amount_out;
+
+        assert_eq!(Arbitrageur::to_quote_amount(&quote_pools, &related_accounts, &mint, &quote_mint, 1_000_000), Some(best));
+        assert!(best < 1_000_000 && best > 999_000);
+        // the quote mint is its own price, a mint without a direct pool has none
+        assert_eq!(Arbitrageur::to_quote_amount(&quote_pools, &related_accounts, &quote_mint, &quote_mint, 1_000_000), Some(1_000_000));
+        assert_eq!(Arbitrageur::to_quote_amount(&quote_pools, &related_accounts, &Pubkey::new_unique(), &quote_mint, 1_000_000), None);
+    }
+
+    #[test]
+    fn opportunities_are_ranked_in_the_quote_mint() {
+        let (cheap_mint, dear_mint, quote_mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        // half of what the cheap mint trades for goes to the pool
+        let (quote_pools, related_accounts) = build_quote_pools(vec![
+            quote_pool(cheap_mint, quote_mint, 5_000),
+            quote_pool(dear_mint, quote_mint, 4),
+        ]);
+        let quote_profit = |mint: &Pubkey, profit: u64| Arbitrageur::to_quote_amount(&quote_pools, &related_accounts, mint, &quote_mint, profit);
+
+        // the most base mint gained is not the most worth
+        let mut opportunities = vec![
+            build_opportunity(cheap_mint, 1_500_000, quote_profit(&cheap_mint, 1_500_000)),
+            build_opportunity(Pubkey::new_unique(), 10_000_000, None),
+            build_opportunity(dear_mint, 1_000_000, quote_profit(&dear_mint, 1_000_000)),
+            build_opportunity(quote_mint, 800_000, quote_profit(&quote_mint, 800_000)),
+        ];
+        Arbitrageur::rank(&mut opportunities);
+
+        assert_eq!(opportunities.iter().map(|opportunity| opportunity.profit).collect::<Vec<u64>>(), vec![1_000_000, 800_000, 1_500_000, 10_000_000]);
+    }
+
+    #[test]
+    fn equal_quote_profits_rank_by_profit() {
+        let mint = Pubkey::new_unique();
+        let mut opportunities = vec![
+            build_opportunity(mint, 1_000, Some(500)),
+            build_opportunity(mint, 2_000, Some(500)),
+            build_opportunity(mint, 3_000, Some(400)),
+        ];
+        Arbitrageur::rank(&mut opportunities);
+
+        assert_eq!(opportunities.iter().map(|opportunity| opportunity.profit).collect::<Vec<u64>>(), vec![2_000, 1_000, 3_000]);
+    }
+}
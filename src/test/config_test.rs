@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod config_test {
+    use crate::config::PoolFilter;
+    use crate::r#struct::account::DeserializedPoolAccount;
+    use crate::r#struct::market::{Market, PoolOperation};
+    use crate::r#struct::pools::{OrcaClmmMarket, RaydiumClmmMarket, SaberMarket};
+
+    fn pool(market: Market, operation: Box<dyn PoolOperation>) -> DeserializedPoolAccount {
+        DeserializedPoolAccount { market, operation, ..DeserializedPoolAccount::default() }
+    }
+
+    #[test]
+    fn executable_only_rejects_pools_without_swap_instructions() {
+        let saber = pool(Market::SABER, Box::new(SaberMarket { is_initialized: true, ..SaberMarket::default() }));
+        let whirlpool = pool(Market::ORCA, Box::new(OrcaClmmMarket::default()));
+        let filter = PoolFilter { executable_only: true, ..PoolFilter::default() };
+
+        assert!(filter.accept(&saber));
+        assert!(!filter.accept(&whirlpool));
+        assert!(PoolFilter::default().accept(&whirlpool));
+    }
+
+    #[test]
+    fn min_liquidity_rejects_shallow_pools() {
+        let filter = PoolFilter { min_liquidity: Some(10_000), ..PoolFilter::default() };
+
+        assert!(!filter.accept(&pool(Market::ORCA, Box::new(OrcaClmmMarket { liquidity: 9_999, ..OrcaClmmMarket::default() }))));
+        assert!(filter.accept(&pool(Market::ORCA, Box::new(OrcaClmmMarket { liquidity: 10_000, ..OrcaClmmMarket::default() }))));
+        // a stable swap reports no liquidity
+        assert!(filter.accept(&pool(Market::SABER, Box::new(SaberMarket::default()))));
+    }
+
+    #[test]
+    fn fee_tiers_keep_listed_rates() {
+        let filter = PoolFilter { fee_tiers: vec![500, 3_000], ..PoolFilter::default() };
+
+        assert!(filter.accept(&pool(Market::ORCA, Box::new(OrcaClmmMarket { fee_rate: 3_000, ..OrcaClmmMarket::default() }))));
+        assert!(!filter.accept(&pool(Market::ORCA, Box::new(OrcaClmmMarket { fee_rate: 10_000, ..OrcaClmmMarket::default() }))));
+        // the fee rate of a Raydium CLMM pool lives in its amm config
+        assert!(filter.accept(&pool(Market::RAYDIUM, Box::new(RaydiumClmmMarket::default()))));
+        assert!(PoolFilter::default().accept(&pool(Market::ORCA, Box::new(OrcaClmmMarket { fee_rate: 10_000, ..OrcaClmmMarket::default() }))));
+    }
+
+    #[test]
+    fn tradable_only_rejects_disabled_pools() {
+        let filter = PoolFilter { tradable_only: true, ..PoolFilter::default() };
+        let paused = pool(Market::SABER, Box::new(SaberMarket { is_initialized: true, is_paused: true, ..SaberMarket::default() }));
+        let swap_disabled = pool(Market::RAYDIUM, Box::new(RaydiumClmmMarket { status: 1 << 4, ..RaydiumClmmMarket::default() }));
+
+        assert!(!filter.accept(&paused));
+        assert!(!filter.accept(&swap_disabled));
+        assert!(filter.accept(&pool(Market::SABER, Box::new(SaberMarket { is_initialized: true, ..SaberMarket::default() }))));
+        assert!(filter.accept(&pool(Market::RAYDIUM, Box::new(RaydiumClmmMarket::default()))));
+        assert!(PoolFilter::default().accept(&paused));
+    }
+}
@@ -0,0 +1,4 @@
+mod path_test;
+mod config_test;
+mod arbitrageur_test;
+mod observer_test;
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod observer_test {
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use tokio::sync::broadcast::error::RecvError;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use crate::observer::{Event, EventBus};
+
+    #[tokio::test]
+    async fn subscribers_receive_typed_payloads() {
+        let bus = EventBus::new(16);
+        let (mut first, mut second) = (bus.subscribe(), bus.subscribe());
+        let pubkeys = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let signature = Signature::new_unique();
+
+        assert_eq!(bus.publish(Event::AccountsUpdated { pubkeys: pubkeys.clone(), slot: 100 }), 2);
+        assert_eq!(bus.publish(Event::TxLanded { signature, slot: 101 }), 2);
+
+        for rx in [&mut first, &mut second] {
+            match rx.recv().await.unwrap() {
+                Event::AccountsUpdated { pubkeys: updated, slot } => assert_eq!((updated, slot), (pubkeys.clone(), 100)),
+                event => panic!("unexpected event: {:?}", event),
+            }
+            match rx.recv().await.unwrap() {
+                Event::TxLanded { signature: landed, slot } => assert_eq!((landed, slot), (signature, 101)),
+                event => panic!("unexpected event: {:?}", event),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn events_without_subscribers_are_dropped() {
+        let bus = EventBus::new(16);
+
+        assert_eq!(bus.publish(Event::Initialized), 0);
+        // a subscriber only receives what is published after it subscribed
+        let mut rx = bus.subscribe();
+        bus.publish(Event::TxFailed { signature: Signature::default(), reason: "expired".to_string() });
+        assert!(matches!(rx.recv().await.unwrap(), Event::TxFailed { reason, .. } if reason == "expired"));
+    }
+
+    #[tokio::test]
+    async fn lagging_subscriber_skips_to_the_latest_events() {
+        let bus = EventBus::new(1);
+        let mut rx = bus.subscribe();
+
+        bus.publish(Event::AccountsUpdated { pubkeys: Vec::new(), slot: 1 });
+        bus.publish(Event::AccountsUpdated { pubkeys: Vec::new(), slot: 2 });
+
+        assert!(matches!(rx.recv().await, Err(RecvError::Lagged(1))));
+        assert!(matches!(rx.recv().await.unwrap(), Event::AccountsUpdated { slot: 2, .. }));
+    }
+
+    #[tokio::test]
+    async fn handlers_run_in_order_until_the_bus_is_dropped() {
+        let bus = EventBus::new(16);
+        let (tx, mut rx) = unbounded_channel();
+        let handle = bus.subscribe_with("test", move |event| {
+            let tx = tx.clone();
+            async move {
+                if let Event::TxLanded { slot, .. } = event {
+                    tx.send(slot).unwrap();
+                }
+            }
+        });
+
+        (1..=3).for_each(|slot| {
+            bus.publish(Event::TxLanded { signature: Signature::default(), slot });
+        });
+        drop(bus);
+        handle.await.unwrap();
+
+        assert_eq!((rx.recv().await, rx.recv().await, rx.recv().await), (Some(1), Some(2), Some(3)));
+    }
+}
@@ -0,0 +1,187 @@
+#[cfg(test)]
+mod path_test {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::{Arc, Mutex};
+
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::config::PoolFilter;
+    use crate::path::{CycleId, CycleIndex, PathFinder};
+    use crate::r#struct::account::{DeserializedPoolAccount, PoolEdge};
+    use crate::r#struct::market::Market;
+    use crate::r#struct::pools::{SaberMarket, SanctumInfinityMarket};
+    use crate::utils::PubkeyPair;
+
+    fn pool(pubkey: Pubkey) -> DeserializedPoolAccount {
+        DeserializedPoolAccount { pubkey, ..DeserializedPoolAccount::default() }
+    }
+
+    fn saber_pool(mint_a: Pubkey, mint_b: Pubkey) -> DeserializedPoolAccount {
+        DeserializedPoolAccount {
+            pubkey: Pubkey::new_unique(),
+            market: Market::SABER,
+            operation: Box::new(SaberMarket { is_initialized: true, token_a_mint: mint_a, token_b_mint: mint_b, ..SaberMarket::default() }),
+            ..DeserializedPoolAccount::default()
+        }
+    }
+
+    // edges of a multi-asset pool share its pubkey
+    fn infinity_edge(pubkey: Pubkey, mint_a: Pubkey, mint_b: Pubkey) -> DeserializedPoolAccount {
+        DeserializedPoolAccount {
+            pubkey,
+            market: Market::SANCTUM,
+            operation: Box::new(SanctumInfinityMarket {
+                mint_pair: PubkeyPair { pubkey_a: mint_a, pubkey_b: mint_b },
+                ..SanctumInfinityMarket::default()
+            }),
+            ..DeserializedPoolAccount::default()
+        }
+    }
+
+    fn edges(pubkeys: &[Pubkey]) -> Vec<PoolEdge> {
+        pubkeys.iter().map(|pubkey| pool(*pubkey).get_edge()).collect()
+    }
+
+    fn cycle_ids(base_mint: Pubkey, indexes: &[usize]) -> HashSet<CycleId> {
+        indexes.iter().map(|index| CycleId { base_mint, index: *index }).collect()
+    }
+
+    // pools a, b and c with their own vaults, a and c read the same config account
+    fn build_index() -> (CycleIndex, Pubkey, [Pubkey; 3], [Pubkey; 3], Pubkey) {
+        let base_mint = Pubkey::new_unique();
+        let pools = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let vaults = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let config = Pubkey::new_unique();
+
+        let related_pubkeys = HashMap::from([
+            (pool(pools[0]).get_edge(), HashSet::from([vaults[0], config])),
+            (pool(pools[1]).get_edge(), HashSet::from([vaults[1]])),
+            (pool(pools[2]).get_edge(), HashSet::from([vaults[2], config])),
+        ]);
+        let path_list = HashMap::from([(base_mint, vec![
+            vec![pool(pools[0]), pool(pools[1])],
+            vec![pool(pools[1]), pool(pools[2])],
+            vec![pool(pools[0]), pool(pools[2])],
+        ])]);
+
+        (CycleIndex::new(&path_list, &related_pubkeys), base_mint, pools, vaults, config)
+    }
+
+    #[test]
+    fn changed_account_requotes_affected_cycles() {
+        let (index, base_mint, pools, vaults, _) = build_index();
+
+        assert_eq!(index.get_cycles(&vec![vaults[0]]), cycle_ids(base_mint, &[0, 2]));
+        assert_eq!(index.get_cycles(&vec![vaults[1]]), cycle_ids(base_mint, &[0, 1]));
+        // a pool depends on its own account
+        assert_eq!(index.get_cycles(&vec![pools[2]]), cycle_ids(base_mint, &[1, 2]));
+        assert_eq!(index.get_pools(&vec![vaults[2]]), HashSet::from_iter(edges(&[pools[2]])));
+    }
+
+    #[test]
+    fn shared_account_requotes_every_dependent_cycle() {
+        let (index, base_mint, pools, vaults, config) = build_index();
+
+        assert_eq!(index.get_pools(&vec![config]), HashSet::from_iter(edges(&[pools[0], pools[2]])));
+        assert_eq!(index.get_cycles(&vec![config]), cycle_ids(base_mint, &[0, 1, 2]));
+        assert_eq!(index.get_cycles(&vec![vaults[0], vaults[1]]), cycle_ids(base_mint, &[0, 1, 2]));
+    }
+
+    #[test]
+    fn unrelated_account_requotes_nothing() {
+        let (index, ..) = build_index();
+
+        assert!(index.get_cycles(&vec![Pubkey::new_unique()]).is_empty());
+    }
+
+    #[test]
+    fn edges_of_a_multi_asset_pool_are_chained() {
+        let (sol, jitosol, msol, inf) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let infinity = Pubkey::new_unique();
+        let pools = vec![
+            saber_pool(sol, jitosol),
+            saber_pool(msol, sol),
+            infinity_edge(infinity, jitosol, inf),
+            infinity_edge(infinity, msol, inf),
+            infinity_edge(infinity, jitosol, msol),
+        ];
+        let pool_edges = pools.iter().map(|pool| pool.get_edge()).collect::<Vec<PoolEdge>>();
+
+        let path_list = Arc::new(Mutex::new(HashMap::new()));
+        let path_finder = PathFinder {
+            pool_accounts: Arc::new(Mutex::new(pools)),
+            path_list: Arc::clone(&path_list),
+            pool_filter: PoolFilter::default(),
+        };
+        path_finder.resolve_path(sol);
+
+        // SOL -> jitoSOL -> mSOL -> SOL, SOL -> jitoSOL -> INF -> mSOL -> SOL and their reverses
+        let paths = path_list.lock().unwrap().get(&sol).cloned().unwrap();
+        let paths = paths.iter().map(|path| path.iter().map(|pool| pool.get_edge()).collect::<Vec<PoolEdge>>()).collect::<Vec<_>>();
+        assert_eq!(paths.len(), 4);
+        assert!(paths.contains(&vec![pool_edges[0], pool_edges[2], pool_edges[3], pool_edges[1]]));
+        assert!(paths.contains(&vec![pool_edges[1], pool_edges[3], pool_edges[2], pool_edges[0]]));
+        assert!(paths.contains(&vec![pool_edges[0], pool_edges[4], pool_edges[1]]));
+    }
+
+    #[test]
+    fn pools_of_the_same_dex_are_chained() {
+        let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = vec![saber_pool(sol, usdc), saber_pool(usdc, sol)];
+        let pool_edges = pools.iter().map(|pool| pool.get_edge()).collect::<Vec<PoolEdge>>();
+
+        let path_list = Arc::new(Mutex::new(HashMap::new()));
+        let path_finder = PathFinder {
+            pool_accounts: Arc::new(Mutex::new(pools)),
+            path_list: Arc::clone(&path_list),
+            pool_filter: PoolFilter::default(),
+        };
+        path_finder.resolve_path(sol);
+
+        let paths = path_list.lock().unwrap().get(&sol).cloned().unwrap();
+        let paths = paths.iter().map(|path| path.iter().map(|pool| pool.get_edge()).collect::<Vec<PoolEdge>>()).collect::<Vec<_>>();
+        assert_eq!(paths, vec![vec![pool_edges[0], pool_edges[1]], vec![pool_edges[1], pool_edges[0]]]);
+    }
+
+    #[test]
+    fn reused_pool_is_rejected() {
+        let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = saber_pool(sol, usdc);
+        let pools = vec![pool.clone(), pool];
+
+        let path_list = Arc::new(Mutex::new(HashMap::new()));
+        let path_finder = PathFinder {
+            pool_accounts: Arc::new(Mutex::new(pools)),
+            path_list: Arc::clone(&path_list),
+            pool_filter: PoolFilter::default(),
+        };
+        path_finder.resolve_path(sol);
+
+        // SOL -> USDC -> SOL through the same pool is no cycle
+        assert!(path_list.lock().unwrap().get(&sol).map_or(true, |paths| paths.is_empty()));
+    }
+
+    #[test]
+    fn edges_of_a_multi_asset_pool_requote_their_own_cycles() {
+        let (sol, jitosol, msol, inf) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let infinity = Pubkey::new_unique();
+        let (jitosol_edge, msol_edge) = (infinity_edge(infinity, jitosol, inf), infinity_edge(infinity, msol, inf));
+        let (jitosol_reserves, msol_reserves) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let related_pubkeys = HashMap::from([
+            (jitosol_edge.get_edge(), HashSet::from([jitosol_reserves])),
+            (msol_edge.get_edge(), HashSet::from([msol_reserves])),
+        ]);
+        let path_list = HashMap::from([(sol, vec![
+            vec![saber_pool(sol, jitosol), jitosol_edge.clone()],
+            vec![saber_pool(sol, msol), msol_edge.clone()],
+        ])]);
+        let index = CycleIndex::new(&path_list, &related_pubkeys);
+
+        assert_eq!(index.get_cycles(&vec![jitosol_reserves]), cycle_ids(sol, &[0]));
+        assert_eq!(index.get_cycles(&vec![msol_reserves]), cycle_ids(sol, &[1]));
+        // every edge depends on the pool account
+        assert_eq!(index.get_pools(&vec![infinity]), HashSet::from([jitosol_edge.get_edge(), msol_edge.get_edge()]));
+        assert_eq!(index.get_cycles(&vec![infinity]), cycle_ids(sol, &[0, 1]));
+    }
+}
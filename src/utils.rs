@@ -5,11 +5,15 @@ use std::io::BufReader;
 use std::path::Path;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use arrayref::array_ref;
 use num_enum::TryFromPrimitive;
 use num_integer::Integer;
 use serde::Deserialize;
 use serde_json::Value::Array;
+use solana_sdk::hash::hashv;
 use solana_sdk::pubkey::Pubkey;
+use crate::constants::{RPC_URL, RPC_URL_ENV};
 use crate::r#struct::market::Market;
 use crate::r#struct::pools::RaydiumRewardInfo;
 
@@ -52,4 +56,20 @@ pub fn is_pool_account_pubkey(pools: Arc<Mutex<HashMap<Market, Vec<Pubkey>>>>, p
 #[derive(Deserialize, Debug)]
 pub struct Pools {
     pub pools: Vec<String>
-}
\ No newline at end of file
+}
+
+/// Unix time in milliseconds
+pub fn get_timestamp_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+/// RPC endpoint from the `RPC_URL` environment variable, the public mainnet endpoint if unset
+pub fn get_rpc_url() -> String {
+    std::env::var(RPC_URL_ENV).unwrap_or_else(|_| RPC_URL.to_string())
+}
+
+/// Anchor discriminator of `name`, the namespace being `global` for instructions and `event` for events
+pub fn get_discriminator(namespace: &str, name: &str) -> [u8; 8] {
+    let hash = hashv(&[namespace.as_bytes(), b":", name.as_bytes()]);
+    *array_ref![hash.as_ref(), 0, 8]
+}